@@ -3,16 +3,22 @@ use druid::{
     im::{vector, Vector},
     lens,
     theme::{WIDGET_PADDING_HORIZONTAL, WIDGET_PADDING_VERTICAL},
-    widget::{Align, Checkbox, CrossAxisAlignment, Flex, Label, Painter, TextBox, ViewSwitcher},
+    widget::{
+        Align, Button, Checkbox, CrossAxisAlignment, Flex, Label, Painter, TextBox, ViewSwitcher,
+    },
     AppLauncher, ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, LayoutCtx, Lens,
     LensExt, LifeCycle, LifeCycleCtx, LocalizedString, PaintCtx, RenderContext, Size, UpdateCtx,
     Widget, WidgetExt, WindowDesc,
 };
 use druid_graphs::{
-    BoxPlot, BoxPlotData, Histogram, HistogramData, LineChart, LineChartData, PieChart,
-    PieChartData,
+    Annotation, AreaFill, AreaSeries, BarChart, BarChartData, BarMode, BarSeries, Baseline,
+    BinStrategy, BinnedHistogram, BinnedHistogramData, BoxPlot, BoxPlotData, Brush, CategoryLabels,
+    ConfidenceBand, Corner, DensityPlot, DensityPlotData, ErrorBar, Formatter, Histogram,
+    LegendPlacement, LineChart, LineChartData, LineSeries, PanZoom, ParetoChart, ParetoChartData,
+    PieChart, PieChartData, Range, RoseChart, RoseChartData, ScatterPlot, ScatterPlotData,
+    SliceLabels, StackedArea, StackedAreaData, TitleAlignment, Trend, TrendLine,
 };
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 const VERTICAL_WIDGET_SPACING: f64 = 20.0;
 const TEXT_BOX_WIDTH: f64 = 200.0;
@@ -26,10 +32,14 @@ struct HelloState {
     box_title: ArcStr,
     line_title: Arc<String>,
     line_x_label: Arc<String>,
+    line_x_range: Option<Range>,
+    line_y_range: Option<Range>,
     show_x_axis: bool,
     show_x_tick_labels: bool,
+    show_x_grid: bool,
     show_y_axis: bool,
     show_y_tick_labels: bool,
+    show_y_grid: bool,
 }
 
 fn main() {
@@ -45,10 +55,14 @@ fn main() {
         box_title: "Systolic BP".into(),
         line_title: Arc::new(String::from("Blood pressure")),
         line_x_label: Arc::new(String::from("Person number (order meaningless)")),
+        line_x_range: None,
+        line_y_range: None,
         show_x_axis: true,
         show_x_tick_labels: true,
+        show_x_grid: false,
         show_y_axis: true,
         show_y_tick_labels: true,
+        show_y_grid: true,
     };
 
     // start the application
@@ -59,7 +73,18 @@ fn main() {
 }
 
 fn build_root_widget() -> impl Widget<HelloState> {
-    let tab_labels = ["Histogram", "Box Plot", "Pie Chart", "Line Chart"];
+    let tab_labels = [
+        "Histogram",
+        "Box Plot",
+        "Pie Chart",
+        "Line Chart",
+        "Bar Chart",
+        "Stacked Area",
+        "Pareto",
+        "Wind Rose",
+        "Density",
+        "Scatter",
+    ];
 
     let mut tabs = Flex::row();
     for (idx, label) in tab_labels.iter().enumerate() {
@@ -80,53 +105,97 @@ fn build_root_widget() -> impl Widget<HelloState> {
             let vspace = env.get(WIDGET_PADDING_VERTICAL);
             let hspace = env.get(WIDGET_PADDING_HORIZONTAL);
             match tab_idx {
-                0 => Histogram::new()
-                    .lens(HistogramData::compose_lens(
-                        Constant("Distribution of BMI".into()),
-                        Constant("BMI".into()),
-                        Constant(vector![
-                            "10-15".into(),
-                            "15-20".into(),
-                            "20-25".into(),
-                            "25-30".into(),
-                            "30-35".into(),
-                            "35-40".into(),
-                            "40-45".into(),
-                            "45-50".into()
-                        ]),
-                        HelloState::monica.then(MonicaData::bucket_bmi),
-                    ))
-                    .boxed(),
+                0 => BinnedHistogram::new(
+                    Histogram::new()
+                        .with_value_labels(Formatter::default())
+                        .with_category_labels(CategoryLabels::Diagonal)
+                        .with_hover_highlight(true),
+                )
+                .with_label_formatter(Formatter::precision(2))
+                .lens(BinnedHistogramData::compose_lens(
+                    Constant("Distribution of BMI".into()),
+                    Constant("BMI".into()),
+                    HelloState::monica.then(MonicaData::bmi),
+                    Constant(None),
+                    Constant(BinStrategy::FixedWidth(5.)),
+                    Constant(None),
+                    Constant(true),
+                ))
+                .boxed(),
                 1 => BoxPlot::new()
                     .lens(BoxPlotData::compose_lens(
                         HelloState::box_title,
-                        HelloState::monica.then(MonicaData::systm),
+                        HelloState::monica.then(MonicaData::systm_by_sex),
+                        Constant(None),
+                        Constant(true),
                     ))
-                    .fix_width(300.)
+                    .fix_width(400.)
                     .boxed(),
                 2 => PieChart::new()
+                    .with_subtitle("From the MONICA dataset")
+                    .with_title_alignment(TitleAlignment::Left)
+                    .with_inner_radius(0.5)
+                    .with_slice_labels(SliceLabels::Percentage)
+                    .with_key_values(SliceLabels::Value)
+                    .with_explode_on_hover(true)
                     .lens(PieChartData::compose_lens(
                         Constant("Gender".into()),
                         Constant(vector!["female".into(), "male".into()]),
                         HelloState::monica.then(MonicaData::bucket_sex),
+                        Constant(vector![]),
                     ))
                     .boxed(),
                 3 => Flex::row()
                     .with_flex_child(
-                        LineChart::new().lens(LineChartData::compose_lens(
-                            HelloState::line_title,
-                            // x axis
-                            HelloState::line_x_label,
-                            Constant(None),
-                            HelloState::show_x_tick_labels,
-                            HelloState::show_x_axis,
-                            Constant(None),
-                            // y axis
-                            Constant(None),
-                            HelloState::show_y_tick_labels,
-                            HelloState::show_y_axis,
-                            HelloState::monica.then(MonicaData::systm),
-                        )),
+                        LineChart::new()
+                            .with_pan_zoom(PanZoom::new())
+                            .with_context_menu::<HelloState>()
+                            .with_brush(Brush::new().x_only().zoom_to_selection())
+                            .with_y_formatter(Formatter::new(|v| format!("{} mmHg", v)))
+                            .with_legend(LegendPlacement::Overlay(Corner::TopRight))
+                            // shade the pulse pressure
+                            .with_area_fill(AreaFill::Between(0, 1))
+                            .with_annotation(
+                                Annotation::y_line(140.).with_label("hypertension threshold"),
+                            )
+                            .with_trend(TrendLine::new(0, Trend::MovingAverage(3)))
+                            .lens(LineChartData::compose_lens(
+                                HelloState::line_title,
+                                // x axis
+                                HelloState::line_x_label,
+                                HelloState::line_x_range,
+                                HelloState::show_x_tick_labels,
+                                HelloState::show_x_axis,
+                                HelloState::show_x_grid,
+                                Constant(None),
+                                Constant(None),
+                                // y axis
+                                Constant("Blood pressure".into()),
+                                HelloState::line_y_range,
+                                HelloState::show_y_tick_labels,
+                                HelloState::show_y_axis,
+                                HelloState::show_y_grid,
+                                Constant(None),
+                                HelloState::monica.then(MonicaData::systm),
+                                Constant(None),
+                                Constant(None),
+                                // secondary y axis
+                                Constant(None),
+                                // series
+                                Constant("Systolic".into()),
+                                HelloState::monica.map(
+                                    |monica| {
+                                        // a made up measurement error of 5 mmHg.
+                                        let band = ConfidenceBand::new(
+                                            monica.diastm.iter().map(|v| v - 5.).collect(),
+                                            monica.diastm.iter().map(|v| v + 5.).collect(),
+                                        );
+                                        vector![LineSeries::new("Diastolic", monica.diastm.clone())
+                                            .with_band(band)]
+                                    },
+                                    |_, _| (),
+                                ),
+                            )),
                         2.,
                     )
                     .with_spacer(hspace)
@@ -150,15 +219,118 @@ fn build_root_widget() -> impl Widget<HelloState> {
                                     .lens(HelloState::show_x_tick_labels),
                             )
                             .with_spacer(vspace)
+                            .with_child(Checkbox::new("show x grid").lens(HelloState::show_x_grid))
+                            .with_spacer(vspace)
                             .with_child(Checkbox::new("show y axis").lens(HelloState::show_y_axis))
                             .with_spacer(vspace)
                             .with_child(
                                 Checkbox::new("show y value labels")
                                     .lens(HelloState::show_y_tick_labels),
-                            ),
+                            )
+                            .with_spacer(vspace)
+                            .with_child(Checkbox::new("show y grid").lens(HelloState::show_y_grid))
+                            .with_spacer(vspace)
+                            .with_child(Button::new("reset view").on_click(
+                                |_, data: &mut HelloState, _| {
+                                    data.line_x_range = None;
+                                    data.line_y_range = None;
+                                },
+                            )),
                         1.,
                     )
                     .boxed(),
+                4 => BarChart::new(BarMode::Grouped)
+                    .with_entrance_animation(Duration::from_millis(600))
+                    .with_keyboard_navigation(true)
+                    .with_context_menu::<HelloState>()
+                    .lens(BarChartData::compose_lens(
+                        Constant("Age by gender".into()),
+                        Constant("Age".into()),
+                        Constant(vector![
+                            "25-34".into(),
+                            "35-44".into(),
+                            "45-54".into(),
+                            "55-64".into()
+                        ]),
+                        HelloState::monica.then(MonicaData::bucket_age_sex),
+                        Constant(true),
+                    ))
+                    .boxed(),
+                5 => StackedArea::new()
+                    .with_baseline(Baseline::Wiggle)
+                    .lens(StackedAreaData::compose_lens(
+                        Constant("Age by gender".into()),
+                        Constant("Age group".into()),
+                        Constant(None),
+                        HelloState::monica.map(
+                            |monica| {
+                                monica
+                                    .bucket_age_sex
+                                    .iter()
+                                    .map(|series| {
+                                        AreaSeries::new(series.label.clone(), series.values.clone())
+                                    })
+                                    .collect()
+                            },
+                            |_, _| (),
+                        ),
+                        Constant(true),
+                    ))
+                    .boxed(),
+                6 => ParetoChart::new()
+                    .lens(ParetoChartData::compose_lens(
+                        Constant("Reasons for missing readings".into()),
+                        Constant("Reason".into()),
+                        Constant(vector![
+                            "Refused".into(),
+                            "Moved away".into(),
+                            "Equipment fault".into(),
+                            "Died".into(),
+                            "Other".into()
+                        ]),
+                        Constant(vector![24., 61., 9., 17., 5.]),
+                        Constant(true),
+                    ))
+                    .boxed(),
+                7 => RoseChart::new()
+                    .lens(RoseChartData::compose_lens(
+                        Constant("Wind direction".into()),
+                        Constant(vector![
+                            "N".into(),
+                            "NE".into(),
+                            "E".into(),
+                            "SE".into(),
+                            "S".into(),
+                            "SW".into(),
+                            "W".into(),
+                            "NW".into()
+                        ]),
+                        Constant(vector![12., 8., 5., 7., 14., 26., 19., 9.]),
+                        Constant(true),
+                    ))
+                    .boxed(),
+                8 => DensityPlot::new()
+                    .lens(DensityPlotData::compose_lens(
+                        Constant("Blood pressure".into()),
+                        Constant("Systolic (y: diastolic)".into()),
+                        HelloState::monica.then(MonicaData::systm),
+                        HelloState::monica.then(MonicaData::diastm),
+                    ))
+                    .boxed(),
+                9 => ScatterPlot::new()
+                    .with_subtitle("Colored by age")
+                    .lens(ScatterPlotData::compose_lens(
+                        Constant("Blood pressure".into()),
+                        Constant("Systolic (y: diastolic)".into()),
+                        HelloState::monica.then(MonicaData::systm),
+                        HelloState::monica.then(MonicaData::diastm),
+                        HelloState::monica.map(
+                            |monica| Some(monica.age.iter().map(|age| *age as f64).collect()),
+                            |_, _| (),
+                        ),
+                        Constant(None),
+                    ))
+                    .boxed(),
                 _ => unreachable!(),
             }
         },
@@ -192,8 +364,9 @@ struct MonicaData {
     systm: Vector<f64>,
     diastm: Vector<f64>,
     bmi: Vector<f64>,
-    bucket_bmi: Vector<usize>,
     bucket_sex: Vector<usize>,
+    bucket_age_sex: Vector<BarSeries>,
+    systm_by_sex: Vector<(ArcStr, Vector<f64>)>,
 }
 
 impl MonicaData {
@@ -211,38 +384,24 @@ impl MonicaData {
             data.diastm.push_back(record.get(5).unwrap().parse()?);
             data.bmi.push_back(record.get(6).unwrap().parse()?);
         }
-        data.calc_bucket_bmi();
         data.calc_bucket_sex();
+        data.calc_bucket_age_sex();
+        data.calc_systm_by_sex();
         Ok(data)
     }
 
-    /// Collect BMI data into buckets.
-    fn calc_bucket_bmi(&mut self) {
-        let mut out = vector![0, 0, 0, 0, 0, 0, 0, 0];
-        for datum in self.bmi.iter().copied() {
-            if datum <= 10.0 {
-                panic!("invalid bmi");
-            } else if datum < 15.0 {
-                out[0] += 1;
-            } else if datum < 20.0 {
-                out[1] += 1;
-            } else if datum < 25.0 {
-                out[2] += 1;
-            } else if datum < 30.0 {
-                out[3] += 1;
-            } else if datum < 35.0 {
-                out[4] += 1;
-            } else if datum < 40.0 {
-                out[5] += 1;
-            } else if datum < 45.0 {
-                out[6] += 1;
-            } else if datum < 50.0 {
-                out[7] += 1;
-            } else {
-                panic!("very large bmi");
+    /// Split the systolic blood pressure readings by sex.
+    fn calc_systm_by_sex(&mut self) {
+        let mut female = Vector::new();
+        let mut male = Vector::new();
+        for (sex, systm) in self.sex.iter().copied().zip(self.systm.iter().copied()) {
+            match sex {
+                0 => female.push_back(systm),
+                1 => male.push_back(systm),
+                _ => panic!("invalid sex"),
             }
         }
-        self.bucket_bmi = out;
+        self.systm_by_sex = vector![("female".into(), female), ("male".into(), male)];
     }
 
     fn calc_bucket_sex(&mut self) {
@@ -257,6 +416,37 @@ impl MonicaData {
         }
         self.bucket_sex = vector![female, male];
     }
+
+    /// Count people in 10 year age bands, split by sex.
+    fn calc_bucket_age_sex(&mut self) {
+        let mut female = vector![0., 0., 0., 0.];
+        let mut male = vector![0., 0., 0., 0.];
+        for (sex, age) in self.sex.iter().copied().zip(self.age.iter().copied()) {
+            let band = match age {
+                25..=34 => 0,
+                35..=44 => 1,
+                45..=54 => 2,
+                55..=64 => 3,
+                _ => panic!("age out of range"),
+            };
+            match sex {
+                0 => female[band] += 1.,
+                1 => male[band] += 1.,
+                _ => panic!("invalid sex"),
+            }
+        }
+        // counts have a standard error of about √n
+        let errors = |counts: &Vector<f64>| {
+            counts
+                .iter()
+                .map(|count| ErrorBar::Symmetric(count.sqrt()))
+                .collect()
+        };
+        self.bucket_age_sex = vector![
+            BarSeries::new("female", female.clone()).with_errors(errors(&female)),
+            BarSeries::new("male", male.clone()).with_errors(errors(&male))
+        ];
+    }
 }
 
 /// A lens that always gives the same value and discards changes.
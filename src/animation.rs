@@ -0,0 +1,117 @@
+//! Animations: a one-shot entrance played when a chart is first shown, and transitions between
+//! old and new values.
+use druid::{Event, EventCtx, LifeCycle, LifeCycleCtx, UpdateCtx};
+use std::time::Duration;
+
+/// Tracks how far through its entrance animation a chart is.
+///
+/// A chart owns one of these (if it is animated), forwards its events and lifecycle events to it,
+/// and draws itself part way in according to [`Entrance::progress`].
+#[derive(Debug, Clone)]
+pub(crate) struct Entrance {
+    duration: Duration,
+    /// How far through the animation we are, from 0 to 1.
+    elapsed: f64,
+}
+
+impl Entrance {
+    pub fn new(duration: Duration) -> Self {
+        Entrance {
+            duration,
+            elapsed: 0.,
+        }
+    }
+
+    /// Start the animation when the chart is added.
+    pub fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        if let LifeCycle::WidgetAdded = event {
+            self.elapsed = 0.;
+            ctx.request_anim_frame();
+        }
+    }
+
+    /// Move the animation on.
+    pub fn event(&mut self, ctx: &mut EventCtx, event: &Event) {
+        step(ctx, event, self.duration, &mut self.elapsed);
+    }
+
+    /// How much of the chart to draw, from 0 to 1. It slows down towards the end.
+    pub fn progress(&self) -> f64 {
+        ease_out(self.elapsed)
+    }
+}
+
+/// Tracks how far a chart is through moving from its old values to its new ones.
+///
+/// The chart keeps whatever it was showing when the values changed, calls [`Transition::start`],
+/// forwards its events, and draws part way between the two according to
+/// [`Transition::progress`].
+#[derive(Debug, Clone)]
+pub(crate) struct Transition {
+    duration: Duration,
+    /// How far through the transition we are, from 0 to 1.
+    elapsed: f64,
+}
+
+impl Transition {
+    /// A transition that hasn't started, so the chart shows its current values.
+    pub fn new(duration: Duration) -> Self {
+        Transition {
+            duration,
+            elapsed: 1.,
+        }
+    }
+
+    /// Start (or restart) moving towards new values.
+    pub fn start(&mut self, ctx: &mut UpdateCtx) {
+        self.elapsed = 0.;
+        ctx.request_anim_frame();
+    }
+
+    /// Move the transition on.
+    pub fn event(&mut self, ctx: &mut EventCtx, event: &Event) {
+        step(ctx, event, self.duration, &mut self.elapsed);
+    }
+
+    /// How far to go from the old values to the new ones, from 0 to 1.
+    pub fn progress(&self) -> f64 {
+        ease_out(self.elapsed)
+    }
+}
+
+/// Move `elapsed` on by the time since the last animation frame, asking for another frame until
+/// it reaches 1.
+fn step(ctx: &mut EventCtx, event: &Event, duration: Duration, elapsed: &mut f64) {
+    if let Event::AnimFrame(interval) = event {
+        if *elapsed >= 1. {
+            return;
+        }
+        let duration = duration.as_secs_f64();
+        *elapsed = if duration > 0. {
+            (*elapsed + *interval as f64 * 1e-9 / duration).min(1.)
+        } else {
+            1.
+        };
+        ctx.request_paint();
+        if *elapsed < 1. {
+            ctx.request_anim_frame();
+        }
+    }
+}
+
+/// How much of the chart to draw after `elapsed` (from 0 to 1) of the animation.
+fn ease_out(elapsed: f64) -> f64 {
+    1. - (1. - elapsed).powi(3)
+}
+
+/// The progress of an optional animation, where no animation means the chart is fully drawn.
+pub(crate) fn progress(entrance: Option<&Entrance>) -> f64 {
+    entrance.map_or(1., Entrance::progress)
+}
+
+#[test]
+fn test_ease_out() {
+    assert_eq!(ease_out(0.), 0.);
+    assert_eq!(ease_out(1.), 1.);
+    assert!(ease_out(0.5) > 0.5);
+}
@@ -0,0 +1,218 @@
+//! Reference lines and shaded bands, drawn behind the data.
+use druid::{
+    kurbo::{Line, Point, Rect},
+    piet::PietText,
+    ArcStr, Color, Data, Env, KeyOrValue, RenderContext, TextLayout,
+};
+
+use crate::{
+    axes::{Direction, Scale},
+    canvas::Canvas,
+    theme, Range,
+};
+
+/// How much of the annotation color shows through in bands.
+const BAND_ALPHA: f64 = 0.2;
+
+/// Space between an annotation and its label, at the normal size.
+const LABEL_MARGIN: f64 = 4.0;
+
+/// A reference line or band, in data coordinates.
+#[derive(Debug, Clone, Data, PartialEq)]
+pub struct Annotation {
+    pub shape: AnnotationShape,
+    pub label: Option<ArcStr>,
+}
+
+/// Where an annotation is drawn.
+#[derive(Debug, Copy, Clone, Data, PartialEq)]
+pub enum AnnotationShape {
+    /// A horizontal line at the given y value.
+    YLine(f64),
+    /// A vertical line at the given x value.
+    XLine(f64),
+    /// A horizontal band covering the given range of y values.
+    YBand(Range),
+    /// A vertical band covering the given range of x values.
+    XBand(Range),
+}
+
+impl AnnotationShape {
+    /// The direction of the axis the shape's values are on.
+    fn direction(self) -> Direction {
+        match self {
+            AnnotationShape::YLine(_) | AnnotationShape::YBand(_) => Direction::Y,
+            AnnotationShape::XLine(_) | AnnotationShape::XBand(_) => Direction::X,
+        }
+    }
+}
+
+impl Annotation {
+    /// A horizontal line across the chart at `y`, e.g. a threshold.
+    pub fn y_line(y: f64) -> Self {
+        Annotation::new(AnnotationShape::YLine(y))
+    }
+
+    /// A vertical line across the chart at `x`.
+    pub fn x_line(x: f64) -> Self {
+        Annotation::new(AnnotationShape::XLine(x))
+    }
+
+    /// A shaded horizontal band, e.g. a target range.
+    pub fn y_band(range: impl Into<Range>) -> Self {
+        Annotation::new(AnnotationShape::YBand(range.into()))
+    }
+
+    /// A shaded vertical band.
+    pub fn x_band(range: impl Into<Range>) -> Self {
+        Annotation::new(AnnotationShape::XBand(range.into()))
+    }
+
+    fn new(shape: AnnotationShape) -> Self {
+        Annotation { shape, label: None }
+    }
+
+    pub fn with_label(mut self, label: impl Into<ArcStr>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+}
+
+/// The annotations for a chart, along with their retained text layouts.
+#[derive(Clone)]
+pub(crate) struct Annotations {
+    annotations: Vec<Annotation>,
+    color: KeyOrValue<Color>,
+    // retained
+    label_layouts: Option<Vec<Option<TextLayout<ArcStr>>>>,
+}
+
+impl Annotations {
+    pub fn new() -> Self {
+        Annotations {
+            annotations: Vec::new(),
+            color: theme::ANNOTATION_COLOR.into(),
+            label_layouts: None,
+        }
+    }
+
+    pub fn push(&mut self, annotation: Annotation) {
+        self.annotations.push(annotation);
+        self.label_layouts = None;
+    }
+
+    pub fn rebuild_if_needed(&mut self, text: &mut PietText, env: &Env) {
+        if self.label_layouts.is_none() {
+            self.label_layouts = Some(
+                self.annotations
+                    .iter()
+                    .map(|annotation| {
+                        annotation.label.clone().map(|label| {
+                            let mut layout = TextLayout::from_text(label);
+                            layout.set_text_color(self.color.clone());
+                            layout
+                        })
+                    })
+                    .collect(),
+            );
+        }
+        for layout in self.label_layouts.as_mut().unwrap().iter_mut().flatten() {
+            layout.rebuild_if_needed(text, env);
+        }
+    }
+
+    /// Draw the annotations using whichever of `scales` matches their direction. Annotations with
+    /// no matching scale are skipped.
+    ///
+    /// Call this before drawing the data, so the annotations end up behind it.
+    pub fn draw(&self, ctx: &mut Canvas, env: &Env, scales: &[&Scale]) {
+        if self.annotations.is_empty() {
+            return;
+        }
+        let color = self.color.resolve(env);
+        let band_color = color.clone().with_alpha(BAND_ALPHA);
+        let size_scale = env.get(theme::SIZE_SCALE);
+        let label_margin = LABEL_MARGIN * size_scale;
+        for (annotation, layout) in self
+            .annotations
+            .iter()
+            .zip(self.label_layouts.as_ref().unwrap().iter())
+        {
+            let shape = annotation.shape;
+            let scale = match scales
+                .iter()
+                .find(|scale| scale.direction() == shape.direction())
+            {
+                Some(scale) => scale,
+                None => continue,
+            };
+            let bounds = scale.graph_bounds();
+            // where the label goes, and which corner of it goes there.
+            let (anchor, right_align) = match shape {
+                AnnotationShape::YLine(y) => {
+                    let y = scale.pixel_location(y);
+                    ctx.stroke(
+                        Line::new((bounds.x0, y), (bounds.x1, y)),
+                        &color,
+                        size_scale,
+                    );
+                    (Point::new(bounds.x1 - label_margin, y - label_margin), true)
+                }
+                AnnotationShape::XLine(x) => {
+                    let x = scale.pixel_location(x);
+                    ctx.stroke(
+                        Line::new((x, bounds.y0), (x, bounds.y1)),
+                        &color,
+                        size_scale,
+                    );
+                    (
+                        Point::new(x + label_margin, bounds.y0 + label_margin),
+                        false,
+                    )
+                }
+                AnnotationShape::YBand(range) => {
+                    let (y0, y1) = (
+                        scale.pixel_location(range.min()),
+                        scale.pixel_location(range.max()),
+                    );
+                    let band = Rect::new(bounds.x0, y0, bounds.x1, y1)
+                        .abs()
+                        .intersect(bounds);
+                    ctx.fill(band, &band_color);
+                    (
+                        Point::new(bounds.x1 - label_margin, band.y0 + label_margin),
+                        true,
+                    )
+                }
+                AnnotationShape::XBand(range) => {
+                    let (x0, x1) = (
+                        scale.pixel_location(range.min()),
+                        scale.pixel_location(range.max()),
+                    );
+                    let band = Rect::new(x0, bounds.y0, x1, bounds.y1)
+                        .abs()
+                        .intersect(bounds);
+                    ctx.fill(band, &band_color);
+                    (
+                        Point::new(band.x0 + label_margin, bounds.y0 + label_margin),
+                        false,
+                    )
+                }
+            };
+            if let Some(layout) = layout {
+                let size = layout.size();
+                // labels on lines sit above them, labels on bands sit inside them.
+                let y = match shape {
+                    AnnotationShape::YLine(_) => anchor.y - size.height,
+                    _ => anchor.y,
+                };
+                let x = if right_align {
+                    anchor.x - size.width
+                } else {
+                    anchor.x
+                };
+                ctx.draw_layout(layout, (x, y));
+            }
+        }
+    }
+}
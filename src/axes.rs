@@ -1,19 +1,43 @@
+//! The axes of a chart: where the ticks go, laying out their labels, and converting between data
+//! values and pixels.
+//!
+//! The charts in this crate are built from these, and you can use them to build your own chart
+//! widgets. A [`Scale`] retains its tick label layouts, so keep it in your widget and call
+//! [`Scale::rebuild_if_needed`] from `layout` (with `ctx.text()`), then draw it in `paint` (or on
+//! a [`Canvas`]).
 // TODO implement toPrecision from javascript - it gives better results.
 // TODO decide how to handle when data range only contains single value, stretch: infinity.
-use crate::{theme, Range};
+use crate::{
+    canvas::{Canvas, ToCanvas},
+    format::{self, Formatter},
+    theme, Range,
+};
 use druid::{
-    kurbo::{Line, Point, Rect},
+    im::Vector,
+    kurbo::{BezPath, Circle, Line, Point, Rect, Vec2},
+    piet::{Piet, PietText},
     text::TextStorage,
-    ArcStr, Color, Env, KeyOrValue, PaintCtx, RenderContext, Size, TextLayout, UpdateCtx,
+    theme::WINDOW_BACKGROUND_COLOR,
+    ArcStr, Color, Data, Env, KeyOrValue, RenderContext, Size, TextLayout, UpdateCtx,
 };
-use to_precision::FloatExt as _;
+use std::{cmp::Ordering, f64::consts::PI, fmt, sync::Arc};
 
+/// The gap between the axis and its tick labels, at the normal size.
 const SCALE_TICK_MARGIN: f64 = 5.;
+/// The smallest gap between neighbouring tick labels, at the normal size.
+const LABEL_GAP: f64 = 4.;
+/// How far the zig-zag marking an axis break reaches either side of the axis, at the normal size.
+const BREAK_MARK_SIZE: f64 = 6.;
 
+/// Which way an axis goes, and which side of the graph it is drawn on.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Direction {
+    /// An x axis along the bottom of the graph, with the labels below.
     X,
+    /// A y axis on the left hand side of the graph, with the labels to the left.
     Y,
+    /// A y axis on the right hand side of the graph, with the labels to the right.
+    YRight,
 }
 
 impl Direction {
@@ -21,25 +45,48 @@ impl Direction {
     fn max_labels(self, bounds: Rect) -> usize {
         match self {
             Direction::X => (bounds.width() / 100.).floor() as usize + 1,
-            Direction::Y => (bounds.height() / 40.).floor() as usize + 1,
+            Direction::Y | Direction::YRight => (bounds.height() / 40.).floor() as usize + 1,
         }
     }
 
     fn label_position(self, bounds: Rect, t: f64, size: Size, margin: f64) -> Point {
         let p = self.position(bounds, t);
         match self {
-            Direction::X => Point::new(p - 0.5 * size.width, bounds.y1 + SCALE_TICK_MARGIN),
-            Direction::Y => Point::new(
-                bounds.x0 - size.width - SCALE_TICK_MARGIN,
-                p - 0.5 * size.height,
-            ),
+            Direction::X => Point::new(p - 0.5 * size.width, bounds.y1 + margin),
+            Direction::Y => Point::new(bounds.x0 - size.width - margin, p - 0.5 * size.height),
+            Direction::YRight => Point::new(bounds.x1 + margin, p - 0.5 * size.height),
         }
     }
 
     fn position(self, bounds: Rect, t: f64) -> f64 {
         match self {
             Direction::X => bounds.x0 + t * bounds.width(),
-            Direction::Y => bounds.y1 - t * bounds.height(),
+            Direction::Y | Direction::YRight => bounds.y1 - t * bounds.height(),
+        }
+    }
+
+    /// The inverse of `position`.
+    fn t_at(self, bounds: Rect, p: f64) -> f64 {
+        match self {
+            Direction::X => (p - bounds.x0) / bounds.width(),
+            Direction::Y | Direction::YRight => (bounds.y1 - p) / bounds.height(),
+        }
+    }
+
+    /// The start and end of a label drawn at `position` along the axis.
+    fn label_extent(self, position: Point, size: Size) -> (f64, f64) {
+        match self {
+            Direction::X => (position.x, position.x + size.width),
+            Direction::Y | Direction::YRight => (position.y, position.y + size.height),
+        }
+    }
+
+    /// A line across the whole graph at `t`.
+    fn grid_line(self, bounds: Rect, t: f64) -> Line {
+        let p = self.position(bounds, t);
+        match self {
+            Direction::X => Line::new((p, bounds.y0), (p, bounds.y1)),
+            Direction::Y | Direction::YRight => Line::new((bounds.x0, p), (bounds.x1, p)),
         }
     }
 
@@ -47,11 +94,43 @@ impl Direction {
         match self {
             Direction::X => Line::new((x0, y1), (x1, y1)),
             Direction::Y => Line::new((x0, y0), (x0, y1)),
+            Direction::YRight => Line::new((x1, y0), (x1, y1)),
+        }
+    }
+}
+
+/// How values are spread along an axis.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScaleType {
+    /// Equal differences are equal distances apart.
+    Linear,
+    /// Equal ratios are equal distances apart, with ticks at powers of 10. Only positive values
+    /// can be shown.
+    Log,
+}
+
+impl ScaleType {
+    /// How far through `range` the value `v` is, where `0` is the start and `1` the end.
+    fn t(self, range: Range, v: f64) -> f64 {
+        let (min, max) = range.into();
+        match self {
+            ScaleType::Linear => (v - min) / (max - min),
+            ScaleType::Log => (v.log10() - min.log10()) / (max.log10() - min.log10()),
+        }
+    }
+
+    /// The inverse of `t`.
+    fn value_at(self, range: Range, t: f64) -> f64 {
+        let (min, max) = range.into();
+        match self {
+            ScaleType::Linear => min + t * (max - min),
+            ScaleType::Log => 10f64.powf(min.log10() + t * (max.log10() - min.log10())),
         }
     }
 }
 
-/// A struct for retaining text layout information for a y axis scale.
+/// One axis of a chart. It converts between data values and pixels, and works out (and retains)
+/// where the ticks go and the layouts of their labels.
 ///
 /// [matplotlib ticker](https://github.com/matplotlib/matplotlib/blob/master/lib/matplotlib/ticker.py#L2057)
 /// is a good resource.
@@ -61,46 +140,102 @@ pub struct Scale {
     /// (min, max) the range of the data we are graphing. Can overspill if you want gaps at the
     /// top/bottom, or include 0 if you want.
     data_range: Range,
+    scale_type: ScaleType,
+    /// If true, larger values are lower (or further left).
+    inverted: bool,
+    /// Decides where the ticks go.
+    tick_locator: Arc<dyn TickLocator>,
     /// The graph area
     graph_bounds: Rect,
     /// Axis/mark color
     axis_color: KeyOrValue<Color>,
+    /// Gridline color
+    grid_color: KeyOrValue<Color>,
+    /// Turns tick values into labels.
+    formatter: Formatter,
+    /// If set, this is a categorical axis, with a tick in the middle of each category.
+    categories: Option<Vec<ArcStr>>,
+    /// If set, these are used instead of the tick locator and formatter.
+    custom_ticks: Option<Vector<CustomTick>>,
+    /// Whether to leave out labels that would overlap their neighbours.
+    thin_labels: bool,
+    /// Whether to take a power of 10 out of big or small tick values, and show it once at the end
+    /// of the axis.
+    shared_exponent: bool,
+    /// A part of the data range to leave out of the axis.
+    axis_break: Option<Range>,
     // retained
-    /// Our computed scale. The length is the computed number of scale ticks we should show. Format
-    /// is `(data value, y-coordinate of the tick)`
-    scale_ticker: Option<Ticker>,
+    /// The labels of the custom ticks that are in range, in the same order as `ticks`.
+    custom_labels: Vec<ArcStr>,
+    /// Our computed scale. The length is the computed number of scale ticks we should show.
+    ticks: Option<Vec<Tick>>,
     /// Our computed text layouts for the tick labels.
     layouts: Option<Vec<PositionedLayout<ArcStr>>>,
+    /// The `×10ⁿ` shown at the end of the axis, if the labels share an exponent.
+    exponent_layout: Option<PositionedLayout<ArcStr>>,
     /// The max size of the layouts.
     max_layout: Option<Size>,
+    /// Only every `label_stride`th label is drawn, so they don't overlap.
+    label_stride: usize,
 }
 
 impl Scale {
     /// Create a new scale object.
     ///
     ///  - `data_range` is the range of the data, from lowest to highest.
-    ///  - `graph_bounds` is the rectangle where the graph will be drawn. We will draw outside this
-    ///    area a bit.
+    ///  - `direction` is which axis this is.
+    ///
+    /// Set the rectangle where the graph will be drawn with `set_graph_bounds` before using the
+    /// scale. The axis and its labels are drawn outside this area a bit.
     pub fn new(data_range: impl Into<Range>, direction: Direction) -> Self {
         Scale {
             direction,
             data_range: data_range.into(),
+            scale_type: ScaleType::Linear,
+            inverted: false,
+            tick_locator: Arc::new(LinearTicker),
             graph_bounds: Rect::ZERO,
             axis_color: theme::AXES_COLOR.into(),
-            scale_ticker: None,
+            grid_color: theme::GRID_COLOR.into(),
+            formatter: Formatter::default(),
+            categories: None,
+            custom_ticks: None,
+            thin_labels: true,
+            shared_exponent: false,
+            axis_break: None,
+            custom_labels: Vec::new(),
+            ticks: None,
             layouts: None,
+            exponent_layout: None,
             max_layout: None,
+            label_stride: 1,
         }
     }
 
+    /// Create a categorical scale, with the categories evenly spaced along the axis in order.
+    ///
+    /// Category `n` covers the data values `n..n + 1`, so use `category_center` and
+    /// `category_width` to position things. Each tick is labelled with the name of its category.
+    pub fn new_categorical(
+        categories: impl IntoIterator<Item = ArcStr>,
+        direction: Direction,
+    ) -> Self {
+        let mut scale = Self::new(Range::new(0., 1.), direction);
+        scale.set_categories(categories);
+        scale
+    }
+
+    /// A scale for the (left hand) y axis.
     pub fn new_y(data_range: impl Into<Range>) -> Self {
         Self::new(data_range, Direction::Y)
     }
 
+    /// A scale for the x axis.
     pub fn new_x(data_range: impl Into<Range>) -> Self {
         Self::new(data_range, Direction::X)
     }
 
+    /// Move the scale to a different axis.
     pub fn set_direction(&mut self, d: Direction) {
         if self.direction != d {
             self.direction = d;
@@ -108,6 +243,123 @@ impl Scale {
         }
     }
 
+    /// Switch between a linear and a log scale. For a log scale, the data range must be positive.
+    pub fn set_scale_type(&mut self, scale_type: ScaleType) {
+        if self.scale_type != scale_type {
+            self.scale_type = scale_type;
+            self.invalidate();
+        }
+    }
+
+    /// Whether the scale is linear or log. The default is `ScaleType::Linear`.
+    pub fn scale_type(&self) -> ScaleType {
+        self.scale_type
+    }
+
+    /// Flip the axis, so larger values are lower on a y axis (or further left on an x axis).
+    /// Useful for depths and rankings.
+    pub fn set_inverted(&mut self, inverted: bool) {
+        if self.inverted != inverted {
+            self.inverted = inverted;
+            self.invalidate();
+        }
+    }
+
+    /// Whether the axis is flipped. The default is `false`.
+    pub fn is_inverted(&self) -> bool {
+        self.inverted
+    }
+
+    /// Make this a categorical axis, with the given categories. The data range is changed to
+    /// cover them all.
+    pub fn set_categories(&mut self, categories: impl IntoIterator<Item = ArcStr>) {
+        let categories: Vec<ArcStr> = categories.into_iter().collect();
+        self.data_range = Range::new(0., categories.len().max(1) as f64);
+        self.categories = Some(categories);
+        self.invalidate();
+    }
+
+    /// The names of the categories, if this is a categorical axis.
+    pub fn categories(&self) -> Option<&[ArcStr]> {
+        self.categories.as_deref()
+    }
+
+    /// The pixel location of the middle of category `idx`.
+    pub fn category_center(&self, idx: usize) -> f64 {
+        self.pixel_location(idx as f64 + 0.5)
+    }
+
+    /// How much space along the axis each category has, in pixels.
+    pub fn category_width(&self) -> f64 {
+        (self.pixel_location(1.) - self.pixel_location(0.)).abs()
+    }
+
+    /// Change how the ticks are chosen, e.g. `FixedTicker` for ticks at specific values. The
+    /// default is `LinearTicker`.
+    pub fn set_tick_locator(&mut self, tick_locator: impl TickLocator + 'static) {
+        self.set_shared_tick_locator(Arc::new(tick_locator));
+    }
+
+    /// Change how the ticks are chosen, to a locator that a chart keeps for its next scale.
+    pub(crate) fn set_shared_tick_locator(&mut self, tick_locator: Arc<dyn TickLocator>) {
+        self.tick_locator = tick_locator;
+        self.invalidate();
+    }
+
+    /// Put the ticks at the given values with the given labels, instead of using the tick
+    /// locator and formatter, e.g. for a Likert scale. Ticks outside the data range are left out.
+    /// `None` goes back to the tick locator.
+    pub fn set_custom_ticks(&mut self, custom_ticks: Option<Vector<CustomTick>>) {
+        if !self.custom_ticks.same(&custom_ticks) {
+            self.custom_ticks = custom_ticks;
+            self.invalidate();
+        }
+    }
+
+    /// The ticks set with `set_custom_ticks`, if any.
+    pub fn custom_ticks(&self) -> Option<&Vector<CustomTick>> {
+        self.custom_ticks.as_ref()
+    }
+
+    /// Whether to leave out tick labels that would overlap their neighbours, e.g. when the chart
+    /// is narrow. Every other label is dropped (or every third, and so on) until they fit, keeping
+    /// the first. The grid lines are still drawn at every tick. On by default.
+    pub fn set_thin_labels(&mut self, thin_labels: bool) {
+        if self.thin_labels != thin_labels {
+            self.thin_labels = thin_labels;
+            self.layouts = None;
+        }
+    }
+
+    /// Whether to take the power of 10 out of tick values that are very big or small, and show it
+    /// once at the end of the axis, like matplotlib. The labels then read e.g. `1.5`, `2`, `2.5`
+    /// with `×10⁶` above the top of a y axis (or under the right hand end of an x axis). Off by
+    /// default. Categorical axes and custom ticks are left alone.
+    pub fn set_shared_exponent(&mut self, shared_exponent: bool) {
+        if self.shared_exponent != shared_exponent {
+            self.shared_exponent = shared_exponent;
+            self.layouts = None;
+            self.max_layout = None;
+        }
+    }
+
+    /// Leave the values in `axis_break` out of the axis, so one outlier doesn't squash the rest of
+    /// the data. The axis jumps from the start of the break to the end of it, and the jump is
+    /// marked with a zig-zag across the axis line. Values inside the break are drawn at the jump.
+    ///
+    /// The break is ignored unless it is inside the data range. `None` removes it.
+    pub fn set_axis_break(&mut self, axis_break: Option<Range>) {
+        if self.axis_break != axis_break {
+            self.axis_break = axis_break;
+            self.invalidate();
+        }
+    }
+
+    /// The break set with `set_axis_break`, if any.
+    pub fn axis_break(&self) -> Option<Range> {
+        self.axis_break
+    }
+
     /// Helper function to make sure the range includes 0.
     pub fn include_zero(&mut self) {
         if self.data_range.extend_to(0.) {
@@ -115,13 +367,15 @@ impl Scale {
         }
     }
 
+    /// Check whether the tick labels need laying out again (e.g. because the env changed). Call
+    /// this from your widget's `update`.
     pub fn needs_rebuild_after_update(&mut self, ctx: &mut UpdateCtx) -> bool {
         match self.layouts.as_mut() {
             Some(layouts) => {
                 // we need to loop manually to avoid short-circuit we would get with
                 // `Iterator::any`.
                 let mut needs_rebuild = false;
-                for layout in layouts.iter_mut() {
+                for layout in layouts.iter_mut().chain(self.exponent_layout.as_mut()) {
                     needs_rebuild |= layout.layout.needs_rebuild_after_update(ctx);
                 }
                 needs_rebuild
@@ -131,46 +385,180 @@ impl Scale {
     }
 
     /// Rebuild the retained state, as needed.
-    pub fn rebuild_if_needed(&mut self, ctx: &mut PaintCtx, env: &Env) {
-        if self.scale_ticker.is_none() {
+    pub fn rebuild_if_needed(&mut self, text: &mut PietText, env: &Env) {
+        let tick_margin = SCALE_TICK_MARGIN * env.get(theme::SIZE_SCALE);
+        if self.ticks.is_none() {
             self.layouts = None;
-            self.scale_ticker = Some(Ticker::new(
-                self.data_range,
-                self.direction.max_labels(self.graph_bounds),
-            ));
+            self.custom_labels.clear();
+            let mut ticks = match (self.custom_ticks.as_ref(), self.categories.as_ref()) {
+                (Some(custom_ticks), _) => {
+                    let (data_range, axis_break) = (self.data_range, self.active_break());
+                    let mut custom_ticks: Vec<&CustomTick> = custom_ticks
+                        .iter()
+                        .filter(|tick| {
+                            tick.value >= data_range.min()
+                                && tick.value <= data_range.max()
+                                && !axis_break.map_or(false, |axis_break| {
+                                    tick.value > axis_break.min() && tick.value < axis_break.max()
+                                })
+                        })
+                        .collect();
+                    custom_ticks
+                        .sort_by(|a, b| a.value.partial_cmp(&b.value).unwrap_or(Ordering::Equal));
+                    self.custom_labels =
+                        custom_ticks.iter().map(|tick| tick.label.clone()).collect();
+                    custom_ticks
+                        .iter()
+                        .map(|tick| Tick::new(self.t(tick.value), tick.value))
+                        .collect()
+                }
+                (None, Some(categories)) => category_ticks(categories.len()),
+                (None, None) => {
+                    let max_labels = self.direction.max_labels(self.graph_bounds);
+                    match self.active_break() {
+                        Some(axis_break) => {
+                            // tick each side of the break separately, sharing out the labels by
+                            // how much of the axis each side gets.
+                            let below = Range::new(self.data_range.min(), axis_break.min());
+                            let above = Range::new(axis_break.max(), self.data_range.max());
+                            let below_labels = (max_labels as f64 * self.t(axis_break.min()))
+                                .round()
+                                .max(2.) as usize;
+                            let above_labels = max_labels.saturating_sub(below_labels).max(2);
+                            self.tick_locator
+                                .ticks(below, below_labels, self.scale_type)
+                                .into_iter()
+                                .chain(self.tick_locator.ticks(
+                                    above,
+                                    above_labels,
+                                    self.scale_type,
+                                ))
+                                .map(|tick| Tick::new(self.t(tick.value), tick.value))
+                                .collect()
+                        }
+                        None => {
+                            self.tick_locator
+                                .ticks(self.data_range, max_labels, self.scale_type)
+                        }
+                    }
+                }
+            };
+            if self.inverted {
+                // keep the ticks in order along the axis.
+                ticks.reverse();
+                self.custom_labels.reverse();
+                for tick in ticks.iter_mut() {
+                    tick.t = 1. - tick.t;
+                }
+            }
+            self.ticks = Some(ticks);
         }
         if self.layouts.is_none() {
+            let exponent = self.shared_exponent();
+            let divisor = 10f64.powi(exponent.unwrap_or(0));
             self.layouts = Some(
-                self.scale_ticker
+                self.ticks
+                    .as_ref()
                     .unwrap()
-                    .into_iter()
-                    .map(|tick| {
-                        let mut layout =
-                            TextLayout::from_text(format!("{}", tick.value.to_precision(5)));
-                        layout.rebuild_if_needed(ctx.text(), env);
-                        let size = layout.size();
-                        let mut layout = PositionedLayout {
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, tick)| {
+                        let label = match (self.custom_ticks.as_ref(), self.categories.as_ref()) {
+                            (Some(_), _) => self.custom_labels[idx].clone(),
+                            (None, Some(categories)) => categories[tick.value as usize].clone(),
+                            (None, None) => self.formatter.format(tick.value / divisor).into(),
+                        };
+                        let mut layout = TextLayout::from_text(label);
+                        layout.set_text_size(theme::TICK_LABEL_TEXT_SIZE);
+                        layout.rebuild_if_needed(text, env);
+                        PositionedLayout {
                             position: self.direction.label_position(
                                 self.graph_bounds,
                                 tick.t,
                                 layout.size(),
-                                SCALE_TICK_MARGIN,
+                                tick_margin,
                             ),
                             layout,
-                        };
-                        layout.rebuild_if_needed(ctx, env);
-                        layout
+                        }
                     })
                     .collect(),
             );
+            self.exponent_layout = exponent.map(|exponent| {
+                let label: ArcStr = format!("×10{}", format::superscript(exponent)).into();
+                let mut layout = TextLayout::from_text(label);
+                layout.set_text_size(theme::TICK_LABEL_TEXT_SIZE);
+                layout.rebuild_if_needed(text, env);
+                let size = layout.size();
+                let bounds = self.graph_bounds;
+                let position = match self.direction {
+                    Direction::X => {
+                        let label_height = self
+                            .layouts
+                            .as_ref()
+                            .unwrap()
+                            .iter()
+                            .map(|layout| layout.layout.size().height)
+                            .fold(0., f64::max);
+                        Point::new(
+                            bounds.x1 - size.width,
+                            bounds.y1 + tick_margin + label_height,
+                        )
+                    }
+                    Direction::Y => Point::new(
+                        bounds.x0 - size.width,
+                        bounds.y0 - size.height - tick_margin,
+                    ),
+                    Direction::YRight => {
+                        Point::new(bounds.x1, bounds.y0 - size.height - tick_margin)
+                    }
+                };
+                PositionedLayout { position, layout }
+            });
+            self.label_stride = if self.thin_labels {
+                let extents: Vec<(f64, f64)> = self
+                    .layouts
+                    .as_ref()
+                    .unwrap()
+                    .iter()
+                    .map(|layout| {
+                        self.direction
+                            .label_extent(layout.position, layout.layout.size())
+                    })
+                    .collect();
+                label_stride(&extents, LABEL_GAP * env.get(theme::SIZE_SCALE))
+            } else {
+                1
+            };
         }
         self.rebuild_max_layout();
     }
 
+    /// The rectangle the graph is drawn in.
     pub fn graph_bounds(&self) -> Rect {
         self.graph_bounds
     }
 
+    /// Which axis this is.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// The range of data values that the axis covers.
+    pub fn data_range(&self) -> Range {
+        self.data_range
+    }
+
+    /// Change the range the axis covers. The ticks are only recalculated if it is different.
+    pub fn set_data_range(&mut self, data_range: impl Into<Range>) {
+        let data_range = data_range.into();
+        if self.data_range != data_range {
+            self.invalidate();
+            self.data_range = data_range;
+        }
+    }
+
+    /// Set the rectangle the graph is drawn in. The ticks are only recalculated if it is
+    /// different.
     pub fn set_graph_bounds(&mut self, graph_bounds: Rect) {
         let graph_bounds = graph_bounds.abs();
         if self.graph_bounds != graph_bounds {
@@ -179,17 +567,56 @@ impl Scale {
         }
     }
 
+    /// Set the color of the axis line. The default is `theme::AXES_COLOR`.
     pub fn set_axis_color(&mut self, color: impl Into<KeyOrValue<Color>>) {
         self.axis_color = color.into();
     }
 
-    /// You must have build layouts before calling this
+    /// Set the color of the grid lines. The default is `theme::GRID_COLOR`.
+    pub fn set_grid_color(&mut self, color: impl Into<KeyOrValue<Color>>) {
+        self.grid_color = color.into();
+    }
+
+    /// Set how tick values are turned into labels.
+    pub fn set_formatter(&mut self, formatter: Formatter) {
+        self.formatter = formatter;
+        // the ticks themselves don't change.
+        self.layouts = None;
+        self.max_layout = None;
+    }
+
+    /// The size of the biggest tick label, for working out how much space the axis needs.
+    ///
+    /// # Panics
+    ///
+    /// If the labels haven't been laid out with `rebuild_if_needed`.
     pub fn max_layout(&self) -> Size {
         self.max_layout.unwrap()
     }
 
+    /// The power of 10 to take out of the tick values, if `shared_exponent` is on and they are
+    /// big or small enough to need it.
+    fn shared_exponent(&self) -> Option<i32> {
+        if !self.shared_exponent || self.custom_ticks.is_some() || self.categories.is_some() {
+            return None;
+        }
+        let largest = self
+            .ticks
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|tick| tick.value.abs())
+            .fold(0., f64::max);
+        let exponent = format::exponent(largest);
+        if exponent >= 4 || exponent <= -3 {
+            Some(exponent)
+        } else {
+            None
+        }
+    }
+
     fn invalidate(&mut self) {
-        self.scale_ticker = None;
+        self.ticks = None;
         self.layouts = None;
         self.max_layout = None;
     }
@@ -211,34 +638,492 @@ impl Scale {
                 max_height = height;
             }
         }
+        if let Some(layout) = self.exponent_layout.as_ref() {
+            // it goes under the labels of an x axis, so needs space of its own.
+            let Size { width, height } = layout.layout.size();
+            match self.direction {
+                Direction::X => max_height += height,
+                Direction::Y | Direction::YRight => max_width = f64::max(max_width, width),
+            }
+        }
         self.max_layout = Some(Size {
             width: max_width,
             height: max_height,
         });
     }
 
-    pub fn draw(&mut self, ctx: &mut PaintCtx, env: &Env, draw_axis: bool, draw_labels: bool) {
-        // draw axis
-        if draw_axis {
-            let axis_brush = ctx.solid_brush(self.axis_color.resolve(env));
-            ctx.stroke(self.direction.axis_line(self.graph_bounds), &axis_brush, 2.);
-        }
-        // draw tick labels
-        if draw_labels {
-            for layout in self.layouts.as_mut().unwrap().iter_mut() {
-                layout.draw(ctx);
+    /// Draw the axis line and the tick labels (either can be left out). Call `rebuild_if_needed`
+    /// first.
+    pub fn draw(&mut self, ctx: &mut impl ToCanvas, env: &Env, draw_axis: bool, draw_labels: bool) {
+        ctx.with_canvas(env, |ctx| {
+            // draw axis
+            if draw_axis {
+                let axis_brush = ctx.solid_brush(self.axis_color.resolve(env));
+                let width = 2. * env.get(theme::SIZE_SCALE);
+                let axis_line = ctx
+                    .snap()
+                    .line(self.direction.axis_line(self.graph_bounds), width);
+                ctx.stroke(axis_line, &axis_brush, width);
+                if let Some(axis_break) = self.active_break() {
+                    self.draw_break_mark(ctx, env, axis_break);
+                }
             }
+            // draw tick labels
+            if draw_labels {
+                let label_stride = self.label_stride;
+                for layout in self
+                    .layouts
+                    .as_mut()
+                    .unwrap()
+                    .iter_mut()
+                    .step_by(label_stride)
+                {
+                    layout.draw(ctx);
+                }
+                if let Some(layout) = self.exponent_layout.as_mut() {
+                    layout.draw(ctx);
+                }
+            }
+        });
+    }
+
+    /// Cut the axis line where it jumps over `axis_break`, and draw a zig-zag each side of the
+    /// cut.
+    fn draw_break_mark(&self, ctx: &mut Canvas, env: &Env, axis_break: Range) {
+        let size_scale = env.get(theme::SIZE_SCALE);
+        let reach = BREAK_MARK_SIZE * size_scale;
+        let half_gap = 0.5 * reach;
+        let p = self.pixel_location(axis_break.min());
+        let axis = self.direction.axis_line(self.graph_bounds);
+        // (along the axis, across the axis) to a point.
+        let to_point = |along: f64, across: f64| match self.direction {
+            Direction::X => Point::new(along, axis.p0.y + across),
+            Direction::Y | Direction::YRight => Point::new(axis.p0.x + across, along),
+        };
+        let gap = Rect::from_points(
+            to_point(p - half_gap, -reach),
+            to_point(p + half_gap, reach),
+        );
+        ctx.fill(gap, &env.get(WINDOW_BACKGROUND_COLOR));
+        let mut marks = BezPath::new();
+        for side in [p - half_gap, p + half_gap].iter().copied() {
+            marks.move_to(to_point(side - 0.5 * half_gap, reach));
+            marks.line_to(to_point(side + 0.5 * half_gap, -reach));
         }
+        ctx.stroke(marks, &self.axis_color.resolve(env), size_scale);
+    }
+
+    /// Draw a line across the graph area at each tick.
+    ///
+    /// Call this before drawing the data, so the grid ends up behind it.
+    pub fn draw_grid(&self, ctx: &mut impl ToCanvas, env: &Env) {
+        ctx.with_canvas(env, |ctx| {
+            let grid_brush = ctx.solid_brush(self.grid_color.resolve(env));
+            let snap = ctx.snap();
+            let width = env.get(theme::SIZE_SCALE);
+            for tick in self.ticks.as_ref().unwrap() {
+                ctx.stroke(
+                    snap.line(self.direction.grid_line(self.graph_bounds, tick.t), width),
+                    &grid_brush,
+                    width,
+                );
+            }
+        });
+    }
+
+    /// Shade every other band between the ticks (zebra striping), so values can be read across a
+    /// wide chart. The shading starts at the first tick.
+    ///
+    /// Call this before drawing the grid and the data, so the bands end up behind them.
+    pub fn draw_bands(&self, ctx: &mut impl ToCanvas, env: &Env) {
+        ctx.with_canvas(env, |ctx| {
+            let band_color = env.get(theme::ZEBRA_COLOR);
+            let ts: Vec<f64> = self.ticks.as_ref().unwrap().iter().map(|t| t.t).collect();
+            for (start, end) in zebra_bands(&ts) {
+                let start = self.direction.grid_line(self.graph_bounds, start);
+                let end = self.direction.grid_line(self.graph_bounds, end);
+                ctx.fill(Rect::from_points(start.p0, end.p1), &band_color);
+            }
+        });
     }
 
     /// Convert a data point to a pixel location on this axis
+    ///
+    /// On a log scale, values that aren't positive are infinitely far below the axis.
     pub fn pixel_location(&self, v: f64) -> f64 {
-        let (min, max) = self.data_range.into();
-        let t = (v - min) / (max - min);
-        self.direction.position(self.graph_bounds(), t)
+        self.direction
+            .position(self.graph_bounds(), self.orient(self.t(v)))
+    }
+
+    /// Convert a pixel location on this axis to a data value. The inverse of `pixel_location`.
+    pub fn data_location(&self, px: f64) -> f64 {
+        let t = self.direction.t_at(self.graph_bounds(), px);
+        self.value_at(self.orient(t))
+    }
+
+    /// The axis break, if it is inside the data range.
+    fn active_break(&self) -> Option<Range> {
+        let data_range = self.data_range;
+        self.axis_break.filter(|axis_break| {
+            axis_break.min() > data_range.min() && axis_break.max() < data_range.max()
+        })
+    }
+
+    /// How far along the axis `v` is, where 0 is the start and 1 the end, skipping the break.
+    fn t(&self, v: f64) -> f64 {
+        let t = self.scale_type.t(self.data_range, v);
+        match self.active_break() {
+            Some(axis_break) => {
+                let (start, end) = self.break_t(axis_break);
+                let t = if t <= start {
+                    t
+                } else if t >= end {
+                    t - (end - start)
+                } else {
+                    start
+                };
+                t / (1. - (end - start))
+            }
+            None => t,
+        }
+    }
+
+    /// The inverse of `t`.
+    fn value_at(&self, t: f64) -> f64 {
+        let t = match self.active_break() {
+            Some(axis_break) => {
+                let (start, end) = self.break_t(axis_break);
+                let t = t * (1. - (end - start));
+                if t <= start {
+                    t
+                } else {
+                    t + (end - start)
+                }
+            }
+            None => t,
+        };
+        self.scale_type.value_at(self.data_range, t)
+    }
+
+    /// Where the break would start and end along the axis, if the values in it weren't skipped.
+    fn break_t(&self, axis_break: Range) -> (f64, f64) {
+        (
+            self.scale_type.t(self.data_range, axis_break.min()),
+            self.scale_type.t(self.data_range, axis_break.max()),
+        )
+    }
+
+    /// Flip `t` if the axis is inverted. This is its own inverse.
+    fn orient(&self, t: f64) -> f64 {
+        if self.inverted {
+            1. - t
+        } else {
+            t
+        }
+    }
+}
+
+/// The axes of a polar chart such as a wind rose: sectors (e.g. compass directions) evenly spaced
+/// clockwise round a circle, and values going out from the center. This is the polar counterpart
+/// of [`Scale`].
+///
+/// Like a `Scale` it retains its label layouts, so call [`PolarScale::rebuild_if_needed`] from
+/// `layout`, after `set_graph_bounds`. The circle is as big as will fit in the graph bounds with
+/// the sector labels round the outside.
+#[derive(Debug, Clone)]
+pub struct PolarScale {
+    /// The range of values from the center to the edge of the circle.
+    data_range: Range,
+    /// The name of each sector, clockwise.
+    sectors: Vec<ArcStr>,
+    /// The angle of the middle of the first sector, in radians clockwise from 3 o'clock.
+    start_angle: f64,
+    /// Decides where the circles of the grid go.
+    tick_locator: Arc<dyn TickLocator>,
+    graph_bounds: Rect,
+    axis_color: KeyOrValue<Color>,
+    grid_color: KeyOrValue<Color>,
+    /// Turns tick values into labels.
+    formatter: Formatter,
+    // retained
+    center: Point,
+    radius: f64,
+    ticks: Option<Vec<Tick>>,
+    /// The value labels, going up from the center.
+    value_layouts: Option<Vec<PositionedLayout<ArcStr>>>,
+    /// The sector labels, round the outside of the circle.
+    sector_layouts: Option<Vec<PositionedLayout<ArcStr>>>,
+}
+
+impl PolarScale {
+    /// A polar scale with the given sectors, and `data_range` from the center to the edge.
+    ///
+    /// The first sector is centered at 12 o'clock, like north on a compass.
+    pub fn new(data_range: impl Into<Range>, sectors: impl IntoIterator<Item = ArcStr>) -> Self {
+        PolarScale {
+            data_range: data_range.into(),
+            sectors: sectors.into_iter().collect(),
+            start_angle: -0.5 * PI,
+            tick_locator: Arc::new(LinearTicker),
+            graph_bounds: Rect::ZERO,
+            axis_color: theme::AXES_COLOR.into(),
+            grid_color: theme::GRID_COLOR.into(),
+            formatter: Formatter::default(),
+            center: Point::ZERO,
+            radius: 0.,
+            ticks: None,
+            value_layouts: None,
+            sector_layouts: None,
+        }
+    }
+
+    /// Set where the middle of the first sector is, in radians clockwise from 3 o'clock. The
+    /// default is `-PI / 2.` (12 o'clock).
+    pub fn set_start_angle(&mut self, start_angle: f64) {
+        if self.start_angle != start_angle {
+            self.start_angle = start_angle;
+            self.invalidate();
+        }
+    }
+
+    /// Change how the circles of the grid are chosen. The default is `LinearTicker`.
+    pub fn set_tick_locator(&mut self, tick_locator: impl TickLocator + 'static) {
+        self.tick_locator = Arc::new(tick_locator);
+        self.invalidate();
+    }
+
+    /// The range of data values from the center to the edge.
+    pub fn data_range(&self) -> Range {
+        self.data_range
+    }
+
+    /// Change the range from the center to the edge. The ticks are only recalculated if it is
+    /// different.
+    pub fn set_data_range(&mut self, data_range: impl Into<Range>) {
+        let data_range = data_range.into();
+        if self.data_range != data_range {
+            self.invalidate();
+            self.data_range = data_range;
+        }
+    }
+
+    /// The names of the sectors, clockwise from the first.
+    pub fn sectors(&self) -> &[ArcStr] {
+        &self.sectors
+    }
+
+    /// Set the rectangle the circle and its labels are drawn in. The layout is only recalculated
+    /// if it is different.
+    pub fn set_graph_bounds(&mut self, graph_bounds: Rect) {
+        let graph_bounds = graph_bounds.abs();
+        if self.graph_bounds != graph_bounds {
+            self.invalidate();
+            self.graph_bounds = graph_bounds;
+        }
+    }
+
+    /// Set the color of the outer circle. The default is `theme::AXES_COLOR`.
+    pub fn set_axis_color(&mut self, color: impl Into<KeyOrValue<Color>>) {
+        self.axis_color = color.into();
+    }
+
+    /// Set the color of the grid. The default is `theme::GRID_COLOR`.
+    pub fn set_grid_color(&mut self, color: impl Into<KeyOrValue<Color>>) {
+        self.grid_color = color.into();
+    }
+
+    /// Set how tick values are turned into labels.
+    pub fn set_formatter(&mut self, formatter: Formatter) {
+        self.formatter = formatter;
+        // the ticks themselves don't change.
+        self.value_layouts = None;
+    }
+
+    /// Check whether the labels need laying out again (e.g. because the env changed). Call this
+    /// from your widget's `update`.
+    pub fn needs_rebuild_after_update(&mut self, ctx: &mut UpdateCtx) -> bool {
+        let mut needs_rebuild = false;
+        for layouts in [self.value_layouts.as_mut(), self.sector_layouts.as_mut()]
+            .iter_mut()
+            .flatten()
+        {
+            for layout in layouts.iter_mut() {
+                needs_rebuild |= layout.layout.needs_rebuild_after_update(ctx);
+            }
+        }
+        needs_rebuild
+    }
+
+    /// Rebuild the retained state, as needed.
+    pub fn rebuild_if_needed(&mut self, text: &mut PietText, env: &Env) {
+        let tick_margin = SCALE_TICK_MARGIN * env.get(theme::SIZE_SCALE);
+        if self.sector_layouts.is_none() {
+            let layouts: Vec<TextLayout<ArcStr>> = self
+                .sectors
+                .iter()
+                .cloned()
+                .map(|label| {
+                    let mut layout = TextLayout::from_text(label);
+                    layout.set_text_size(theme::TICK_LABEL_TEXT_SIZE);
+                    layout.rebuild_if_needed(text, env);
+                    layout
+                })
+                .collect();
+            // leave room for the biggest label all the way round.
+            let max_label = layouts.iter().fold(0., |max: f64, layout| {
+                let size = layout.size();
+                max.max(size.width).max(size.height)
+            });
+            self.center = self.graph_bounds.center();
+            self.radius = (0.5 * self.graph_bounds.width().min(self.graph_bounds.height())
+                - max_label
+                - tick_margin)
+                .max(0.);
+            self.sector_layouts = Some(
+                layouts
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, layout)| {
+                        let size = layout.size();
+                        let direction = Vec2::from_angle(self.sector_angle(idx));
+                        // the middle of the label, far enough out that it doesn't touch the
+                        // circle.
+                        let mid = self.center
+                            + direction * (self.radius + tick_margin)
+                            + Vec2::new(
+                                0.5 * size.width * direction.x,
+                                0.5 * size.height * direction.y,
+                            );
+                        PositionedLayout {
+                            position: mid - size.to_vec2() * 0.5,
+                            layout,
+                        }
+                    })
+                    .collect(),
+            );
+        }
+        if self.ticks.is_none() {
+            self.value_layouts = None;
+            let max_ticks = (self.radius / 40.).floor() as usize + 1;
+            self.ticks = Some(self.tick_locator.ticks(
+                self.data_range,
+                max_ticks,
+                ScaleType::Linear,
+            ));
+        }
+        if self.value_layouts.is_none() {
+            self.value_layouts = Some(
+                self.ticks
+                    .as_ref()
+                    .unwrap()
+                    .iter()
+                    // there's no room for a label at the center.
+                    .filter(|tick| tick.t > 0.)
+                    .map(|tick| {
+                        let mut layout =
+                            TextLayout::from_text(ArcStr::from(self.formatter.format(tick.value)));
+                        layout.set_text_size(theme::TICK_LABEL_TEXT_SIZE);
+                        layout.rebuild_if_needed(text, env);
+                        // just inside each circle, up from the center.
+                        let position = Point::new(
+                            self.center.x + tick_margin,
+                            self.center.y - tick.t * self.radius,
+                        );
+                        PositionedLayout { position, layout }
+                    })
+                    .collect(),
+            );
+        }
+    }
+
+    /// The middle of the circle.
+    pub fn center(&self) -> Point {
+        self.center
+    }
+
+    /// The radius of the circle, in pixels.
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// The angle of the middle of sector `idx`, in radians clockwise from 3 o'clock.
+    pub fn sector_angle(&self, idx: usize) -> f64 {
+        self.start_angle + idx as f64 * self.sector_sweep()
+    }
+
+    /// How much of the circle each sector covers, in radians.
+    pub fn sector_sweep(&self) -> f64 {
+        2. * PI / self.sectors.len().max(1) as f64
+    }
+
+    /// The `(start angle, sweep angle)` of sector `idx`, as used by `kurbo::CircleSegment`.
+    pub fn sector_angles(&self, idx: usize) -> (f64, f64) {
+        let sweep = self.sector_sweep();
+        (self.sector_angle(idx) - 0.5 * sweep, sweep)
+    }
+
+    /// The distance from the center of the value `v`, in pixels.
+    pub fn pixel_radius(&self, v: f64) -> f64 {
+        ScaleType::Linear.t(self.data_range, v) * self.radius
+    }
+
+    /// The point at `angle` (in radians clockwise from 3 o'clock) and value `v`.
+    pub fn pixel_location(&self, angle: f64, v: f64) -> Point {
+        self.center + Vec2::from_angle(angle) * self.pixel_radius(v)
+    }
+
+    fn invalidate(&mut self) {
+        self.ticks = None;
+        self.value_layouts = None;
+        self.sector_layouts = None;
+    }
+
+    /// Draw a circle at each tick, and a line out from the center between each pair of sectors.
+    ///
+    /// Call this before drawing the data, so the grid ends up behind it.
+    pub fn draw_grid(&self, ctx: &mut impl ToCanvas, env: &Env) {
+        ctx.with_canvas(env, |ctx| {
+            let grid_brush = ctx.solid_brush(self.grid_color.resolve(env));
+            let width = env.get(theme::SIZE_SCALE);
+            for tick in self.ticks.as_ref().unwrap() {
+                ctx.stroke(
+                    Circle::new(self.center, tick.t * self.radius),
+                    &grid_brush,
+                    width,
+                );
+            }
+            for idx in 0..self.sectors.len() {
+                let (start_angle, _) = self.sector_angles(idx);
+                let end = self.center + Vec2::from_angle(start_angle) * self.radius;
+                ctx.stroke(Line::new(self.center, end), &grid_brush, width);
+            }
+        });
+    }
+
+    /// Draw the outer circle and the labels (either can be left out). Call `rebuild_if_needed`
+    /// first.
+    pub fn draw(&mut self, ctx: &mut impl ToCanvas, env: &Env, draw_axis: bool, draw_labels: bool) {
+        ctx.with_canvas(env, |ctx| {
+            if draw_axis {
+                let axis_brush = ctx.solid_brush(self.axis_color.resolve(env));
+                let width = 2. * env.get(theme::SIZE_SCALE);
+                ctx.stroke(Circle::new(self.center, self.radius), &axis_brush, width);
+            }
+            if draw_labels {
+                for layouts in [self.value_layouts.as_mut(), self.sector_layouts.as_mut()]
+                    .iter_mut()
+                    .flatten()
+                {
+                    for layout in layouts.iter_mut() {
+                        layout.draw(ctx);
+                    }
+                }
+            }
+        });
     }
 }
 
+/// A text layout along with where it should be drawn.
 #[derive(Debug, Clone)]
 pub struct PositionedLayout<T> {
     /// The position that the layout should be displayed.
@@ -247,56 +1132,108 @@ pub struct PositionedLayout<T> {
 }
 
 impl<T: TextStorage> PositionedLayout<T> {
-    pub fn rebuild_if_needed(&mut self, ctx: &mut PaintCtx, env: &Env) {
-        self.layout.rebuild_if_needed(ctx.text(), env);
+    pub fn rebuild_if_needed(&mut self, text: &mut PietText, env: &Env) {
+        self.layout.rebuild_if_needed(text, env);
     }
-    pub fn draw(&mut self, ctx: &mut PaintCtx) {
-        self.layout.draw(ctx, self.position)
+
+    pub fn draw(&mut self, ctx: &mut Piet) {
+        if let Some(layout) = self.layout.layout() {
+            ctx.draw_text(layout, self.position);
+        }
     }
 }
 
-/// Able to return a sequence of locations along an axis where ticks should be displayed, and the
-/// values that should be displayed there.
-#[derive(Debug, Copy, Clone)]
-pub struct Ticker {
-    data_range: Range,
-    target_num_points: usize,
-    // calculated
-    spacing: f64,
+/// Decides where the ticks on an axis go, and the values that should be displayed there.
+///
+/// Give your own locator to a [`Scale`] with [`Scale::set_tick_locator`] if the default ticks
+/// aren't what you want.
+pub trait TickLocator: fmt::Debug {
+    /// The ticks to show for `data_range`, in order along the axis.
+    ///
+    /// `max_ticks` is how many labels there is room for, and `scale_type` is needed to work out
+    /// where along the axis (`Tick::t`) each value goes.
+    fn ticks(&self, data_range: Range, max_ticks: usize, scale_type: ScaleType) -> Vec<Tick>;
 }
 
-impl Ticker {
-    pub fn new(data_range: Range, target_num_points: usize) -> Self {
-        let spacing = calc_tick_spacing(data_range, target_num_points);
-        Self {
+/// The default tick locator.
+///
+/// On a linear scale the ticks are 1, 2 or 5 ×10<sup>n</sup> apart, and on a log scale they are at
+/// powers of 10.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct LinearTicker;
+
+impl LinearTicker {
+    /// Ticks covering `data_range`, with no more than `target_num_points` of them.
+    pub fn iter(data_range: Range, target_num_points: usize, scale_type: ScaleType) -> TickerIter {
+        let spacing = match scale_type {
+            ScaleType::Linear => calc_tick_spacing(data_range, target_num_points),
+            ScaleType::Log => calc_log_tick_spacing(data_range, target_num_points),
+        };
+        TickerIter {
             data_range,
             target_num_points,
+            scale_type,
             spacing,
+            next_tick: 0,
         }
     }
+}
 
-    fn first_tick(&self) -> f64 {
-        match self.target_num_points {
-            0 | 1 | 2 => self.data_range.min(),
-            n => calc_next_tick(self.data_range.min(), self.spacing),
-        }
+impl TickLocator for LinearTicker {
+    fn ticks(&self, data_range: Range, max_ticks: usize, scale_type: ScaleType) -> Vec<Tick> {
+        Self::iter(data_range, max_ticks, scale_type).collect()
     }
 }
 
-// Ticker is `Copy`able, so pass by value to iter.
-impl IntoIterator for Ticker {
-    type IntoIter = TickerIter;
-    type Item = Tick;
+/// Ticks at fixed data values, e.g. at the start of each shift on a time axis.
+///
+/// Values outside the data range are left out. All the others are shown, however many labels
+/// there is room for.
+#[derive(Debug, Clone)]
+pub struct FixedTicker {
+    values: Vec<f64>,
+}
 
-    fn into_iter(self) -> Self::IntoIter {
-        TickerIter {
-            inner: self,
-            next_tick: 0,
+impl FixedTicker {
+    /// Ticks at each of `values`.
+    pub fn new(values: impl IntoIterator<Item = f64>) -> Self {
+        let mut values: Vec<f64> = values.into_iter().collect();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        FixedTicker { values }
+    }
+}
+
+impl TickLocator for FixedTicker {
+    fn ticks(&self, data_range: Range, _max_ticks: usize, scale_type: ScaleType) -> Vec<Tick> {
+        self.values
+            .iter()
+            .copied()
+            .filter(|&value| value >= data_range.min() && value <= data_range.max())
+            .map(|value| Tick::new(scale_type.t(data_range, value), value))
+            .collect()
+    }
+}
+
+/// A tick chosen by the user, at a data value with its own label. See
+/// [`Scale::set_custom_ticks`].
+#[derive(Debug, Clone, Data, PartialEq)]
+pub struct CustomTick {
+    /// Where the tick goes, in data coordinates.
+    pub value: f64,
+    /// The text shown at the tick.
+    pub label: ArcStr,
+}
+
+impl CustomTick {
+    pub fn new(value: f64, label: impl Into<ArcStr>) -> Self {
+        CustomTick {
+            value,
+            label: label.into(),
         }
     }
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 /// The position at which a tick should be drawn.
 pub struct Tick {
     /// The distance along the axis that the value should be displayed at.
@@ -311,8 +1248,14 @@ impl Tick {
     }
 }
 
+/// The ticks of a `LinearTicker`, in order along the axis.
+#[derive(Debug)]
 pub struct TickerIter {
-    inner: Ticker,
+    data_range: Range,
+    target_num_points: usize,
+    scale_type: ScaleType,
+    /// For a log scale, this is the number of powers of 10 between ticks.
+    spacing: f64,
     next_tick: usize,
 }
 
@@ -320,29 +1263,36 @@ impl Iterator for TickerIter {
     type Item = Tick;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.inner.target_num_points {
+        if self.scale_type == ScaleType::Log {
+            return self.next_log();
+        }
+        match self.target_num_points {
             0 => None,
             1 => match self.next_tick {
                 0 => {
                     self.next_tick += 1;
-                    Some(Tick::new(0., self.inner.data_range.min()))
+                    Some(Tick::new(0., self.data_range.min()))
                 }
                 _ => None,
             },
             2 => match self.next_tick {
                 0 => {
                     self.next_tick += 1;
-                    Some(Tick::new(0., self.inner.data_range.min()))
+                    Some(Tick::new(0., self.data_range.min()))
                 }
                 1 => {
                     self.next_tick += 1;
-                    Some(Tick::new(1., self.inner.data_range.max()))
+                    Some(Tick::new(1., self.data_range.max()))
                 }
                 _ => None,
             },
             n => {
-                let value = self.inner.first_tick() + (self.next_tick as f64) * self.inner.spacing;
-                let (min, max) = self.inner.data_range.into();
+                let mut value = self.first_tick() + (self.next_tick as f64) * self.spacing;
+                // rounding can leave the tick at 0 just off it, which would be labelled e.g. -0.
+                if value.abs() < self.spacing * 1e-9 {
+                    value = 0.;
+                }
+                let (min, max) = self.data_range.into();
                 let t = (value - min) / (max - min);
                 if t <= 1. {
                     self.next_tick += 1;
@@ -355,6 +1305,65 @@ impl Iterator for TickerIter {
     }
 }
 
+impl TickerIter {
+    fn first_tick(&self) -> f64 {
+        match self.target_num_points {
+            0 | 1 | 2 => self.data_range.min(),
+            n => calc_next_tick(self.data_range.min(), self.spacing),
+        }
+    }
+
+    /// The next power of 10 (skipping some if there are too many).
+    fn next_log(&mut self) -> Option<Tick> {
+        let range = self.data_range;
+        if !self.spacing.is_finite() {
+            return None;
+        }
+        let exponent = range.min().log10().ceil() + (self.next_tick as f64) * self.spacing;
+        if exponent > range.max().log10().floor() {
+            return None;
+        }
+        self.next_tick += 1;
+        let value = 10f64.powf(exponent);
+        Some(Tick::new(ScaleType::Log.t(range, value), value))
+    }
+}
+
+/// The smallest `n` such that drawing every `n`th label leaves at least `gap` between them, given
+/// the `(start, end)` of each label along the axis.
+fn label_stride(extents: &[(f64, f64)], gap: f64) -> usize {
+    let overlap = |a: (f64, f64), b: (f64, f64)| a.0 < b.1 + gap && b.0 < a.1 + gap;
+    (1..extents.len().max(1))
+        .find(|&stride| {
+            extents
+                .iter()
+                .step_by(stride)
+                .zip(extents.iter().step_by(stride).skip(1))
+                .all(|(&a, &b)| !overlap(a, b))
+        })
+        .unwrap_or_else(|| extents.len().max(1))
+}
+
+/// A tick in the middle of each of `count` categories.
+fn category_ticks(count: usize) -> Vec<Tick> {
+    (0..count)
+        .map(|idx| {
+            let value = idx as f64 + 0.5;
+            Tick::new(value / count as f64, value)
+        })
+        .collect()
+}
+
+/// How many powers of 10 apart the ticks on a log scale should be, so there are at most
+/// `target_count` of them. Returns NaN if the range isn't positive.
+fn calc_log_tick_spacing(range: Range, target_count: usize) -> f64 {
+    if !(range.min() > 0.) || target_count == 0 {
+        return f64::NAN;
+    }
+    let powers = range.max().log10().floor() - range.min().log10().ceil() + 1.;
+    (powers / target_count as f64).ceil().max(1.)
+}
+
 /// Returns gap between each scale tick, in terms of the y variable, that gives closest to the
 /// requested `target_count` and is either 1, 2 or 5 ×10<sup>n</sup> for some n (hardcoded for now).
 ///
@@ -416,6 +1425,17 @@ fn pow_10_just_too_many(range: Range, num_ticks: usize) -> f64 {
     }
 }
 
+/// The bands to shade between ticks at `ts`: from every other tick to the next one, or to the end
+/// of the axis.
+fn zebra_bands(ts: &[f64]) -> Vec<(f64, f64)> {
+    ts.iter()
+        .enumerate()
+        .step_by(2)
+        .map(|(idx, start)| (*start, ts.get(idx + 1).copied().unwrap_or(1.)))
+        .filter(|(start, end)| start < end)
+        .collect()
+}
+
 /// Get the location of the first tick of the given spacing after the value.
 #[inline]
 pub fn calc_next_tick(v: f64, spacing: f64) -> f64 {
@@ -448,27 +1468,6 @@ fn count_ticks(range: Range, tick_step: f64) -> usize {
     ((end - start) / tick_step).floor() as usize + 1 // fence/fencepost
 }
 
-/// Returns (min, max) of the vector.
-///
-/// NaNs are propogated.
-#[inline]
-pub fn data_as_range(mut data: impl Iterator<Item = f64>) -> Range {
-    let mut min = f64::INFINITY;
-    let mut max = f64::NEG_INFINITY;
-    for v in data {
-        if v.is_nan() {
-            return (f64::NAN, f64::NAN).into();
-        }
-        if v < min {
-            min = v;
-        }
-        if v > max {
-            max = v;
-        }
-    }
-    (min, max).into()
-}
-
 #[inline]
 fn count_ticks_slow(range: Range, tick_step: f64) -> usize {
     let mut start = calc_next_tick(range.min(), tick_step);
@@ -533,3 +1532,104 @@ fn test_count_ticks() {
         assert_eq!(count_ticks(r, step), count_ticks_slow(r, step));
     }
 }
+
+#[test]
+fn test_signed_ticks() {
+    let values: Vec<f64> = LinearTicker::iter(Range::new(-0.3, 0.5), 10, ScaleType::Linear)
+        .map(|tick| tick.value)
+        .collect();
+    // the tick at 0 is exactly 0, so it is labelled "0".
+    let zero = values.iter().find(|value| value.abs() < 0.05).unwrap();
+    assert_eq!(*zero, 0.);
+    assert!(zero.is_sign_positive());
+    assert!(values[0] < 0.);
+}
+
+#[test]
+fn test_zebra_bands() {
+    assert_eq!(
+        zebra_bands(&[0.1, 0.3, 0.5, 0.7, 0.9]),
+        vec![(0.1, 0.3), (0.5, 0.7), (0.9, 1.)]
+    );
+    // a tick at the very end doesn't start a band.
+    assert_eq!(zebra_bands(&[0., 0.5, 1.]), vec![(0., 0.5)]);
+}
+
+#[test]
+fn test_log_ticks() {
+    let values: Vec<f64> = LinearTicker::iter(Range::new(0.5, 2000.), 10, ScaleType::Log)
+        .map(|tick| tick.value)
+        .collect();
+    assert_eq!(values, vec![1., 10., 100., 1000.]);
+    // too many powers of 10, so skip every other one.
+    let ticks = LinearTicker.ticks(Range::new(1., 1e6), 4, ScaleType::Log);
+    assert_eq!(ticks.len(), 4);
+    assert_eq!(ticks[1].value, 100.);
+    assert!((ticks[3].t - 1.).abs() < 1e-9);
+    // a log scale can't show zero.
+    assert_eq!(
+        LinearTicker::iter(Range::new(0., 10.), 4, ScaleType::Log).count(),
+        0
+    );
+}
+
+#[test]
+fn test_fixed_ticks() {
+    let ticker = FixedTicker::new(vec![22., 6., 14., 30.]);
+    let ticks = ticker.ticks(Range::new(0., 24.), 2, ScaleType::Linear);
+    let values: Vec<f64> = ticks.iter().map(|tick| tick.value).collect();
+    assert_eq!(values, vec![6., 14., 22.]);
+    assert_eq!(ticks[0].t, 0.25);
+}
+
+#[test]
+fn test_category_ticks() {
+    let ticks = category_ticks(4);
+    assert_eq!(ticks.len(), 4);
+    assert_eq!(ticks[0], Tick::new(0.125, 0.5));
+    assert_eq!(ticks[3], Tick::new(0.875, 3.5));
+    assert!(category_ticks(0).is_empty());
+}
+
+#[test]
+fn test_polar_sector_angles() {
+    let compass = ["N", "E", "S", "W"]
+        .iter()
+        .map(|&label| ArcStr::from(label));
+    let scale = PolarScale::new((0., 10.), compass);
+    assert_eq!(scale.sector_sweep(), 0.5 * PI);
+    // north is centered at 12 o'clock, east at 3 o'clock.
+    assert_eq!(scale.sector_angles(0), (-0.75 * PI, 0.5 * PI));
+    assert_eq!(scale.sector_angle(1), 0.);
+}
+
+#[test]
+fn test_label_stride() {
+    // labels 30 wide, 40 apart.
+    let extents: Vec<(f64, f64)> = (0..5)
+        .map(|idx| (idx as f64 * 40., idx as f64 * 40. + 30.))
+        .collect();
+    assert_eq!(label_stride(&extents, 4.), 1);
+    assert_eq!(label_stride(&extents, 20.), 2);
+    // y axis labels go up the screen.
+    let reversed: Vec<(f64, f64)> = extents.iter().rev().copied().collect();
+    assert_eq!(label_stride(&reversed, 20.), 2);
+    assert_eq!(label_stride(&[], 4.), 1);
+}
+
+#[test]
+fn test_axis_break() {
+    let mut scale = Scale::new_y(Range::new(0., 100.));
+    scale.set_graph_bounds(Rect::new(0., 0., 100., 100.));
+    scale.set_axis_break(Some(Range::new(10., 90.)));
+    // the 20 values either side of the break get half the axis each.
+    assert!((scale.pixel_location(0.) - 100.).abs() < 1e-9);
+    assert!((scale.pixel_location(5.) - 75.).abs() < 1e-9);
+    assert!((scale.pixel_location(50.) - 50.).abs() < 1e-9);
+    assert!((scale.pixel_location(95.) - 25.).abs() < 1e-9);
+    assert!((scale.data_location(75.) - 5.).abs() < 1e-9);
+    assert!((scale.data_location(25.) - 95.).abs() < 1e-9);
+    // a break outside the data does nothing.
+    scale.set_axis_break(Some(Range::new(150., 200.)));
+    assert!((scale.pixel_location(5.) - 95.).abs() < 1e-9);
+}
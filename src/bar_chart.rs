@@ -0,0 +1,845 @@
+use druid::{
+    im::Vector,
+    kurbo::{BezPath, Line, Point, Rect},
+    piet::PietText,
+    ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, Insets, KeyOrValue, LayoutCtx, Lens,
+    LifeCycle, LifeCycleCtx, MouseButton, PaintCtx, RenderContext, Size, TextLayout, UpdateCtx,
+    Widget,
+};
+use druid_lens_compose::ComposeLens;
+use std::time::Duration;
+
+use crate::{
+    animation::{self, Entrance},
+    axes::Scale,
+    canvas::Canvas,
+    chart_size,
+    commands::{
+        PointSelected, CHART_POINT_SELECTED, COPY_CHART_DATA, COPY_CHART_IMAGE, EXPORT_IMAGE,
+        TOGGLE_CHART_GRID,
+    },
+    context_menu::{copy_csv, copy_image, csv_cell, ChartMenu, MenuItems},
+    describe::{count, describe, min_max},
+    empty::EmptyState,
+    error_bars::{add_error_bar, add_horizontal_error_bar, ErrorBar, CAP_WIDTH},
+    fill::{BarStyle, Fill, SeriesFills},
+    fit_aspect_ratio,
+    focus::KeyboardFocus,
+    format::Formatter,
+    legend::{Legend, LegendPlacement},
+    new_color,
+    offscreen::{export_image, OffscreenChart},
+    theme,
+    title::{ChartTitle, TitleAlignment},
+    Orientation, SeriesData, DEFAULT_CHART_SIZE,
+};
+
+/// A bar chart with one or more series of values for each category.
+#[derive(Debug, Clone, Data, ComposeLens)]
+pub struct BarChartData<S = Vector<f64>> {
+    pub title: ArcStr,
+    pub x_axis_label: ArcStr,
+    /// The name of each category.
+    pub categories: Vector<ArcStr>,
+    /// Each series has a value for each category.
+    pub series: Vector<BarSeries<S>>,
+    pub draw_y_grid: bool,
+}
+
+/// One value per category, drawn in the same color. The values can be any [`SeriesData`], e.g.
+/// a `Vector<i32>` or an `Arc<Vec<f64>>`.
+#[derive(Debug, Clone, Data, Lens)]
+pub struct BarSeries<S = Vector<f64>> {
+    /// The name of the series, used in the legend.
+    pub label: ArcStr,
+    pub values: S,
+    /// The uncertainty of each value, if known.
+    pub errors: Option<Vector<ErrorBar>>,
+}
+
+impl<S: SeriesData> BarSeries<S> {
+    pub fn new(label: impl Into<ArcStr>, values: S) -> Self {
+        BarSeries {
+            label: label.into(),
+            values,
+            errors: None,
+        }
+    }
+
+    /// Draw error bars showing the uncertainty of each value.
+    pub fn with_errors(mut self, errors: Vector<ErrorBar>) -> Self {
+        self.errors = Some(errors);
+        self
+    }
+
+    /// The ends of the error bar for the value at `idx`, or the value if there isn't one.
+    fn error_bounds(&self, idx: usize, value: f64) -> (f64, f64) {
+        match self.errors.as_ref().and_then(|errors| errors.get(idx)) {
+            Some(error) => error.bounds(value),
+            None => (value, value),
+        }
+    }
+}
+
+/// How the bars for different series in the same category are arranged.
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub enum BarMode {
+    /// Side by side.
+    Grouped,
+    /// On top of each other. Negative values stack down from 0, under the positive ones.
+    Stacked,
+}
+
+impl<S: SeriesData> BarChartData<S> {
+    /// The number of categories we can draw.
+    fn category_count(&self) -> usize {
+        self.series
+            .iter()
+            .map(|series| series.values.len())
+            .max()
+            .unwrap_or(0)
+            .min(self.categories.len())
+    }
+
+    /// The values of the lowest and highest bars (or error bars), including the baseline the
+    /// bars grow from.
+    fn value_range(&self, mode: BarMode, baseline: f64) -> (f64, f64) {
+        match mode {
+            BarMode::Grouped => self
+                .series
+                .iter()
+                .flat_map(|series| {
+                    series
+                        .values
+                        .values()
+                        .enumerate()
+                        .map(move |(idx, value)| series.error_bounds(idx, value))
+                })
+                .fold((baseline, baseline), |(min, max), (low, high)| {
+                    (min.min(low), max.max(high))
+                }),
+            BarMode::Stacked => {
+                let (mut min, mut max) = (0., 0.);
+                for idx in 0..self.category_count() {
+                    let mut stack = Stack::default();
+                    for series in self.series.iter() {
+                        if let Some(value) = series.values.get(idx) {
+                            let (_, end) = stack.push(value);
+                            let (low, high) = series.error_bounds(idx, end);
+                            min = low.min(min);
+                            max = high.max(max);
+                        }
+                    }
+                }
+                (min, max)
+            }
+        }
+    }
+
+    /// The data as a table, with a row for each category and a column for each series.
+    fn csv_table(&self) -> Vec<Vec<String>> {
+        let mut header = vec!["category".to_string()];
+        header.extend(self.series.iter().map(|series| series.label.to_string()));
+        let mut table = vec![header];
+        for (idx, category) in self.categories.iter().enumerate() {
+            let mut row = vec![category.to_string()];
+            row.extend(
+                self.series
+                    .iter()
+                    .map(|series| csv_cell(series.values.get(idx))),
+            );
+            table.push(row);
+        }
+        table
+    }
+}
+
+/// The ends of a stack of bars, where positive values stack up from 0 and negative values stack
+/// down from it.
+#[derive(Debug, Copy, Clone, Default)]
+struct Stack {
+    top: f64,
+    bottom: f64,
+}
+
+impl Stack {
+    /// Add a bar for `value` to the stack, and return where it starts and ends.
+    fn push(&mut self, value: f64) -> (f64, f64) {
+        let end = if value < 0. {
+            &mut self.bottom
+        } else {
+            &mut self.top
+        };
+        let start = *end;
+        *end += value;
+        (start, *end)
+    }
+}
+
+pub struct BarChart {
+    mode: BarMode,
+    orientation: Orientation,
+    /// The value the bars grow from, in grouped mode.
+    baseline: f64,
+    below_baseline_color: Option<KeyOrValue<Color>>,
+    fills: SeriesFills,
+    style: BarStyle,
+    bar_spacing: KeyOrValue<f64>,
+    axis_color: KeyOrValue<Color>,
+    graph_insets: KeyOrValue<Insets>,
+    preferred_size: Size,
+    aspect_ratio: Option<f64>,
+    y_formatter: Formatter,
+    zebra_bands: bool,
+    // retained state
+    title: ChartTitle,
+    x_label_layout: TextLayout<ArcStr>,
+    /// The categories along the bottom, or down the side when the bars are horizontal.
+    category_scale: Option<Scale>,
+    value_scale: Option<Scale>,
+    legend: Legend,
+    empty_state: EmptyState,
+    /// The area the bars are drawn in.
+    graph_bounds: Rect,
+    /// Where each bar was last drawn, for hit testing.
+    bar_rects: Vec<(PointSelected, Rect)>,
+    entrance: Option<Entrance>,
+    keyboard: Option<KeyboardFocus>,
+    context_menu: Option<ChartMenu>,
+}
+
+impl BarChart {
+    pub fn new(mode: BarMode) -> Self {
+        BarChart {
+            mode,
+            orientation: Orientation::Vertical,
+            baseline: 0.,
+            below_baseline_color: None,
+            fills: SeriesFills::default(),
+            style: BarStyle::default(),
+            bar_spacing: theme::BAR_SPACING.into(),
+            axis_color: theme::AXES_COLOR.into(),
+            graph_insets: theme::GRAPH_INSETS.into(),
+            preferred_size: DEFAULT_CHART_SIZE,
+            aspect_ratio: None,
+            y_formatter: Formatter::default(),
+            zebra_bands: false,
+            title: ChartTitle::new(),
+            x_label_layout: TextLayout::new(),
+            category_scale: None,
+            value_scale: None,
+            legend: Legend::new(LegendPlacement::Right),
+            empty_state: EmptyState::new(),
+            graph_bounds: Rect::ZERO,
+            bar_rects: Vec::new(),
+            entrance: None,
+            keyboard: None,
+            context_menu: None,
+        }
+    }
+
+    /// Draw the bars horizontally, with the categories down the side and the value axis along the
+    /// bottom. This leaves room for long category names.
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Make the bars grow up or down from `baseline` rather than from 0, e.g. to show each value as
+    /// a deviation from a target. The value axis always shows the baseline.
+    ///
+    /// Stacked bars still stack from 0.
+    pub fn with_baseline(mut self, baseline: f64) -> Self {
+        self.baseline = baseline;
+        self
+    }
+
+    /// Draw the bars below the baseline in `color`, instead of their series color. Use
+    /// `theme::BELOW_BASELINE_COLOR` for the theme's color.
+    pub fn with_below_baseline_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.below_baseline_color = Some(color.into());
+        self
+    }
+
+    /// Fill the bars with `fill` instead of a flat color, e.g. a gradient or hatch pattern.
+    pub fn with_fill(mut self, fill: Fill) -> Self {
+        self.fills.set_all(fill);
+        self
+    }
+
+    /// Fill the bars of one series with `fill`, where `0` is the first series. This overrides
+    /// `with_fill`.
+    pub fn with_series_fill(mut self, series: usize, fill: Fill) -> Self {
+        self.fills.set(series, fill);
+        self
+    }
+
+    /// Round the corners of the bars. `radius` is at the normal size (see `theme::SIZE_SCALE`).
+    pub fn with_corner_radius(mut self, radius: f64) -> Self {
+        self.style.corner_radius = radius;
+        self
+    }
+
+    /// Draw a line `width` wide around each bar.
+    pub fn with_bar_border(mut self, color: impl Into<KeyOrValue<Color>>, width: f64) -> Self {
+        self.style.border = Some((color.into(), width));
+        self
+    }
+
+    /// Don't let the bars get wider than `width`, when there are only a few categories. The bars
+    /// of each category stay together in the middle of its space.
+    pub fn with_max_bar_width(mut self, width: f64) -> Self {
+        self.style.max_width = Some(width);
+        self
+    }
+
+    /// Shade every other band between the value axis grid lines, to help read values across a wide
+    /// chart. The color is `theme::ZEBRA_COLOR`.
+    pub fn with_zebra_bands(mut self, zebra_bands: bool) -> Self {
+        self.zebra_bands = zebra_bands;
+        self
+    }
+
+    /// Set how the value axis tick labels are formatted.
+    pub fn with_y_formatter(mut self, formatter: Formatter) -> Self {
+        self.y_formatter = formatter;
+        self
+    }
+
+    /// Set the space around the plot area. The default is `theme::GRAPH_INSETS`.
+    pub fn with_graph_insets(mut self, insets: impl Into<KeyOrValue<Insets>>) -> Self {
+        self.graph_insets = insets.into();
+        self
+    }
+
+    /// Set the size to take when the space isn't bounded, e.g. inside a `Scroll`. Otherwise the
+    /// chart fills the space it is given. The default is 400×300.
+    pub fn with_preferred_size(mut self, size: impl Into<Size>) -> Self {
+        self.preferred_size = size.into();
+        self
+    }
+
+    /// Keep the plot area at the given width:height ratio, centered in the space left over from
+    /// the title, legend and axes. By default the plot area fills that space.
+    pub fn with_aspect_ratio(mut self, ratio: f64) -> Self {
+        assert!(ratio > 0.0, "aspect ratio must be positive");
+        self.aspect_ratio = Some(ratio);
+        self
+    }
+
+    /// Where to put the title (and subtitle). Defaults to the center.
+    pub fn with_title_alignment(mut self, alignment: TitleAlignment) -> Self {
+        self.title.set_alignment(alignment);
+        self
+    }
+
+    /// Draw a line of smaller text under the title.
+    pub fn with_subtitle(mut self, subtitle: impl Into<ArcStr>) -> Self {
+        self.title.set_subtitle(subtitle.into());
+        self
+    }
+
+    /// Make the bars grow out from the baseline when the chart is first shown.
+    pub fn with_entrance_animation(mut self, duration: Duration) -> Self {
+        self.entrance = Some(Entrance::new(duration));
+        self
+    }
+
+    /// Let the user move between the bars with the arrow keys (up and down change series), and
+    /// select one with Enter. The chart takes part in tab focus.
+    pub fn with_keyboard_navigation(mut self, enabled: bool) -> Self {
+        self.keyboard = if enabled {
+            Some(KeyboardFocus::new())
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Show a menu when the chart is right-clicked, to toggle the grid, copy the chart as an
+    /// image, or copy or save the data as CSV. `T` is the type of your app's data, which druid
+    /// needs to show a menu.
+    pub fn with_context_menu<T: Data>(mut self) -> Self {
+        self.context_menu = Some(ChartMenu::new::<T>());
+        self
+    }
+
+    /// Where to draw the legend. Defaults to the right of the chart.
+    pub fn with_legend_placement(mut self, placement: LegendPlacement) -> Self {
+        self.legend.set_placement(placement);
+        self
+    }
+
+    /// A short description of the chart for screen readers, e.g. "Bar chart, Age by gender, 5
+    /// categories, 2 series, min 3, max 20".
+    pub fn describe<S: SeriesData>(&self, data: &BarChartData<S>) -> String {
+        let mut facts = vec![
+            count(data.category_count(), "category", "categories"),
+            count(data.series.len(), "series", "series"),
+        ];
+        facts.extend(min_max(
+            data.series.iter().flat_map(|series| series.values.values()),
+            &self.y_formatter,
+        ));
+        describe("Bar chart", &data.title, facts)
+    }
+
+    /// The value the bars grow from.
+    fn base_value(&self) -> f64 {
+        match self.mode {
+            BarMode::Grouped => self.baseline,
+            BarMode::Stacked => 0.,
+        }
+    }
+
+    /// The line across the plot at the base of the bars, if it isn't at the edge of the plot
+    /// already (where the axis is).
+    fn baseline_line(&self) -> Option<Line> {
+        let value_scale = self.value_scale.as_ref()?;
+        let base_value = self.base_value();
+        if value_scale.data_range().min() >= base_value {
+            return None;
+        }
+        let pos = value_scale.pixel_location(base_value);
+        let bounds = self.graph_bounds;
+        Some(match self.orientation {
+            Orientation::Vertical => Line::new((bounds.x0, pos), (bounds.x1, pos)),
+            Orientation::Horizontal => Line::new((pos, bounds.y0), (pos, bounds.y1)),
+        })
+    }
+
+    /// Take the title, axis label and legend labels from `data`, when the chart is added.
+    fn set_data<S: SeriesData>(&mut self, data: &BarChartData<S>) {
+        self.title.set_text(data.title.clone());
+        self.x_label_layout.set_text(data.x_axis_label.clone());
+        self.legend
+            .set_labels(data.series.iter().map(|series| series.label.clone()));
+    }
+
+    /// Lay out the chart, or the empty state if there are no categories.
+    fn lay_out<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &BarChartData<S>,
+        env: &Env,
+    ) {
+        if data.category_count() == 0 {
+            self.empty_state.rebuild_if_needed(text, env);
+        } else {
+            self.rebuild_if_needed(text, size, data, env);
+        }
+    }
+
+    /// Rebuild the layouts and scales for a chart of size `size`, as needed.
+    fn rebuild_if_needed<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &BarChartData<S>,
+        env: &Env,
+    ) {
+        self.title.rebuild_if_needed(text, env);
+        self.x_label_layout.rebuild_if_needed(text, env);
+        self.legend.rebuild_if_needed(text, env);
+        if self.category_scale.is_none() {
+            let categories = data.categories.iter().take(data.category_count()).cloned();
+            let mut category_scale =
+                Scale::new_categorical(categories, self.orientation.category_direction());
+            category_scale.set_axis_color(self.axis_color.clone());
+            // list the categories from the top down.
+            category_scale.set_inverted(self.orientation == Orientation::Horizontal);
+            self.category_scale = Some(category_scale);
+        }
+        if self.value_scale.is_none() {
+            let mut value_scale = Scale::new(
+                data.value_range(self.mode, self.baseline),
+                self.orientation.value_direction(),
+            );
+            value_scale.set_formatter(self.y_formatter.clone());
+            self.value_scale = Some(value_scale);
+        }
+        let bounds = Rect::from_origin_size(Point::ZERO, size);
+        let draw_area = self
+            .legend
+            .reserve_space(self.title.reserve_space(bounds, env), env);
+        let mut insets = self.graph_insets.resolve(env);
+        if self.orientation == Orientation::Horizontal {
+            // the category names can be long, so make sure they fit down the side.
+            let category_scale = self.category_scale.as_mut().unwrap();
+            category_scale.set_graph_bounds(draw_area);
+            category_scale.rebuild_if_needed(text, env);
+            let labels_width = category_scale.max_layout().width;
+            insets.x0 = insets
+                .x0
+                .max(env.get(theme::MARGIN) + labels_width + env.get(theme::SCALE_MARGIN));
+        }
+        self.graph_bounds = draw_area.inset(-insets);
+        if let Some(ratio) = self.aspect_ratio {
+            self.graph_bounds = fit_aspect_ratio(self.graph_bounds, ratio);
+        }
+        self.legend.set_graph_bounds(self.graph_bounds, env);
+        let category_scale = self.category_scale.as_mut().unwrap();
+        category_scale.set_graph_bounds(self.graph_bounds);
+        category_scale.rebuild_if_needed(text, env);
+        let value_scale = self.value_scale.as_mut().unwrap();
+        value_scale.set_graph_bounds(self.graph_bounds);
+        value_scale.rebuild_if_needed(text, env);
+    }
+
+    /// Draw the chart. Call `lay_out` first.
+    fn draw<S: SeriesData>(&mut self, ctx: &mut Canvas, data: &BarChartData<S>, env: &Env) {
+        if data.category_count() == 0 {
+            self.bar_rects.clear();
+            self.empty_state.draw(ctx);
+            return;
+        }
+        let axes_brush = ctx.solid_brush(self.axis_color.resolve(env));
+        let size = ctx.size();
+        let bar_spacing = self.bar_spacing.resolve(env);
+        let size_scale = env.get(theme::SIZE_SCALE);
+        let cap_width = CAP_WIDTH * size_scale;
+        let category_count = data.category_count();
+        let series_count = data.series.len();
+        self.bar_rects.clear();
+        let mut error_bars = BezPath::new();
+
+        // grid
+        if self.zebra_bands {
+            self.value_scale.as_ref().unwrap().draw_bands(ctx, env);
+        }
+        if data.draw_y_grid {
+            self.value_scale.as_ref().unwrap().draw_grid(ctx, env);
+        }
+
+        // data
+        let category_scale = self.category_scale.as_ref().unwrap();
+        // each category gets an equal slice of the category axis, with a gap between the bars.
+        let category_width = category_scale.category_width() - bar_spacing;
+        // give up if the area is too small.
+        if category_width > 0. {
+            let value_scale = self.value_scale.as_ref().unwrap();
+            let base_value = self.base_value();
+            let baseline = value_scale.pixel_location(base_value);
+            let below_baseline_color = self
+                .below_baseline_color
+                .as_ref()
+                .map(|color| color.resolve(env));
+            // the bars grow from the baseline while the chart animates in.
+            let progress = animation::progress(self.entrance.as_ref());
+            let value_y =
+                |value| baseline + (value_scale.pixel_location(value) - baseline) * progress;
+            let orientation = self.orientation;
+            let snap = ctx.snap();
+            // the width of each bar, and of all the bars of a category together.
+            let (bar_width, group_width) = match self.mode {
+                BarMode::Grouped => {
+                    let bar_width =
+                        (category_width / series_count as f64).min(self.style.max_width(env));
+                    (bar_width, bar_width * series_count as f64)
+                }
+                BarMode::Stacked => {
+                    let bar_width = category_width.min(self.style.max_width(env));
+                    (bar_width, bar_width)
+                }
+            };
+            // the bar from `c0` to `c1` across the category axis, and `v0` to `v1` along the
+            // value axis (all in pixels).
+            let bar_rect = |c0, v0, c1, v1| match orientation {
+                Orientation::Vertical => Rect::new(c0, v0, c1, v1),
+                Orientation::Horizontal => Rect::new(v0, c0, v1, c1),
+            };
+            for cat_idx in 0..category_count {
+                let start_x = category_scale.category_center(cat_idx) - group_width * 0.5;
+                // In stacked mode, where the next bar should start.
+                let mut stack = Stack::default();
+                for (series_idx, series) in data.series.iter().enumerate() {
+                    let value = match series.values.get(cat_idx) {
+                        Some(value) => value,
+                        None => continue,
+                    };
+                    // the value at the end of the bar, away from the baseline.
+                    let (top, bar) = match self.mode {
+                        BarMode::Grouped => {
+                            let x0 = start_x + series_idx as f64 * bar_width;
+                            (
+                                value,
+                                bar_rect(x0, value_y(value), x0 + bar_width, baseline),
+                            )
+                        }
+                        BarMode::Stacked => {
+                            let (start, end) = stack.push(value);
+                            (
+                                end,
+                                bar_rect(
+                                    start_x,
+                                    value_y(end),
+                                    start_x + bar_width,
+                                    value_y(start),
+                                ),
+                            )
+                        }
+                    };
+                    let bar = snap.rect(bar.abs());
+                    let color = match below_baseline_color.as_ref() {
+                        Some(color) if top < base_value => color.clone(),
+                        _ => new_color(series_idx, env),
+                    };
+                    let fill = self.fills.resolve(series_idx, env);
+                    self.style
+                        .paint(ctx, &self.style.shape(bar, env), fill, &color, env);
+                    if let Some(error) = series.errors.as_ref().and_then(|e| e.get(cat_idx)) {
+                        let (low, high) = error.bounds(top);
+                        let (low, high) = (value_y(low), value_y(high));
+                        match orientation {
+                            Orientation::Vertical => {
+                                add_error_bar(&mut error_bars, bar.center().x, low, high, cap_width)
+                            }
+                            Orientation::Horizontal => add_horizontal_error_bar(
+                                &mut error_bars,
+                                bar.center().y,
+                                low,
+                                high,
+                                cap_width,
+                            ),
+                        }
+                    }
+                    self.bar_rects
+                        .push((PointSelected::new(series_idx, cat_idx), bar.abs()));
+                }
+            }
+        }
+
+        // error bars go on top of all the bars.
+        ctx.stroke(error_bars, &axes_brush, size_scale);
+
+        // a line along the baseline, when there are bars on both sides of it.
+        if let Some(line) = self.baseline_line() {
+            let line = ctx.snap().line(line, size_scale);
+            ctx.stroke(line, &axes_brush, size_scale);
+        }
+
+        // focus ring
+        if let Some(keyboard) = self.keyboard.as_ref() {
+            let highlighted = keyboard.highlighted().and_then(|highlighted| {
+                self.bar_rects
+                    .iter()
+                    .find(|(selected, _)| *selected == highlighted)
+            });
+            if let Some((_, bar)) = highlighted {
+                keyboard.draw_ring(ctx, env, bar.inflate(2., 2.));
+            }
+        }
+
+        // title
+        self.title.draw(ctx, env);
+
+        // x axis
+        self.category_scale
+            .as_mut()
+            .unwrap()
+            .draw(ctx, env, true, true);
+        let x_label_width = self.x_label_layout.size().width;
+        ctx.draw_layout(
+            &self.x_label_layout,
+            ((size.width - x_label_width) * 0.5, size.height - 40.0),
+        );
+
+        // y axis
+        self.value_scale
+            .as_mut()
+            .unwrap()
+            .draw(ctx, env, true, true);
+
+        // legend
+        let fills = &self.fills;
+        self.legend.draw_with_fills(
+            ctx,
+            env,
+            |idx| new_color(idx, env),
+            |idx| fills.resolve(idx, env),
+        );
+    }
+}
+
+impl<S: SeriesData> Widget<BarChartData<S>> for BarChart {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut BarChartData<S>, env: &Env) {
+        if let Some(entrance) = self.entrance.as_mut() {
+            entrance.event(ctx, event);
+        }
+        if let Some(keyboard) = self.keyboard.as_mut() {
+            let category_count = data.category_count();
+            let series_lens: Vec<usize> = data
+                .series
+                .iter()
+                .map(|series| series.values.len().min(category_count))
+                .collect();
+            keyboard.event(ctx, event, &series_lens);
+        }
+        if let Some(context_menu) = self.context_menu.as_mut() {
+            let items = MenuItems {
+                grid: true,
+                data: true,
+                ..MenuItems::default()
+            };
+            context_menu.event(ctx, event, items, || data.csv_table());
+        }
+        match event {
+            Event::Command(cmd) if cmd.is(TOGGLE_CHART_GRID) => {
+                data.draw_y_grid = !data.draw_y_grid;
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_DATA) => {
+                copy_csv(data.csv_table());
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_IMAGE) => {
+                copy_image(ctx, env, |ctx| self.draw(ctx, data, env));
+            }
+            Event::Command(cmd) if cmd.is(EXPORT_IMAGE) => {
+                let scale = *cmd.get_unchecked(EXPORT_IMAGE);
+                export_image(ctx, scale, env, |ctx| self.draw(ctx, data, env));
+            }
+            _ => (),
+        }
+        if let Event::MouseDown(mouse) = event {
+            if mouse.button == MouseButton::Left {
+                if let Some((selected, _)) = self
+                    .bar_rects
+                    .iter()
+                    .find(|(_, bar)| bar.contains(mouse.pos))
+                {
+                    ctx.submit_notification(CHART_POINT_SELECTED.with(*selected));
+                    ctx.set_handled();
+                }
+            }
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &BarChartData<S>,
+        env: &Env,
+    ) {
+        if let Some(entrance) = self.entrance.as_mut() {
+            entrance.lifecycle(ctx, event);
+        }
+        if let Some(keyboard) = self.keyboard.as_mut() {
+            keyboard.lifecycle(ctx, event);
+        }
+        match event {
+            LifeCycle::WidgetAdded => self.set_data(data),
+            _ => (),
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &BarChartData<S>,
+        data: &BarChartData<S>,
+        env: &Env,
+    ) {
+        if !old_data.title.same(&data.title) {
+            self.title.set_text(data.title.clone());
+        }
+        if self.title.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !old_data.x_axis_label.same(&data.x_axis_label) {
+            self.x_label_layout.set_text(data.x_axis_label.clone());
+        }
+        if self.x_label_layout.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !old_data.categories.same(&data.categories) {
+            self.category_scale = None;
+            ctx.request_layout();
+        }
+        if !old_data.series.same(&data.series) {
+            self.legend
+                .set_labels(data.series.iter().map(|series| series.label.clone()));
+            // the number of categories we can draw might have changed too.
+            self.category_scale = None;
+            self.value_scale = None;
+            ctx.request_layout();
+        } else if let Some(category_scale) = self.category_scale.as_mut() {
+            if category_scale.needs_rebuild_after_update(ctx) {
+                ctx.request_layout();
+            }
+        }
+        if old_data.draw_y_grid != data.draw_y_grid {
+            ctx.request_paint();
+        }
+        if self.legend.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &BarChartData<S>,
+        env: &Env,
+    ) -> Size {
+        let size = chart_size(bc, self.preferred_size);
+        self.lay_out(ctx.text(), size, data, env);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &BarChartData<S>, env: &Env) {
+        self.draw(&mut Canvas::new(ctx, env), data, env);
+    }
+}
+
+impl<S: SeriesData> OffscreenChart<BarChartData<S>> for BarChart {
+    fn draw_offscreen(&mut self, ctx: &mut Canvas, data: &BarChartData<S>, env: &Env) {
+        self.entrance = None;
+        self.set_data(data);
+        let size = ctx.size();
+        self.lay_out(ctx.text(), size, data, env);
+        self.draw(ctx, data, env);
+    }
+}
+
+#[test]
+fn test_value_range() {
+    let data = BarChartData {
+        title: "".into(),
+        x_axis_label: "".into(),
+        categories: vec!["a".into(), "b".into(), "c".into()].into(),
+        series: vec![BarSeries::new("", Vector::from(vec![95., 110., 102.]))].into(),
+        draw_y_grid: false,
+    };
+    assert_eq!(data.value_range(BarMode::Grouped, 0.), (0., 110.));
+    // the baseline is always shown, but 0 doesn't have to be.
+    assert_eq!(data.value_range(BarMode::Grouped, 100.), (95., 110.));
+    assert_eq!(data.value_range(BarMode::Grouped, 120.), (95., 120.));
+    assert_eq!(data.value_range(BarMode::Stacked, 100.), (0., 307.));
+}
+
+#[test]
+fn test_negative_stacks() {
+    let data = BarChartData {
+        title: "".into(),
+        x_axis_label: "".into(),
+        categories: vec!["a".into(), "b".into()].into(),
+        series: vec![
+            BarSeries::new("", Vector::from(vec![3., -2.])),
+            BarSeries::new("", Vector::from(vec![-4., -1.])),
+            BarSeries::new("", Vector::from(vec![2., 5.])),
+        ]
+        .into(),
+        draw_y_grid: false,
+    };
+    assert_eq!(data.value_range(BarMode::Grouped, 0.), (-4., 5.));
+    assert_eq!(data.value_range(BarMode::Stacked, 0.), (-4., 5.));
+
+    let mut stack = Stack::default();
+    assert_eq!(stack.push(-2.), (0., -2.));
+    assert_eq!(stack.push(-1.), (-2., -3.));
+    assert_eq!(stack.push(5.), (0., 5.));
+}
@@ -0,0 +1,169 @@
+//! Sorting raw values into histogram buckets.
+use druid::Data;
+
+use crate::stats::{self, QuantileMethod};
+
+/// We never make more buckets than this, whatever the strategy says.
+const MAX_BINS: usize = 1000;
+
+/// How to choose the buckets for a histogram.
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub enum BinStrategy {
+    /// Buckets of the given width, starting at a multiple of the width.
+    FixedWidth(f64),
+    /// The given number of equal width buckets covering the data.
+    FixedCount(usize),
+    /// The Freedman–Diaconis rule: width `2 IQR / ∛n`. Good for data with outliers.
+    FreedmanDiaconis,
+    /// Sturges' rule: `⌈log₂ n⌉ + 1` buckets. Good for roughly normal data.
+    Sturges,
+}
+
+/// The result of binning some data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bins {
+    /// The edges of the buckets. There is always one more edge than there are buckets (unless
+    /// there are no buckets).
+    pub edges: Vec<f64>,
+    /// The number of values in each bucket.
+    pub counts: Vec<usize>,
+}
+
+impl Bins {
+    /// The `(start, end)` of each bucket.
+    pub fn ranges(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.edges.windows(2).map(|edges| (edges[0], edges[1]))
+    }
+
+    /// Count how many of the (finite) `values` fall in each of these buckets, e.g. to compare
+    /// another set of values on the same buckets. Values outside the buckets are ignored.
+    pub fn count(&self, values: impl IntoIterator<Item = f64>) -> Vec<usize> {
+        let mut counts = vec![0; self.counts.len()];
+        let (first, last) = match (self.edges.first(), self.edges.last()) {
+            (Some(first), Some(last)) if !counts.is_empty() => (*first, *last),
+            _ => return counts,
+        };
+        for value in values {
+            if !(first..=last).contains(&value) {
+                continue;
+            }
+            // the number of edges at or below the value, and the last bucket includes its upper
+            // edge.
+            let idx = self.edges.partition_point(|edge| *edge <= value);
+            let last_bucket = counts.len() - 1;
+            counts[(idx.max(1) - 1).min(last_bucket)] += 1;
+        }
+        counts
+    }
+}
+
+impl BinStrategy {
+    /// Put the (finite) values of `data` into buckets. NaNs and infinities are ignored.
+    pub fn bin(self, data: impl IntoIterator<Item = f64>) -> Bins {
+        let mut sorted: Vec<f64> = data.into_iter().filter(|v| v.is_finite()).collect();
+        sorted.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+        let (min, max) = match (sorted.first(), sorted.last()) {
+            (Some(min), Some(max)) => (*min, *max),
+            _ => {
+                return Bins {
+                    edges: vec![],
+                    counts: vec![],
+                }
+            }
+        };
+        if min == max {
+            // all the values are the same, so use a single bucket.
+            return Bins {
+                edges: vec![min, max],
+                counts: vec![sorted.len()],
+            };
+        }
+
+        let sturges = || {
+            let count = sturges_count(sorted.len());
+            (min, (max - min) / count as f64, count)
+        };
+        let (start, width, count) = match self {
+            BinStrategy::FixedWidth(width) if width > 0. => {
+                let start = (min / width).floor() * width;
+                (start, width, bins_needed(start, max, width))
+            }
+            BinStrategy::FixedCount(count) => {
+                let count = count.max(1);
+                (min, (max - min) / count as f64, count)
+            }
+            BinStrategy::FreedmanDiaconis => {
+                let iqr = stats::iqr(&sorted, QuantileMethod::Type7).unwrap();
+                if iqr > 0. {
+                    let width = 2. * iqr / (sorted.len() as f64).cbrt();
+                    (min, width, bins_needed(min, max, width))
+                } else {
+                    // the rule breaks down if most of the data is the same value.
+                    sturges()
+                }
+            }
+            BinStrategy::Sturges | BinStrategy::FixedWidth(_) => sturges(),
+        };
+        let count = count.min(MAX_BINS);
+        // recalculate the width in case we hit the limit.
+        let width = width.max((max - start) / count as f64);
+        let edges: Vec<f64> = (0..=count).map(|idx| start + idx as f64 * width).collect();
+
+        let mut counts = vec![0; count];
+        for value in sorted {
+            // the last bucket includes its upper edge.
+            let idx = (((value - start) / width).floor() as usize).min(count - 1);
+            counts[idx] += 1;
+        }
+        Bins { edges, counts }
+    }
+}
+
+/// The number of buckets of `width` needed to cover `start..=max`.
+fn bins_needed(start: f64, max: f64, width: f64) -> usize {
+    (((max - start) / width).ceil() as usize).max(1)
+}
+
+fn sturges_count(n: usize) -> usize {
+    (n as f64).log2().ceil() as usize + 1
+}
+
+#[test]
+fn test_fixed_width() {
+    let bins = BinStrategy::FixedWidth(5.).bin(vec![11., 14., 15., 22., 24.9]);
+    assert_eq!(bins.edges, vec![10., 15., 20., 25.]);
+    assert_eq!(bins.counts, vec![2, 1, 2]);
+}
+
+#[test]
+fn test_fixed_count() {
+    let bins = BinStrategy::FixedCount(2).bin(vec![0., 1., 2., 3., 4.]);
+    assert_eq!(bins.edges, vec![0., 2., 4.]);
+    // the max goes in the last bucket.
+    assert_eq!(bins.counts, vec![2, 3]);
+}
+
+#[test]
+fn test_sturges() {
+    let bins = BinStrategy::Sturges.bin((0..16).map(|v| v as f64));
+    // log2(16) + 1
+    assert_eq!(bins.counts.len(), 5);
+    assert_eq!(bins.counts.iter().sum::<usize>(), 16);
+}
+
+#[test]
+fn test_count() {
+    let bins = BinStrategy::FixedWidth(5.).bin(vec![11., 14., 15., 22., 24.9]);
+    assert_eq!(
+        bins.count(vec![10., 12., 20., 25., 30., f64::NAN]),
+        vec![2, 0, 2]
+    );
+}
+
+#[test]
+fn test_degenerate() {
+    assert_eq!(BinStrategy::Sturges.bin(vec![]).counts, Vec::<usize>::new());
+    let bins = BinStrategy::FreedmanDiaconis.bin(vec![3., 3., f64::NAN]);
+    assert_eq!(bins.edges, vec![3., 3.]);
+    assert_eq!(bins.counts, vec![2]);
+}
@@ -1,84 +1,642 @@
 use druid::{
     im::Vector,
-    kurbo::{Line, Rect},
+    kurbo::{Circle, Line, Point, Rect},
+    piet::PietText,
     theme::LABEL_COLOR,
-    ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, KeyOrValue, LayoutCtx, LifeCycle,
-    LifeCycleCtx, PaintCtx, RenderContext, Size, TextLayout, UpdateCtx, Widget,
+    ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, Insets, KeyOrValue, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, RenderContext, Size, UpdateCtx, Widget,
 };
 use druid_lens_compose::ComposeLens;
 
 use crate::{
-    axes::{data_as_range, Scale},
-    GRAPH_INSETS,
+    annotations::{Annotation, Annotations},
+    axes::Scale,
+    canvas::Canvas,
+    chart_size,
+    commands::{COPY_CHART_DATA, COPY_CHART_IMAGE, EXPORT_IMAGE, TOGGLE_CHART_GRID},
+    context_menu::{copy_csv, copy_image, csv_cell, ChartMenu, MenuItems},
+    describe::{count, describe, min_max},
+    empty::EmptyState,
+    fit_aspect_ratio,
+    format::Formatter,
+    hit_test::HitInfo,
+    offscreen::{export_image, OffscreenChart},
+    stats::{self, QuantileMethod},
+    swarm, theme,
+    title::{ChartTitle, TitleAlignment},
+    Orientation, Range, SeriesData, DEFAULT_CHART_SIZE,
 };
 
-/// A histogram of equal width categories
+/// The widest we will draw a box, at the normal size.
+const PLOT_WIDTH: f64 = 32.0;
+/// The radius of the dots for the raw data points, at the normal size.
+const POINT_RADIUS: f64 = 2.5;
+
+/// Box plots of one or more groups of data, drawn side by side. The data points can be any
+/// [`SeriesData`], e.g. a `Vector<i32>` or an `Arc<Vec<f64>>`.
 #[derive(Debug, Clone, Data, ComposeLens)]
-pub struct BoxPlotData {
+pub struct BoxPlotData<S = Vector<f64>> {
     pub title: ArcStr,
-    pub data_points: Vector<f64>,
+    /// `(label, data points)` for each box.
+    pub groups: Vector<(ArcStr, S)>,
+    /// The range of the value axis. If `None`, the range of the data (with any range padding) is
+    /// used.
+    pub y_range: Option<Range>,
+    pub draw_y_grid: bool,
+}
+
+impl<S: SeriesData> BoxPlotData<S> {
+    /// The data as a table, with a column of data points for each group.
+    fn csv_table(&self) -> Vec<Vec<String>> {
+        let header: Vec<String> = self
+            .groups
+            .iter()
+            .map(|(label, _)| label.to_string())
+            .collect();
+        let len = self
+            .groups
+            .iter()
+            .map(|(_, values)| values.len())
+            .max()
+            .unwrap_or(0);
+        let mut table = vec![header];
+        for idx in 0..len {
+            table.push(
+                self.groups
+                    .iter()
+                    .map(|(_, values)| csv_cell(values.get(idx)))
+                    .collect(),
+            );
+        }
+        table
+    }
+}
+
+/// How to draw every data point over the boxes, for when there are few enough to see them.
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub enum RawPoints {
+    /// Scattered across the width of the box, so points with the same value don't hide each other.
+    Jitter,
+    /// Spread out from the center of the box just enough that no 2 points overlap, so the width
+    /// of the swarm shows how many points there are at each value.
+    BeeSwarm,
 }
 
 #[derive(Clone)]
 pub struct BoxPlot {
-    title_layout: TextLayout<ArcStr>,
-    // retained sorted list of data points
-    sorted_data_points: Option<Vec<f64>>,
+    orientation: Orientation,
+    quantile_method: QuantileMethod,
+    title: ChartTitle,
+    // retained sorted list of data points for each group
+    sorted_groups: Option<Vec<Vec<f64>>>,
     graph_color: KeyOrValue<Color>,
-    // retained state for rendering the y axis.
+    outlier_color: KeyOrValue<Color>,
+    stroke_width: KeyOrValue<f64>,
+    draw_axis: bool,
+    /// If `None`, we measure the axis and labels.
+    graph_insets: Option<KeyOrValue<Insets>>,
+    preferred_size: Size,
+    aspect_ratio: Option<f64>,
+    /// How much to grow the value range, as a fraction of its size.
+    range_padding: f64,
+    y_formatter: Formatter,
+    annotations: Annotations,
+    raw_points: Option<RawPoints>,
+    // retained state for rendering the value axis.
     y_scale: Option<Scale>,
+    /// The axis the groups are spread along.
+    group_scale: Option<Scale>,
+    /// The area the boxes are drawn in.
+    graph_bounds: Rect,
+    /// How wide each box is drawn.
+    box_width: f64,
+    /// For each group, the offset of each raw data point from the center of the box.
+    raw_point_offsets: Option<Vec<Vec<f64>>>,
+    empty_state: EmptyState,
+    context_menu: Option<ChartMenu>,
 }
 
 impl BoxPlot {
     pub fn new() -> Self {
-        let mut title_layout = TextLayout::new();
-        title_layout.set_text_size(20.);
         BoxPlot {
-            title_layout,
-            sorted_data_points: None,
+            orientation: Orientation::Vertical,
+            quantile_method: QuantileMethod::default(),
+            title: ChartTitle::new(),
+            sorted_groups: None,
             graph_color: LABEL_COLOR.into(),
+            outlier_color: theme::OUTLIER_COLOR.into(),
+            stroke_width: theme::LINE_STROKE_WIDTH.into(),
+            draw_axis: true,
+            graph_insets: None,
+            preferred_size: DEFAULT_CHART_SIZE,
+            aspect_ratio: None,
+            range_padding: 0.,
+            y_formatter: Formatter::default(),
+            annotations: Annotations::new(),
+            raw_points: None,
             y_scale: None,
+            group_scale: None,
+            graph_bounds: Rect::ZERO,
+            box_width: 0.,
+            raw_point_offsets: None,
+            empty_state: EmptyState::new(),
+            context_menu: None,
         }
     }
 
-    /// Rebuild any parts of the retained state that need rebuilding.
-    fn rebuild_if_needed(&mut self, ctx: &mut PaintCtx, data: &BoxPlotData, env: &Env) {
-        self.title_layout.rebuild_if_needed(ctx.text(), env);
-        if self.sorted_data_points.is_none() {
-            let mut dp: Vec<f64> = data.data_points.iter().copied().collect();
-            dp.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
-            self.sorted_data_points = Some(dp);
+    /// Draw the boxes horizontally, with the value axis along the bottom.
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Choose how the quartiles and whisker ends are estimated. The default is
+    /// `QuantileMethod::Type7`.
+    pub fn with_quantile_method(mut self, method: QuantileMethod) -> Self {
+        self.quantile_method = method;
+        self
+    }
+
+    /// Set the color of the boxes and whiskers.
+    pub fn with_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.graph_color = color.into();
+        self
+    }
+
+    /// Set the color of the outliers. The default is `theme::OUTLIER_COLOR`.
+    pub fn with_outlier_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.outlier_color = color.into();
+        self
+    }
+
+    /// Set the width of the lines the boxes are drawn with. The default is
+    /// `theme::LINE_STROKE_WIDTH`.
+    pub fn with_stroke_width(mut self, width: impl Into<KeyOrValue<f64>>) -> Self {
+        self.stroke_width = width.into();
+        self
+    }
+
+    /// Whether to draw the value axis, with its tick labels. A hidden axis doesn't take up any
+    /// room. The group labels are drawn either way.
+    pub fn with_axis_visible(mut self, visible: bool) -> Self {
+        self.draw_axis = visible;
+        self
+    }
+
+    /// Set the font size of the title. The default is `theme::TITLE_TEXT_SIZE`.
+    pub fn with_title_size(mut self, size: impl Into<KeyOrValue<f64>>) -> Self {
+        self.title.set_text_size(size);
+        self
+    }
+
+    /// Where to put the title (and subtitle). Defaults to the center.
+    pub fn with_title_alignment(mut self, alignment: TitleAlignment) -> Self {
+        self.title.set_alignment(alignment);
+        self
+    }
+
+    /// Draw a line of smaller text under the title.
+    pub fn with_subtitle(mut self, subtitle: impl Into<ArcStr>) -> Self {
+        self.title.set_subtitle(subtitle.into());
+        self
+    }
+
+    /// Use fixed insets around the plot area, instead of measuring the axes (see
+    /// `theme::GRAPH_INSETS`).
+    pub fn with_graph_insets(mut self, insets: impl Into<KeyOrValue<Insets>>) -> Self {
+        self.graph_insets = Some(insets.into());
+        self
+    }
+
+    /// Set the size to take when the space isn't bounded, e.g. inside a `Scroll`. Otherwise the
+    /// chart fills the space it is given. The default is 400×300.
+    pub fn with_preferred_size(mut self, size: impl Into<Size>) -> Self {
+        self.preferred_size = size.into();
+        self
+    }
+
+    /// Show a menu when the chart is right-clicked, to toggle the grid, copy the chart as an image,
+    /// or copy or save the data as CSV. `T` is the type of your app's data, which druid needs to
+    /// show a menu.
+    pub fn with_context_menu<T: Data>(mut self) -> Self {
+        self.context_menu = Some(ChartMenu::new::<T>());
+        self
+    }
+
+    /// Keep the plot area at the given width:height ratio, centered in the space left over from
+    /// the title, legend and axes. By default the plot area fills that space.
+    pub fn with_aspect_ratio(mut self, ratio: f64) -> Self {
+        assert!(ratio > 0.0, "aspect ratio must be positive");
+        self.aspect_ratio = Some(ratio);
+        self
+    }
+
+    /// Leave a gap of `fraction` (e.g. `0.05` for 5%) of the range at each end of the value axis,
+    /// so the whiskers and outliers don't sit right on the edges of the graph.
+    pub fn with_range_padding(mut self, fraction: f64) -> Self {
+        self.range_padding = fraction;
+        self
+    }
+
+    /// Set how the value axis tick labels are formatted.
+    pub fn with_y_formatter(mut self, formatter: Formatter) -> Self {
+        self.y_formatter = formatter;
+        self
+    }
+
+    /// Add a reference line or band, drawn behind the boxes.
+    ///
+    /// Use `y_line`/`y_band` for vertical box plots, and `x_line`/`x_band` for horizontal ones.
+    pub fn with_annotation(mut self, annotation: Annotation) -> Self {
+        self.annotations.push(annotation);
+        self
+    }
+
+    /// Draw every data point over the boxes. Outliers are then shown as points rather than
+    /// crosses.
+    pub fn with_raw_points(mut self, raw_points: RawPoints) -> Self {
+        self.raw_points = Some(raw_points);
+        self
+    }
+
+    /// A short description of the chart for screen readers, e.g. "Box plot, Systolic blood
+    /// pressure, 2 groups, 400 values, min 90, max 180".
+    pub fn describe<S: SeriesData>(&self, data: &BoxPlotData<S>) -> String {
+        let values: usize = data
+            .groups
+            .iter()
+            .map(|(_, data_points)| data_points.len())
+            .sum();
+        let mut facts = vec![
+            count(data.groups.len(), "group", "groups"),
+            count(values, "value", "values"),
+        ];
+        facts.extend(min_max(
+            data.groups
+                .iter()
+                .flat_map(|(_, data_points)| data_points.values()),
+            &self.y_formatter,
+        ));
+        describe("Box plot", &data.title, facts)
+    }
+
+    /// Take the title from `data`, when the chart is added.
+    fn set_data<S: SeriesData>(&mut self, data: &BoxPlotData<S>) {
+        self.title.set_text(data.title.clone());
+    }
+
+    /// Lay out the chart, or the empty state if there is no data.
+    fn lay_out<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &BoxPlotData<S>,
+        env: &Env,
+    ) {
+        if data_range(data).is_none() {
+            self.empty_state.rebuild_if_needed(text, env);
+        } else {
+            self.rebuild_if_needed(text, size, data, env);
+        }
+    }
+
+    /// Rebuild any parts of the retained state that need rebuilding, for a plot of size `size`.
+    fn rebuild_if_needed<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &BoxPlotData<S>,
+        env: &Env,
+    ) {
+        let margin = env.get(theme::MARGIN);
+        let scale_margin = env.get(theme::SCALE_MARGIN);
+        self.title.rebuild_if_needed(text, env);
+        self.annotations.rebuild_if_needed(text, env);
+        if self.sorted_groups.is_none() {
+            self.sorted_groups = Some(
+                data.groups
+                    .iter()
+                    .map(|(_, data_points)| {
+                        // missing values are left out.
+                        let mut dp: Vec<f64> =
+                            data_points.values().filter(|v| v.is_finite()).collect();
+                        dp.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+                        dp
+                    })
+                    .collect(),
+            );
+        }
+        if self.group_scale.is_none() {
+            let labels = data.groups.iter().map(|(label, _)| label.clone());
+            let mut group_scale =
+                Scale::new_categorical(labels, self.orientation.category_direction());
+            // list the groups from the top down.
+            group_scale.set_inverted(self.orientation == Orientation::Horizontal);
+            self.group_scale = Some(group_scale);
         }
         if self.y_scale.is_none() {
-            self.y_scale = Some(Scale::new_y(data_as_range(
-                self.sorted_data_points.as_ref().unwrap().iter().copied(),
-            )));
+            // all the boxes share the same scale.
+            let y_range = data
+                .y_range
+                .unwrap_or_else(|| data_range(data).unwrap().pad(self.range_padding));
+            let mut y_scale = Scale::new(y_range, self.orientation.value_direction());
+            y_scale.set_formatter(self.y_formatter.clone());
+            self.y_scale = Some(y_scale);
+        }
+
+        // Lay out the value scale for the whole area first, so we know how big its labels are.
+        let draw_area = self.title.reserve_space(size.to_rect(), env);
+        let y_scale = self.y_scale.as_mut().unwrap();
+        y_scale.set_graph_bounds(draw_area);
+        y_scale.rebuild_if_needed(text, env);
+        // a hidden value axis doesn't need any room.
+        let scale_room = if self.draw_axis {
+            let labels = y_scale.max_layout();
+            Size::new(labels.width + scale_margin, labels.height + scale_margin)
+        } else {
+            Size::ZERO
+        };
+        let group_scale = self.group_scale.as_mut().unwrap();
+        group_scale.set_graph_bounds(draw_area);
+        group_scale.rebuild_if_needed(text, env);
+        let group_labels = group_scale.max_layout();
+
+        // the title has its own space above the draw area.
+        let y0 = margin;
+        // space for the value axis and its tick labels on one side, and the group labels on the
+        // other.
+        let (x0, y1) = match self.orientation {
+            Orientation::Vertical => (
+                margin + scale_room.width,
+                margin + group_labels.height + scale_margin,
+            ),
+            Orientation::Horizontal => (
+                margin + group_labels.width + scale_margin,
+                margin + scale_room.height,
+            ),
+        };
+        self.graph_bounds = draw_area.inset(match self.graph_insets.as_ref() {
+            Some(graph_insets) => -graph_insets.resolve(env),
+            None => Insets {
+                x0: -x0,
+                y0: -y0,
+                x1: -margin,
+                y1: -y1,
+            },
+        });
+        if let Some(ratio) = self.aspect_ratio {
+            self.graph_bounds = fit_aspect_ratio(self.graph_bounds, ratio);
         }
-        let graph_bounds = self.graph_bounds(ctx.size());
         let y_scale = self.y_scale.as_mut().unwrap();
-        y_scale.set_graph_bounds(graph_bounds);
-        y_scale.rebuild_if_needed(ctx, env);
+        y_scale.set_graph_bounds(self.graph_bounds);
+        y_scale.rebuild_if_needed(text, env);
+        let group_scale = self.group_scale.as_mut().unwrap();
+        group_scale.set_graph_bounds(self.graph_bounds);
+        group_scale.rebuild_if_needed(text, env);
+
+        // the spread of the points depends on their positions in pixels, so on the layout.
+        let plot_width = self.plot_width(env);
+        self.box_width = plot_width;
+        let point_radius = POINT_RADIUS * env.get(theme::SIZE_SCALE);
+        let y_scale = self.y_scale.as_ref().unwrap();
+        let sorted_groups = self.sorted_groups.as_ref().unwrap();
+        self.raw_point_offsets = self.raw_points.map(|raw_points| {
+            sorted_groups
+                .iter()
+                .map(|data_points| match raw_points {
+                    RawPoints::Jitter => swarm::jitter(data_points.len())
+                        .into_iter()
+                        .map(|offset| offset * plot_width)
+                        .collect(),
+                    RawPoints::BeeSwarm => {
+                        let positions: Vec<f64> = data_points
+                            .iter()
+                            .map(|&datum| y_scale.pixel_location(datum))
+                            .collect();
+                        swarm::bee_swarm(&positions, 2. * point_radius)
+                    }
+                })
+                .collect()
+        });
+    }
+
+    /// How wide to draw each box.
+    fn plot_width(&self, env: &Env) -> f64 {
+        (PLOT_WIDTH * env.get(theme::SIZE_SCALE))
+            .min(self.group_scale.as_ref().unwrap().category_width() * 0.6)
+    }
+
+    /// The box under `pos`, which is in the chart's coordinates, like the position of a mouse
+    /// event. A box reaches to the ends of its whiskers, and its position is its median.
+    ///
+    /// This returns `None` before the chart has been laid out.
+    pub fn hit_test(&self, pos: Point) -> Option<HitInfo> {
+        let y_scale = self.y_scale.as_ref()?;
+        let group_scale = self.group_scale.as_ref()?;
+        let to_point = |c: f64, value: f64| -> Point {
+            let v = y_scale.pixel_location(value);
+            match self.orientation {
+                Orientation::Vertical => Point::new(c, v),
+                Orientation::Horizontal => Point::new(v, c),
+            }
+        };
+        self.sorted_groups
+            .as_ref()?
+            .iter()
+            .enumerate()
+            .find_map(|(index, data_points)| {
+                let quantile = |p| stats::quantile(data_points, p, self.quantile_method);
+                let center = group_scale.category_center(index);
+                let (c0, c1) = (center - self.box_width * 0.5, center + self.box_width * 0.5);
+                let whiskers =
+                    Rect::from_points(to_point(c0, quantile(0.1)?), to_point(c1, quantile(0.9)?));
+                if !whiskers.contains(pos) {
+                    return None;
+                }
+                Some(HitInfo {
+                    series: 0,
+                    index,
+                    position: to_point(center, quantile(0.5)?),
+                    distance: 0.,
+                })
+            })
+    }
+
+    /// The area the boxes were last drawn in.
+    pub fn graph_bounds(&self) -> Rect {
+        self.graph_bounds
     }
 
-    pub fn graph_bounds(&self, size: Size) -> Rect {
-        size.to_rect().inset(GRAPH_INSETS)
+    /// Draw the chart, as laid out by `layout`.
+    fn draw<S: SeriesData + PartialEq>(
+        &mut self,
+        ctx: &mut Canvas,
+        data: &BoxPlotData<S>,
+        env: &Env,
+    ) {
+        if data_range(data).is_none() {
+            self.empty_state.draw(ctx);
+            return;
+        }
+        let text_brush = ctx.solid_brush(self.graph_color.resolve(env));
+        let outlier_brush = ctx.solid_brush(self.outlier_color.resolve(env));
+        let stroke_width = self.stroke_width.resolve(env);
+        let orientation = self.orientation;
+        let quantile_method = self.quantile_method;
+
+        // title
+        self.title.draw(ctx, env);
+
+        // value axis
+        if data.draw_y_grid {
+            self.y_scale.as_ref().unwrap().draw_grid(ctx, env);
+        }
+        self.annotations
+            .draw(ctx, env, &[self.y_scale.as_ref().unwrap()]);
+        if self.draw_axis {
+            self.y_scale.as_mut().unwrap().draw(ctx, env, true, true);
+        }
+        // group labels
+        self.group_scale
+            .as_mut()
+            .unwrap()
+            .draw(ctx, env, false, true);
+
+        // data
+        let y_scale = self.y_scale.as_ref().unwrap();
+        let group_scale = self.group_scale.as_ref().unwrap();
+        let sorted_groups = self.sorted_groups.as_ref().unwrap();
+        let plot_width = self.plot_width(env);
+        let point_radius = POINT_RADIUS * env.get(theme::SIZE_SCALE);
+        let point_brush = ctx.solid_brush(self.graph_color.resolve(env).with_alpha(0.6));
+        let raw_point_offsets = self.raw_point_offsets.as_ref();
+        // (position along the category axis, data value) to pixels.
+        let to_point = |c: f64, value: f64| -> Point {
+            let v = y_scale.pixel_location(value);
+            match orientation {
+                Orientation::Vertical => Point::new(c, v),
+                Orientation::Horizontal => Point::new(v, c),
+            }
+        };
+        // values outside a fixed `y_range` are cut off at the edge of the plot.
+        let graph_bounds = self.graph_bounds;
+        let clip = data.y_range.is_some();
+        ctx.with_save(|ctx| {
+            if clip {
+                ctx.clip(graph_bounds);
+            }
+            for (idx, data_points) in sorted_groups.iter().enumerate() {
+                let center = group_scale.category_center(idx);
+                if data_points.is_empty() {
+                    continue;
+                }
+                // the data isn't empty, so there is always a quantile.
+                let quantile = |p| stats::quantile(data_points, p, quantile_method).unwrap();
+                let data_qn10 = quantile(0.1);
+                let data_qn25 = quantile(0.25);
+                let data_qn50 = quantile(0.5);
+                let data_qn75 = quantile(0.75);
+                let data_qn90 = quantile(0.9);
+
+                let (c0, c1) = (center - plot_width * 0.5, center + plot_width * 0.5);
+                let cross_line = |datum| Line::new(to_point(c0, datum), to_point(c1, datum));
+                ctx.stroke(cross_line(data_qn90), &text_brush, stroke_width);
+                ctx.stroke(
+                    Line::new(to_point(center, data_qn90), to_point(center, data_qn75)),
+                    &text_brush,
+                    stroke_width,
+                );
+                ctx.stroke(
+                    Rect::from_points(to_point(c0, data_qn75), to_point(c1, data_qn25)),
+                    &text_brush,
+                    stroke_width,
+                );
+                ctx.stroke(cross_line(data_qn50), &text_brush, stroke_width);
+                ctx.stroke(
+                    Line::new(to_point(center, data_qn25), to_point(center, data_qn10)),
+                    &text_brush,
+                    stroke_width,
+                );
+                ctx.stroke(cross_line(data_qn10), &text_brush, stroke_width);
+
+                if let Some(offsets) = raw_point_offsets {
+                    for (datum, offset) in data_points.iter().copied().zip(&offsets[idx]) {
+                        ctx.fill(
+                            Circle::new(to_point(center + offset, datum), point_radius),
+                            &point_brush,
+                        );
+                    }
+                    continue;
+                }
+
+                // outliers
+                let mut prev_datum = None;
+                for datum in data_points.iter().copied() {
+                    if datum < data_qn10 || datum > data_qn90 {
+                        if prev_datum == Some(datum) {
+                            continue;
+                        }
+                        let cross = Rect::from_center_size(
+                            to_point(center, datum),
+                            (plot_width * 0.25, plot_width * 0.25),
+                        );
+                        ctx.stroke(
+                            Line::new((cross.x0, cross.y0), (cross.x1, cross.y1)),
+                            &outlier_brush,
+                            stroke_width,
+                        );
+                        ctx.stroke(
+                            Line::new((cross.x0, cross.y1), (cross.x1, cross.y0)),
+                            &outlier_brush,
+                            stroke_width,
+                        );
+                        prev_datum = Some(datum);
+                    }
+                }
+            }
+        });
     }
 }
 
-impl Widget<BoxPlotData> for BoxPlot {
-    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut BoxPlotData, env: &Env) {}
+impl<S: SeriesData + PartialEq> Widget<BoxPlotData<S>> for BoxPlot {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut BoxPlotData<S>, env: &Env) {
+        let items = MenuItems {
+            grid: true,
+            data: true,
+            ..MenuItems::default()
+        };
+        if let Some(context_menu) = self.context_menu.as_mut() {
+            context_menu.event(ctx, event, items, || data.csv_table());
+        }
+        match event {
+            Event::Command(cmd) if cmd.is(TOGGLE_CHART_GRID) => {
+                data.draw_y_grid = !data.draw_y_grid;
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_DATA) => {
+                copy_csv(data.csv_table());
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_IMAGE) => {
+                copy_image(ctx, env, |ctx| self.draw(ctx, data, env));
+            }
+            Event::Command(cmd) if cmd.is(EXPORT_IMAGE) => {
+                let scale = *cmd.get_unchecked(EXPORT_IMAGE);
+                export_image(ctx, scale, env, |ctx| self.draw(ctx, data, env));
+            }
+            _ => (),
+        }
+    }
 
     fn lifecycle(
         &mut self,
         ctx: &mut LifeCycleCtx,
         event: &LifeCycle,
-        data: &BoxPlotData,
+        data: &BoxPlotData<S>,
         env: &Env,
     ) {
         match event {
-            LifeCycle::WidgetAdded => {
-                self.title_layout.set_text(data.title.clone());
-            }
+            LifeCycle::WidgetAdded => self.set_data(data),
             _ => (),
         }
     }
@@ -86,26 +644,44 @@ impl Widget<BoxPlotData> for BoxPlot {
     fn update(
         &mut self,
         ctx: &mut UpdateCtx,
-        old_data: &BoxPlotData,
-        data: &BoxPlotData,
+        old_data: &BoxPlotData<S>,
+        data: &BoxPlotData<S>,
         env: &Env,
     ) {
         if !Data::same(&old_data.title, &data.title) {
             // relaying out the text is potentially expensive, so worth an equality check.
             if old_data.title != data.title {
-                self.title_layout.set_text(data.title.clone());
+                self.title.set_text(data.title.clone());
             }
         }
-        self.title_layout.needs_rebuild_after_update(ctx);
-        if !Data::same(&old_data.data_points, &data.data_points) {
-            if old_data.data_points != data.data_points {
-                self.sorted_data_points = None;
+        if self.title.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !Data::same(&old_data.groups, &data.groups) {
+            if old_data.groups != data.groups {
+                self.sorted_groups = None;
+                self.group_scale = None;
                 self.y_scale = None;
+                ctx.request_layout();
             }
         } else {
+            let mut needs_rebuild = false;
             if let Some(y_scale) = self.y_scale.as_mut() {
-                y_scale.needs_rebuild_after_update(ctx);
+                needs_rebuild |= y_scale.needs_rebuild_after_update(ctx);
+            }
+            if let Some(group_scale) = self.group_scale.as_mut() {
+                needs_rebuild |= group_scale.needs_rebuild_after_update(ctx);
             }
+            if needs_rebuild {
+                ctx.request_layout();
+            }
+        }
+        if old_data.y_range != data.y_range {
+            self.y_scale = None;
+            ctx.request_layout();
+        }
+        if old_data.draw_y_grid != data.draw_y_grid {
+            ctx.request_paint();
         }
     }
 
@@ -113,128 +689,40 @@ impl Widget<BoxPlotData> for BoxPlot {
         &mut self,
         ctx: &mut LayoutCtx,
         bc: &BoxConstraints,
-        data: &BoxPlotData,
+        data: &BoxPlotData<S>,
         env: &Env,
     ) -> Size {
-        bc.constrain((f64::INFINITY, f64::INFINITY))
+        let size = chart_size(bc, self.preferred_size);
+        self.lay_out(ctx.text(), size, data, env);
+        size
     }
 
-    fn paint(&mut self, ctx: &mut PaintCtx, data: &BoxPlotData, env: &Env) {
-        self.rebuild_if_needed(ctx, data, env);
-        let size = ctx.size();
-        let bounds = size.to_rect();
-        let graph_bounds = self.graph_bounds(size);
-        let axes_brush = ctx.solid_brush(Color::hlc(0.0, 60.0, 0.0));
-        let text_brush = ctx.solid_brush(Color::WHITE);
-        let bar_brush = ctx.solid_brush(Color::hlc(0.0, 50.0, 50.0));
-
-        // data stats
-        let mut data_points = data.data_points.clone();
-        data_points.sort_by(|left, right| left.partial_cmp(right).expect("cannot sort NaNs"));
-        assert!(data.data_points.len() > 0);
-        let data_min = *data_points.front().unwrap();
-        let data_qn10 = quantile(&data_points, 0.1);
-        let data_qn25 = quantile(&data_points, 0.25);
-        let data_qn50 = quantile(&data_points, 0.5);
-        let data_qn75 = quantile(&data_points, 0.75);
-        let data_qn90 = quantile(&data_points, 0.9);
-        let data_max = *data_points.back().unwrap();
-
-        // title
-        let title_size = self.title_layout.size();
-        self.title_layout
-            .draw(ctx, ((size.width - title_size.width) * 0.5, 40.0));
-
-        let datum_to_height = |datum: f64| -> f64 {
-            let t = (datum - data_min) / (data_max - data_min);
-            graph_bounds.y1 - t * graph_bounds.height()
-        };
-
-        // y axis
-        self.y_scale.as_mut().unwrap().draw(ctx, env, true, true);
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &BoxPlotData<S>, env: &Env) {
+        self.draw(&mut Canvas::new(ctx, env), data, env);
+    }
+}
 
-        // data
-        const PLOT_WIDTH: f64 = 32.0;
-        let x_center =
-            ((graph_bounds.x1 + graph_bounds.x0) * 0.5).max(graph_bounds.x0 + PLOT_WIDTH * 0.5);
-        let horiz_line = |datum| {
-            let y = datum_to_height(datum);
-            Line::new(
-                (x_center - PLOT_WIDTH * 0.5, y),
-                (x_center + PLOT_WIDTH * 0.5, y),
-            )
-        };
-        ctx.stroke(horiz_line(data_qn90), &text_brush, 1.0);
-        ctx.stroke(
-            Line::new(
-                (x_center, datum_to_height(data_qn90)),
-                (x_center, datum_to_height(data_qn75)),
-            ),
-            &text_brush,
-            1.0,
-        );
-        ctx.stroke(
-            Rect::new(
-                x_center - PLOT_WIDTH * 0.5,
-                datum_to_height(data_qn75),
-                x_center + PLOT_WIDTH * 0.5,
-                datum_to_height(data_qn25),
-            ),
-            &text_brush,
-            1.0,
-        );
-        ctx.stroke(horiz_line(data_qn50), &text_brush, 1.0);
-        ctx.stroke(
-            Line::new(
-                (x_center, datum_to_height(data_qn25)),
-                (x_center, datum_to_height(data_qn10)),
-            ),
-            &text_brush,
-            1.0,
-        );
-        ctx.stroke(horiz_line(data_qn10), &text_brush, 1.0);
-
-        let mut draw_cross = |(x, y)| {
-            let cross = Rect::from_center_size((x, y), (PLOT_WIDTH * 0.25, PLOT_WIDTH * 0.25));
-            ctx.stroke(
-                Line::new((cross.x0, cross.y0), (cross.x1, cross.y1)),
-                &text_brush,
-                1.0,
-            );
-            ctx.stroke(
-                Line::new((cross.x0, cross.y1), (cross.x1, cross.y0)),
-                &text_brush,
-                1.0,
-            );
-        };
-        for datum in data_points.iter().copied() {
-            let mut prev_datum = None;
-            if datum < data_qn10 || datum > data_qn90 {
-                if let Some(d) = prev_datum {
-                    if d == datum {
-                        continue;
-                    }
-                }
-                /*
-                ctx.stroke(
-                    Circle::new((x_center, datum_to_height(datum)), 4.0),
-                    &text_brush,
-                    1.0,
-                );
-                */
-                draw_cross((x_center, datum_to_height(datum)));
-                prev_datum = Some(datum);
-            }
-        }
+impl<S: SeriesData + PartialEq> OffscreenChart<BoxPlotData<S>> for BoxPlot {
+    fn draw_offscreen(&mut self, ctx: &mut Canvas, data: &BoxPlotData<S>, env: &Env) {
+        self.set_data(data);
+        let size = ctx.size();
+        self.lay_out(ctx.text(), size, data, env);
+        self.draw(ctx, data, env);
     }
 }
 
-/// Get the pth quantile from data.
-fn quantile(data: &Vector<f64>, p: f64) -> f64 {
-    let np1 = (data.len() + 1) as f64;
-    let k = (p * np1).floor() as usize;
-    let x_k = data.iter().copied().nth(k).unwrap();
-    let x_kp1 = data.iter().copied().nth(k + 1).unwrap();
-    let alpha = p * np1 - k as f64;
-    x_k + alpha * (x_kp1 - x_k)
+/// The range of all the data points, widened if they are all the same, or `None` if there aren't
+/// any.
+fn data_range<S: SeriesData>(data: &BoxPlotData<S>) -> Option<Range> {
+    let range = Range::from_finite(
+        data.groups
+            .iter()
+            .flat_map(|(_, data_points)| data_points.values()),
+    )?;
+    // a scale needs some size to place the values on, even if they are all the same.
+    Some(if range.size() == 0. {
+        Range::new(range.min() - 0.5, range.max() + 0.5)
+    } else {
+        range
+    })
 }
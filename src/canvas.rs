@@ -0,0 +1,109 @@
+//! What charts draw on, so the same drawing code can paint a widget or an offscreen image.
+use druid::{
+    kurbo::Point,
+    piet::{Piet, RenderContext},
+    text::TextStorage,
+    Env, PaintCtx, Size, TextLayout,
+};
+use std::ops::{Deref, DerefMut};
+
+use crate::snap::PixelSnap;
+
+/// A render context to draw a chart with, and the things about the surface the chart needs to
+/// know: its size, and where its pixels are.
+///
+/// Widgets make one from their `PaintCtx` at the start of `paint`, and
+/// [`render_chart_offscreen`](crate::render_chart_offscreen) makes one for an image. All of piet's
+/// `RenderContext` methods can be called on it.
+pub struct Canvas<'a, 'b> {
+    rc: &'a mut Piet<'b>,
+    size: Size,
+    snap: PixelSnap,
+}
+
+impl<'a, 'b> Canvas<'a, 'b> {
+    /// Draw on the widget being painted.
+    pub fn new(ctx: &'a mut PaintCtx<'_, '_, 'b>, env: &Env) -> Self {
+        let size = ctx.size();
+        let snap = PixelSnap::new(ctx, env);
+        Canvas {
+            rc: &mut *ctx.render_ctx,
+            size,
+            snap,
+        }
+    }
+
+    /// Draw on an image `size` display points big, with `scale` pixels per point.
+    pub(crate) fn for_image(rc: &'a mut Piet<'b>, size: Size, scale: f64, env: &Env) -> Self {
+        Canvas {
+            rc,
+            size,
+            snap: PixelSnap::for_image(scale, env),
+        }
+    }
+
+    /// The size of the area being drawn on.
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    pub(crate) fn snap(&self) -> PixelSnap {
+        self.snap
+    }
+
+    /// Draw `layout` with its top left corner at `point`. The layout must have been built.
+    pub fn draw_layout<T: TextStorage>(&mut self, layout: &TextLayout<T>, point: impl Into<Point>) {
+        debug_assert!(
+            layout.layout().is_some(),
+            "text layout drawn before it was built"
+        );
+        if let Some(layout) = layout.layout() {
+            self.rc.draw_text(layout, point);
+        }
+    }
+
+    /// Run `f`, then put the transform and clip back as they were.
+    pub fn with_save(&mut self, f: impl FnOnce(&mut Self)) {
+        if let Err(e) = self.rc.save() {
+            log::error!("failed to save render context: {}", e);
+            return;
+        }
+        f(self);
+        if let Err(e) = self.rc.restore() {
+            log::error!("failed to restore render context: {}", e);
+        }
+    }
+}
+
+/// Something the parts of a chart, like a [`Scale`](crate::axes::Scale), can be drawn on: the
+/// `PaintCtx` of a widget, or a [`Canvas`].
+pub trait ToCanvas {
+    /// Call `f` with a canvas that draws on this.
+    fn with_canvas(&mut self, env: &Env, f: impl FnOnce(&mut Canvas));
+}
+
+impl ToCanvas for Canvas<'_, '_> {
+    fn with_canvas(&mut self, _env: &Env, f: impl FnOnce(&mut Canvas)) {
+        f(self)
+    }
+}
+
+impl ToCanvas for PaintCtx<'_, '_, '_> {
+    fn with_canvas(&mut self, env: &Env, f: impl FnOnce(&mut Canvas)) {
+        f(&mut Canvas::new(self, env))
+    }
+}
+
+impl<'b> Deref for Canvas<'_, 'b> {
+    type Target = Piet<'b>;
+
+    fn deref(&self) -> &Piet<'b> {
+        self.rc
+    }
+}
+
+impl<'b> DerefMut for Canvas<'_, 'b> {
+    fn deref_mut(&mut self) -> &mut Piet<'b> {
+        self.rc
+    }
+}
@@ -0,0 +1,348 @@
+//! Small multiples: several charts of the same kind, laid out in a grid.
+use druid::{
+    im::Vector, BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, Rect, Size, UpdateCtx, Widget, WidgetPod,
+};
+
+use crate::{chart_size, Range, DEFAULT_CHART_SIZE};
+
+/// Chart data that a `ChartGrid` can coordinate between its cells.
+pub trait GridData: Data {
+    /// The x range that would be shown, or `None` if there is nothing to show.
+    fn data_x_range(&self) -> Option<Range>;
+    /// The y range that would be shown, or `None` if there is nothing to show.
+    fn data_y_range(&self) -> Option<Range>;
+    /// Show `range` on the x axis, whatever the data is.
+    fn set_x_range(&mut self, range: Range);
+    /// Show `range` on the y axis, whatever the data is.
+    fn set_y_range(&mut self, range: Range);
+    /// Whether to draw the tick labels on the x axis.
+    fn set_x_tick_labels(&mut self, visible: bool);
+    /// Whether to draw the tick labels on the y axis.
+    fn set_y_tick_labels(&mut self, visible: bool);
+    /// Put back the x range and x tick labels of `original`, undoing `set_x_range` and
+    /// `set_x_tick_labels`.
+    fn restore_x(&mut self, original: &Self);
+    /// Put back the y range and y tick labels of `original`, undoing `set_y_range` and
+    /// `set_y_tick_labels`.
+    fn restore_y(&mut self, original: &Self);
+}
+
+/// A grid of charts, one for each item in a `Vector`, filled in a row at a time.
+///
+/// The charts can share their x and/or y ranges, so they can be compared at a glance. When a
+/// range is shared, its tick labels are only drawn on the outside of the grid.
+pub struct ChartGrid<T, W> {
+    columns: usize,
+    spacing: f64,
+    share_x: bool,
+    share_y: bool,
+    make_chart: Box<dyn Fn() -> W>,
+    children: Vec<WidgetPod<T, W>>,
+    // retained state
+    /// The data each chart is given, after sharing the ranges.
+    cells: Option<Vec<T>>,
+    /// The shared ranges given to the charts, if any.
+    x_range: Option<Range>,
+    y_range: Option<Range>,
+}
+
+impl<T: GridData, W: Widget<T>> ChartGrid<T, W> {
+    /// Create a grid with `columns` charts in each row. `make_chart` is called to make the chart
+    /// for each item.
+    pub fn new(columns: usize, make_chart: impl Fn() -> W + 'static) -> Self {
+        assert!(columns > 0, "a chart grid needs at least one column");
+        ChartGrid {
+            columns,
+            spacing: 0.,
+            share_x: false,
+            share_y: false,
+            make_chart: Box::new(make_chart),
+            children: Vec::new(),
+            cells: None,
+            x_range: None,
+            y_range: None,
+        }
+    }
+
+    /// Set the gap between the charts. The default is `0`.
+    pub fn with_spacing(mut self, spacing: f64) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Give all the charts the same x range, covering all of their data. The x tick labels are
+    /// only drawn on the charts at the bottom of each column.
+    pub fn with_shared_x(mut self, share_x: bool) -> Self {
+        self.share_x = share_x;
+        self
+    }
+
+    /// Give all the charts the same y range, covering all of their data. The y tick labels are
+    /// only drawn on the left hand column.
+    pub fn with_shared_y(mut self, share_y: bool) -> Self {
+        self.share_y = share_y;
+        self
+    }
+
+    /// Make sure there is a chart for each item. Returns `true` if the charts changed.
+    fn update_child_count(&mut self, data: &Vector<T>) -> bool {
+        let len = self.children.len();
+        if len > data.len() {
+            self.children.truncate(data.len());
+        } else {
+            for _ in len..data.len() {
+                self.children.push(WidgetPod::new((self.make_chart)()));
+            }
+        }
+        len != data.len()
+    }
+
+    /// Work out the shared ranges and the data for each chart, if the data has changed since
+    /// they were last worked out.
+    fn rebuild_cells_if_needed(&mut self, data: &Vector<T>) {
+        if self.cells.is_some() {
+            return;
+        }
+        self.x_range = shared_range(self.share_x, data.iter().map(GridData::data_x_range));
+        self.y_range = shared_range(self.share_y, data.iter().map(GridData::data_y_range));
+        let cells = data
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| {
+                let mut item = item.clone();
+                self.share(idx, data.len(), &mut item);
+                item
+            })
+            .collect();
+        self.cells = Some(cells);
+    }
+
+    /// Give the data of the chart at `idx` (of `len`) the shared ranges.
+    fn share(&self, idx: usize, len: usize, cell: &mut T) {
+        if let Some(x_range) = self.x_range {
+            cell.set_x_range(x_range);
+            // there's no chart below this one.
+            cell.set_x_tick_labels(idx + self.columns >= len);
+        }
+        if let Some(y_range) = self.y_range {
+            cell.set_y_range(y_range);
+            cell.set_y_tick_labels(idx % self.columns == 0);
+        }
+    }
+
+    /// Take the shared ranges back out of the data of a chart, leaving what the item had.
+    fn unshare(&self, cell: &mut T, item: &T) {
+        if self.x_range.is_some() {
+            cell.restore_x(item);
+        }
+        if self.y_range.is_some() {
+            cell.restore_y(item);
+        }
+    }
+}
+
+impl<T: GridData, W: Widget<T>> Widget<Vector<T>> for ChartGrid<T, W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut Vector<T>, env: &Env) {
+        self.rebuild_cells_if_needed(data);
+        let mut cells = self.cells.take().unwrap();
+        for (idx, cell) in cells.iter_mut().enumerate().take(self.children.len()) {
+            self.children[idx].event(ctx, event, cell, env);
+            // the chart changed its data (e.g. it was zoomed), so keep the change, but not the
+            // shared ranges, which would stop them following the data.
+            self.unshare(cell, &data[idx]);
+            if !cell.same(&data[idx]) {
+                data.set(idx, cell.clone());
+            }
+            self.share(idx, data.len(), cell);
+        }
+        self.cells = Some(cells);
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &Vector<T>,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            if self.update_child_count(data) {
+                ctx.children_changed();
+            }
+        }
+        self.rebuild_cells_if_needed(data);
+        let cells = self.cells.as_ref().unwrap();
+        for (child, cell) in self.children.iter_mut().zip(cells) {
+            child.lifecycle(ctx, event, cell, env);
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &Vector<T>, data: &Vector<T>, env: &Env) {
+        if !old_data.same(data) {
+            self.cells = None;
+        }
+        self.rebuild_cells_if_needed(data);
+        let cells = self.cells.as_ref().unwrap();
+        for (child, cell) in self.children.iter_mut().zip(cells) {
+            child.update(ctx, cell, env);
+        }
+        if self.update_child_count(data) {
+            ctx.children_changed();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &Vector<T>,
+        env: &Env,
+    ) -> Size {
+        let columns = self.columns;
+        let rows = row_count(data.len(), columns);
+        // When unbounded, give each cell the size a lone chart would take.
+        let preferred = Size::new(
+            columns as f64 * (DEFAULT_CHART_SIZE.width + self.spacing) - self.spacing,
+            rows as f64 * (DEFAULT_CHART_SIZE.height + self.spacing) - self.spacing,
+        );
+        let size = chart_size(bc, preferred);
+        let cell_size = Size::new(
+            cell_length(size.width, columns, self.spacing),
+            cell_length(size.height, rows, self.spacing),
+        );
+        self.rebuild_cells_if_needed(data);
+        let cells = self.cells.as_ref().unwrap();
+        for (idx, (child, cell)) in self.children.iter_mut().zip(cells).enumerate() {
+            let child_size = child.layout(ctx, &BoxConstraints::tight(cell_size), cell, env);
+            let origin = Point::new(
+                (idx % columns) as f64 * (cell_size.width + self.spacing),
+                (idx / columns) as f64 * (cell_size.height + self.spacing),
+            );
+            child.set_layout_rect(ctx, cell, env, Rect::from_origin_size(origin, child_size));
+        }
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &Vector<T>, env: &Env) {
+        self.rebuild_cells_if_needed(data);
+        let cells = self.cells.as_ref().unwrap();
+        for (child, cell) in self.children.iter_mut().zip(cells) {
+            child.paint(ctx, cell, env);
+        }
+    }
+}
+
+/// The range covering all of `ranges`, if `shared` is set.
+fn shared_range(shared: bool, ranges: impl Iterator<Item = Option<Range>>) -> Option<Range> {
+    if !shared {
+        return None;
+    }
+    ranges.flatten().fold(None, |total: Option<Range>, range| {
+        Some(match total {
+            Some(mut total) => {
+                total.extend_to(range.min());
+                total.extend_to(range.max());
+                total
+            }
+            None => range,
+        })
+    })
+}
+
+/// The number of rows needed for `count` charts.
+fn row_count(count: usize, columns: usize) -> usize {
+    (count + columns - 1) / columns
+}
+
+/// The length of each of `count` cells along `length`, with `spacing` between them.
+fn cell_length(length: f64, count: usize, spacing: f64) -> f64 {
+    if count == 0 {
+        return 0.;
+    }
+    ((length - spacing * (count - 1) as f64) / count as f64).max(0.)
+}
+
+#[test]
+fn test_layout_helpers() {
+    assert_eq!(row_count(0, 3), 0);
+    assert_eq!(row_count(4, 3), 2);
+    assert_eq!(cell_length(100., 3, 5.), 30.);
+    assert_eq!(
+        shared_range(
+            true,
+            vec![Some(Range::new(0., 1.)), None, Some(Range::new(-1., 0.5))].into_iter()
+        ),
+        Some(Range::new(-1., 1.))
+    );
+    assert_eq!(
+        shared_range(false, vec![Some(Range::new(0., 1.))].into_iter()),
+        None
+    );
+}
+
+#[cfg(test)]
+#[derive(Clone, Data)]
+struct TestCell {
+    x_range: Option<Range>,
+    x_tick_labels: bool,
+    zoomed: bool,
+}
+
+#[cfg(test)]
+impl GridData for TestCell {
+    fn data_x_range(&self) -> Option<Range> {
+        self.x_range
+    }
+
+    fn data_y_range(&self) -> Option<Range> {
+        None
+    }
+
+    fn set_x_range(&mut self, range: Range) {
+        self.x_range = Some(range);
+    }
+
+    fn set_y_range(&mut self, _: Range) {}
+
+    fn set_x_tick_labels(&mut self, visible: bool) {
+        self.x_tick_labels = visible;
+    }
+
+    fn set_y_tick_labels(&mut self, _: bool) {}
+
+    fn restore_x(&mut self, original: &Self) {
+        self.x_range = original.x_range;
+        self.x_tick_labels = original.x_tick_labels;
+    }
+
+    fn restore_y(&mut self, _: &Self) {}
+}
+
+#[test]
+fn test_unshare() {
+    let mut grid = ChartGrid::new(2, druid::widget::SizedBox::empty).with_shared_x(true);
+    let data: Vector<TestCell> = vec![
+        TestCell {
+            x_range: None,
+            x_tick_labels: false,
+            zoomed: false,
+        },
+        TestCell {
+            x_range: Some(Range::new(0., 2.)),
+            x_tick_labels: false,
+            zoomed: false,
+        },
+    ]
+    .into();
+    grid.rebuild_cells_if_needed(&data);
+    let mut cell = grid.cells.as_ref().unwrap()[0].clone();
+    assert_eq!(cell.x_range, Some(Range::new(0., 2.)));
+    assert!(cell.x_tick_labels);
+    // the chart changed its own data, which is kept, but the shared range isn't.
+    cell.zoomed = true;
+    grid.unshare(&mut cell, &data[0]);
+    assert_eq!(cell.x_range, None);
+    assert!(!cell.x_tick_labels);
+    assert!(cell.zoomed);
+}
@@ -0,0 +1,1006 @@
+//! Several kinds of plot drawn on one set of axes.
+use druid::{
+    kurbo::{BezPath, Line, Point, Rect},
+    piet::PietText,
+    ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, Insets, KeyOrValue, LayoutCtx,
+    LifeCycle, LifeCycleCtx, MouseButton, PaintCtx, RenderContext, Size, UpdateCtx, Widget,
+};
+
+use crate::{
+    axes::Scale,
+    canvas::Canvas,
+    chart_size,
+    commands::{
+        PointSelected, CHART_POINT_SELECTED, COPY_CHART_IMAGE, EXPORT_IMAGE, RESET_CHART_VIEW,
+        ZOOM_TO_RANGE,
+    },
+    context_menu::{copy_image, ChartMenu, MenuItems},
+    empty::EmptyState,
+    fill::{BarStyle, Fill},
+    format::Formatter,
+    hit_test::{HitInfo, HIT_RADIUS},
+    interaction::CartesianChart,
+    legend::{Legend, LegendPlacement},
+    line_chart::{finish_line, line_path, Interpolation, MissingValues},
+    new_color,
+    offscreen::{export_image, OffscreenChart},
+    scatter::{Dot, POINT_RADIUS},
+    theme,
+    title::ChartTitle,
+    trend::{line_across, RegressionStats},
+    Range, DEFAULT_CHART_SIZE,
+};
+
+/// One plot in a [`ChartStack`], drawn on the axes it shares with the other layers.
+///
+/// Implement this to draw your own kind of plot (a custom glyph plot, say) and get the axes,
+/// title, legend and interactions of the stack for free.
+pub trait PlotLayer<T> {
+    /// The `(x, y)` values the layer covers, so the axes can be made to fit every layer. An axis
+    /// the layer doesn't mind about is `None`.
+    fn data_range(&self, data: &T) -> (Option<Range>, Option<Range>);
+
+    /// The name of the layer in the legend. Layers without one are left out of it.
+    fn label(&self) -> Option<ArcStr> {
+        None
+    }
+
+    /// The color to draw in. If `None`, the layer gets the color of the series with its index.
+    fn color(&self) -> Option<&KeyOrValue<Color>> {
+        None
+    }
+
+    /// Work out what to draw, e.g. where the data goes in pixels. This is called after layout,
+    /// whenever the data or the scales have changed.
+    fn rebuild(
+        &mut self,
+        text: &mut PietText,
+        data: &T,
+        env: &Env,
+        x_scale: &Scale,
+        y_scale: &Scale,
+    );
+
+    /// Draw what was worked out in `rebuild`. Drawing is cut off at the edges of the graph.
+    fn paint(&self, ctx: &mut Canvas, env: &Env, color: &Color);
+
+    /// The item under `pos`, if there is one. The stack fills in `series` with the index of the
+    /// layer.
+    fn hit_test(&self, _pos: Point) -> Option<HitInfo> {
+        None
+    }
+
+    /// The indices of the items inside `rect`.
+    fn points_in(&self, _rect: Rect) -> Vec<usize> {
+        Vec::new()
+    }
+}
+
+/// Draws several plots (layers) over each other on a shared pair of axes, e.g. bars with a line
+/// over them, or points with a regression line through them.
+///
+/// The stack owns the scales, and makes them big enough for all of the layers. Each layer gets
+/// its points from the data with a function, so the layers can show different parts of it.
+///
+/// Clicking on an item sends `CHART_POINT_SELECTED`, with the index of its layer as the series.
+/// The stack is a [`CartesianChart`], so it can be wrapped in `Zoomable`, `Selectable` or
+/// `Tooltip`.
+pub struct ChartStack<T> {
+    layers: Vec<Box<dyn PlotLayer<T>>>,
+    title: ChartTitle,
+    x_formatter: Formatter,
+    y_formatter: Formatter,
+    /// If `None`, the x axis fits the layers.
+    x_range: Option<Range>,
+    /// If `None`, the y axis fits the layers.
+    y_range: Option<Range>,
+    draw_grid: bool,
+    preferred_size: Size,
+    legend: Option<Legend>,
+    // retained
+    /// The part of the x axis the user zoomed in to.
+    x_view: Option<Range>,
+    /// The part of the y axis the user zoomed in to.
+    y_view: Option<Range>,
+    x_scale: Option<Scale>,
+    y_scale: Option<Scale>,
+    graph_bounds: Rect,
+    /// Whether the layers have been rebuilt since the data or scales changed.
+    layers_built: bool,
+    /// The index of the layer for each legend entry.
+    legend_layers: Vec<usize>,
+    empty_state: EmptyState,
+    context_menu: Option<ChartMenu>,
+}
+
+impl<T: Data> ChartStack<T> {
+    pub fn new() -> Self {
+        ChartStack {
+            layers: Vec::new(),
+            title: ChartTitle::new(),
+            x_formatter: Formatter::default(),
+            y_formatter: Formatter::default(),
+            x_range: None,
+            y_range: None,
+            draw_grid: false,
+            preferred_size: DEFAULT_CHART_SIZE,
+            legend: None,
+            x_view: None,
+            y_view: None,
+            x_scale: None,
+            y_scale: None,
+            graph_bounds: Rect::ZERO,
+            layers_built: false,
+            legend_layers: Vec::new(),
+            empty_state: EmptyState::new(),
+            context_menu: None,
+        }
+    }
+
+    /// Add a layer on top of the ones already added.
+    pub fn with_layer(mut self, layer: impl PlotLayer<T> + 'static) -> Self {
+        self.layers.push(Box::new(layer));
+        self
+    }
+
+    /// Draw a title above the graph.
+    pub fn with_title(mut self, title: impl Into<ArcStr>) -> Self {
+        self.title.set_text(title.into());
+        self
+    }
+
+    /// Set how the numbers on the x axis are formatted.
+    pub fn with_x_formatter(mut self, formatter: Formatter) -> Self {
+        self.x_formatter = formatter;
+        self
+    }
+
+    /// Set how the numbers on the y axis are formatted.
+    pub fn with_y_formatter(mut self, formatter: Formatter) -> Self {
+        self.y_formatter = formatter;
+        self
+    }
+
+    /// Show `range` on the x axis, instead of fitting it to the layers.
+    pub fn with_x_range(mut self, range: impl Into<Range>) -> Self {
+        self.x_range = Some(range.into());
+        self
+    }
+
+    /// Show `range` on the y axis, instead of fitting it to the layers.
+    pub fn with_y_range(mut self, range: impl Into<Range>) -> Self {
+        self.y_range = Some(range.into());
+        self
+    }
+
+    /// Draw horizontal grid lines behind the layers.
+    pub fn with_grid(mut self, draw_grid: bool) -> Self {
+        self.draw_grid = draw_grid;
+        self
+    }
+
+    /// Show a legend with an entry for each layer that has a label.
+    pub fn with_legend(mut self, placement: LegendPlacement) -> Self {
+        self.legend = Some(Legend::new(placement));
+        self
+    }
+
+    /// Set the size the chart would like to be, when it can be any size.
+    pub fn with_preferred_size(mut self, size: impl Into<Size>) -> Self {
+        self.preferred_size = size.into();
+        self
+    }
+
+    /// Show a menu when the chart is right-clicked, to reset the zoom or copy the chart as an
+    /// image. `A` is the type of your app's data, which druid needs to show a menu.
+    pub fn with_context_menu<A: Data>(mut self) -> Self {
+        self.context_menu = Some(ChartMenu::new::<A>());
+        self
+    }
+
+    /// The ranges to show: where the user has zoomed to, or else the fixed ranges, or else ranges
+    /// that cover all the layers. `None` if there is nothing to show.
+    fn data_ranges(&self, data: &T) -> Option<(Range, Range)> {
+        let (mut x_range, mut y_range) = (self.x_range, self.y_range);
+        for layer in self.layers.iter() {
+            let (layer_x, layer_y) = layer.data_range(data);
+            if self.x_range.is_none() {
+                x_range = union(x_range, layer_x);
+            }
+            if self.y_range.is_none() {
+                y_range = union(y_range, layer_y);
+            }
+        }
+        Some((self.x_view.or(x_range)?, self.y_view.or(y_range)?))
+    }
+
+    /// Take the legend labels from the layers, when the chart is added.
+    fn set_legend_labels(&mut self) {
+        if let Some(legend) = self.legend.as_mut() {
+            let mut labels = Vec::new();
+            self.legend_layers.clear();
+            for (idx, layer) in self.layers.iter().enumerate() {
+                if let Some(label) = layer.label() {
+                    labels.push(label);
+                    self.legend_layers.push(idx);
+                }
+            }
+            legend.set_labels(labels);
+        }
+    }
+
+    /// Lay out the chart, or the empty state if there is no data.
+    fn lay_out(&mut self, text: &mut PietText, size: Size, data: &T, env: &Env) {
+        if self.data_ranges(data).is_none() {
+            self.empty_state.rebuild_if_needed(text, env);
+        } else {
+            self.rebuild_if_needed(text, size, data, env);
+        }
+    }
+
+    fn rebuild_if_needed(&mut self, text: &mut PietText, size: Size, data: &T, env: &Env) {
+        let margin = env.get(theme::MARGIN);
+        let scale_margin = env.get(theme::SCALE_MARGIN);
+        self.title.rebuild_if_needed(text, env);
+        if let Some(legend) = self.legend.as_mut() {
+            legend.rebuild_if_needed(text, env);
+        }
+        if self.x_scale.is_none() || self.y_scale.is_none() {
+            let (x_range, y_range) = self.data_ranges(data).unwrap();
+            let mut x_scale = Scale::new_x(x_range);
+            x_scale.set_formatter(self.x_formatter.clone());
+            self.x_scale = Some(x_scale);
+            let mut y_scale = Scale::new_y(y_range);
+            y_scale.set_formatter(self.y_formatter.clone());
+            self.y_scale = Some(y_scale);
+            self.layers_built = false;
+        }
+
+        // Lay out the scales for the whole area first, so we know how big their labels are.
+        let mut draw_area = self.title.reserve_space(size.to_rect(), env);
+        if let Some(legend) = self.legend.as_mut() {
+            draw_area = legend.reserve_space(draw_area, env);
+        }
+        let x_scale = self.x_scale.as_mut().unwrap();
+        x_scale.set_graph_bounds(draw_area);
+        x_scale.rebuild_if_needed(text, env);
+        let x_labels = x_scale.max_layout();
+        let y_scale = self.y_scale.as_mut().unwrap();
+        y_scale.set_graph_bounds(draw_area);
+        y_scale.rebuild_if_needed(text, env);
+        let y_labels = y_scale.max_layout();
+
+        // the title has its own space above the draw area.
+        let graph_bounds = draw_area.inset(Insets {
+            x0: -(margin + y_labels.width + scale_margin),
+            y0: -margin,
+            x1: -margin,
+            y1: -(margin + x_labels.height + scale_margin),
+        });
+        if self.graph_bounds != graph_bounds {
+            self.graph_bounds = graph_bounds;
+            self.layers_built = false;
+        }
+        if let Some(legend) = self.legend.as_mut() {
+            legend.set_graph_bounds(graph_bounds, env);
+        }
+        let x_scale = self.x_scale.as_mut().unwrap();
+        x_scale.set_graph_bounds(graph_bounds);
+        x_scale.rebuild_if_needed(text, env);
+        let y_scale = self.y_scale.as_mut().unwrap();
+        y_scale.set_graph_bounds(graph_bounds);
+        y_scale.rebuild_if_needed(text, env);
+
+        if !self.layers_built {
+            let x_scale = self.x_scale.as_ref().unwrap();
+            let y_scale = self.y_scale.as_ref().unwrap();
+            for layer in self.layers.iter_mut() {
+                layer.rebuild(text, data, env, x_scale, y_scale);
+            }
+            self.layers_built = true;
+        }
+    }
+
+    /// Draw the chart, as laid out by `layout`.
+    fn draw(&mut self, ctx: &mut Canvas, data: &T, env: &Env) {
+        if !self.layers_built || self.data_ranges(data).is_none() {
+            self.empty_state.draw(ctx);
+            return;
+        }
+        if self.draw_grid {
+            self.y_scale.as_ref().unwrap().draw_grid(ctx, env);
+        }
+        let layers = &self.layers;
+        let graph_bounds = self.graph_bounds;
+        ctx.with_save(|ctx| {
+            ctx.clip(graph_bounds);
+            for (idx, layer) in layers.iter().enumerate() {
+                layer.paint(ctx, env, &layer_color(&**layer, idx, env));
+            }
+        });
+
+        self.title.draw(ctx, env);
+        self.x_scale.as_mut().unwrap().draw(ctx, env, true, true);
+        self.y_scale.as_mut().unwrap().draw(ctx, env, true, true);
+
+        if let Some(legend) = self.legend.as_mut() {
+            let legend_layers = &self.legend_layers;
+            legend.draw(ctx, env, |entry| {
+                let idx = legend_layers[entry];
+                layer_color(&*layers[idx], idx, env)
+            });
+        }
+    }
+}
+
+impl<T: Data> CartesianChart<T> for ChartStack<T> {
+    fn scales(&self) -> Option<(&Scale, &Scale)> {
+        Some((self.x_scale.as_ref()?, self.y_scale.as_ref()?))
+    }
+
+    fn set_view(&mut self, _data: &mut T, x_range: Option<Range>, y_range: Option<Range>) {
+        // the view belongs to the stack, not the data.
+        if x_range.is_some() {
+            self.x_view = x_range;
+        }
+        if y_range.is_some() {
+            self.y_view = y_range;
+        }
+        self.x_scale = None;
+        self.y_scale = None;
+    }
+
+    fn reset_view(&mut self, _data: &mut T) {
+        self.x_view = None;
+        self.y_view = None;
+        self.x_scale = None;
+        self.y_scale = None;
+    }
+
+    fn hit_test(&self, pos: Point, _data: &T) -> Option<HitInfo> {
+        if !self.layers_built || !self.graph_bounds.contains(pos) {
+            return None;
+        }
+        // the top layer gets first go.
+        self.layers
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(series, layer)| {
+                let hit = layer.hit_test(pos)?;
+                Some(HitInfo { series, ..hit })
+            })
+    }
+
+    fn points_in(&self, rect: Rect, _data: &T) -> Vec<PointSelected> {
+        if !self.layers_built {
+            return Vec::new();
+        }
+        self.layers
+            .iter()
+            .enumerate()
+            .flat_map(|(series, layer)| {
+                layer
+                    .points_in(rect)
+                    .into_iter()
+                    .map(move |index| PointSelected::new(series, index))
+            })
+            .collect()
+    }
+}
+
+impl<T: Data> Widget<T> for ChartStack<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        let items = MenuItems {
+            reset_zoom: self.x_view.is_some() || self.y_view.is_some(),
+            ..MenuItems::default()
+        };
+        if let Some(context_menu) = self.context_menu.as_mut() {
+            context_menu.event(ctx, event, items, Vec::new);
+        }
+        match event {
+            Event::Command(cmd) if cmd.is(ZOOM_TO_RANGE) => {
+                let zoom = cmd.get_unchecked(ZOOM_TO_RANGE);
+                self.set_view(data, zoom.x_range, zoom.y_range);
+                ctx.request_layout();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(RESET_CHART_VIEW) => {
+                self.reset_view(data);
+                ctx.request_layout();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_IMAGE) => {
+                copy_image(ctx, env, |ctx| self.draw(ctx, data, env));
+            }
+            Event::Command(cmd) if cmd.is(EXPORT_IMAGE) => {
+                let scale = *cmd.get_unchecked(EXPORT_IMAGE);
+                export_image(ctx, scale, env, |ctx| self.draw(ctx, data, env));
+            }
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Left => {
+                if let Some(hit) = <Self as CartesianChart<T>>::hit_test(self, mouse.pos, data) {
+                    ctx.submit_notification(CHART_POINT_SELECTED.with(hit.point()));
+                    ctx.set_handled();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &T, _env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            self.set_legend_labels();
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, _env: &Env) {
+        if self.title.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if let Some(legend) = self.legend.as_mut() {
+            if legend.needs_rebuild_after_update(ctx) {
+                ctx.request_layout();
+            }
+        }
+        if !old_data.same(data) {
+            self.x_scale = None;
+            self.y_scale = None;
+            ctx.request_layout();
+        } else {
+            let mut needs_rebuild = false;
+            for scale in [&mut self.x_scale, &mut self.y_scale]
+                .iter_mut()
+                .filter_map(|scale| scale.as_mut())
+            {
+                needs_rebuild |= scale.needs_rebuild_after_update(ctx);
+            }
+            if needs_rebuild {
+                self.layers_built = false;
+                ctx.request_layout();
+            }
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let size = chart_size(bc, self.preferred_size);
+        self.lay_out(ctx.text(), size, data, env);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.draw(&mut Canvas::new(ctx, env), data, env);
+    }
+}
+
+impl<T: Data> OffscreenChart<T> for ChartStack<T> {
+    fn draw_offscreen(&mut self, ctx: &mut Canvas, data: &T, env: &Env) {
+        self.set_legend_labels();
+        let size = ctx.size();
+        self.lay_out(ctx.text(), size, data, env);
+        self.draw(ctx, data, env);
+    }
+}
+
+/// The color to draw layer `idx` in.
+pub(crate) fn layer_color<T>(layer: &dyn PlotLayer<T>, idx: usize, env: &Env) -> Color {
+    match layer.color() {
+        Some(color) => color.resolve(env),
+        None => new_color(idx, env),
+    }
+}
+
+/// A bar for each point, from 0 up to its y value, centered on its x value.
+pub struct BarLayer<T> {
+    points: Box<dyn Fn(&T) -> Vec<Point>>,
+    label: Option<ArcStr>,
+    color: Option<KeyOrValue<Color>>,
+    /// How wide each bar is, in data units.
+    bar_width: f64,
+    fill: Fill,
+    style: BarStyle,
+    // retained
+    /// Each bar in pixels, or `None` where the point is missing.
+    bars: Vec<Option<Rect>>,
+}
+
+impl<T> BarLayer<T> {
+    /// Draw bars for the points `points` gets from the data.
+    pub fn new(points: impl Fn(&T) -> Vec<Point> + 'static) -> Self {
+        BarLayer {
+            points: Box::new(points),
+            label: None,
+            color: Some(theme::BAR_COLOR.into()),
+            bar_width: 0.8,
+            fill: Fill::Solid,
+            style: BarStyle::default(),
+            bars: Vec::new(),
+        }
+    }
+
+    /// Name the layer in the legend.
+    pub fn with_label(mut self, label: impl Into<ArcStr>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Set the color of the bars. The default is `theme::BAR_COLOR`.
+    pub fn with_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    /// Set how wide each bar is, in the units of the x axis. The default is `0.8`, which leaves
+    /// gaps between bars at each whole number.
+    pub fn with_bar_width(mut self, bar_width: f64) -> Self {
+        self.bar_width = bar_width;
+        self
+    }
+
+    /// Fill the bars with `fill` instead of a flat color, e.g. a gradient or hatch pattern.
+    pub fn with_fill(mut self, fill: Fill) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    /// Round the corners of the bars. `radius` is at the normal size (see `theme::SIZE_SCALE`).
+    pub fn with_corner_radius(mut self, radius: f64) -> Self {
+        self.style.corner_radius = radius;
+        self
+    }
+
+    /// Draw a line `width` wide around each bar.
+    pub fn with_bar_border(mut self, color: impl Into<KeyOrValue<Color>>, width: f64) -> Self {
+        self.style.border = Some((color.into(), width));
+        self
+    }
+}
+
+impl<T> PlotLayer<T> for BarLayer<T> {
+    fn data_range(&self, data: &T) -> (Option<Range>, Option<Range>) {
+        let points = (self.points)(data);
+        let half_width = self.bar_width * 0.5;
+        let x_range = Range::from_finite(
+            points
+                .iter()
+                .flat_map(|point| vec![point.x - half_width, point.x + half_width]),
+        );
+        // the bars start at 0.
+        let y_range = Range::from_finite(points.iter().map(|point| point.y))
+            .map(|range| Range::from_iter(vec![range.min(), range.max(), 0.]));
+        (x_range, y_range)
+    }
+
+    fn label(&self) -> Option<ArcStr> {
+        self.label.clone()
+    }
+
+    fn color(&self) -> Option<&KeyOrValue<Color>> {
+        self.color.as_ref()
+    }
+
+    fn rebuild(
+        &mut self,
+        _text: &mut PietText,
+        data: &T,
+        _env: &Env,
+        x_scale: &Scale,
+        y_scale: &Scale,
+    ) {
+        let half_width = self.bar_width * 0.5;
+        self.bars = (self.points)(data)
+            .into_iter()
+            .map(|point| {
+                if !(point.x.is_finite() && point.y.is_finite()) {
+                    return None;
+                }
+                let bar = Rect::new(
+                    x_scale.pixel_location(point.x - half_width),
+                    y_scale.pixel_location(point.y),
+                    x_scale.pixel_location(point.x + half_width),
+                    y_scale.pixel_location(0.),
+                );
+                Some(bar.abs())
+            })
+            .collect();
+    }
+
+    fn paint(&self, ctx: &mut Canvas, env: &Env, color: &Color) {
+        // drawn like the bars of a `BarChart`.
+        let snap = ctx.snap();
+        for bar in self.bars.iter().flatten() {
+            let shape = self.style.shape(snap.rect(*bar), env);
+            self.style.paint(ctx, &shape, self.fill, color, env);
+        }
+    }
+
+    fn hit_test(&self, pos: Point) -> Option<HitInfo> {
+        let index = self
+            .bars
+            .iter()
+            .position(|bar| bar.map_or(false, |bar| bar.contains(pos)))?;
+        Some(HitInfo {
+            series: 0,
+            index,
+            position: self.bars[index].unwrap().center(),
+            distance: 0.,
+        })
+    }
+
+    fn points_in(&self, rect: Rect) -> Vec<usize> {
+        positions_in(
+            self.bars.iter().map(|bar| bar.map(|bar| bar.center())),
+            rect,
+        )
+    }
+}
+
+/// A line through the points, in order, drawn like a series of a [`LineChart`]. By default it is
+/// broken at any missing values.
+///
+/// [`LineChart`]: crate::LineChart
+pub struct LineLayer<T> {
+    points: Box<dyn Fn(&T) -> Vec<Point>>,
+    label: Option<ArcStr>,
+    color: Option<KeyOrValue<Color>>,
+    stroke_width: KeyOrValue<f64>,
+    missing_values: MissingValues,
+    interpolation: Interpolation,
+    // retained
+    /// Each point in pixels, or `None` where it is missing.
+    pixels: Vec<Option<Point>>,
+    path: BezPath,
+}
+
+impl<T> LineLayer<T> {
+    /// Draw a line through the points `points` gets from the data.
+    pub fn new(points: impl Fn(&T) -> Vec<Point> + 'static) -> Self {
+        LineLayer {
+            points: Box::new(points),
+            label: None,
+            color: None,
+            stroke_width: theme::LINE_STROKE_WIDTH.into(),
+            missing_values: MissingValues::Gap,
+            interpolation: Interpolation::Linear,
+            pixels: Vec::new(),
+            path: BezPath::new(),
+        }
+    }
+
+    /// Name the layer in the legend.
+    pub fn with_label(mut self, label: impl Into<ArcStr>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Set the color of the line. The default is the series color for the layer.
+    pub fn with_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    /// Set the width of the line. The default is `theme::LINE_STROKE_WIDTH`.
+    pub fn with_stroke_width(mut self, width: impl Into<KeyOrValue<f64>>) -> Self {
+        self.stroke_width = width.into();
+        self
+    }
+
+    /// Choose how missing values in the data are drawn. The default is to leave a gap.
+    pub fn with_missing_values(mut self, missing_values: MissingValues) -> Self {
+        self.missing_values = missing_values;
+        self
+    }
+
+    /// Choose how the points are joined up. The default is straight lines.
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+}
+
+impl<T> PlotLayer<T> for LineLayer<T> {
+    fn data_range(&self, data: &T) -> (Option<Range>, Option<Range>) {
+        point_ranges(&(self.points)(data))
+    }
+
+    fn label(&self) -> Option<ArcStr> {
+        self.label.clone()
+    }
+
+    fn color(&self) -> Option<&KeyOrValue<Color>> {
+        self.color.as_ref()
+    }
+
+    fn rebuild(
+        &mut self,
+        _text: &mut PietText,
+        data: &T,
+        _env: &Env,
+        x_scale: &Scale,
+        y_scale: &Scale,
+    ) {
+        self.pixels = to_pixels(&(self.points)(data), x_scale, y_scale);
+        let missing = Point::new(f64::NAN, f64::NAN);
+        let points = self
+            .pixels
+            .iter()
+            .map(|pixel| pixel.unwrap_or(missing))
+            .collect();
+        self.path = line_path(
+            finish_line(points, self.missing_values, self.interpolation),
+            self.interpolation,
+        );
+    }
+
+    fn paint(&self, ctx: &mut Canvas, env: &Env, color: &Color) {
+        ctx.stroke(&self.path, color, self.stroke_width.resolve(env));
+    }
+
+    fn hit_test(&self, pos: Point) -> Option<HitInfo> {
+        nearest(&self.pixels, pos, HIT_RADIUS)
+    }
+
+    fn points_in(&self, rect: Rect) -> Vec<usize> {
+        positions_in(self.pixels.iter().copied(), rect)
+    }
+}
+
+/// A dot at each point, drawn like the points of a [`ScatterPlot`].
+///
+/// [`ScatterPlot`]: crate::ScatterPlot
+pub struct ScatterLayer<T> {
+    points: Box<dyn Fn(&T) -> Vec<Point>>,
+    label: Option<ArcStr>,
+    color: Option<KeyOrValue<Color>>,
+    radius: KeyOrValue<f64>,
+    // retained
+    /// Each point in pixels, or `None` where it is missing.
+    pixels: Vec<Option<Point>>,
+    /// The radius of the dots, in pixels.
+    pixel_radius: f64,
+}
+
+impl<T> ScatterLayer<T> {
+    /// Draw a dot for each of the points `points` gets from the data.
+    pub fn new(points: impl Fn(&T) -> Vec<Point> + 'static) -> Self {
+        ScatterLayer {
+            points: Box::new(points),
+            label: None,
+            color: None,
+            radius: POINT_RADIUS.into(),
+            pixels: Vec::new(),
+            pixel_radius: 0.,
+        }
+    }
+
+    /// Name the layer in the legend.
+    pub fn with_label(mut self, label: impl Into<ArcStr>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Set the color of the dots. The default is the series color for the layer.
+    pub fn with_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    /// Set the radius of the dots. The default is `3`.
+    pub fn with_radius(mut self, radius: impl Into<KeyOrValue<f64>>) -> Self {
+        self.radius = radius.into();
+        self
+    }
+}
+
+impl<T> PlotLayer<T> for ScatterLayer<T> {
+    fn data_range(&self, data: &T) -> (Option<Range>, Option<Range>) {
+        point_ranges(&(self.points)(data))
+    }
+
+    fn label(&self) -> Option<ArcStr> {
+        self.label.clone()
+    }
+
+    fn color(&self) -> Option<&KeyOrValue<Color>> {
+        self.color.as_ref()
+    }
+
+    fn rebuild(
+        &mut self,
+        _text: &mut PietText,
+        data: &T,
+        env: &Env,
+        x_scale: &Scale,
+        y_scale: &Scale,
+    ) {
+        self.pixels = to_pixels(&(self.points)(data), x_scale, y_scale);
+        self.pixel_radius = self.radius.resolve(env) * env.get(theme::SIZE_SCALE);
+    }
+
+    fn paint(&self, ctx: &mut Canvas, _env: &Env, color: &Color) {
+        for pixel in self.pixels.iter().flatten() {
+            let dot = Dot {
+                center: *pixel,
+                color_value: None,
+                radius: self.pixel_radius,
+            };
+            dot.paint(ctx, color);
+        }
+    }
+
+    fn hit_test(&self, pos: Point) -> Option<HitInfo> {
+        nearest(&self.pixels, pos, HIT_RADIUS.max(self.pixel_radius))
+    }
+
+    fn points_in(&self, rect: Rect) -> Vec<usize> {
+        positions_in(self.pixels.iter().copied(), rect)
+    }
+}
+
+/// The least-squares straight line through the points, drawn across the whole x axis like the
+/// reference line of a [`ScatterPlot`](crate::ScatterPlot).
+///
+/// It doesn't change the ranges of the axes, so put it over a layer that shows the points.
+pub struct RegressionLayer<T> {
+    points: Box<dyn Fn(&T) -> Vec<Point>>,
+    label: Option<ArcStr>,
+    color: KeyOrValue<Color>,
+    stroke_width: KeyOrValue<f64>,
+    // retained
+    /// The line in pixels, if one could be fitted.
+    line: Option<Line>,
+}
+
+impl<T> RegressionLayer<T> {
+    /// Fit a line to the points `points` gets from the data.
+    pub fn new(points: impl Fn(&T) -> Vec<Point> + 'static) -> Self {
+        RegressionLayer {
+            points: Box::new(points),
+            label: None,
+            color: theme::ANNOTATION_COLOR.into(),
+            stroke_width: 2.0.into(),
+            line: None,
+        }
+    }
+
+    /// Name the layer in the legend.
+    pub fn with_label(mut self, label: impl Into<ArcStr>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Set the color of the line. The default is `theme::ANNOTATION_COLOR`.
+    pub fn with_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    /// Set the width of the line. The default is 2.
+    pub fn with_stroke_width(mut self, width: impl Into<KeyOrValue<f64>>) -> Self {
+        self.stroke_width = width.into();
+        self
+    }
+}
+
+impl<T> PlotLayer<T> for RegressionLayer<T> {
+    fn data_range(&self, _data: &T) -> (Option<Range>, Option<Range>) {
+        (None, None)
+    }
+
+    fn label(&self) -> Option<ArcStr> {
+        self.label.clone()
+    }
+
+    fn color(&self) -> Option<&KeyOrValue<Color>> {
+        Some(&self.color)
+    }
+
+    fn rebuild(
+        &mut self,
+        _text: &mut PietText,
+        data: &T,
+        _env: &Env,
+        x_scale: &Scale,
+        y_scale: &Scale,
+    ) {
+        let points = (self.points)(data);
+        let xs: Vec<f64> = points.iter().map(|point| point.x).collect();
+        let ys: Vec<f64> = points.iter().map(|point| point.y).collect();
+        self.line = RegressionStats::fit(&xs, &ys)
+            .map(|stats| line_across(x_scale, y_scale, stats.slope, stats.intercept));
+    }
+
+    fn paint(&self, ctx: &mut Canvas, env: &Env, color: &Color) {
+        if let Some(line) = self.line {
+            ctx.stroke(line, color, self.stroke_width.resolve(env));
+        }
+    }
+}
+
+/// Where each point goes in pixels, or `None` where it is missing.
+fn to_pixels(points: &[Point], x_scale: &Scale, y_scale: &Scale) -> Vec<Option<Point>> {
+    points
+        .iter()
+        .map(|point| {
+            if point.x.is_finite() && point.y.is_finite() {
+                Some(Point::new(
+                    x_scale.pixel_location(point.x),
+                    y_scale.pixel_location(point.y),
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// The pixel closest to `pos`, if it is within `radius`.
+fn nearest(pixels: &[Option<Point>], pos: Point, radius: f64) -> Option<HitInfo> {
+    let mut closest: Option<HitInfo> = None;
+    let present = pixels
+        .iter()
+        .enumerate()
+        .filter_map(|(index, pixel)| Some((index, (*pixel)?)));
+    for (index, pixel) in present {
+        let distance = pixel.distance(pos);
+        if distance <= closest.map_or(radius, |closest| closest.distance) {
+            closest = Some(HitInfo {
+                series: 0,
+                index,
+                position: pixel,
+                distance,
+            });
+        }
+    }
+    closest
+}
+
+/// The indices of the positions inside `rect`.
+fn positions_in(positions: impl Iterator<Item = Option<Point>>, rect: Rect) -> Vec<usize> {
+    positions
+        .enumerate()
+        .filter(|(_, position)| position.map_or(false, |position| rect.contains(position)))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// The ranges of the x and y values of `points`.
+fn point_ranges(points: &[Point]) -> (Option<Range>, Option<Range>) {
+    (
+        Range::from_finite(points.iter().map(|point| point.x)),
+        Range::from_finite(points.iter().map(|point| point.y)),
+    )
+}
+
+/// The smallest range covering both ranges.
+pub(crate) fn union(a: Option<Range>, b: Option<Range>) -> Option<Range> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(Range::from_iter(vec![a.min(), a.max(), b.min(), b.max()])),
+        (a, b) => a.or(b),
+    }
+}
+
+#[test]
+fn test_bar_layer_range() {
+    let layer = BarLayer::new(|data: &Vec<Point>| data.clone()).with_bar_width(1.);
+    let (x_range, y_range) = layer.data_range(&vec![Point::new(0., 2.), Point::new(2., 5.)]);
+    assert_eq!(x_range, Some(Range::new(-0.5, 2.5)));
+    // the bars go down to 0.
+    assert_eq!(y_range, Some(Range::new(0., 5.)));
+    assert_eq!(union(x_range, None), x_range);
+    assert_eq!(
+        union(y_range, Some(Range::new(-1., 3.))),
+        Some(Range::new(-1., 5.))
+    );
+}
+
+#[test]
+fn test_nearest() {
+    let pixels = [Some(Point::new(0., 0.)), None, Some(Point::new(10., 0.))];
+    let hit = nearest(&pixels, Point::new(7., 0.), 5.).unwrap();
+    assert_eq!((hit.index, hit.distance), (2, 3.));
+    assert!(nearest(&pixels, Point::new(5., 20.), 5.).is_none());
+    assert_eq!(
+        positions_in(pixels.iter().copied(), Rect::new(-1., -1., 1., 1.)),
+        vec![0]
+    );
+}
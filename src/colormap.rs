@@ -0,0 +1,108 @@
+//! Mapping continuous values to colors.
+use druid::{kurbo::Rect, Color, Data, RenderContext};
+
+use crate::canvas::Canvas;
+
+/// The width of a bar showing which color is which value.
+pub(crate) const COLOR_BAR_WIDTH: f64 = 12.;
+/// How many steps a color bar is drawn in.
+const COLOR_BAR_STEPS: usize = 32;
+
+/// A map from values in `0..=1` to colors, for showing a value with color.
+///
+/// The sequential maps (`Viridis` and `Magma`) are perceptually uniform, so equal steps in value
+/// look like equal steps in color. `Coolwarm` is diverging, for values either side of a midpoint.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Data)]
+pub enum Colormap {
+    Viridis,
+    Magma,
+    Coolwarm,
+}
+
+// Evenly spaced samples of each map, which we interpolate between. Taken from matplotlib.
+const VIRIDIS: [u32; 9] = [
+    0x440154, 0x472d7b, 0x3b528b, 0x2c728e, 0x21918c, 0x28ae80, 0x5ec962, 0xaddc30, 0xfde725,
+];
+const MAGMA: [u32; 9] = [
+    0x000004, 0x1c1044, 0x4f127b, 0x812581, 0xb5367a, 0xe55064, 0xfb8761, 0xfec287, 0xfcfdbf,
+];
+const COOLWARM: [u32; 9] = [
+    0x3b4cc0, 0x6282ea, 0x8db0fe, 0xb8d0f9, 0xdddddd, 0xf5c4ac, 0xf49a7b, 0xde604d, 0xb40426,
+];
+
+impl Colormap {
+    /// The color for `t`, which is clamped to `0..=1`.
+    pub fn value_to_color(self, t: f64) -> Color {
+        let stops = match self {
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Magma => &MAGMA,
+            Colormap::Coolwarm => &COOLWARM,
+        };
+        let t = if t.is_nan() { 0. } else { t.max(0.).min(1.) };
+        let pos = t * (stops.len() - 1) as f64;
+        let idx = (pos.floor() as usize).min(stops.len() - 2);
+        let frac = pos - idx as f64;
+        let (r0, g0, b0) = rgb(stops[idx]);
+        let (r1, g1, b1) = rgb(stops[idx + 1]);
+        let lerp = |a: f64, b: f64| (a + frac * (b - a)) / 255.;
+        Color::rgb(lerp(r0, r1), lerp(g0, g1), lerp(b0, b1))
+    }
+
+    /// The color for `value`, where `min` maps to the start of the map and `max` to the end.
+    pub fn map_value(self, value: f64, min: f64, max: f64) -> Color {
+        let t = if max > min {
+            (value - min) / (max - min)
+        } else {
+            0.5
+        };
+        self.value_to_color(t)
+    }
+}
+
+/// Draw `colormap` filling `bounds`, from the start of the map at the bottom to the end at the
+/// top.
+pub(crate) fn draw_color_bar(ctx: &mut Canvas, colormap: Colormap, bounds: Rect) {
+    let step = bounds.height() / COLOR_BAR_STEPS as f64;
+    for idx in 0..COLOR_BAR_STEPS {
+        let y1 = bounds.y1 - idx as f64 * step;
+        let color = colormap.value_to_color((idx as f64 + 0.5) / COLOR_BAR_STEPS as f64);
+        ctx.fill(Rect::new(bounds.x0, y1 - step, bounds.x1, y1), &color);
+    }
+}
+
+fn rgb(color: u32) -> (f64, f64, f64) {
+    (
+        ((color >> 16) & 0xff) as f64,
+        ((color >> 8) & 0xff) as f64,
+        (color & 0xff) as f64,
+    )
+}
+
+#[test]
+fn test_ends() {
+    assert_eq!(
+        Colormap::Viridis.value_to_color(0.).as_rgba_u32(),
+        0x440154ff
+    );
+    assert_eq!(
+        Colormap::Viridis.value_to_color(1.).as_rgba_u32(),
+        0xfde725ff
+    );
+    // out of range values are clamped.
+    assert_eq!(
+        Colormap::Magma.value_to_color(-1.).as_rgba_u32(),
+        0x000004ff
+    );
+    assert_eq!(
+        Colormap::Coolwarm.value_to_color(0.5).as_rgba_u32(),
+        0xddddddff
+    );
+}
+
+#[test]
+fn test_map_value() {
+    assert_eq!(
+        Colormap::Coolwarm.map_value(0., -10., 10.).as_rgba_u32(),
+        0xddddddff
+    );
+}
@@ -0,0 +1,136 @@
+//! Commands and notifications sent by (or to) the chart widgets.
+use druid::{ImageBuf, Selector};
+
+use crate::Range;
+
+/// Sent as a notification when the user clicks on a data point, bar or slice.
+///
+/// Handle it in a `Controller` (or any ancestor widget) to respond to the selection.
+pub const CHART_POINT_SELECTED: Selector<PointSelected> =
+    Selector::new("org.derekdreery.druid-graphs.chart-point-selected");
+
+/// Sent as a notification when the user clicks on (or selects with the keyboard) the slice a
+/// `PieChart` puts its small slices together in. It holds the indices of the categories in it.
+///
+/// This is sent instead of `CHART_POINT_SELECTED`, as the slice isn't one point in the data.
+pub const PIE_OTHER_SLICE_SELECTED: Selector<Vec<usize>> =
+    Selector::new("org.derekdreery.druid-graphs.pie-other-slice-selected");
+
+/// Identifies a single data point in a chart.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PointSelected {
+    /// The index of the series the point belongs to. Charts with only one series always use `0`.
+    pub series: usize,
+    /// The index of the point within the series.
+    pub index: usize,
+}
+
+impl PointSelected {
+    pub fn new(series: usize, index: usize) -> Self {
+        PointSelected { series, index }
+    }
+}
+
+/// Send this command to a chart to undo any panning and zooming, so it shows all its data again.
+///
+/// The charts that respond to [`ZOOM_TO_RANGE`] respond to this too.
+pub const RESET_CHART_VIEW: Selector =
+    Selector::new("org.derekdreery.druid-graphs.reset-chart-view");
+
+/// Send this command to a chart to turn its grid lines on or off, by flipping the `draw_*_grid`
+/// flags in its data.
+pub const TOGGLE_CHART_GRID: Selector =
+    Selector::new("org.derekdreery.druid-graphs.toggle-chart-grid");
+
+/// Send this command to a chart to put its data on the clipboard as CSV, with a header row.
+pub const COPY_CHART_DATA: Selector = Selector::new("org.derekdreery.druid-graphs.copy-chart-data");
+
+/// Send this command to a chart to put a picture of it on the clipboard, as a PNG image at the
+/// resolution of the screen.
+pub const COPY_CHART_IMAGE: Selector =
+    Selector::new("org.derekdreery.druid-graphs.copy-chart-image");
+
+/// Send this command to a chart to show just part of its data, as if the user had zoomed in.
+///
+/// Only charts with a number line to zoom along respond to it:
+///
+/// - `LineChart` writes the new view into its data, just like when the user zooms.
+/// - `ScatterPlot` (and `QqPlot`), `DensityPlot`, `StackedArea` and `ChartStack` keep the view
+///   themselves, until they are sent [`RESET_CHART_VIEW`].
+/// - `Histogram` (and `BinnedHistogram`) only respond when the buckets have `bin_edges`, so the x
+///   axis is a number line.
+/// - `SharedXPanels` only zoom the shared x axis, as each panel has its own y axis.
+///
+/// The others are left out: `BarChart`, `BoxPlot` and `ParetoChart` put each category in its own
+/// slot along the x axis, so there is no range of x values to show, and `PieChart` and
+/// `RoseChart` have no x and y axes at all.
+pub const ZOOM_TO_RANGE: Selector<ZoomToRange> =
+    Selector::new("org.derekdreery.druid-graphs.zoom-to-range");
+
+/// The part of a chart to show. An axis without a range stays as it is.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ZoomToRange {
+    pub x_range: Option<Range>,
+    pub y_range: Option<Range>,
+}
+
+impl ZoomToRange {
+    /// Show the x values in `x_range`, leaving the y axis as it is.
+    pub fn x(x_range: Range) -> Self {
+        ZoomToRange {
+            x_range: Some(x_range),
+            y_range: None,
+        }
+    }
+
+    /// Show the x values in `x_range` and the y values in `y_range`.
+    pub fn xy(x_range: Range, y_range: Range) -> Self {
+        ZoomToRange {
+            x_range: Some(x_range),
+            y_range: Some(y_range),
+        }
+    }
+}
+
+/// Send this command to a chart to draw a focus ring around one of its points, bars or slices,
+/// as if the user had moved there with the arrow keys. `None` removes it.
+///
+/// Keyboard navigation must be turned on, with `with_keyboard_navigation(true)`: charts without it
+/// ignore this command. `BarChart`, `Histogram`, `LineChart` and `PieChart` have keyboard
+/// navigation. The ring is shown even when the chart doesn't have focus, until the user moves it
+/// with the keyboard.
+pub const HIGHLIGHT_POINT: Selector<Option<PointSelected>> =
+    Selector::new("org.derekdreery.druid-graphs.highlight-point");
+
+/// Sent as a notification when the user drags out a rectangle over a chart to select part of it.
+pub const CHART_RANGE_SELECTED: Selector<RangeSelected> =
+    Selector::new("org.derekdreery.druid-graphs.chart-range-selected");
+
+/// The part of a chart the user selected, and the data points inside it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeSelected {
+    pub x_range: Range,
+    /// `None` if only a range of x values was selected.
+    pub y_range: Option<Range>,
+    /// The points inside the selection, in series order.
+    pub points: Vec<PointSelected>,
+}
+
+/// Send this command to a chart to draw it onto an image, just as it is on screen. The chart sends
+/// the image up in a [`CHART_IMAGE_EXPORTED`] notification.
+///
+/// The payload is the number of pixels per display point, e.g. `2.0` for an image twice the size
+/// of the widget. The image is drawn straight away, so keep it to sizes that draw quickly; use
+/// [`render_chart_offscreen`](crate::render_chart_offscreen) for big ones.
+pub const EXPORT_IMAGE: Selector<f64> = Selector::new("org.derekdreery.druid-graphs.export-image");
+
+/// Sent as a notification by a chart when it has been drawn onto an image, for an
+/// [`EXPORT_IMAGE`] command. The error says why it couldn't be, e.g. the image was too big.
+pub const CHART_IMAGE_EXPORTED: Selector<Result<ImageBuf, String>> =
+    Selector::new("org.derekdreery.druid-graphs.chart-image-exported");
+
+/// Sent by [`render_offscreen`](crate::render_offscreen) and
+/// [`render_chart_offscreen`](crate::render_chart_offscreen) to the widget that asked for the
+/// image, when it has been drawn. The error says why it couldn't be, e.g. the image was too big.
+pub const OFFSCREEN_RENDER_DONE: Selector<Result<ImageBuf, String>> =
+    Selector::new("org.derekdreery.druid-graphs.offscreen-render-done");
@@ -0,0 +1,211 @@
+//! The right-click menu a chart can show, with actions for its view and data.
+use druid::{
+    commands::SHOW_SAVE_PANEL, Application, ClipboardFormat, ContextMenu, Data, Env, Event,
+    EventCtx, FileDialogOptions, FileInfo, FileSpec, ImageBuf, LocalizedString, MenuDesc, MenuItem,
+    MouseButton, Point, Selector,
+};
+use std::{fs, rc::Rc};
+
+use crate::{
+    canvas::Canvas,
+    commands::{COPY_CHART_DATA, COPY_CHART_IMAGE, RESET_CHART_VIEW, TOGGLE_CHART_GRID},
+    offscreen::render_canvas,
+};
+
+/// Sent by the "Export CSV" item to the chart that showed the menu, to ask where to save the data.
+const EXPORT_CSV: Selector = Selector::new("org.derekdreery.druid-graphs.export-csv");
+
+/// Sent by the save dialog when the user has picked a file for the data.
+const SAVE_CSV_AS: Selector<FileInfo> = Selector::new("org.derekdreery.druid-graphs.save-csv-as");
+
+/// Sent by the save dialog when the user closes it without picking a file.
+const SAVE_CSV_CANCELLED: Selector =
+    Selector::new("org.derekdreery.druid-graphs.save-csv-cancelled");
+
+/// The clipboard format for PNG images, which each platform names differently.
+#[cfg(target_os = "macos")]
+const PNG_FORMAT: &str = "public.png";
+#[cfg(target_os = "windows")]
+const PNG_FORMAT: &str = "PNG";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const PNG_FORMAT: &str = "image/png";
+
+/// Which of the optional items to put in the menu. The chart must handle the commands they send.
+#[derive(Debug, Copy, Clone, Default)]
+pub(crate) struct MenuItems {
+    /// "Reset zoom", which sends `RESET_CHART_VIEW`.
+    pub reset_zoom: bool,
+    /// "Toggle grid", which sends `TOGGLE_CHART_GRID`.
+    pub grid: bool,
+    /// "Copy data as CSV", which sends `COPY_CHART_DATA`, and "Export CSV...".
+    pub data: bool,
+}
+
+/// Shows a context menu when the chart is right-clicked.
+///
+/// Druid only shows menus built for the app's data type, which the chart doesn't know about, so
+/// the menu is built by a function chosen when the chart is configured. The menu items all send
+/// commands to the chart that showed the menu, so they work whatever the app's data is.
+#[derive(Clone)]
+pub(crate) struct ChartMenu {
+    show: Rc<dyn Fn(&mut EventCtx, Point, MenuItems)>,
+    /// Whether this chart opened the save dialog that is showing. The dialog's answer goes to
+    /// every widget in the window, and only this chart should save its data.
+    saving_csv: bool,
+}
+
+impl ChartMenu {
+    /// A menu for an app whose root data is `T`.
+    pub fn new<T: Data>() -> Self {
+        ChartMenu {
+            show: Rc::new(|ctx, pos, items| {
+                let id = ctx.widget_id();
+                let mut menu = MenuDesc::<T>::empty();
+                if items.reset_zoom {
+                    menu = menu.append(MenuItem::new(
+                        LocalizedString::new("druid-graphs-reset-zoom")
+                            .with_placeholder("Reset zoom"),
+                        RESET_CHART_VIEW.to(id),
+                    ));
+                }
+                if items.grid {
+                    menu = menu.append(MenuItem::new(
+                        LocalizedString::new("druid-graphs-toggle-grid")
+                            .with_placeholder("Toggle grid"),
+                        TOGGLE_CHART_GRID.to(id),
+                    ));
+                }
+                menu = menu.append(MenuItem::new(
+                    LocalizedString::new("druid-graphs-copy-image").with_placeholder("Copy image"),
+                    COPY_CHART_IMAGE.to(id),
+                ));
+                if items.data {
+                    menu = menu
+                        .append(MenuItem::new(
+                            LocalizedString::new("druid-graphs-copy-data")
+                                .with_placeholder("Copy data as CSV"),
+                            COPY_CHART_DATA.to(id),
+                        ))
+                        .append(MenuItem::new(
+                            LocalizedString::new("druid-graphs-export-csv")
+                                .with_placeholder("Export CSV..."),
+                            EXPORT_CSV.to(id),
+                        ));
+                }
+                ctx.show_context_menu(ContextMenu::new(menu, pos));
+            }),
+            saving_csv: false,
+        }
+    }
+
+    /// Show the menu on a right click, and save the data to a file when the user asks.
+    /// `csv_table` gives the data as a table, with a header row, when it is needed.
+    pub fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        items: MenuItems,
+        csv_table: impl FnOnce() -> Vec<Vec<String>>,
+    ) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Right => {
+                (self.show)(ctx, mouse.window_pos, items);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(EXPORT_CSV) => {
+                let options = FileDialogOptions::new()
+                    .allowed_types(vec![FileSpec::new("CSV", &["csv"])])
+                    .default_name("data.csv")
+                    .accept_command(SAVE_CSV_AS)
+                    .cancel_command(SAVE_CSV_CANCELLED);
+                ctx.submit_command(SHOW_SAVE_PANEL.with(options));
+                self.saving_csv = true;
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(SAVE_CSV_AS) && self.saving_csv => {
+                self.saving_csv = false;
+                let path = cmd.get_unchecked(SAVE_CSV_AS).path();
+                if let Err(err) = fs::write(path, to_csv(csv_table())) {
+                    log::error!("failed to save chart data to {}: {}", path.display(), err);
+                }
+                ctx.set_handled();
+            }
+            // not handled, as the dialog isn't ours if we weren't saving.
+            Event::Command(cmd) if cmd.is(SAVE_CSV_CANCELLED) => self.saving_csv = false,
+            _ => (),
+        }
+    }
+}
+
+/// Put `table` (a header row followed by the data) on the clipboard as CSV, so it can be pasted
+/// into a spreadsheet.
+pub(crate) fn copy_csv(table: Vec<Vec<String>>) {
+    Application::global().clipboard().put_string(to_csv(table));
+}
+
+/// Handle a [`COPY_CHART_IMAGE`] command: draw the widget with `draw` at the resolution of the
+/// screen, and put it on the clipboard as a PNG image.
+pub(crate) fn copy_image(ctx: &mut EventCtx, env: &Env, draw: impl FnOnce(&mut Canvas)) {
+    let scale = ctx.scale().x();
+    let bytes = render_canvas(ctx.size(), scale, env, draw)
+        .map_err(|err| err.to_string())
+        .and_then(|image| to_png(&image).map_err(|err| err.to_string()));
+    match bytes {
+        Ok(bytes) => Application::global()
+            .clipboard()
+            .put_formats(&[ClipboardFormat::new(PNG_FORMAT, bytes)]),
+        Err(err) => log::error!("failed to copy the chart as an image: {}", err),
+    }
+    ctx.set_handled();
+}
+
+/// Encode `image` as a PNG. The image is drawn over the window background, so there are no
+/// see-through pixels to un-premultiply.
+fn to_png(image: &ImageBuf) -> Result<Vec<u8>, png::EncodingError> {
+    let mut bytes = Vec::new();
+    {
+        let (width, height) = (image.width() as u32, image.height() as u32);
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(image.raw_pixels())?;
+    }
+    Ok(bytes)
+}
+
+/// Write `table` as CSV, quoting any cells that need it.
+fn to_csv(table: Vec<Vec<String>>) -> String {
+    let mut writer = csv::WriterBuilder::new().flexible(true).from_writer(vec![]);
+    // writing strings to memory can't fail.
+    for row in table {
+        writer.write_record(row).unwrap();
+    }
+    String::from_utf8(writer.into_inner().unwrap()).unwrap()
+}
+
+/// A cell for a value that might be missing.
+pub(crate) fn csv_cell(value: Option<f64>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+#[test]
+fn test_to_csv() {
+    let table = vec![
+        vec!["category".to_string(), "Sales, 2020".to_string()],
+        vec!["Apples".to_string(), csv_cell(Some(1.5))],
+        vec!["Pears".to_string(), csv_cell(None)],
+    ];
+    assert_eq!(
+        to_csv(table),
+        "category,\"Sales, 2020\"\nApples,1.5\nPears,\n"
+    );
+}
+
+#[test]
+fn test_to_png() {
+    let pixels = vec![255, 0, 0, 255, 0, 0, 255, 255];
+    let image = ImageBuf::from_raw(pixels, druid::piet::ImageFormat::RgbaPremul, 2, 1);
+    let png = to_png(&image).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+}
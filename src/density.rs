@@ -0,0 +1,624 @@
+use druid::{
+    im::Vector,
+    kurbo::{BezPath, Point, Rect, Shape, Vec2},
+    piet::PietText,
+    ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, Insets, KeyOrValue, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, RenderContext, Size, TextLayout, UpdateCtx, Widget,
+};
+use druid_lens_compose::ComposeLens;
+use std::{collections::BTreeMap, f64::consts::PI};
+
+use crate::{
+    axes::{Direction, Scale},
+    canvas::Canvas,
+    chart_size,
+    colormap::{draw_color_bar, Colormap, COLOR_BAR_WIDTH},
+    commands::{COPY_CHART_DATA, COPY_CHART_IMAGE, EXPORT_IMAGE, RESET_CHART_VIEW, ZOOM_TO_RANGE},
+    context_menu::{copy_csv, copy_image, csv_cell, ChartMenu, MenuItems},
+    describe::{count, describe},
+    empty::EmptyState,
+    format::Formatter,
+    offscreen::{export_image, OffscreenChart},
+    theme,
+    title::{ChartTitle, TitleAlignment},
+    Range, SeriesData, DEFAULT_CHART_SIZE,
+};
+
+/// `(x, y)` pairs to be counted up in a grid, for scatter data too dense to draw point by point.
+/// The values can be any [`SeriesData`], e.g. a `Vector<i32>` or an `Arc<Vec<f64>>`.
+#[derive(Debug, Clone, Data, ComposeLens)]
+pub struct DensityPlotData<S = Vector<f64>> {
+    pub title: ArcStr,
+    pub x_axis_label: ArcStr,
+    /// The x value of each point.
+    pub x: S,
+    /// The y value of each point. Points missing either value are left out.
+    pub y: S,
+}
+
+impl<S: SeriesData> DensityPlotData<S> {
+    /// The data as a table, with a row for each point.
+    fn csv_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![vec!["x".to_string(), "y".to_string()]];
+        for idx in 0..self.x.len().max(self.y.len()) {
+            table.push(vec![csv_cell(self.x.get(idx)), csv_cell(self.y.get(idx))]);
+        }
+        table
+    }
+}
+
+/// The shape of the cells the points are counted in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Data)]
+pub enum CellShape {
+    Square,
+    /// Hexagons tile the plane like squares do, but their neighbours are all the same distance
+    /// away, so the bins follow the shape of the data better.
+    Hexagon,
+}
+
+impl CellShape {
+    /// The size of the cells (the side of a square, or the radius of a hexagon) to fit `count`
+    /// cells across `width` pixels.
+    fn size(self, width: f64, count: usize) -> f64 {
+        let count = count.max(1) as f64;
+        match self {
+            CellShape::Square => width / count,
+            // a hexagon with its points up and down is √3 × its radius wide.
+            CellShape::Hexagon => width / (count * 3f64.sqrt()),
+        }
+    }
+
+    /// Which cell `p` is in, for cells of the given `size`.
+    fn cell_at(self, p: Point, size: f64) -> (i64, i64) {
+        match self {
+            CellShape::Square => ((p.x / size).floor() as i64, (p.y / size).floor() as i64),
+            CellShape::Hexagon => {
+                // axial coordinates, see https://www.redblobgames.com/grids/hexagons/
+                let q = (3f64.sqrt() / 3. * p.x - p.y / 3.) / size;
+                let r = (2. / 3. * p.y) / size;
+                hex_round(q, r)
+            }
+        }
+    }
+
+    /// The middle of `cell`, for cells of the given `size`.
+    fn cell_center(self, (i, j): (i64, i64), size: f64) -> Point {
+        let (i, j) = (i as f64, j as f64);
+        match self {
+            CellShape::Square => Point::new((i + 0.5) * size, (j + 0.5) * size),
+            CellShape::Hexagon => Point::new(size * 3f64.sqrt() * (i + 0.5 * j), size * 1.5 * j),
+        }
+    }
+
+    /// The outline of the cell centered on `center`.
+    fn cell_path(self, center: Point, size: f64) -> BezPath {
+        match self {
+            CellShape::Square => Rect::from_center_size(center, (size, size)).to_path(0.1),
+            CellShape::Hexagon => {
+                let mut path = BezPath::new();
+                for corner in 0..6 {
+                    let angle = PI / 6. + corner as f64 * PI / 3.;
+                    let point = center + Vec2::from_angle(angle) * size;
+                    if corner == 0 {
+                        path.move_to(point);
+                    } else {
+                        path.line_to(point);
+                    }
+                }
+                path.close_path();
+                path
+            }
+        }
+    }
+}
+
+/// Round fractional axial hexagon coordinates to the hexagon they are in.
+fn hex_round(q: f64, r: f64) -> (i64, i64) {
+    let s = -q - r;
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    // the coordinates must add up to 0, so fix the one that was rounded furthest.
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+    (rq as i64, rr as i64)
+}
+
+/// Count the points in each cell, for cells of the given shape and size. Only the cells with
+/// points in are returned, in order.
+fn count_cells(
+    points: impl IntoIterator<Item = Point>,
+    shape: CellShape,
+    size: f64,
+) -> Vec<((i64, i64), usize)> {
+    let mut counts = BTreeMap::new();
+    for point in points {
+        *counts.entry(shape.cell_at(point, size)).or_insert(0) += 1;
+    }
+    counts.into_iter().collect()
+}
+
+/// A 2D histogram: the plot area is divided into cells, and each is colored by how many points
+/// fall in it.
+pub struct DensityPlot {
+    cell_shape: CellShape,
+    /// How many cells there are across the plot.
+    cells_across: usize,
+    colormap: Colormap,
+    x_formatter: Formatter,
+    y_formatter: Formatter,
+    preferred_size: Size,
+    // retained state
+    title: ChartTitle,
+    x_label_layout: TextLayout<ArcStr>,
+    x_scale: Option<Scale>,
+    y_scale: Option<Scale>,
+    /// The part of the x axis zoomed in to with `ZOOM_TO_RANGE`.
+    x_view: Option<Range>,
+    /// The part of the y axis zoomed in to with `ZOOM_TO_RANGE`.
+    y_view: Option<Range>,
+    /// The counts, by the color bar.
+    count_scale: Option<Scale>,
+    /// The outline and color of each cell with points in.
+    cells: Option<Vec<(BezPath, Color)>>,
+    /// The area the cells are drawn in.
+    graph_bounds: Rect,
+    empty_state: EmptyState,
+    context_menu: Option<ChartMenu>,
+}
+
+impl DensityPlot {
+    pub fn new() -> Self {
+        DensityPlot {
+            cell_shape: CellShape::Hexagon,
+            cells_across: 30,
+            colormap: Colormap::Viridis,
+            x_formatter: Formatter::default(),
+            y_formatter: Formatter::default(),
+            preferred_size: DEFAULT_CHART_SIZE,
+            title: ChartTitle::new(),
+            x_label_layout: TextLayout::new(),
+            x_scale: None,
+            y_scale: None,
+            x_view: None,
+            y_view: None,
+            count_scale: None,
+            cells: None,
+            graph_bounds: Rect::ZERO,
+            empty_state: EmptyState::new(),
+            context_menu: None,
+        }
+    }
+
+    /// Choose the shape of the cells. The default is `CellShape::Hexagon`.
+    pub fn with_cell_shape(mut self, shape: CellShape) -> Self {
+        self.cell_shape = shape;
+        self
+    }
+
+    /// Set how many cells fit across the plot. Fewer, bigger cells smooth out the noise. The
+    /// default is 30.
+    pub fn with_cells_across(mut self, count: usize) -> Self {
+        self.cells_across = count;
+        self
+    }
+
+    /// Choose the colors for the counts. The default is `Colormap::Viridis`.
+    pub fn with_colormap(mut self, colormap: Colormap) -> Self {
+        self.colormap = colormap;
+        self
+    }
+
+    /// Set how the x axis tick labels are formatted.
+    pub fn with_x_formatter(mut self, formatter: Formatter) -> Self {
+        self.x_formatter = formatter;
+        self
+    }
+
+    /// Set how the y axis tick labels are formatted.
+    pub fn with_y_formatter(mut self, formatter: Formatter) -> Self {
+        self.y_formatter = formatter;
+        self
+    }
+
+    /// Set the font size of the title. The default is `theme::TITLE_TEXT_SIZE`.
+    pub fn with_title_size(mut self, size: impl Into<KeyOrValue<f64>>) -> Self {
+        self.title.set_text_size(size);
+        self
+    }
+
+    /// Where to put the title (and subtitle). Defaults to the center.
+    pub fn with_title_alignment(mut self, alignment: TitleAlignment) -> Self {
+        self.title.set_alignment(alignment);
+        self
+    }
+
+    /// Draw a line of smaller text under the title.
+    pub fn with_subtitle(mut self, subtitle: impl Into<ArcStr>) -> Self {
+        self.title.set_subtitle(subtitle.into());
+        self
+    }
+
+    /// Set the size to take when the space isn't bounded, e.g. inside a `Scroll`. Otherwise the
+    /// chart fills the space it is given. The default is 400×300.
+    pub fn with_preferred_size(mut self, size: impl Into<Size>) -> Self {
+        self.preferred_size = size.into();
+        self
+    }
+
+    /// Show a menu when the chart is right-clicked, to reset the zoom, copy the chart as an image,
+    /// or copy or save the data as CSV. `T` is the type of your app's data, which druid needs to
+    /// show a menu.
+    pub fn with_context_menu<T: Data>(mut self) -> Self {
+        self.context_menu = Some(ChartMenu::new::<T>());
+        self
+    }
+
+    /// A short description of the chart for screen readers, e.g. "Density plot, Blood pressure,
+    /// 500 points".
+    pub fn describe<S: SeriesData>(&self, data: &DensityPlotData<S>) -> String {
+        let points = points(data).count();
+        describe(
+            "Density plot",
+            &data.title,
+            vec![count(points, "point", "points")],
+        )
+    }
+
+    /// Show `x_view` and `y_view` of the axes, or all of an axis if its view is `None`.
+    fn set_view(&mut self, x_view: Option<Range>, y_view: Option<Range>) {
+        self.x_view = x_view;
+        self.y_view = y_view;
+        self.x_scale = None;
+        self.y_scale = None;
+        self.cells = None;
+    }
+
+    /// Take the title and axis label from `data`, when the chart is added.
+    fn set_data<S: SeriesData>(&mut self, data: &DensityPlotData<S>) {
+        self.title.set_text(data.title.clone());
+        self.x_label_layout.set_text(data.x_axis_label.clone());
+    }
+
+    /// Lay out the chart, or the empty state if there is no data.
+    fn lay_out<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &DensityPlotData<S>,
+        env: &Env,
+    ) {
+        if points(data).next().is_none() {
+            self.empty_state.rebuild_if_needed(text, env);
+        } else {
+            self.rebuild_if_needed(text, size, data, env);
+        }
+    }
+
+    /// Rebuild the layouts, scales and cells for a chart of size `size`, as needed.
+    fn rebuild_if_needed<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &DensityPlotData<S>,
+        env: &Env,
+    ) {
+        let margin = env.get(theme::MARGIN);
+        let scale_margin = env.get(theme::SCALE_MARGIN);
+        self.title.rebuild_if_needed(text, env);
+        self.x_label_layout.rebuild_if_needed(text, env);
+        if self.x_scale.is_none() || self.y_scale.is_none() {
+            let x_range = self
+                .x_view
+                .unwrap_or_else(|| Range::from_finite(points(data).map(|(x, _)| x)).unwrap());
+            let y_range = self
+                .y_view
+                .unwrap_or_else(|| Range::from_finite(points(data).map(|(_, y)| y)).unwrap());
+            let mut x_scale = Scale::new_x(x_range);
+            x_scale.set_formatter(self.x_formatter.clone());
+            self.x_scale = Some(x_scale);
+            let mut y_scale = Scale::new_y(y_range);
+            y_scale.set_formatter(self.y_formatter.clone());
+            self.y_scale = Some(y_scale);
+        }
+        if self.count_scale.is_none() {
+            // no cell can have more than all the points, so this leaves enough room for the
+            // labels until we know the real counts.
+            let mut count_scale = Scale::new((0., points(data).count() as f64), Direction::YRight);
+            count_scale.set_formatter(Formatter::precision(3));
+            self.count_scale = Some(count_scale);
+        }
+
+        // Lay out the scales for the whole area first, so we know how big their labels are.
+        let draw_area = self.title.reserve_space(size.to_rect(), env);
+        let mut label_sizes = Vec::with_capacity(3);
+        for scale in [&mut self.x_scale, &mut self.y_scale, &mut self.count_scale].iter_mut() {
+            let scale = scale.as_mut().unwrap();
+            scale.set_graph_bounds(draw_area);
+            scale.rebuild_if_needed(text, env);
+            label_sizes.push(scale.max_layout());
+        }
+        let x0 = margin + label_sizes[1].width + scale_margin;
+        // space for the color bar and its labels
+        let x1 = margin + COLOR_BAR_WIDTH + scale_margin + label_sizes[2].width + margin;
+        // the title has its own space above the draw area.
+        let y0 = margin;
+        let mut y1 = margin + label_sizes[0].height + scale_margin;
+        if !data.x_axis_label.is_empty() {
+            y1 += margin + self.x_label_layout.size().height;
+        }
+        let graph_bounds = draw_area.inset(Insets {
+            x0: -x0,
+            y0: -y0,
+            x1: -x1,
+            y1: -y1,
+        });
+        if self.graph_bounds != graph_bounds {
+            self.graph_bounds = graph_bounds;
+            // the cells are a fixed size on screen.
+            self.cells = None;
+        }
+        let x_scale = self.x_scale.as_mut().unwrap();
+        x_scale.set_graph_bounds(graph_bounds);
+        x_scale.rebuild_if_needed(text, env);
+        let y_scale = self.y_scale.as_mut().unwrap();
+        y_scale.set_graph_bounds(graph_bounds);
+        y_scale.rebuild_if_needed(text, env);
+
+        if self.cells.is_none() {
+            let shape = self.cell_shape;
+            let cell_size = shape.size(graph_bounds.width(), self.cells_across);
+            let origin = graph_bounds.origin().to_vec2();
+            let x_scale = self.x_scale.as_ref().unwrap();
+            let y_scale = self.y_scale.as_ref().unwrap();
+            // count in pixels, from the top left of the graph. Only the points in view count, so
+            // the colors show the density of the part zoomed in to.
+            let (x_range, y_range) = (x_scale.data_range(), y_scale.data_range());
+            let counts = count_cells(
+                points(data)
+                    .filter(|(x, y)| {
+                        x_range.min() <= *x
+                            && *x <= x_range.max()
+                            && y_range.min() <= *y
+                            && *y <= y_range.max()
+                    })
+                    .map(|(x, y)| {
+                        Point::new(x_scale.pixel_location(x), y_scale.pixel_location(y)) - origin
+                    }),
+                shape,
+                cell_size,
+            );
+            let max_count = counts.iter().map(|(_, count)| *count).max().unwrap_or(1);
+            let colormap = self.colormap;
+            self.cells = Some(
+                counts
+                    .into_iter()
+                    .map(|(cell, count)| {
+                        let center = shape.cell_center(cell, cell_size) + origin;
+                        (
+                            shape.cell_path(center, cell_size),
+                            colormap.map_value(count as f64, 0., max_count as f64),
+                        )
+                    })
+                    .collect(),
+            );
+            self.count_scale
+                .as_mut()
+                .unwrap()
+                .set_data_range((0., max_count as f64));
+        }
+        let color_bar_bounds = self.color_bar_bounds(env);
+        let count_scale = self.count_scale.as_mut().unwrap();
+        count_scale.set_graph_bounds(color_bar_bounds);
+        count_scale.rebuild_if_needed(text, env);
+    }
+
+    /// Where the color bar goes, to the right of the graph.
+    fn color_bar_bounds(&self, env: &Env) -> Rect {
+        let x0 = self.graph_bounds.x1 + env.get(theme::MARGIN);
+        Rect::new(
+            x0,
+            self.graph_bounds.y0,
+            x0 + COLOR_BAR_WIDTH,
+            self.graph_bounds.y1,
+        )
+    }
+
+    /// Draw the chart, as laid out by `layout`.
+    fn draw<S: SeriesData>(&mut self, ctx: &mut Canvas, data: &DensityPlotData<S>, env: &Env) {
+        if points(data).next().is_none() {
+            self.empty_state.draw(ctx);
+            return;
+        }
+        let size = ctx.size();
+
+        // cells, cut off at the edges of the graph.
+        let graph_bounds = self.graph_bounds;
+        let cells = self.cells.as_ref().unwrap();
+        ctx.with_save(|ctx| {
+            ctx.clip(graph_bounds);
+            for (path, color) in cells.iter() {
+                ctx.fill(path, color);
+            }
+        });
+
+        // color bar, from no points at the bottom to the most at the top.
+        draw_color_bar(ctx, self.colormap, self.color_bar_bounds(env));
+        self.count_scale
+            .as_mut()
+            .unwrap()
+            .draw(ctx, env, false, true);
+
+        // title
+        self.title.draw(ctx, env);
+
+        // axes
+        self.x_scale.as_mut().unwrap().draw(ctx, env, true, true);
+        if !data.x_axis_label.is_empty() {
+            let label_size = self.x_label_layout.size();
+            let margin = env.get(theme::MARGIN);
+            ctx.draw_layout(
+                &self.x_label_layout,
+                (
+                    (size.width - label_size.width) * 0.5,
+                    size.height - label_size.height - margin,
+                ),
+            );
+        }
+        self.y_scale.as_mut().unwrap().draw(ctx, env, true, true);
+    }
+}
+
+impl<S: SeriesData> Widget<DensityPlotData<S>> for DensityPlot {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut DensityPlotData<S>,
+        env: &Env,
+    ) {
+        let items = MenuItems {
+            reset_zoom: self.x_view.is_some() || self.y_view.is_some(),
+            data: true,
+            ..MenuItems::default()
+        };
+        if let Some(context_menu) = self.context_menu.as_mut() {
+            context_menu.event(ctx, event, items, || data.csv_table());
+        }
+        match event {
+            Event::Command(cmd) if cmd.is(ZOOM_TO_RANGE) => {
+                let zoom = cmd.get_unchecked(ZOOM_TO_RANGE);
+                self.set_view(zoom.x_range.or(self.x_view), zoom.y_range.or(self.y_view));
+                ctx.request_layout();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(RESET_CHART_VIEW) => {
+                self.set_view(None, None);
+                ctx.request_layout();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_DATA) => {
+                copy_csv(data.csv_table());
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_IMAGE) => {
+                copy_image(ctx, env, |ctx| self.draw(ctx, data, env));
+            }
+            Event::Command(cmd) if cmd.is(EXPORT_IMAGE) => {
+                let scale = *cmd.get_unchecked(EXPORT_IMAGE);
+                export_image(ctx, scale, env, |ctx| self.draw(ctx, data, env));
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &DensityPlotData<S>,
+        env: &Env,
+    ) {
+        match event {
+            LifeCycle::WidgetAdded => self.set_data(data),
+            _ => (),
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &DensityPlotData<S>,
+        data: &DensityPlotData<S>,
+        env: &Env,
+    ) {
+        if !old_data.title.same(&data.title) {
+            self.title.set_text(data.title.clone());
+        }
+        if self.title.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !old_data.x_axis_label.same(&data.x_axis_label) {
+            self.x_label_layout.set_text(data.x_axis_label.clone());
+        }
+        if self.x_label_layout.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !old_data.x.same(&data.x) || !old_data.y.same(&data.y) {
+            self.x_scale = None;
+            self.y_scale = None;
+            self.count_scale = None;
+            self.cells = None;
+            ctx.request_layout();
+        } else {
+            let mut needs_rebuild = false;
+            for scale in [&mut self.x_scale, &mut self.y_scale, &mut self.count_scale]
+                .iter_mut()
+                .filter_map(|scale| scale.as_mut())
+            {
+                needs_rebuild |= scale.needs_rebuild_after_update(ctx);
+            }
+            if needs_rebuild {
+                ctx.request_layout();
+            }
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &DensityPlotData<S>,
+        env: &Env,
+    ) -> Size {
+        let size = chart_size(bc, self.preferred_size);
+        self.lay_out(ctx.text(), size, data, env);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &DensityPlotData<S>, env: &Env) {
+        self.draw(&mut Canvas::new(ctx, env), data, env);
+    }
+}
+
+impl<S: SeriesData> OffscreenChart<DensityPlotData<S>> for DensityPlot {
+    fn draw_offscreen(&mut self, ctx: &mut Canvas, data: &DensityPlotData<S>, env: &Env) {
+        self.set_data(data);
+        let size = ctx.size();
+        self.lay_out(ctx.text(), size, data, env);
+        self.draw(ctx, data, env);
+    }
+}
+
+/// The points with both an x and a y value.
+fn points<S: SeriesData>(data: &DensityPlotData<S>) -> impl Iterator<Item = (f64, f64)> + '_ {
+    data.x
+        .values()
+        .zip(data.y.values())
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+}
+
+#[test]
+fn test_cell_at() {
+    for shape in [CellShape::Square, CellShape::Hexagon].iter().copied() {
+        // the middle of a cell is in that cell.
+        for cell in [(0, 0), (3, -2), (-1, 5)].iter().copied() {
+            assert_eq!(shape.cell_at(shape.cell_center(cell, 10.), 10.), cell);
+        }
+    }
+    assert_eq!(
+        CellShape::Square.cell_at(Point::new(9.9, 10.1), 10.),
+        (0, 1)
+    );
+}
+
+#[test]
+fn test_count_cells() {
+    let points = vec![Point::new(1., 1.), Point::new(2., 2.), Point::new(15., 1.)];
+    assert_eq!(
+        count_cells(points, CellShape::Square, 10.),
+        vec![((0, 0), 2), ((1, 0), 1)]
+    );
+}
@@ -0,0 +1,39 @@
+//! Short text descriptions of the charts, for screen readers and other assistive technology.
+use crate::{format::Formatter, Range};
+
+/// Join up a description: the kind of chart, then its title (if it has one), then some facts
+/// about the data.
+pub(crate) fn describe(kind: &str, title: &str, facts: impl IntoIterator<Item = String>) -> String {
+    let mut parts = vec![kind.to_string()];
+    if !title.is_empty() {
+        parts.push(title.to_string());
+    }
+    parts.extend(facts);
+    parts.join(", ")
+}
+
+/// How many of something there are, e.g. "1 point" or "200 points".
+pub(crate) fn count(count: usize, singular: &str, plural: &str) -> String {
+    format!("{} {}", count, if count == 1 { singular } else { plural })
+}
+
+/// The smallest and largest of `values`, ignoring missing values. Empty if there aren't any.
+pub(crate) fn min_max(values: impl IntoIterator<Item = f64>, formatter: &Formatter) -> Vec<String> {
+    match Range::from_finite(values) {
+        Some(range) => vec![
+            format!("min {}", formatter.format(range.min())),
+            format!("max {}", formatter.format(range.max())),
+        ],
+        None => vec![],
+    }
+}
+
+#[test]
+fn test_describe() {
+    let facts = vec![count(200, "point", "points"), count(1, "series", "series")];
+    assert_eq!(
+        describe("Line chart", "Blood pressure", facts),
+        "Line chart, Blood pressure, 200 points, 1 series"
+    );
+    assert_eq!(describe("Pie chart", "", vec![]), "Pie chart");
+}
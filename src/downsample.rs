@@ -0,0 +1,122 @@
+//! Reducing the number of points in a line without changing how it looks.
+use druid::kurbo::Point;
+
+/// Reduce a line (in pixel coordinates) to at most 4 points per pixel column.
+///
+/// For each run of points in the same column we keep the first, the lowest, the highest and the
+/// last, in their original order. Because every other point in the column lies between these,
+/// the line is drawn identically, but a million points become a few thousand.
+///
+/// Missing points (with a non-finite coordinate) are kept, so any gaps in the line stay put.
+pub fn downsample(points: impl IntoIterator<Item = Point>) -> Vec<Point> {
+    let mut out = Vec::new();
+    let mut column: Option<Column> = None;
+    for (idx, point) in points.into_iter().enumerate() {
+        if !(point.x.is_finite() && point.y.is_finite()) {
+            if let Some(column) = column.take() {
+                column.flush(&mut out);
+            }
+            out.push(point);
+            continue;
+        }
+        match column.as_mut() {
+            Some(column) if column.x == point.x.floor() => column.add(idx, point),
+            _ => {
+                if let Some(column) = column.take() {
+                    column.flush(&mut out);
+                }
+                column = Some(Column::new(idx, point));
+            }
+        }
+    }
+    if let Some(column) = column {
+        column.flush(&mut out);
+    }
+    out
+}
+
+/// The points we are keeping from a single pixel column, with their indices.
+struct Column {
+    x: f64,
+    first: (usize, Point),
+    min: (usize, Point),
+    max: (usize, Point),
+    last: (usize, Point),
+}
+
+impl Column {
+    fn new(idx: usize, point: Point) -> Self {
+        Column {
+            x: point.x.floor(),
+            first: (idx, point),
+            min: (idx, point),
+            max: (idx, point),
+            last: (idx, point),
+        }
+    }
+
+    fn add(&mut self, idx: usize, point: Point) {
+        if point.y < self.min.1.y {
+            self.min = (idx, point);
+        }
+        if point.y > self.max.1.y {
+            self.max = (idx, point);
+        }
+        self.last = (idx, point);
+    }
+
+    fn flush(self, out: &mut Vec<Point>) {
+        let mut points = [self.first, self.min, self.max, self.last];
+        points.sort_unstable_by_key(|(idx, _)| *idx);
+        let mut prev_idx = None;
+        for (idx, point) in points.iter().copied() {
+            if prev_idx != Some(idx) {
+                out.push(point);
+                prev_idx = Some(idx);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_downsample_column() {
+    let points = vec![
+        Point::new(0.1, 5.),
+        Point::new(0.2, 9.),
+        Point::new(0.3, 7.),
+        Point::new(0.4, 1.),
+        Point::new(0.5, 4.),
+        Point::new(1.5, 3.),
+    ];
+    assert_eq!(
+        downsample(points),
+        vec![
+            Point::new(0.1, 5.),
+            Point::new(0.2, 9.),
+            Point::new(0.4, 1.),
+            Point::new(0.5, 4.),
+            Point::new(1.5, 3.),
+        ]
+    );
+}
+
+#[test]
+fn test_downsample_sparse() {
+    // fewer points than columns are left alone.
+    let points: Vec<Point> = (0..10)
+        .map(|x| Point::new(x as f64 * 3., x as f64))
+        .collect();
+    assert_eq!(downsample(points.clone()), points);
+}
+
+#[test]
+fn test_downsample_gap() {
+    let points = vec![
+        Point::new(0.1, 5.),
+        Point::new(0.2, f64::NAN),
+        Point::new(0.3, 7.),
+    ];
+    let downsampled = downsample(points);
+    assert_eq!(downsampled.len(), 3);
+    assert!(downsampled[1].y.is_nan());
+}
@@ -0,0 +1,217 @@
+//! Plots of the empirical cumulative distribution of some values.
+use druid::{
+    im::Vector, ArcStr, BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, Size, UpdateCtx, Widget,
+};
+use druid_lens_compose::ComposeLens;
+use std::iter;
+
+use crate::{
+    canvas::Canvas,
+    line_chart::{Interpolation, LineChart, LineChartData, LineSeries},
+    offscreen::OffscreenChart,
+    stats, Range, SeriesData,
+};
+
+/// The data for an ECDF plot of raw values. The values can be any [`SeriesData`], e.g. a
+/// `Vector<i32>` or an `Arc<Vec<f64>>`.
+#[derive(Debug, Clone, Data, ComposeLens)]
+pub struct EcdfData<S = Vector<f64>> {
+    pub title: ArcStr,
+    pub x_axis_label: ArcStr,
+    pub values: S,
+    /// Other sets of values to compare with `values`, with their names for the legend (`values` is
+    /// named by `x_axis_label`).
+    pub overlays: Option<Vector<(ArcStr, S)>>,
+    pub draw_grid: bool,
+}
+
+/// A plot of the empirical cumulative distribution of some values: at each x, the fraction of
+/// the values that are no bigger than x. Unlike a histogram, there are no buckets to choose.
+///
+/// This is a [`LineChart`] drawn as steps, on a y axis from 0 to 1. The steps are recalculated
+/// only when the values change.
+pub struct Ecdf {
+    inner: LineChart<ArcStr, ArcStr>,
+    // retained state
+    converted: Option<LineChartData<ArcStr, ArcStr>>,
+}
+
+impl Ecdf {
+    /// Wrap a line chart (so it can be configured first). It is drawn as steps, whatever its
+    /// interpolation.
+    pub fn new(inner: LineChart<ArcStr, ArcStr>) -> Self {
+        Ecdf {
+            inner: inner.with_interpolation(Interpolation::StepAfter),
+            converted: None,
+        }
+    }
+
+    /// A short description of the plot for screen readers. See [`LineChart::describe`].
+    pub fn describe<S: SeriesData>(&self, data: &EcdfData<S>) -> String {
+        self.inner.describe(&convert(data))
+    }
+}
+
+/// The steps of the distribution of each set of values, as a line chart.
+fn convert<S: SeriesData>(data: &EcdfData<S>) -> LineChartData<ArcStr, ArcStr> {
+    let sorted = |values: &S| {
+        let mut sorted: Vec<f64> = values.values().filter(|v| v.is_finite()).collect();
+        sorted.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+        sorted
+    };
+    let samples: Vec<Vec<f64>> = iter::once(&data.values)
+        .chain(data.overlays.iter().flatten().map(|(_, values)| values))
+        .map(sorted)
+        .collect();
+    // the series share their x values, so we find each distribution wherever any of them steps.
+    let mut xs: Vec<f64> = samples.iter().flatten().copied().collect();
+    xs.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+    xs.dedup();
+    // start from 0, so the first step is drawn.
+    let x_data: Vector<f64> = xs
+        .first()
+        .copied()
+        .into_iter()
+        .chain(xs.iter().copied())
+        .collect();
+    let steps = |sample: &[f64]| -> Vector<f64> {
+        if xs.is_empty() {
+            return Vector::new();
+        }
+        iter::once(0.)
+            .chain(xs.iter().map(|x| stats::ecdf(sample, *x)))
+            .collect()
+    };
+    LineChartData {
+        title: data.title.clone(),
+        x_axis_label: data.x_axis_label.clone(),
+        x_range: None,
+        draw_x_tick_labels: true,
+        draw_x_axis: true,
+        draw_x_grid: data.draw_grid,
+        x_ticks: None,
+        x_data: Some(x_data),
+        y_axis_label: "".into(),
+        y_range: Some(Range::new(0., 1.)),
+        draw_y_tick_labels: true,
+        draw_y_axis: true,
+        draw_y_grid: data.draw_grid,
+        y_ticks: None,
+        y_data: steps(&samples[0]),
+        y_errors: None,
+        y_band: None,
+        y2_range: None,
+        y_data_label: data.x_axis_label.clone(),
+        extra_series: data
+            .overlays
+            .iter()
+            .flatten()
+            .zip(samples[1..].iter())
+            .map(|((label, _), sample)| LineSeries::new(label.clone(), steps(sample)))
+            .collect(),
+    }
+}
+
+impl<S: SeriesData> Widget<EcdfData<S>> for Ecdf {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut EcdfData<S>, env: &Env) {
+        if let Some(converted) = self.converted.as_ref() {
+            // the chart can't change the raw data.
+            let mut converted = converted.clone();
+            self.inner.event(ctx, event, &mut converted, env);
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &EcdfData<S>,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            self.converted = Some(convert(data));
+        }
+        if let Some(converted) = self.converted.as_ref() {
+            self.inner.lifecycle(ctx, event, converted, env);
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &EcdfData<S>,
+        data: &EcdfData<S>,
+        env: &Env,
+    ) {
+        let old_converted = match self.converted.take() {
+            Some(old_converted) => old_converted,
+            None => return,
+        };
+        let converted = if !old_data.values.same(&data.values)
+            || !old_data.overlays.same(&data.overlays)
+            || !old_data.x_axis_label.same(&data.x_axis_label)
+        {
+            convert(data)
+        } else {
+            // keep the steps we already have.
+            LineChartData {
+                title: data.title.clone(),
+                draw_x_grid: data.draw_grid,
+                draw_y_grid: data.draw_grid,
+                ..old_converted.clone()
+            }
+        };
+        self.inner.update(ctx, &old_converted, &converted, env);
+        self.converted = Some(converted);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _data: &EcdfData<S>,
+        env: &Env,
+    ) -> Size {
+        match self.converted.as_ref() {
+            Some(converted) => self.inner.layout(ctx, bc, converted, env),
+            None => bc.min(),
+        }
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &EcdfData<S>, env: &Env) {
+        if let Some(converted) = self.converted.as_ref() {
+            self.inner.paint(ctx, converted, env);
+        }
+    }
+}
+
+impl<S: SeriesData> OffscreenChart<EcdfData<S>> for Ecdf {
+    fn draw_offscreen(&mut self, ctx: &mut Canvas, data: &EcdfData<S>, env: &Env) {
+        let converted = convert(data);
+        self.inner.draw_offscreen(ctx, &converted, env);
+        self.converted = Some(converted);
+    }
+}
+
+#[test]
+fn test_convert() {
+    let data = EcdfData {
+        title: "".into(),
+        x_axis_label: "a".into(),
+        values: Vector::from(vec![2., 1., f64::NAN]),
+        overlays: Some(Vector::from(vec![(
+            ArcStr::from("b"),
+            Vector::from(vec![3.]),
+        )])),
+        draw_grid: false,
+    };
+    let converted = convert(&data);
+    assert_eq!(converted.x_data, Some(Vector::from(vec![1., 1., 2., 3.])));
+    assert_eq!(converted.y_data, Vector::from(vec![0., 0.5, 1., 1.]));
+    assert_eq!(converted.extra_series.len(), 1);
+    assert_eq!(
+        converted.extra_series[0].y_data,
+        Vector::from(vec![0., 0., 0., 1.])
+    );
+}
@@ -0,0 +1,40 @@
+//! What a chart shows before it has any data.
+use druid::{piet::PietText, ArcStr, Data, Env, TextLayout};
+
+use crate::{canvas::Canvas, theme};
+
+/// A placeholder message, drawn in the middle of a chart that has nothing to plot.
+///
+/// The text comes from `theme::EMPTY_MESSAGE`.
+#[derive(Clone)]
+pub(crate) struct EmptyState {
+    layout: TextLayout<ArcStr>,
+}
+
+impl EmptyState {
+    pub fn new() -> Self {
+        EmptyState {
+            layout: TextLayout::new(),
+        }
+    }
+
+    pub fn rebuild_if_needed(&mut self, text: &mut PietText, env: &Env) {
+        let message = env.get(theme::EMPTY_MESSAGE);
+        if !self.layout.text().map_or(false, |text| text.same(&message)) {
+            self.layout.set_text(message);
+        }
+        self.layout.rebuild_if_needed(text, env);
+    }
+
+    pub fn draw(&mut self, ctx: &mut Canvas) {
+        let size = ctx.size();
+        let text_size = self.layout.size();
+        ctx.draw_layout(
+            &self.layout,
+            (
+                (size.width - text_size.width) * 0.5,
+                (size.height - text_size.height) * 0.5,
+            ),
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! Showing the uncertainty of values.
+use druid::{kurbo::BezPath, Data};
+
+/// The width of the caps on the ends of an error bar, at the normal size.
+pub(crate) const CAP_WIDTH: f64 = 6.0;
+
+/// The uncertainty in a value, drawn as an error bar.
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub enum ErrorBar {
+    /// The value could be this much higher or lower.
+    Symmetric(f64),
+    /// The value could be `below` lower or `above` higher.
+    Asymmetric { below: f64, above: f64 },
+}
+
+impl ErrorBar {
+    /// The lowest and highest the value could be.
+    pub fn bounds(self, value: f64) -> (f64, f64) {
+        match self {
+            ErrorBar::Symmetric(error) => (value - error, value + error),
+            ErrorBar::Asymmetric { below, above } => (value - below, value + above),
+        }
+    }
+}
+
+/// Add an error bar from `low` to `high` at `x` (all in pixels) to `path`, with caps
+/// `cap_width` wide.
+pub(crate) fn add_error_bar(path: &mut BezPath, x: f64, low: f64, high: f64, cap_width: f64) {
+    let half_cap = cap_width * 0.5;
+    path.move_to((x, low));
+    path.line_to((x, high));
+    for y in [low, high].iter().copied() {
+        path.move_to((x - half_cap, y));
+        path.line_to((x + half_cap, y));
+    }
+}
+
+/// Add an error bar from `low` to `high` at `y` (all in pixels) to `path`, for a value that goes
+/// along the x axis.
+pub(crate) fn add_horizontal_error_bar(
+    path: &mut BezPath,
+    y: f64,
+    low: f64,
+    high: f64,
+    cap_width: f64,
+) {
+    let half_cap = cap_width * 0.5;
+    path.move_to((low, y));
+    path.line_to((high, y));
+    for x in [low, high].iter().copied() {
+        path.move_to((x, y - half_cap));
+        path.line_to((x, y + half_cap));
+    }
+}
+
+#[test]
+fn test_bounds() {
+    assert_eq!(ErrorBar::Symmetric(1.).bounds(5.), (4., 6.));
+    assert_eq!(
+        ErrorBar::Asymmetric {
+            below: 1.,
+            above: 2.
+        }
+        .bounds(5.),
+        (4., 7.)
+    );
+}
@@ -0,0 +1,247 @@
+//! How bars and areas are filled and outlined, beyond a flat color, e.g. for printing in black and
+//! white.
+use druid::{
+    kurbo::{Line, Rect, RoundedRect, Shape},
+    Color, Env, KeyOrValue, LinearGradient, RenderContext, UnitPoint,
+};
+
+use crate::{canvas::Canvas, theme};
+
+/// How a bar or area is filled with its color.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Fill {
+    /// A flat color.
+    Solid,
+    /// A vertical gradient, from the color at the top to `fade` times its opacity at the bottom,
+    /// e.g. `0.2` to fade most of the way out.
+    Gradient { fade: f64 },
+    /// Lines in the color over the background, with the outline of the shape. Give each series a
+    /// different pattern so they can be told apart without color.
+    Hatch(Hatch),
+}
+
+/// The lines of a [`Fill::Hatch`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Hatch {
+    /// `─` lines.
+    Horizontal,
+    /// `│` lines.
+    Vertical,
+    /// `╱` lines.
+    Diagonal,
+    /// `╲` lines.
+    BackDiagonal,
+    /// Horizontal and vertical lines.
+    Cross,
+    /// Both diagonals.
+    DiagonalCross,
+}
+
+impl Hatch {
+    /// A different pattern for each of the first 6 series, then the same again.
+    pub fn nth(idx: usize) -> Self {
+        const ALL: [Hatch; 6] = [
+            Hatch::Diagonal,
+            Hatch::Horizontal,
+            Hatch::BackDiagonal,
+            Hatch::Vertical,
+            Hatch::DiagonalCross,
+            Hatch::Cross,
+        ];
+        ALL[idx % ALL.len()]
+    }
+
+    /// The lines covering `bounds`, `spacing` apart. They are lined up with the origin rather
+    /// than `bounds`, so the pattern carries on from one shape to the next.
+    fn lines(self, bounds: Rect, spacing: f64) -> Vec<Line> {
+        let mut lines = Vec::new();
+        // `f(c)` is the line at `c`, for `c` in `min..=max` at multiples of `spacing`.
+        let mut add = |min: f64, max: f64, f: &dyn Fn(f64) -> Line| {
+            let mut k = (min / spacing).ceil();
+            while k * spacing <= max {
+                lines.push(f(k * spacing));
+                k += 1.;
+            }
+        };
+        let Rect { x0, y0, x1, y1 } = bounds;
+        if let Hatch::Horizontal | Hatch::Cross = self {
+            add(y0, y1, &|y| Line::new((x0, y), (x1, y)));
+        }
+        if let Hatch::Vertical | Hatch::Cross = self {
+            add(x0, x1, &|x| Line::new((x, y0), (x, y1)));
+        }
+        // `x + y = c`, going up to the right.
+        if let Hatch::Diagonal | Hatch::DiagonalCross = self {
+            add(x0 + y0, x1 + y1, &|c| Line::new((c - y1, y1), (c - y0, y0)));
+        }
+        // `x - y = c`, going down to the right.
+        if let Hatch::BackDiagonal | Hatch::DiagonalCross = self {
+            add(x0 - y1, x1 - y0, &|c| Line::new((c + y0, y0), (c + y1, y1)));
+        }
+        lines
+    }
+}
+
+impl Default for Fill {
+    fn default() -> Self {
+        Fill::Solid
+    }
+}
+
+impl Fill {
+    /// Fill `shape` with `color`.
+    pub(crate) fn paint(&self, ctx: &mut Canvas, shape: &impl Shape, color: &Color, env: &Env) {
+        match *self {
+            Fill::Solid => ctx.fill(shape, color),
+            Fill::Gradient { fade } => {
+                let alpha = (color.as_rgba_u32() & 0xff) as f64 / 255.;
+                let bottom = color.clone().with_alpha(alpha * fade.max(0.).min(1.));
+                let gradient =
+                    LinearGradient::new(UnitPoint::TOP, UnitPoint::BOTTOM, (color.clone(), bottom));
+                ctx.fill(shape, &gradient);
+            }
+            Fill::Hatch(hatch) => {
+                let width = env.get(theme::SIZE_SCALE);
+                let lines = hatch.lines(shape.bounding_box(), env.get(theme::HATCH_SPACING));
+                ctx.with_save(|ctx| {
+                    ctx.clip(shape);
+                    for line in lines {
+                        ctx.stroke(line, color, width);
+                    }
+                });
+                ctx.stroke(shape, color, width);
+            }
+        }
+    }
+}
+
+/// Fills chosen for all or some of the series of a chart.
+///
+/// A series without its own fill uses the chart's, and if the chart doesn't have one either it is
+/// solid, or hatched when `theme::HATCH_FILLS` is on.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SeriesFills {
+    all: Option<Fill>,
+    series: Vec<Option<Fill>>,
+}
+
+impl SeriesFills {
+    /// Use `fill` for the series that don't have their own.
+    pub fn set_all(&mut self, fill: Fill) {
+        self.all = Some(fill);
+    }
+
+    pub fn set(&mut self, idx: usize, fill: Fill) {
+        if self.series.len() <= idx {
+            self.series.resize(idx + 1, None);
+        }
+        self.series[idx] = Some(fill);
+    }
+
+    pub fn resolve(&self, idx: usize, env: &Env) -> Fill {
+        match (self.series.get(idx).copied().flatten(), self.all) {
+            (Some(fill), _) | (None, Some(fill)) => fill,
+            (None, None) if env.get(theme::HATCH_FILLS) => Fill::Hatch(Hatch::nth(idx)),
+            (None, None) => Fill::Solid,
+        }
+    }
+}
+
+/// How the bars of a chart are drawn, apart from their color and fill. The sizes are at the normal
+/// size, and scaled by `theme::SIZE_SCALE`.
+#[derive(Clone)]
+pub(crate) struct BarStyle {
+    pub corner_radius: f64,
+    /// The color and width of a line around each bar.
+    pub border: Option<(KeyOrValue<Color>, f64)>,
+    /// The widest a bar can be, however much room it has.
+    pub max_width: Option<f64>,
+}
+
+impl Default for BarStyle {
+    fn default() -> Self {
+        BarStyle {
+            corner_radius: 0.,
+            border: None,
+            max_width: None,
+        }
+    }
+}
+
+impl BarStyle {
+    /// The widest a bar can be, in pixels.
+    pub fn max_width(&self, env: &Env) -> f64 {
+        match self.max_width {
+            Some(max_width) => max_width * env.get(theme::SIZE_SCALE),
+            None => f64::INFINITY,
+        }
+    }
+
+    /// `start..end` across the bar, narrowed around its middle if it is too wide.
+    pub fn limit_width(&self, start: f64, end: f64, env: &Env) -> (f64, f64) {
+        narrow(start, end, self.max_width(env))
+    }
+
+    /// The outline of `bar`, with its corners rounded.
+    pub fn shape(&self, bar: Rect, env: &Env) -> RoundedRect {
+        // the corners can't be rounder than the bar.
+        let radius = (self.corner_radius * env.get(theme::SIZE_SCALE))
+            .min(0.5 * bar.width())
+            .min(0.5 * bar.height());
+        bar.to_rounded_rect(radius.max(0.))
+    }
+
+    /// Draw a bar with outline `shape` (from [`BarStyle::shape`]).
+    pub fn paint(
+        &self,
+        ctx: &mut Canvas,
+        shape: &RoundedRect,
+        fill: Fill,
+        color: &Color,
+        env: &Env,
+    ) {
+        fill.paint(ctx, shape, color, env);
+        if let Some((border_color, width)) = self.border.as_ref() {
+            let width = width * env.get(theme::SIZE_SCALE);
+            ctx.stroke(shape, &border_color.resolve(env), width);
+        }
+    }
+}
+
+/// `start..end`, narrowed around its middle to at most `max_width`.
+fn narrow(start: f64, end: f64, max_width: f64) -> (f64, f64) {
+    let half_width = 0.5 * (end - start).min(max_width);
+    let mid = 0.5 * (start + end);
+    (mid - half_width, mid + half_width)
+}
+
+#[test]
+fn test_hatch_lines() {
+    let bounds = Rect::new(1., 0., 9., 4.);
+    let horizontal = Hatch::Horizontal.lines(bounds, 2.);
+    assert_eq!(
+        horizontal,
+        vec![
+            Line::new((1., 0.), (9., 0.)),
+            Line::new((1., 2.), (9., 2.)),
+            Line::new((1., 4.), (9., 4.)),
+        ]
+    );
+    // the vertical lines line up with the origin, not the edge of the shape.
+    let vertical = Hatch::Vertical.lines(bounds, 4.);
+    assert_eq!(
+        vertical,
+        vec![Line::new((4., 0.), (4., 4.)), Line::new((8., 0.), (8., 4.))]
+    );
+    let diagonal = Hatch::Diagonal.lines(bounds, 2.);
+    // `x + y` from 2 to 12.
+    assert_eq!(diagonal.len(), 6);
+    assert_eq!(diagonal[0], Line::new((-2., 4.), (2., 0.)));
+}
+
+#[test]
+fn test_narrow() {
+    assert_eq!(narrow(10., 110., f64::INFINITY), (10., 110.));
+    assert_eq!(narrow(10., 110., 40.), (40., 80.));
+    assert_eq!(narrow(10., 30., 40.), (10., 30.));
+}
@@ -0,0 +1,178 @@
+//! Keyboard navigation, for using the charts without a mouse.
+use druid::{kurbo::Shape, Env, Event, EventCtx, KbKey, LifeCycle, LifeCycleCtx, RenderContext};
+
+use crate::{
+    canvas::Canvas,
+    commands::{PointSelected, CHART_POINT_SELECTED, HIGHLIGHT_POINT},
+    theme,
+};
+
+/// How wide the focus ring is drawn, at the normal size.
+const FOCUS_RING_WIDTH: f64 = 2.0;
+
+/// Lets the user move a highlight between the points, bars or slices of a chart with the arrow
+/// keys, and select the highlighted one with Enter.
+///
+/// A chart owns one of these, forwards its events and lifecycle events to it, and draws a focus
+/// ring around [`KeyboardFocus::highlighted`] with [`KeyboardFocus::draw_ring`]. Selecting sends
+/// `CHART_POINT_SELECTED`, just like clicking. The app can also move the highlight with
+/// `HIGHLIGHT_POINT`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct KeyboardFocus {
+    highlighted: Option<PointSelected>,
+    has_focus: bool,
+    /// Whether the app chose the highlight, so it is shown without focus.
+    from_command: bool,
+}
+
+/// A move of the highlight.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Move {
+    Next,
+    Previous,
+    NextSeries,
+    PreviousSeries,
+}
+
+impl KeyboardFocus {
+    pub fn new() -> Self {
+        KeyboardFocus::default()
+    }
+
+    /// Take part in tab focus, and repaint when focus comes or goes.
+    pub fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        match event {
+            LifeCycle::BuildFocusChain => ctx.register_for_focus(),
+            LifeCycle::FocusChanged(has_focus) => {
+                self.has_focus = *has_focus;
+                ctx.request_paint();
+            }
+            _ => (),
+        }
+    }
+
+    /// Handle the arrow keys, Enter and `HIGHLIGHT_POINT`. `series_lens` is the number of points
+    /// in each series.
+    ///
+    /// Left and right move along a series, up and down move between series.
+    pub fn event(&mut self, ctx: &mut EventCtx, event: &Event, series_lens: &[usize]) {
+        let key = match event {
+            Event::Command(cmd) if cmd.is(HIGHLIGHT_POINT) => {
+                self.highlight(ctx, *cmd.get_unchecked(HIGHLIGHT_POINT));
+                ctx.set_handled();
+                return;
+            }
+            Event::MouseDown(_) => {
+                ctx.request_focus();
+                return;
+            }
+            Event::KeyDown(key) if ctx.is_focused() => &key.key,
+            _ => return,
+        };
+        let step = match key {
+            KbKey::ArrowRight => Move::Next,
+            KbKey::ArrowLeft => Move::Previous,
+            KbKey::ArrowDown => Move::NextSeries,
+            KbKey::ArrowUp => Move::PreviousSeries,
+            KbKey::Enter => {
+                if let Some(highlighted) = self.highlighted {
+                    ctx.submit_notification(CHART_POINT_SELECTED.with(highlighted));
+                    ctx.set_handled();
+                }
+                return;
+            }
+            _ => return,
+        };
+        let highlighted = move_highlight(self.highlighted, step, series_lens);
+        if highlighted != self.highlighted {
+            self.highlighted = highlighted;
+            ctx.request_paint();
+        }
+        self.from_command = false;
+        ctx.set_handled();
+    }
+
+    /// Move the highlight to `highlighted`, as `HIGHLIGHT_POINT` does. Charts that number their
+    /// points differently from their data translate the command and call this.
+    pub fn highlight(&mut self, ctx: &mut EventCtx, highlighted: Option<PointSelected>) {
+        self.highlighted = highlighted;
+        self.from_command = true;
+        ctx.request_paint();
+    }
+
+    /// The point to draw a focus ring around, if the chart has focus or the app chose it.
+    pub fn highlighted(&self) -> Option<PointSelected> {
+        self.highlighted
+            .filter(|_| self.has_focus || self.from_command)
+    }
+
+    /// Draw the focus ring around `shape`.
+    pub fn draw_ring(&self, ctx: &mut Canvas, env: &Env, shape: impl Shape) {
+        let width = FOCUS_RING_WIDTH * env.get(theme::SIZE_SCALE);
+        ctx.stroke(shape, &env.get(theme::FOCUS_COLOR), width);
+    }
+}
+
+/// Where the highlight goes after `step`, skipping empty series. Without a highlight, we start at
+/// the first point.
+fn move_highlight(
+    current: Option<PointSelected>,
+    step: Move,
+    series_lens: &[usize],
+) -> Option<PointSelected> {
+    let current = match current {
+        Some(current)
+            if series_lens
+                .get(current.series)
+                .map_or(false, |len| current.index < *len) =>
+        {
+            current
+        }
+        // start at the first point we can.
+        _ => {
+            let series = series_lens.iter().position(|len| *len > 0)?;
+            return Some(PointSelected::new(series, 0));
+        }
+    };
+    let len = series_lens[current.series];
+    Some(match step {
+        Move::Next => PointSelected::new(current.series, (current.index + 1).min(len - 1)),
+        Move::Previous => PointSelected::new(current.series, current.index.saturating_sub(1)),
+        Move::NextSeries | Move::PreviousSeries => {
+            let not_empty = |series: &usize| series_lens[*series] > 0;
+            let series = if step == Move::NextSeries {
+                (current.series + 1..series_lens.len()).find(not_empty)
+            } else {
+                (0..current.series).rev().find(not_empty)
+            };
+            match series {
+                Some(series) => {
+                    PointSelected::new(series, current.index.min(series_lens[series] - 1))
+                }
+                None => current,
+            }
+        }
+    })
+}
+
+#[test]
+fn test_move_highlight() {
+    let lens = [3, 0, 2];
+    let start = move_highlight(None, Move::Next, &lens);
+    assert_eq!(start, Some(PointSelected::new(0, 0)));
+    let at_end = PointSelected::new(0, 2);
+    assert_eq!(
+        move_highlight(Some(at_end), Move::Next, &lens),
+        Some(at_end)
+    );
+    // the empty series is skipped, and the index is clamped to the shorter series.
+    assert_eq!(
+        move_highlight(Some(at_end), Move::NextSeries, &lens),
+        Some(PointSelected::new(2, 1))
+    );
+    assert_eq!(
+        move_highlight(Some(PointSelected::new(2, 1)), Move::PreviousSeries, &lens),
+        Some(PointSelected::new(0, 1))
+    );
+    assert_eq!(move_highlight(None, Move::Next, &[0, 0]), None);
+}
@@ -0,0 +1,262 @@
+//! Formatting numbers for display as labels.
+use std::{fmt, sync::Arc};
+use to_precision::FloatExt as _;
+
+/// Turns a value into the text of a label.
+///
+/// Cloning is cheap: the formatting function is shared.
+#[derive(Clone)]
+pub struct Formatter(Arc<dyn Fn(f64) -> String>);
+
+impl Formatter {
+    /// Use a custom formatting function.
+    pub fn new(f: impl Fn(f64) -> String + 'static) -> Self {
+        Formatter(Arc::new(f))
+    }
+
+    /// Show the given number of significant figures. The default is 5.
+    pub fn precision(digits: u8) -> Self {
+        Formatter::new(move |v| format!("{}", v.to_precision(digits)))
+    }
+
+    /// Show a fixed number of decimal places.
+    pub fn fixed(decimals: usize) -> Self {
+        Formatter::new(move |v| format!("{:.*}", decimals, v))
+    }
+
+    /// Show a percentage with a fixed number of decimal places, e.g. `12.5%`. The value should
+    /// already be a percentage (so 12.5, not 0.125).
+    pub fn percent(decimals: usize) -> Self {
+        Formatter::new(move |v| format!("{:.*}%", decimals, v))
+    }
+
+    /// Show a fraction as a percentage with a fixed number of decimal places, so 0.125 is shown as
+    /// `12.5%`.
+    pub fn fraction_percent(decimals: usize) -> Self {
+        Formatter::new(move |v| format!("{:.*}%", decimals, v * 100.))
+    }
+
+    /// Show an amount of money, with `symbol` in front, commas between the thousands and 2
+    /// decimal places, e.g. `€1,234.50` or `-$20.00`.
+    pub fn currency(symbol: impl Into<String>) -> Self {
+        Formatter::currency_with_decimals(symbol, 2)
+    }
+
+    /// Like `currency`, with a different number of decimal places, e.g. 0 for whole dollars.
+    pub fn currency_with_decimals(symbol: impl Into<String>, decimals: usize) -> Self {
+        let symbol = symbol.into();
+        Formatter::new(move |v| {
+            let sign = if v < 0. { "-" } else { "" };
+            format!("{}{}{}", sign, symbol, group_thousands(v.abs(), decimals))
+        })
+    }
+
+    /// Show a fixed number of decimal places, with commas between the thousands, e.g.
+    /// `1,234,567.8`.
+    pub fn grouped(decimals: usize) -> Self {
+        Formatter::new(move |v| {
+            let sign = if v < 0. { "-" } else { "" };
+            format!("{}{}", sign, group_thousands(v.abs(), decimals))
+        })
+    }
+
+    /// Show the given number of significant figures in scientific notation, e.g. `1.23e5`.
+    pub fn scientific(digits: u8) -> Self {
+        let decimals = digits.max(1) as usize - 1;
+        Formatter::new(move |v| format!("{:.*e}", decimals, v))
+    }
+
+    /// Like `scientific`, but the exponent is always a multiple of 3, e.g. `123e3`.
+    pub fn engineering(digits: u8) -> Self {
+        Formatter::new(move |v| {
+            let exponent = engineering_exponent(v);
+            let mantissa = significant(v / 10f64.powi(exponent), digits);
+            format!("{}e{}", mantissa, exponent)
+        })
+    }
+
+    /// Show the given number of significant figures with an SI prefix, e.g. `123 k` or `12 µ`.
+    /// Values too big or small for a prefix use scientific notation.
+    pub fn si(digits: u8) -> Self {
+        Formatter::new(move |v| {
+            let exponent = engineering_exponent(v);
+            match SI_PREFIXES
+                .iter()
+                .find(|(prefix_exponent, _)| *prefix_exponent == exponent)
+            {
+                Some((_, "")) => significant(v, digits),
+                Some((_, prefix)) => format!(
+                    "{} {}",
+                    significant(v / 10f64.powi(exponent), digits),
+                    prefix
+                ),
+                None => Formatter::scientific(digits).format(v),
+            }
+        })
+    }
+
+    pub fn format(&self, value: f64) -> String {
+        (self.0)(value)
+    }
+}
+
+/// The SI prefixes with their powers of 10.
+const SI_PREFIXES: [(i32, &str); 17] = [
+    (-24, "y"),
+    (-21, "z"),
+    (-18, "a"),
+    (-15, "f"),
+    (-12, "p"),
+    (-9, "n"),
+    (-6, "µ"),
+    (-3, "m"),
+    (0, ""),
+    (3, "k"),
+    (6, "M"),
+    (9, "G"),
+    (12, "T"),
+    (15, "P"),
+    (18, "E"),
+    (21, "Z"),
+    (24, "Y"),
+];
+
+/// The power of 10 of the most significant digit of `v`, or 0 if there isn't one.
+pub(crate) fn exponent(v: f64) -> i32 {
+    if v == 0. || !v.is_finite() {
+        0
+    } else {
+        v.abs().log10().floor() as i32
+    }
+}
+
+/// The largest multiple of 3 that is no bigger than `exponent(v)`.
+fn engineering_exponent(v: f64) -> i32 {
+    exponent(v).div_euclid(3) * 3
+}
+
+/// `v` with `digits` significant figures, without an exponent.
+fn significant(v: f64, digits: u8) -> String {
+    let decimals = (digits.max(1) as i32 - 1 - exponent(v)).max(0) as usize;
+    format!("{:.*}", decimals, v)
+}
+
+/// Non-negative `v` with a fixed number of decimal places and commas between the thousands.
+fn group_thousands(v: f64, decimals: usize) -> String {
+    let text = format!("{:.*}", decimals, v);
+    let (whole, fraction) = match text.find('.') {
+        Some(idx) => text.split_at(idx),
+        None => (text.as_str(), ""),
+    };
+    let mut grouped = String::with_capacity(text.len() + whole.len() / 3);
+    for (idx, digit) in whole.chars().enumerate() {
+        if idx > 0 && (whole.len() - idx) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped.push_str(fraction);
+    grouped
+}
+
+/// `n` written with superscript digits, for showing powers of 10.
+pub(crate) fn superscript(n: i32) -> String {
+    n.to_string()
+        .chars()
+        .map(|c| match c {
+            '-' => '⁻',
+            '0' => '⁰',
+            '1' => '¹',
+            '2' => '²',
+            '3' => '³',
+            '4' => '⁴',
+            '5' => '⁵',
+            '6' => '⁶',
+            '7' => '⁷',
+            '8' => '⁸',
+            _ => '⁹',
+        })
+        .collect()
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Formatter::precision(5)
+    }
+}
+
+impl fmt::Debug for Formatter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Formatter(..)")
+    }
+}
+
+#[test]
+fn test_fixed() {
+    let formatter = Formatter::fixed(2);
+    assert_eq!(formatter.format(1.), "1.00");
+    assert_eq!(formatter.format(-0.126), "-0.13");
+}
+
+#[test]
+fn test_percent() {
+    assert_eq!(Formatter::percent(1).format(12.34), "12.3%");
+    assert_eq!(Formatter::percent(0).format(50.), "50%");
+}
+
+#[test]
+fn test_fraction_percent() {
+    assert_eq!(Formatter::fraction_percent(1).format(0.125), "12.5%");
+    assert_eq!(Formatter::fraction_percent(0).format(1.), "100%");
+}
+
+#[test]
+fn test_currency() {
+    let formatter = Formatter::currency("€");
+    assert_eq!(formatter.format(1234.5), "€1,234.50");
+    assert_eq!(formatter.format(-20.), "-€20.00");
+    assert_eq!(formatter.format(0.), "€0.00");
+    let formatter = Formatter::currency_with_decimals("$", 0);
+    assert_eq!(formatter.format(1_234_567.), "$1,234,567");
+    assert_eq!(formatter.format(999.), "$999");
+}
+
+#[test]
+fn test_grouped() {
+    assert_eq!(Formatter::grouped(1).format(1_234_567.89), "1,234,567.9");
+    assert_eq!(Formatter::grouped(0).format(-123_456.), "-123,456");
+}
+
+#[test]
+fn test_custom() {
+    let formatter = Formatter::new(|v| format!("{}%", v * 100.));
+    assert_eq!(formatter.format(0.5), "50%");
+}
+
+#[test]
+fn test_scientific() {
+    assert_eq!(Formatter::scientific(3).format(123_450.), "1.23e5");
+    assert_eq!(Formatter::scientific(2).format(0.000_012_345), "1.2e-5");
+}
+
+#[test]
+fn test_engineering() {
+    assert_eq!(Formatter::engineering(3).format(123_450.), "123e3");
+    assert_eq!(Formatter::engineering(3).format(0.000_012_345), "12.3e-6");
+}
+
+#[test]
+fn test_si() {
+    let formatter = Formatter::si(3);
+    assert_eq!(formatter.format(123_450.), "123 k");
+    assert_eq!(formatter.format(0.000_012_345), "12.3 µ");
+    assert_eq!(formatter.format(-2_500_000.), "-2.50 M");
+    assert_eq!(formatter.format(5.), "5.00");
+    assert_eq!(formatter.format(0.), "0.00");
+}
+
+#[test]
+fn test_superscript() {
+    assert_eq!(superscript(5), "⁵");
+    assert_eq!(superscript(-12), "⁻¹²");
+}
@@ -1,16 +1,46 @@
 use druid::{
     im::Vector,
-    kurbo::{Affine, Line, Point, Rect},
-    ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, KeyOrValue, LayoutCtx, LifeCycle,
-    LifeCycleCtx, PaintCtx, RenderContext, Size, TextLayout, UpdateCtx, Widget,
+    kurbo::{Affine, BezPath, Point, Rect, Vec2},
+    piet::PietText,
+    ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, Insets, KeyOrValue, LayoutCtx, Lens,
+    LifeCycle, LifeCycleCtx, MouseButton, PaintCtx, RenderContext, Size, TextLayout, UpdateCtx,
+    Widget,
 };
 use druid_lens_compose::ComposeLens;
 use itertools::izip;
-use std::sync::Arc;
+use std::{
+    f64::consts::{FRAC_PI_2, FRAC_PI_4},
+    iter,
+    sync::Arc,
+    time::Duration,
+};
 
 use crate::{
-    axes::{calc_tick_spacing, Scale},
-    theme, GRAPH_INSETS,
+    animation::{self, Entrance},
+    annotations::{Annotation, Annotations},
+    axes::{calc_tick_spacing, CustomTick, Direction, Scale, ScaleType},
+    binning::{BinStrategy, Bins},
+    canvas::Canvas,
+    chart_size,
+    commands::{
+        PointSelected, CHART_POINT_SELECTED, COPY_CHART_DATA, COPY_CHART_IMAGE, EXPORT_IMAGE,
+        RESET_CHART_VIEW, TOGGLE_CHART_GRID, ZOOM_TO_RANGE,
+    },
+    context_menu::{copy_csv, copy_image, ChartMenu, MenuItems},
+    describe::{count, describe},
+    empty::EmptyState,
+    error_bars::{add_error_bar, ErrorBar, CAP_WIDTH},
+    fill::{BarStyle, Fill, SeriesFills},
+    fit_aspect_ratio,
+    focus::KeyboardFocus,
+    format::Formatter,
+    hit_test::HitInfo,
+    legend::{Legend, LegendPlacement},
+    offscreen::{export_image, OffscreenChart},
+    stats::{self, Bandwidth, Distribution},
+    theme,
+    title::{ChartTitle, TitleAlignment},
+    Range, SeriesColors, SeriesData, DEFAULT_CHART_SIZE,
 };
 
 /// A histogram of equal width categories
@@ -19,36 +49,721 @@ pub struct HistogramData {
     pub title: ArcStr,
     pub x_axis_label: ArcStr,
     pub x_axis: Vector<ArcStr>,
+    /// The edges of the buckets, if they are ranges of numbers. There should be one more edge
+    /// than there are counts.
+    ///
+    /// If these are given, the x axis is a number line with ticks at the edges, and each bar
+    /// covers its range, like a statistical histogram. Otherwise the bars are spread evenly, with
+    /// the `x_axis` labels under them.
+    pub bin_edges: Option<Vector<f64>>,
+    pub counts: Vector<usize>,
+    /// The uncertainty of each count, if known.
+    pub errors: Option<Vector<ErrorBar>>,
+    /// Other distributions to draw over `counts`, on the same buckets. When there are any, all the
+    /// bars are see-through so they can be compared, and the legend names each distribution
+    /// (`counts` is named by `x_axis_label`).
+    pub overlays: Option<Vector<HistogramSeries>>,
+    /// If `None`, the y axis runs from 0 to the tallest bar. Give several histograms the same
+    /// range to draw them on the same scale, so they can be compared.
+    pub y_range: Option<Range>,
+    pub draw_y_grid: bool,
+}
+
+/// A distribution drawn over the main one in a histogram, e.g. to compare two groups.
+#[derive(Debug, Clone, Data, Lens)]
+pub struct HistogramSeries {
+    /// The name of the distribution, used in the legend.
+    pub label: ArcStr,
+    /// The number of values in each bucket.
     pub counts: Vector<usize>,
 }
 
+impl HistogramSeries {
+    pub fn new(label: impl Into<ArcStr>, counts: Vector<usize>) -> Self {
+        HistogramSeries {
+            label: label.into(),
+            counts,
+        }
+    }
+}
+
+impl HistogramData {
+    /// The names of the distributions for the legend.
+    fn legend_labels(&self) -> Vec<ArcStr> {
+        iter::once(self.x_axis_label.clone())
+            .chain(
+                self.overlays
+                    .iter()
+                    .flatten()
+                    .map(|overlay| overlay.label.clone()),
+            )
+            .collect()
+    }
+
+    /// The data as a table, with a row for each bucket: its label, its edges if it has them,
+    /// and then the count of each distribution.
+    fn csv_table(&self) -> Vec<Vec<String>> {
+        let edges = self
+            .bin_edges
+            .as_ref()
+            .filter(|edges| edges.len() == self.counts.len() + 1);
+        let mut header = vec!["bucket".to_string()];
+        if edges.is_some() {
+            header.extend(vec!["from".to_string(), "to".to_string()]);
+        }
+        // `counts` is named by the x axis label, as in the legend.
+        header.push(match &*self.x_axis_label {
+            "" => "count".to_string(),
+            label => label.to_string(),
+        });
+        header.extend(
+            self.overlays
+                .iter()
+                .flatten()
+                .map(|overlay| overlay.label.to_string()),
+        );
+        let mut table = vec![header];
+        for (idx, count) in self.counts.iter().enumerate() {
+            let label = self
+                .x_axis
+                .get(idx)
+                .map_or_else(String::new, |label| label.to_string());
+            let mut row = vec![label];
+            if let Some(edges) = edges {
+                row.extend(vec![edges[idx].to_string(), edges[idx + 1].to_string()]);
+            }
+            row.push(count.to_string());
+            row.extend(self.overlays.iter().flatten().map(|overlay| {
+                overlay
+                    .counts
+                    .get(idx)
+                    .map_or_else(String::new, |count| count.to_string())
+            }));
+            table.push(row);
+        }
+        table
+    }
+}
+
+/// What the height of each bar of a histogram shows.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Data)]
+pub enum Normalization {
+    /// The number of values in the bucket.
+    Count,
+    /// The fraction of all the values that are in the bucket, so the bars add up to 1.
+    Proportion,
+    /// The percentage of all the values that are in the bucket, so the bars add up to 100.
+    Percentage,
+}
+
+impl Normalization {
+    /// What to multiply the counts by, when there are `total` values.
+    fn factor(self, total: usize) -> f64 {
+        match self {
+            Normalization::Count => 1.,
+            // there's nothing to draw anyway.
+            _ if total == 0 => 0.,
+            Normalization::Proportion => 1. / total as f64,
+            Normalization::Percentage => 100. / total as f64,
+        }
+    }
+}
+
+/// How the labels under the bars of a histogram are laid out.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Data)]
+pub enum CategoryLabels {
+    /// On one line, left to right.
+    Horizontal,
+    /// Broken onto more lines if they are wider than their bar.
+    Wrapped,
+    /// Turned 45° anticlockwise, so they can be longer before they overlap.
+    Diagonal,
+    /// Turned 90° anticlockwise, reading up the page.
+    Vertical,
+}
+
+impl CategoryLabels {
+    /// How far the labels are turned anticlockwise, in radians.
+    fn angle(self) -> f64 {
+        match self {
+            CategoryLabels::Horizontal | CategoryLabels::Wrapped => 0.,
+            CategoryLabels::Diagonal => FRAC_PI_4,
+            CategoryLabels::Vertical => FRAC_PI_2,
+        }
+    }
+}
+
+/// The gap between the x axis and the category labels.
+const CATEGORY_LABEL_GAP: f64 = 2.;
+/// Drawn over the bar under the mouse, to lighten it.
+const HOVER_OVERLAY: Color = Color::rgba8(0xff, 0xff, 0xff, 0x40);
+/// Drawn over the bar being clicked, to darken it.
+const PRESSED_OVERLAY: Color = Color::rgba8(0x00, 0x00, 0x00, 0x40);
+/// How much of each distribution's color shows through, when several are drawn over each other.
+const OVERLAID_ALPHA: f64 = 0.5;
+
+/// How many points we draw along a curve over the bars.
+const CURVE_POINTS: usize = 200;
+
+/// A curve drawn over the bars of a histogram, e.g. a density estimate.
+#[derive(Clone)]
+struct Curve {
+    /// The points along the curve, with x in the units of the x axis and y in the units of the
+    /// bars.
+    points: Vec<Point>,
+    color: KeyOrValue<Color>,
+}
+
+/// The height of a bar, and the ends of its error bar (if it has one).
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct BarValue {
+    value: f64,
+    error_bounds: Option<(f64, f64)>,
+}
+
 pub struct Histogram {
     bar_spacing: KeyOrValue<f64>,
     axis_color: KeyOrValue<Color>,
+    bar_color: KeyOrValue<Color>,
+    /// The value the bars grow from.
+    baseline: f64,
+    below_baseline_color: KeyOrValue<Color>,
+    overlay_colors: SeriesColors,
+    /// The fill of the bars, then of each overlay.
+    fills: SeriesFills,
+    style: BarStyle,
+    draw_axes: bool,
+    cumulative: bool,
+    normalization: Normalization,
+    log_scale: bool,
+    category_labels: CategoryLabels,
+    /// If `None`, we measure the axes and labels.
+    graph_insets: Option<KeyOrValue<Insets>>,
+    preferred_size: Size,
+    aspect_ratio: Option<f64>,
+    x_formatter: Formatter,
+    y_formatter: Formatter,
+    zebra_bands: bool,
+    /// If set, the value of each bar is drawn above it.
+    value_formatter: Option<Formatter>,
+    annotations: Annotations,
+    hover_highlight: bool,
     // retained state
-    title_layout: TextLayout<ArcStr>,
+    title: ChartTitle,
     x_label_layout: TextLayout<ArcStr>,
     x_axis_layouts: Option<Vec<TextLayout<ArcStr>>>,
+    /// What each bar shows, after accumulating and normalizing the counts.
+    bar_values: Option<Vec<BarValue>>,
+    /// The same for each of the overlaid distributions. These are rebuilt along with
+    /// `bar_values`.
+    overlay_values: Vec<Vec<f64>>,
+    value_layouts: Option<Vec<TextLayout<ArcStr>>>,
+    /// Curves to draw over the bars. These are worked out by `BinnedHistogram`, which has the raw
+    /// values.
+    curves: Vec<Curve>,
+    /// Where the bars go. We draw the category labels ourselves, so they can be turned.
+    x_scale: Option<Scale>,
     y_scale: Option<Scale>,
+    /// The part of the x axis zoomed in to with `ZOOM_TO_RANGE`, on a number line.
+    x_view: Option<Range>,
+    /// The part of the y axis zoomed in to with `ZOOM_TO_RANGE`.
+    y_view: Option<Range>,
+    legend: Option<Legend>,
+    empty_state: EmptyState,
+    /// The area the bars are drawn in.
+    graph_bounds: Rect,
+    /// Where each bar was last drawn, for hit testing.
+    bar_rects: Vec<Rect>,
+    /// The bar under the mouse, if we are highlighting it.
+    hovered: Option<usize>,
+    /// The bar the mouse was pressed on, while the button is held.
+    pressed: Option<usize>,
+    entrance: Option<Entrance>,
+    keyboard: Option<KeyboardFocus>,
+    context_menu: Option<ChartMenu>,
 }
 
 impl Histogram {
     pub fn new() -> Self {
-        let mut title_layout = TextLayout::new();
-        title_layout.set_text_size(20.);
         Histogram {
             bar_spacing: theme::BAR_SPACING.into(),
             axis_color: theme::AXES_COLOR.into(),
-            title_layout,
+            bar_color: theme::BAR_COLOR.into(),
+            baseline: 0.,
+            below_baseline_color: theme::BELOW_BASELINE_COLOR.into(),
+            overlay_colors: SeriesColors::default(),
+            fills: SeriesFills::default(),
+            style: BarStyle::default(),
+            draw_axes: true,
+            cumulative: false,
+            normalization: Normalization::Count,
+            log_scale: false,
+            category_labels: CategoryLabels::Horizontal,
+            graph_insets: None,
+            preferred_size: DEFAULT_CHART_SIZE,
+            aspect_ratio: None,
+            x_formatter: Formatter::default(),
+            y_formatter: Formatter::default(),
+            zebra_bands: false,
+            value_formatter: None,
+            annotations: Annotations::new(),
+            hover_highlight: false,
+            title: ChartTitle::new(),
             x_label_layout: TextLayout::new(),
             x_axis_layouts: None,
+            bar_values: None,
+            overlay_values: Vec::new(),
+            value_layouts: None,
+            curves: Vec::new(),
+            x_scale: None,
             y_scale: None,
+            x_view: None,
+            y_view: None,
+            legend: None,
+            empty_state: EmptyState::new(),
+            graph_bounds: Rect::ZERO,
+            bar_rects: Vec::new(),
+            hovered: None,
+            pressed: None,
+            entrance: None,
+            keyboard: None,
+            context_menu: None,
         }
     }
 
-    fn rebuild_if_needed(&mut self, ctx: &mut PaintCtx, data: &HistogramData, env: &Env) {
-        self.title_layout.rebuild_if_needed(ctx.text(), env);
-        self.x_label_layout.rebuild_if_needed(ctx.text(), env);
+    /// Set how the bucket edges are formatted on a numeric x axis (see
+    /// [`HistogramData::bin_edges`]).
+    pub fn with_x_formatter(mut self, formatter: Formatter) -> Self {
+        self.x_formatter = formatter;
+        self
+    }
+
+    /// Set how the y axis tick labels are formatted.
+    pub fn with_y_formatter(mut self, formatter: Formatter) -> Self {
+        self.y_formatter = formatter;
+        self
+    }
+
+    /// Shade every other band between the y grid lines, to help read values across a wide
+    /// chart. The color is `theme::ZEBRA_COLOR`.
+    pub fn with_zebra_bands(mut self, zebra_bands: bool) -> Self {
+        self.zebra_bands = zebra_bands;
+        self
+    }
+
+    /// Draw the value of each bar above it, formatted with `formatter`.
+    ///
+    /// If there isn't room above a bar the label goes just inside its top, and labels that would
+    /// overlap the one to their left are left out.
+    pub fn with_value_labels(mut self, formatter: Formatter) -> Self {
+        self.value_formatter = Some(formatter);
+        self
+    }
+
+    /// Make each bar show the total of its bucket and all the buckets before it.
+    pub fn with_cumulative(mut self, cumulative: bool) -> Self {
+        self.cumulative = cumulative;
+        self
+    }
+
+    /// Choose whether the bars show counts, or the proportion of all the values. The default is
+    /// `Normalization::Count`. Any error bars are scaled along with the bars.
+    pub fn with_normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Show each bar as a percentage of the total count, on the y axis and in a label on each
+    /// bar. `formatter` is used for both, e.g. `Formatter::percent(1)`.
+    ///
+    /// This is the same as `Normalization::Percentage` along with `with_y_formatter` and
+    /// `with_value_labels`.
+    pub fn with_percentages(mut self, formatter: Formatter) -> Self {
+        self.normalization = Normalization::Percentage;
+        self.y_formatter = formatter.clone();
+        self.value_formatter = Some(formatter);
+        self
+    }
+
+    /// Make the bars rise up from the x axis when the histogram is first shown.
+    pub fn with_entrance_animation(mut self, duration: Duration) -> Self {
+        self.entrance = Some(Entrance::new(duration));
+        self
+    }
+
+    /// Lighten the bar under the mouse, and darken it while it is being clicked.
+    pub fn with_hover_highlight(mut self, hover_highlight: bool) -> Self {
+        self.hover_highlight = hover_highlight;
+        self
+    }
+
+    /// Let the user move between the bars with the arrow keys, and select one with Enter. The
+    /// histogram takes part in tab focus.
+    pub fn with_keyboard_navigation(mut self, enabled: bool) -> Self {
+        self.keyboard = if enabled {
+            Some(KeyboardFocus::new())
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Use a log scale for the y axis, so small buckets can still be seen next to a big one.
+    ///
+    /// The bars start from a power of 10 below the smallest bar, rather than 0.
+    pub fn with_log_scale(mut self, log_scale: bool) -> Self {
+        self.log_scale = log_scale;
+        self
+    }
+
+    /// Choose how the labels under the bars are laid out. The default is
+    /// `CategoryLabels::Horizontal`. The graph is made shorter to fit the labels if it needs to be.
+    pub fn with_category_labels(mut self, category_labels: CategoryLabels) -> Self {
+        self.category_labels = category_labels;
+        self
+    }
+
+    /// Use fixed insets around the plot area, instead of measuring the axes (see
+    /// `theme::GRAPH_INSETS`).
+    pub fn with_graph_insets(mut self, insets: impl Into<KeyOrValue<Insets>>) -> Self {
+        self.graph_insets = Some(insets.into());
+        self
+    }
+
+    /// Set the size to take when the space isn't bounded, e.g. inside a `Scroll`. Otherwise the
+    /// chart fills the space it is given. The default is 400×300.
+    pub fn with_preferred_size(mut self, size: impl Into<Size>) -> Self {
+        self.preferred_size = size.into();
+        self
+    }
+
+    /// Show a menu when the chart is right-clicked, to reset the zoom, toggle the grid, copy the
+    /// chart as an image, or copy or save the data as CSV. `T` is the type of your app's data,
+    /// which druid needs to show a menu.
+    pub fn with_context_menu<T: Data>(mut self) -> Self {
+        self.context_menu = Some(ChartMenu::new::<T>());
+        self
+    }
+
+    /// Keep the plot area at the given width:height ratio, centered in the space left over from
+    /// the title, legend and axes. By default the plot area fills that space.
+    pub fn with_aspect_ratio(mut self, ratio: f64) -> Self {
+        assert!(ratio > 0.0, "aspect ratio must be positive");
+        self.aspect_ratio = Some(ratio);
+        self
+    }
+
+    /// Set the color of the bars. The default is `theme::BAR_COLOR`.
+    pub fn with_bar_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.bar_color = color.into();
+        self
+    }
+
+    /// Make the bars grow up or down from `baseline` rather than from 0, e.g. to show how far each
+    /// bucket is from the count you expected. The y axis always shows the baseline.
+    ///
+    /// This doesn't apply on a log scale, where the bars start from the bottom of the axis.
+    pub fn with_baseline(mut self, baseline: f64) -> Self {
+        self.baseline = baseline;
+        self
+    }
+
+    /// Set the color of the bars below the baseline (see [`Histogram::with_baseline`]). The
+    /// default is `theme::BELOW_BASELINE_COLOR`.
+    pub fn with_below_baseline_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.below_baseline_color = color.into();
+        self
+    }
+
+    /// Fill the bars (and any overlays) with `fill` instead of a flat color, e.g. a gradient or
+    /// hatch pattern.
+    pub fn with_fill(mut self, fill: Fill) -> Self {
+        self.fills.set_all(fill);
+        self
+    }
+
+    /// Fill the bars of one of the overlaid distributions with `fill`, where `0` is the first
+    /// overlay. This overrides `with_fill`.
+    pub fn with_overlay_fill(mut self, overlay: usize, fill: Fill) -> Self {
+        self.fills.set(overlay + 1, fill);
+        self
+    }
+
+    /// Round the corners of the bars. `radius` is at the normal size (see `theme::SIZE_SCALE`).
+    pub fn with_corner_radius(mut self, radius: f64) -> Self {
+        self.style.corner_radius = radius;
+        self
+    }
+
+    /// Draw a line `width` wide around each bar.
+    pub fn with_bar_border(mut self, color: impl Into<KeyOrValue<Color>>, width: f64) -> Self {
+        self.style.border = Some((color.into(), width));
+        self
+    }
+
+    /// Don't let the bars get wider than `width`, e.g. when there are only 2 buckets. Narrowed
+    /// bars stay in the middle of their bucket.
+    pub fn with_max_bar_width(mut self, width: f64) -> Self {
+        self.style.max_width = Some(width);
+        self
+    }
+
+    /// Set the color of one of the overlaid distributions (see [`HistogramData::overlays`]), where
+    /// `0` is the first overlay. Overlays without a color get one picked for them.
+    pub fn with_overlay_color(
+        mut self,
+        overlay: usize,
+        color: impl Into<KeyOrValue<Color>>,
+    ) -> Self {
+        self.overlay_colors.set(overlay, color);
+        self
+    }
+
+    /// Set the color of the axes and error bars.
+    pub fn with_axis_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.axis_color = color.into();
+        self
+    }
+
+    /// Whether to draw the axes, with their tick labels. Hidden axes don't take up any room. The
+    /// category labels are drawn either way.
+    pub fn with_axes_visible(mut self, visible: bool) -> Self {
+        self.draw_axes = visible;
+        self
+    }
+
+    /// Set the font size of the title. The default is `theme::TITLE_TEXT_SIZE`.
+    pub fn with_title_size(mut self, size: impl Into<KeyOrValue<f64>>) -> Self {
+        self.title.set_text_size(size);
+        self
+    }
+
+    /// Where to put the title (and subtitle). Defaults to the center.
+    pub fn with_title_alignment(mut self, alignment: TitleAlignment) -> Self {
+        self.title.set_alignment(alignment);
+        self
+    }
+
+    /// Draw a line of smaller text under the title.
+    pub fn with_subtitle(mut self, subtitle: impl Into<ArcStr>) -> Self {
+        self.title.set_subtitle(subtitle.into());
+        self
+    }
+
+    /// Add a horizontal reference line or band, drawn behind the bars.
+    pub fn with_annotation(mut self, annotation: Annotation) -> Self {
+        self.annotations.push(annotation);
+        self
+    }
+
+    /// Show a legend, labelled with the x axis label.
+    pub fn with_legend(mut self, placement: LegendPlacement) -> Self {
+        self.legend = Some(Legend::new(placement));
+        self
+    }
+
+    fn calc_bar_values(&self, data: &HistogramData) -> Vec<BarValue> {
+        let counts: Vec<usize> = data.counts.iter().copied().collect();
+        let factor = self.normalization.factor(counts.iter().sum());
+        bar_values(&counts, self.cumulative, self.normalization)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, value)| {
+                let error_bounds = data.errors.as_ref().and_then(|e| e.get(idx)).map(|error| {
+                    // keep the size of the error, relative to the bar.
+                    let count = counts[idx] as f64;
+                    let (low, high) = error.bounds(count);
+                    (
+                        value + (low - count) * factor,
+                        value + (high - count) * factor,
+                    )
+                });
+                BarValue {
+                    value,
+                    error_bounds,
+                }
+            })
+            .collect()
+    }
+
+    /// The height of the highest bar (or error bar).
+    fn max_value(&self) -> f64 {
+        self.bar_values
+            .iter()
+            .flatten()
+            .map(|bar| match bar.error_bounds {
+                Some((_, high)) => high.max(bar.value),
+                None => bar.value,
+            })
+            .chain(self.overlay_values.iter().flatten().copied())
+            .chain(
+                self.curves
+                    .iter()
+                    .flat_map(|curve| curve.points.iter().map(|p| p.y)),
+            )
+            .fold(0., f64::max)
+    }
+
+    /// The bottom of the lowest error bar, or 0. Counts can't be negative, but the uncertainty in
+    /// a small count can reach below 0.
+    fn min_value(&self) -> f64 {
+        self.bar_values
+            .iter()
+            .flatten()
+            .filter_map(|bar| bar.error_bounds)
+            .map(|(low, _)| low)
+            .fold(0., f64::min)
+    }
+
+    /// Replace the curves drawn over the bars.
+    fn set_curves(&mut self, curves: Vec<Curve>) {
+        self.curves = curves;
+        // the curves might be taller than the bars.
+        self.y_scale = None;
+    }
+
+    /// The range of the y axis: where it has been zoomed to, or else the range the data gives,
+    /// or else fitted to the bars.
+    fn y_range(&self, data: &HistogramData) -> Range {
+        if let Some(range) = self.y_view.or(data.y_range) {
+            return range;
+        }
+        let (min, max) = self.fitted_y_range();
+        Range::new(min, max)
+    }
+
+    /// The range of the y axis that fits all the bars.
+    fn fitted_y_range(&self) -> (f64, f64) {
+        let max = self.max_value();
+        if !self.log_scale {
+            return (self.min_value().min(self.baseline), max.max(self.baseline));
+        }
+        let min_positive = self
+            .bar_values
+            .iter()
+            .flatten()
+            .map(|bar| bar.value)
+            .chain(self.overlay_values.iter().flatten().copied())
+            .filter(|value| *value > 0.)
+            .fold(f64::INFINITY, f64::min);
+        if min_positive.is_finite() {
+            (log_baseline(min_positive), max)
+        } else {
+            // all the buckets are empty.
+            (1., 10.)
+        }
+    }
+
+    /// A short description of the histogram for screen readers, e.g. "Histogram, Distribution of
+    /// BMI, 8 buckets, 200 values, tallest 25-30".
+    pub fn describe(&self, data: &HistogramData) -> String {
+        let total: usize = data.counts.iter().sum();
+        let mut facts = vec![
+            count(data.counts.len(), "bucket", "buckets"),
+            count(total, "value", "values"),
+        ];
+        // the first of the tallest, if there are any values at all.
+        let tallest = data
+            .counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .max_by(|(a_idx, a), (b_idx, b)| a.cmp(b).then(b_idx.cmp(a_idx)))
+            .and_then(|(idx, _)| data.x_axis.get(idx));
+        if let Some(label) = tallest {
+            facts.push(format!("tallest {}", label));
+        }
+        describe("Histogram", &data.title, facts)
+    }
+
+    /// Whether the x axis is a number line, rather than a category for each bar.
+    fn is_numeric(data: &HistogramData) -> bool {
+        match data.bin_edges.as_ref() {
+            Some(edges) => edges.len() == data.counts.len() + 1,
+            None => false,
+        }
+    }
+
+    /// The bar under `pos`, which is in the chart's coordinates, like the position of a mouse
+    /// event.
+    ///
+    /// Bars are only known once they have been painted, so this returns `None` before then.
+    pub fn hit_test(&self, pos: Point) -> Option<HitInfo> {
+        let index = self.bar_at(pos)?;
+        Some(HitInfo {
+            series: 0,
+            index,
+            position: self.bar_rects[index].center(),
+            distance: 0.,
+        })
+    }
+
+    /// The bar at `pos`, if there is one.
+    fn bar_at(&self, pos: Point) -> Option<usize> {
+        // when zoomed in, some bars are cut off at the edges of the graph.
+        if !self.graph_bounds.contains(pos) {
+            return None;
+        }
+        self.bar_rects.iter().position(|bar| bar.contains(pos))
+    }
+
+    /// Show `x_view` and `y_view` of the axes, or all of an axis if its view is `None`.
+    fn set_view(&mut self, x_view: Option<Range>, y_view: Option<Range>) {
+        self.x_view = x_view;
+        self.y_view = y_view;
+        self.x_scale = None;
+        self.y_scale = None;
+    }
+
+    /// The area to repaint when the highlight of bar `idx` changes.
+    fn bar_damage(&self, idx: Option<usize>) -> Option<Rect> {
+        // a little bigger, for the antialiasing.
+        Some(self.bar_rects.get(idx?)?.inflate(1., 1.))
+    }
+
+    /// Take the title, axis label and legend labels from `data`, when the chart is added.
+    fn set_data(&mut self, data: &HistogramData) {
+        self.title.set_text(data.title.clone());
+        self.x_label_layout.set_text(data.x_axis_label.clone());
+        if let Some(legend) = self.legend.as_mut() {
+            legend.set_labels(data.legend_labels());
+        }
+    }
+
+    /// Lay out the chart, or the empty state if there is no data.
+    fn lay_out(&mut self, text: &mut PietText, size: Size, data: &HistogramData, env: &Env) {
+        if data.counts.is_empty() {
+            self.empty_state.rebuild_if_needed(text, env);
+        } else {
+            self.rebuild_if_needed(text, size, data, env);
+        }
+    }
+
+    /// Rebuild the layouts, scales and bars for a histogram of size `size`, as needed.
+    fn rebuild_if_needed(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &HistogramData,
+        env: &Env,
+    ) {
+        let margin = env.get(theme::MARGIN);
+        let scale_margin = env.get(theme::SCALE_MARGIN);
+        self.title.rebuild_if_needed(text, env);
+        self.x_label_layout.rebuild_if_needed(text, env);
+        self.annotations.rebuild_if_needed(text, env);
+        if self.bar_values.is_none() {
+            self.bar_values = Some(self.calc_bar_values(data));
+            self.overlay_values = data
+                .overlays
+                .iter()
+                .flatten()
+                .map(|overlay| {
+                    let counts: Vec<usize> = overlay.counts.iter().copied().collect();
+                    bar_values(&counts, self.cumulative, self.normalization)
+                })
+                .collect();
+        }
         if self.x_axis_layouts.is_none() {
             self.x_axis_layouts = Some(
                 data.x_axis
@@ -56,31 +771,457 @@ impl Histogram {
                     .cloned()
                     .map(|label| {
                         let mut layout = TextLayout::from_text(label);
-                        layout.rebuild_if_needed(ctx.text(), env);
+                        layout.set_text_size(theme::TICK_LABEL_TEXT_SIZE);
                         layout
                     })
                     .collect(),
             );
         }
+        if let (None, Some(formatter)) =
+            (self.value_layouts.as_ref(), self.value_formatter.as_ref())
+        {
+            self.value_layouts = Some(
+                self.bar_values
+                    .iter()
+                    .flatten()
+                    .map(|bar| {
+                        let mut layout =
+                            TextLayout::from_text(ArcStr::from(formatter.format(bar.value)));
+                        layout.set_text_size(theme::TICK_LABEL_TEXT_SIZE);
+                        layout.rebuild_if_needed(text, env);
+                        layout
+                    })
+                    .collect(),
+            );
+        }
+        let numeric = Histogram::is_numeric(data);
+        if self.x_scale.is_none() {
+            let mut x_scale = match data.bin_edges.as_ref() {
+                Some(edges) if numeric => {
+                    // a tick at each edge.
+                    let x_range = self
+                        .x_view
+                        .unwrap_or_else(|| Range::new(edges[0], edges[edges.len() - 1]));
+                    let mut x_scale = Scale::new_x(x_range);
+                    x_scale.set_custom_ticks(Some(
+                        edges
+                            .iter()
+                            .map(|edge| CustomTick::new(*edge, self.x_formatter.format(*edge)))
+                            .collect(),
+                    ));
+                    x_scale
+                }
+                _ => {
+                    // a category for each bar, even if it doesn't have a label.
+                    let categories = (0..data.counts.len())
+                        .map(|idx| data.x_axis.get(idx).cloned().unwrap_or_else(|| "".into()));
+                    Scale::new_categorical(categories, Direction::X)
+                }
+            };
+            x_scale.set_axis_color(self.axis_color.clone());
+            self.x_scale = Some(x_scale);
+        }
         if self.y_scale.is_none() {
-            self.y_scale = Some(Scale::new_y((
-                0.,
-                data.counts.iter().copied().max().unwrap_or(0) as f64,
-            )))
+            let mut y_scale = Scale::new_y(self.y_range(data));
+            if self.log_scale {
+                y_scale.set_scale_type(ScaleType::Log);
+            }
+            y_scale.set_formatter(self.y_formatter.clone());
+            self.y_scale = Some(y_scale);
         }
-        let graph_bounds = self.graph_bounds(ctx.size());
+        let bounds = Rect::from_origin_size(Point::ZERO, size);
+        let mut draw_area = self.title.reserve_space(bounds, env);
+        if let Some(legend) = self.legend.as_mut() {
+            legend.rebuild_if_needed(text, env);
+            draw_area = legend.reserve_space(draw_area, env);
+        }
+
+        // Lay out the y scale for the whole area first, so we know how wide its labels are.
         let y_scale = self.y_scale.as_mut().unwrap();
-        y_scale.set_graph_bounds(graph_bounds);
-        y_scale.rebuild_if_needed(ctx, env);
+        y_scale.set_graph_bounds(draw_area);
+        y_scale.rebuild_if_needed(text, env);
+
+        // space for the y axis and tick labels (if they're drawn)
+        let x0 = if self.draw_axes {
+            margin + self.y_scale.as_ref().unwrap().max_layout().width + scale_margin
+        } else {
+            margin
+        };
+        let x1 = margin;
+        // the title has its own space above the draw area.
+        let y0 = margin;
+
+        // the category labels can wrap to fit the width of the bars, so we only know how much
+        // height they need once we know the width.
+        let wrap_width = match self.category_labels {
+            CategoryLabels::Wrapped => {
+                (draw_area.width() - x0 - x1) / data.x_axis.len().max(1) as f64
+            }
+            _ => f64::INFINITY,
+        };
+        let angle = self.category_labels.angle();
+        let mut labels_depth: f64 = 0.;
+        if numeric {
+            // the x axis draws its own tick labels.
+            let x_scale = self.x_scale.as_mut().unwrap();
+            x_scale.set_graph_bounds(draw_area);
+            x_scale.rebuild_if_needed(text, env);
+            if self.draw_axes {
+                labels_depth = x_scale.max_layout().height + scale_margin;
+            }
+        } else {
+            for layout in self.x_axis_layouts.as_mut().unwrap().iter_mut() {
+                layout.set_wrap_width(wrap_width);
+                layout.rebuild_if_needed(text, env);
+                labels_depth = labels_depth.max(category_label_depth(layout.size(), angle));
+            }
+        }
+        // space for the category labels, and the x axis label (if it's there)
+        let mut y1 = margin + labels_depth;
+        if !data.x_axis_label.is_empty() {
+            y1 += margin + self.x_label_layout.size().height;
+        }
+
+        self.graph_bounds = draw_area.inset(match self.graph_insets.as_ref() {
+            Some(graph_insets) => -graph_insets.resolve(env),
+            None => Insets {
+                x0: -x0,
+                y0: -y0,
+                x1: -x1,
+                y1: -y1,
+            },
+        });
+        if let Some(ratio) = self.aspect_ratio {
+            self.graph_bounds = fit_aspect_ratio(self.graph_bounds, ratio);
+        }
+
+        if let Some(legend) = self.legend.as_mut() {
+            legend.set_graph_bounds(self.graph_bounds, env);
+        }
+        let x_scale = self.x_scale.as_mut().unwrap();
+        x_scale.set_graph_bounds(self.graph_bounds);
+        x_scale.rebuild_if_needed(text, env);
+        let y_scale = self.y_scale.as_mut().unwrap();
+        y_scale.set_graph_bounds(self.graph_bounds);
+        y_scale.rebuild_if_needed(text, env);
     }
 
-    fn graph_bounds(&self, size: Size) -> Rect {
-        Rect::from_origin_size(Point::ZERO, size).inset(GRAPH_INSETS)
+    /// Draw the chart, as laid out by `layout`.
+    fn draw(&mut self, ctx: &mut Canvas, data: &HistogramData, env: &Env) {
+        if data.counts.is_empty() {
+            self.bar_rects.clear();
+            self.empty_state.draw(ctx);
+            return;
+        }
+        // the distributions show through each other if there are several.
+        let alpha = if self.overlay_values.is_empty() {
+            1.
+        } else {
+            OVERLAID_ALPHA
+        };
+        let bar_color = self.bar_color.resolve(env).with_alpha(alpha);
+        let below_baseline_color = self.below_baseline_color.resolve(env).with_alpha(alpha);
+        let overlay_colors: Vec<Color> = (0..self.overlay_values.len())
+            .map(|idx| self.overlay_colors.resolve(idx, env).with_alpha(alpha))
+            .collect();
+        // the bars are fill 0, and the overlays follow.
+        let fills: Vec<Fill> = (0..=self.overlay_values.len())
+            .map(|idx| self.fills.resolve(idx, env))
+            .collect();
+        let size = ctx.size();
+        let bounds = size.to_rect();
+        let graph_bounds = self.graph_bounds;
+        let bar_spacing = self.bar_spacing.resolve(env);
+        self.bar_rects.clear();
+
+        // data
+        let height = graph_bounds.height();
+        let numeric = Histogram::is_numeric(data);
+        // each bar gets an equal slice of the x axis, with a gap between the bars. On a number
+        // line the bars cover their buckets, with just a hairline between them.
+        let bar_width = self.x_scale.as_ref().unwrap().category_width() - bar_spacing;
+        // give up if the area is too small.
+        if !numeric && bar_width <= 0. {
+            return;
+        }
+        if self.zebra_bands {
+            self.y_scale.as_ref().unwrap().draw_bands(ctx, env);
+        }
+        if data.draw_y_grid {
+            self.y_scale.as_ref().unwrap().draw_grid(ctx, env);
+        }
+        if numeric {
+            self.annotations.draw(
+                ctx,
+                env,
+                &[
+                    self.x_scale.as_ref().unwrap(),
+                    self.y_scale.as_ref().unwrap(),
+                ],
+            );
+        } else {
+            self.annotations
+                .draw(ctx, env, &[self.y_scale.as_ref().unwrap()]);
+        }
+        // borrow the fields separately, so the closure doesn't borrow all of `self`.
+        let x_axis_layouts = self.x_axis_layouts.as_ref().unwrap();
+        let bar_values = self.bar_values.as_ref().unwrap();
+        let overlay_values = &self.overlay_values;
+        let curves = &self.curves;
+        let style = &self.style;
+        let value_layouts = self.value_layouts.as_ref();
+        let bar_rects = &mut self.bar_rects;
+        let (hovered, pressed) = (self.hovered, self.pressed);
+        let x_scale = self.x_scale.as_ref().unwrap();
+        let y_scale = self.y_scale.as_ref().unwrap();
+        // the y position of `value`, relative to the top of the graph. Anything that can't be
+        // shown on a log scale goes at the bottom.
+        let pixel_y = |value: f64| (y_scale.pixel_location(value) - graph_bounds.y0).min(height);
+        let baseline = if self.log_scale {
+            None
+        } else {
+            Some(self.baseline)
+        };
+        let base_y = baseline.map(pixel_y).unwrap_or(height);
+        // the bars grow from the baseline while the histogram animates in.
+        let progress = animation::progress(self.entrance.as_ref());
+        let value_y = |value: f64| base_y + (pixel_y(value) - base_y) * progress;
+        let origin = Vec2::new(graph_bounds.x0, graph_bounds.y0);
+        let snap = ctx.snap();
+        let size_scale = env.get(theme::SIZE_SCALE);
+        let cap_width = CAP_WIDTH * size_scale;
+        let angle = self.category_labels.angle();
+        let axes_color = self.axis_color.resolve(env);
+        let zoomed = self.x_view.is_some() || self.y_view.is_some();
+        ctx.with_save(|ctx| {
+            if zoomed {
+                ctx.clip(graph_bounds);
+            }
+            ctx.transform(Affine::translate(origin));
+            let mut error_bars = BezPath::new();
+            // the right hand edge of the last value label we drew.
+            let mut value_labels_end = f64::NEG_INFINITY;
+            for (idx, bar_value) in bar_values.iter().copied().enumerate() {
+                let (start_x, end_x) = match data.bin_edges.as_ref() {
+                    Some(edges) if numeric => (
+                        x_scale.pixel_location(edges[idx]) - graph_bounds.x0 + 0.5,
+                        x_scale.pixel_location(edges[idx + 1]) - graph_bounds.x0 - 0.5,
+                    ),
+                    _ => {
+                        let mid_x = x_scale.category_center(idx) - graph_bounds.x0;
+                        (mid_x - bar_width * 0.5, mid_x + bar_width * 0.5)
+                    }
+                };
+                let (start_x, end_x) = style.limit_width(start_x, end_x, env);
+                let mid_x = (start_x + end_x) * 0.5;
+
+                // bar
+                let bar = Rect::new(start_x, value_y(bar_value.value), end_x, base_y).abs();
+                // the pixel grid lines up with the window, not the graph.
+                let bar = snap.rect(bar + origin) - origin;
+                let color = match baseline {
+                    Some(baseline) if bar_value.value < baseline => &below_baseline_color,
+                    _ => &bar_color,
+                };
+                let shape = style.shape(bar, env);
+                style.paint(ctx, &shape, fills[0], color, env);
+                if pressed == Some(idx) {
+                    ctx.fill(shape, &PRESSED_OVERLAY);
+                } else if hovered == Some(idx) {
+                    ctx.fill(shape, &HOVER_OVERLAY);
+                }
+                bar_rects.push(bar + origin);
+                let mut top = bar.y0;
+                for (values, color, fill) in izip!(overlay_values, &overlay_colors, &fills[1..]) {
+                    if let Some(value) = values.get(idx) {
+                        let overlay = Rect::new(start_x, value_y(*value), end_x, base_y).abs();
+                        let overlay = snap.rect(overlay + origin) - origin;
+                        style.paint(ctx, &style.shape(overlay, env), *fill, color, env);
+                        top = top.min(overlay.y0);
+                    }
+                }
+                if let Some((low, high)) = bar_value.error_bounds {
+                    let high = value_y(high);
+                    add_error_bar(&mut error_bars, mid_x, value_y(low), high, cap_width);
+                    top = top.min(high);
+                }
+
+                // value label
+                if let Some(value_layout) = value_layouts.and_then(|l| l.get(idx)) {
+                    let pos = value_label_pos(mid_x, top, value_layout.size());
+                    if pos.x >= value_labels_end {
+                        ctx.draw_layout(value_layout, pos);
+                        value_labels_end = pos.x + value_layout.size().width;
+                    }
+                }
+
+                // data label
+                let label_layout = match x_axis_layouts.get(idx) {
+                    Some(label_layout) if !numeric => label_layout,
+                    _ => continue,
+                };
+                let label_size = label_layout.size();
+                ctx.with_save(|ctx| {
+                    // the label is turned about the end nearest its bar.
+                    let anchor_y =
+                        height + CATEGORY_LABEL_GAP + 0.5 * label_size.height * angle.cos();
+                    ctx.transform(Affine::translate((mid_x, anchor_y)) * Affine::rotate(-angle));
+                    let x = if angle == 0. { -0.5 } else { -1. } * label_size.width;
+                    ctx.draw_layout(label_layout, (x, -0.5 * label_size.height));
+                });
+            }
+            ctx.stroke(error_bars, &axes_color, size_scale);
+
+            for curve in curves {
+                let mut path = BezPath::new();
+                for (idx, point) in curve.points.iter().enumerate() {
+                    let point = Point::new(
+                        x_scale.pixel_location(point.x) - graph_bounds.x0,
+                        value_y(point.y),
+                    );
+                    if idx == 0 {
+                        path.move_to(point);
+                    } else {
+                        path.line_to(point);
+                    }
+                }
+                ctx.stroke(path, &curve.color.resolve(env), 2. * size_scale);
+            }
+        });
+
+        // focus ring
+        if let Some(keyboard) = self.keyboard.as_ref() {
+            let highlighted = keyboard
+                .highlighted()
+                .and_then(|highlighted| self.bar_rects.get(highlighted.index));
+            if let Some(bar) = highlighted {
+                keyboard.draw_ring(ctx, env, bar.inflate(2., 2.));
+            }
+        }
+
+        // title
+        self.title.draw(ctx, env);
+
+        // x axis (we drew the category labels with the bars)
+        if self.draw_axes {
+            self.x_scale.as_mut().unwrap().draw(ctx, env, true, numeric);
+        }
+        if !data.x_axis_label.is_empty() {
+            let label_size = self.x_label_layout.size();
+            let margin = env.get(theme::MARGIN);
+            ctx.draw_layout(
+                &self.x_label_layout,
+                (
+                    (size.width - label_size.width) * 0.5,
+                    size.height - label_size.height - margin,
+                ),
+            );
+        }
+
+        // y axis
+        if self.draw_axes {
+            self.y_scale.as_mut().unwrap().draw(ctx, env, true, true);
+        }
+
+        // legend
+        if let Some(legend) = self.legend.as_mut() {
+            legend.draw_with_fills(
+                ctx,
+                env,
+                |idx| match idx {
+                    0 => bar_color.clone(),
+                    _ => overlay_colors[idx - 1].clone(),
+                },
+                |idx| fills[idx],
+            );
+        }
     }
 }
 
 impl Widget<HistogramData> for Histogram {
-    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut HistogramData, env: &Env) {}
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut HistogramData, env: &Env) {
+        if let Some(entrance) = self.entrance.as_mut() {
+            entrance.event(ctx, event);
+        }
+        if let Some(keyboard) = self.keyboard.as_mut() {
+            keyboard.event(ctx, event, &[data.counts.len()]);
+        }
+        let items = MenuItems {
+            reset_zoom: self.x_view.is_some() || self.y_view.is_some(),
+            grid: true,
+            data: true,
+        };
+        if let Some(context_menu) = self.context_menu.as_mut() {
+            context_menu.event(ctx, event, items, || data.csv_table());
+        }
+        match event {
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Left => {
+                if let Some(idx) = self.bar_at(mouse.pos) {
+                    ctx.submit_notification(CHART_POINT_SELECTED.with(PointSelected::new(0, idx)));
+                    if self.hover_highlight {
+                        ctx.set_active(true);
+                        self.pressed = Some(idx);
+                        if let Some(rect) = self.bar_damage(self.pressed) {
+                            ctx.request_paint_rect(rect);
+                        }
+                    }
+                    ctx.set_handled();
+                }
+            }
+            Event::MouseUp(mouse) if mouse.button == MouseButton::Left && ctx.is_active() => {
+                ctx.set_active(false);
+                if let Some(rect) = self.bar_damage(self.pressed.take()) {
+                    ctx.request_paint_rect(rect);
+                }
+            }
+            Event::MouseMove(mouse) if self.hover_highlight => {
+                let hovered = if ctx.is_hot() {
+                    self.bar_at(mouse.pos)
+                } else {
+                    None
+                };
+                if hovered != self.hovered {
+                    // only the bars that changed need repainting.
+                    for rect in self
+                        .bar_damage(self.hovered)
+                        .into_iter()
+                        .chain(self.bar_damage(hovered))
+                    {
+                        ctx.request_paint_rect(rect);
+                    }
+                    self.hovered = hovered;
+                }
+            }
+            // only a number line can be zoomed.
+            Event::Command(cmd) if cmd.is(ZOOM_TO_RANGE) && Histogram::is_numeric(data) => {
+                let zoom = cmd.get_unchecked(ZOOM_TO_RANGE);
+                self.set_view(zoom.x_range.or(self.x_view), zoom.y_range.or(self.y_view));
+                ctx.request_layout();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(RESET_CHART_VIEW) => {
+                self.set_view(None, None);
+                ctx.request_layout();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(TOGGLE_CHART_GRID) => {
+                data.draw_y_grid = !data.draw_y_grid;
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_DATA) => {
+                copy_csv(data.csv_table());
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_IMAGE) => {
+                copy_image(ctx, env, |ctx| self.draw(ctx, data, env));
+            }
+            Event::Command(cmd) if cmd.is(EXPORT_IMAGE) => {
+                let scale = *cmd.get_unchecked(EXPORT_IMAGE);
+                export_image(ctx, scale, env, |ctx| self.draw(ctx, data, env));
+            }
+            _ => (),
+        }
+    }
 
     fn lifecycle(
         &mut self,
@@ -89,11 +1230,19 @@ impl Widget<HistogramData> for Histogram {
         data: &HistogramData,
         env: &Env,
     ) {
+        if let Some(entrance) = self.entrance.as_mut() {
+            entrance.lifecycle(ctx, event);
+        }
+        if let Some(keyboard) = self.keyboard.as_mut() {
+            keyboard.lifecycle(ctx, event);
+        }
         match event {
-            LifeCycle::WidgetAdded => {
-                self.title_layout.set_text(data.title.clone());
-                self.x_label_layout.set_text(data.x_axis_label.clone());
-                // TODO reuse x axis tick label layouts
+            // TODO reuse x axis tick label layouts
+            LifeCycle::WidgetAdded => self.set_data(data),
+            LifeCycle::HotChanged(false) if self.hovered.is_some() => {
+                if let Some(rect) = self.bar_damage(self.hovered.take()) {
+                    ctx.request_paint_rect(rect);
+                }
             }
             _ => (),
         }
@@ -107,13 +1256,52 @@ impl Widget<HistogramData> for Histogram {
         env: &Env,
     ) {
         if !old_data.title.same(&data.title) {
-            self.title_layout.set_text(data.title.clone());
+            self.title.set_text(data.title.clone());
+        }
+        if self.title.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
         }
         if !old_data.x_axis_label.same(&data.x_axis_label) {
             self.x_label_layout.set_text(data.x_axis_label.clone());
         }
-        if !old_data.x_axis.same(&data.x_axis) {
+        if !old_data.x_axis_label.same(&data.x_axis_label)
+            || !old_data.overlays.same(&data.overlays)
+        {
+            if let Some(legend) = self.legend.as_mut() {
+                legend.set_labels(data.legend_labels());
+            }
+        }
+        if self.x_label_layout.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if let Some(legend) = self.legend.as_mut() {
+            if legend.needs_rebuild_after_update(ctx) {
+                ctx.request_layout();
+            }
+        }
+        if !old_data.x_axis.same(&data.x_axis) || !old_data.bin_edges.same(&data.bin_edges) {
             self.x_axis_layouts = None;
+            self.x_scale = None;
+            ctx.request_layout();
+        }
+        if !old_data.counts.same(&data.counts)
+            || !old_data.errors.same(&data.errors)
+            || !old_data.overlays.same(&data.overlays)
+        {
+            self.bar_values = None;
+            self.value_layouts = None;
+            self.x_scale = None;
+            self.y_scale = None;
+            self.hovered = None;
+            self.pressed = None;
+            ctx.request_layout();
+        }
+        if old_data.y_range != data.y_range {
+            self.y_scale = None;
+            ctx.request_layout();
+        }
+        if old_data.draw_y_grid != data.draw_y_grid {
+            ctx.request_paint();
         }
     }
 
@@ -124,79 +1312,401 @@ impl Widget<HistogramData> for Histogram {
         data: &HistogramData,
         env: &Env,
     ) -> Size {
-        bc.max() // or costrain to some size.
+        let size = chart_size(bc, self.preferred_size);
+        self.lay_out(ctx.text(), size, data, env);
+        size
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &HistogramData, env: &Env) {
-        self.rebuild_if_needed(ctx, data, env);
-        let bg_brush = ctx.solid_brush(Color::hlc(0.0, 90.0, 0.0));
-        let axes_brush = ctx.solid_brush(self.axis_color.resolve(env));
-        let bar_brush = ctx.solid_brush(Color::hlc(0.0, 50.0, 50.0));
+        self.draw(&mut Canvas::new(ctx, env), data, env);
+    }
+}
+
+impl OffscreenChart<HistogramData> for Histogram {
+    fn draw_offscreen(&mut self, ctx: &mut Canvas, data: &HistogramData, env: &Env) {
+        self.entrance = None;
+        self.set_data(data);
         let size = ctx.size();
-        let bounds = size.to_rect();
-        let graph_bounds = bounds.inset(GRAPH_INSETS);
-        let max_data = *data.counts.iter().max().unwrap() as f64;
-        let bar_spacing = self.bar_spacing.resolve(env);
+        self.lay_out(ctx.text(), size, data, env);
+        self.draw(ctx, data, env);
+    }
+}
 
-        // data
-        let data_len = data.counts.len() as f64;
-        let (width, height) = (graph_bounds.width(), graph_bounds.height());
-        let total_space = (data_len + 1.0) * bar_spacing;
-        // give up if the area is too small.
-        if total_space >= width {
-            return;
+/// The height of each bar, given the count in each bucket.
+fn bar_values(counts: &[usize], cumulative: bool, normalization: Normalization) -> Vec<f64> {
+    let factor = normalization.factor(counts.iter().sum());
+    let mut total = 0;
+    counts
+        .iter()
+        .map(|count| {
+            total += count;
+            let count = if cumulative { total } else { *count };
+            count as f64 * factor
+        })
+        .collect()
+}
+
+/// How far below the x axis a category label of the given size reaches, once it is turned through
+/// `angle`.
+fn category_label_depth(size: Size, angle: f64) -> f64 {
+    CATEGORY_LABEL_GAP + size.width * angle.sin() + size.height * angle.cos()
+}
+
+/// Where the bars start on a log scale: the power of 10 below `min_positive`, the smallest bar.
+fn log_baseline(min_positive: f64) -> f64 {
+    10f64.powf(min_positive.log10().ceil() - 1.)
+}
+
+/// Where to draw the value label of a bar whose top (including any error bar) is at `top`.
+///
+/// The label goes above the bar, unless it would stick out of the top of the graph, in which case
+/// it goes just inside.
+fn value_label_pos(mid_x: f64, top: f64, label_size: Size) -> Point {
+    const GAP: f64 = 2.;
+    let above = top - GAP - label_size.height;
+    let y = if above >= 0. { above } else { top + GAP };
+    Point::new(mid_x - label_size.width * 0.5, y)
+}
+
+/// The data for a histogram of raw values, that will be sorted into buckets by the widget. The
+/// values can be any [`SeriesData`], e.g. a `Vector<i32>` or an `Arc<Vec<f64>>`.
+#[derive(Debug, Clone, Data, ComposeLens)]
+pub struct BinnedHistogramData<S = Vector<f64>> {
+    pub title: ArcStr,
+    pub x_axis_label: ArcStr,
+    pub values: S,
+    /// Other sets of values to draw over `values`, with their names for the legend. The buckets
+    /// are chosen to cover all the values. See [`HistogramData::overlays`].
+    pub overlays: Option<Vector<(ArcStr, S)>>,
+    pub bin_strategy: BinStrategy,
+    /// See [`HistogramData::y_range`].
+    pub y_range: Option<Range>,
+    pub draw_y_grid: bool,
+}
+
+/// A [`Histogram`] of raw values, using a [`BinStrategy`] to choose the buckets.
+///
+/// The buckets and their labels are cached, and only recalculated when the values or the strategy
+/// change.
+pub struct BinnedHistogram {
+    inner: Histogram,
+    label_formatter: Formatter,
+    numeric_axis: bool,
+    /// If set, a kernel density estimate of the values is drawn over the bars.
+    kde: Option<Bandwidth>,
+    kde_color: KeyOrValue<Color>,
+    /// If set, this distribution is fitted to the values and drawn over the bars.
+    fit: Option<Distribution>,
+    fit_color: KeyOrValue<Color>,
+    // retained state
+    binned: Option<HistogramData>,
+}
+
+impl BinnedHistogram {
+    /// Wrap a histogram (so it can be configured first).
+    pub fn new(inner: Histogram) -> Self {
+        BinnedHistogram {
+            inner,
+            label_formatter: Formatter::default(),
+            numeric_axis: false,
+            kde: None,
+            kde_color: theme::ANNOTATION_COLOR.into(),
+            fit: None,
+            fit_color: theme::AXES_COLOR.into(),
+            binned: None,
         }
-        let total_bar_width = width - total_space;
-        let bar_width = total_bar_width / data_len;
-        assert_eq!(bar_width * data_len + bar_spacing * (data_len + 1.0), width);
-        ctx.with_save(|ctx| {
-            ctx.transform(Affine::translate((
-                graph_bounds.x0 + bar_spacing,
-                graph_bounds.y0,
-            )));
-            for (idx, (count, label, label_layout)) in izip!(
-                data.counts.iter().copied(),
-                data.x_axis.iter().cloned(),
-                self.x_axis_layouts.as_ref().unwrap()
-            )
-            .enumerate()
-            {
-                let idx = idx as f64;
-                let start_x = width * idx / data_len;
-                let end_x = start_x + bar_width;
-                let mid_x = start_x + (end_x - start_x) * 0.5;
+    }
 
-                // bar
-                let end_y = (count as f64) * height / max_data;
-                ctx.fill(
-                    Rect::new(start_x, height - end_y, end_x, height),
-                    &bar_brush,
-                );
+    /// Set how the bucket edges are formatted in the bucket labels.
+    pub fn with_label_formatter(mut self, formatter: Formatter) -> Self {
+        self.label_formatter = formatter;
+        self
+    }
 
-                // data label
-                let label_width = label_layout.size().width;
-                label_layout.draw(ctx, (mid_x - label_width * 0.5, height + 2.));
+    /// Draw the x axis as a number line with a tick at each bucket edge, instead of a label under
+    /// each bar. The edges are formatted with the formatter given to
+    /// [`Histogram::with_x_formatter`].
+    pub fn with_numeric_axis(mut self, numeric_axis: bool) -> Self {
+        self.numeric_axis = numeric_axis;
+        self
+    }
+
+    /// Draw a smooth estimate of the distribution of the values (a Gaussian kernel density
+    /// estimate) over the bars, scaled to match them. It isn't drawn on a cumulative histogram.
+    pub fn with_kde(mut self, bandwidth: Bandwidth) -> Self {
+        self.kde = Some(bandwidth);
+        self
+    }
+
+    /// Set the color of the density estimate. The default is `theme::ANNOTATION_COLOR`.
+    pub fn with_kde_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.kde_color = color.into();
+        self
+    }
+
+    /// Fit `distribution` to the values (using their mean and standard deviation) and draw it
+    /// over the bars, scaled to match them, to show how well the values follow it. It isn't drawn
+    /// on a cumulative histogram, or if the distribution can't be fitted.
+    pub fn with_fit(mut self, distribution: Distribution) -> Self {
+        self.fit = Some(distribution);
+        self
+    }
+
+    /// Set the color of the fitted distribution. The default is `theme::AXES_COLOR`.
+    pub fn with_fit_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.fit_color = color.into();
+        self
+    }
+
+    /// A short description of the histogram for screen readers. See [`Histogram::describe`].
+    pub fn describe<S: SeriesData>(&self, data: &BinnedHistogramData<S>) -> String {
+        self.inner.describe(&self.bin(data).0)
+    }
+
+    /// The bucket under `pos`. See [`Histogram::hit_test`].
+    pub fn hit_test(&self, pos: Point) -> Option<HitInfo> {
+        self.inner.hit_test(pos)
+    }
+
+    /// Sort the values into buckets, and work out the curves to draw over them.
+    fn rebin<S: SeriesData>(&mut self, data: &BinnedHistogramData<S>) -> HistogramData {
+        let (binned, bins) = self.bin(data);
+        let curves = self.curves(data, &bins);
+        self.inner.set_curves(curves);
+        binned
+    }
+
+    /// The curves to draw over the bars.
+    fn curves<S: SeriesData>(&self, data: &BinnedHistogramData<S>, bins: &Bins) -> Vec<Curve> {
+        let (start, end) = match (bins.edges.first(), bins.edges.last()) {
+            (Some(start), Some(end)) if end > start => (*start, *end),
+            _ => return vec![],
+        };
+        if self.inner.cumulative {
+            return vec![];
+        }
+        let mut sorted: Vec<f64> = data.values.values().filter(|v| v.is_finite()).collect();
+        sorted.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+        // the buckets are all the same width. On a category axis each one is 1 wide.
+        let width = bins.edges[1] - bins.edges[0];
+        let to_axis = |x: f64| {
+            if self.numeric_axis {
+                x
+            } else {
+                (x - start) / width
             }
-        });
+        };
+        // a density times the bucket width is the expected proportion of the values in a bucket.
+        let y_factor = sorted.len() as f64 * width * self.inner.normalization.factor(sorted.len());
+        let sample = |density: &dyn Fn(f64) -> f64| -> Vec<Point> {
+            (0..=CURVE_POINTS)
+                .map(|idx| {
+                    let x = start + (end - start) * idx as f64 / CURVE_POINTS as f64;
+                    Point::new(to_axis(x), density(x) * y_factor)
+                })
+                .collect()
+        };
 
-        // title
-        let title_width = self.title_layout.size().width;
-        self.title_layout
-            .draw(ctx, ((size.width - title_width) * 0.5, 10.0));
-
-        // x axis
-        let x_axis = Line::new(
-            (graph_bounds.x0 - 1.0, graph_bounds.y1),
-            (graph_bounds.x1, graph_bounds.y1),
-        );
-        ctx.stroke(x_axis, &axes_brush, 2.0);
-        let x_label_width = self.x_label_layout.size().width;
-        self.x_label_layout.draw(
-            ctx,
-            ((size.width - x_label_width) * 0.5, size.height - 40.0),
-        );
+        let mut curves = Vec::new();
+        if let Some(bandwidth) = self.kde.and_then(|kde| kde.calculate(&sorted)) {
+            curves.push(Curve {
+                points: sample(&|x| stats::kernel_density(&sorted, bandwidth, x)),
+                color: self.kde_color.clone(),
+            });
+        }
+        if let Some(density) = self.fit.and_then(|fit| fit.fit(&sorted)) {
+            curves.push(Curve {
+                points: sample(&density),
+                color: self.fit_color.clone(),
+            });
+        }
+        curves
+    }
 
-        // y axis
-        self.y_scale.as_mut().unwrap().draw(ctx, env, true, true);
+    fn bin<S: SeriesData>(&self, data: &BinnedHistogramData<S>) -> (HistogramData, Bins) {
+        let overlay_values = data
+            .overlays
+            .iter()
+            .flatten()
+            .flat_map(|(_, values)| values.values());
+        let bins = data
+            .bin_strategy
+            .bin(data.values.values().chain(overlay_values));
+        let counts = match data.overlays {
+            Some(_) => bins.count(data.values.values()),
+            None => bins.counts.clone(),
+        };
+        let binned = HistogramData {
+            title: data.title.clone(),
+            x_axis_label: data.x_axis_label.clone(),
+            x_axis: bins
+                .ranges()
+                .map(|(start, end)| {
+                    format!(
+                        "{}-{}",
+                        self.label_formatter.format(start),
+                        self.label_formatter.format(end)
+                    )
+                    .into()
+                })
+                .collect(),
+            bin_edges: if self.numeric_axis {
+                Some(bins.edges.iter().copied().collect())
+            } else {
+                None
+            },
+            counts: counts.into_iter().collect(),
+            errors: None,
+            overlays: data.overlays.as_ref().map(|overlays| {
+                overlays
+                    .iter()
+                    .map(|(label, values)| {
+                        HistogramSeries::new(label.clone(), bins.count(values.values()).into())
+                    })
+                    .collect()
+            }),
+            y_range: data.y_range,
+            draw_y_grid: data.draw_y_grid,
+        };
+        (binned, bins)
+    }
+}
+
+impl<S: SeriesData> Widget<BinnedHistogramData<S>> for BinnedHistogram {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut BinnedHistogramData<S>,
+        env: &Env,
+    ) {
+        if let Some(binned) = self.binned.as_ref() {
+            // the histogram can't change the raw data.
+            let mut binned = binned.clone();
+            self.inner.event(ctx, event, &mut binned, env);
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &BinnedHistogramData<S>,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            self.binned = Some(self.rebin(data));
+        }
+        if let Some(binned) = self.binned.as_ref() {
+            self.inner.lifecycle(ctx, event, binned, env);
+        }
     }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &BinnedHistogramData<S>,
+        data: &BinnedHistogramData<S>,
+        env: &Env,
+    ) {
+        let old_binned = match self.binned.take() {
+            Some(old_binned) => old_binned,
+            None => return,
+        };
+        let binned = if !old_data.values.same(&data.values)
+            || !old_data.overlays.same(&data.overlays)
+            || old_data.bin_strategy != data.bin_strategy
+        {
+            ctx.request_layout();
+            self.rebin(data)
+        } else {
+            // keep the buckets we already have.
+            HistogramData {
+                title: data.title.clone(),
+                x_axis_label: data.x_axis_label.clone(),
+                y_range: data.y_range,
+                draw_y_grid: data.draw_y_grid,
+                ..old_binned.clone()
+            }
+        };
+        self.inner.update(ctx, &old_binned, &binned, env);
+        self.binned = Some(binned);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &BinnedHistogramData<S>,
+        env: &Env,
+    ) -> Size {
+        match self.binned.as_ref() {
+            Some(binned) => self.inner.layout(ctx, bc, binned, env),
+            None => chart_size(bc, self.inner.preferred_size),
+        }
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &BinnedHistogramData<S>, env: &Env) {
+        if let Some(binned) = self.binned.as_ref() {
+            self.inner.paint(ctx, binned, env);
+        }
+    }
+}
+
+impl<S: SeriesData> OffscreenChart<BinnedHistogramData<S>> for BinnedHistogram {
+    fn draw_offscreen(&mut self, ctx: &mut Canvas, data: &BinnedHistogramData<S>, env: &Env) {
+        let binned = self.rebin(data);
+        self.inner.draw_offscreen(ctx, &binned, env);
+        self.binned = Some(binned);
+    }
+}
+
+#[test]
+fn test_value_label_pos() {
+    let label = Size::new(10., 8.);
+    assert_eq!(value_label_pos(50., 30., label), Point::new(45., 20.));
+    // no room above the bar.
+    assert_eq!(value_label_pos(50., 5., label), Point::new(45., 7.));
+}
+
+#[test]
+fn test_bar_values() {
+    let counts = [1, 3, 0, 4];
+    assert_eq!(
+        bar_values(&counts, false, Normalization::Count),
+        vec![1., 3., 0., 4.]
+    );
+    assert_eq!(
+        bar_values(&counts, true, Normalization::Proportion),
+        vec![0.125, 0.5, 0.5, 1.]
+    );
+    assert_eq!(
+        bar_values(&counts, false, Normalization::Percentage),
+        vec![12.5, 37.5, 0., 50.]
+    );
+    assert_eq!(
+        bar_values(&[0, 0], true, Normalization::Percentage),
+        vec![0., 0.]
+    );
+}
+
+#[test]
+fn test_log_baseline() {
+    assert_eq!(log_baseline(3.), 1.);
+    // a bar of 1 still needs some height.
+    assert_eq!(log_baseline(1.), 0.1);
+    assert_eq!(log_baseline(0.25), 0.1);
+}
+
+#[test]
+fn test_category_label_depth() {
+    let size = Size::new(40., 10.);
+    assert_eq!(
+        category_label_depth(size, CategoryLabels::Horizontal.angle()),
+        12.
+    );
+    let vertical = category_label_depth(size, CategoryLabels::Vertical.angle());
+    assert!((vertical - 42.).abs() < 1e-9);
+    let diagonal = category_label_depth(size, CategoryLabels::Diagonal.angle());
+    assert!(diagonal > 12. && diagonal < 42.);
 }
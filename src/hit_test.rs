@@ -0,0 +1,30 @@
+//! Finding what is drawn under the mouse, for building your own interactions.
+use druid::kurbo::Point;
+
+use crate::commands::PointSelected;
+
+/// How close (in pixels) a click needs to be to a point to select it.
+pub(crate) const HIT_RADIUS: f64 = 8.0;
+
+/// The data point, bar, slice or box under a position on a chart, as found by the chart's
+/// `hit_test` method.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HitInfo {
+    /// The index of the series. Charts with only one series always use `0`.
+    pub series: usize,
+    /// The index of the point, bar, slice or box within its series.
+    pub index: usize,
+    /// Where the item is drawn, in the chart's coordinates: the data point itself, or the middle
+    /// of a bar, slice or box.
+    pub position: Point,
+    /// How far the position that was tested is from the item. This is `0` for anything inside a
+    /// bar, slice or box.
+    pub distance: f64,
+}
+
+impl HitInfo {
+    /// The item that was hit, as it would be sent with `CHART_POINT_SELECTED`.
+    pub fn point(&self) -> PointSelected {
+        PointSelected::new(self.series, self.index)
+    }
+}
@@ -0,0 +1,558 @@
+//! Mouse interactions that can be shared between cartesian charts.
+//!
+//! Charts can own a [`PanZoom`] or [`Brush`] themselves, or be wrapped in [`Zoomable`],
+//! [`Selectable`] or [`Tooltip`], which work with any [`CartesianChart`] and can be stacked to mix
+//! the interactions you want.
+use druid::{
+    kurbo::{Point, Rect, Vec2},
+    theme::{LABEL_COLOR, WINDOW_BACKGROUND_COLOR},
+    ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, KeyOrValue, LayoutCtx, LifeCycle,
+    LifeCycleCtx, MouseButton, PaintCtx, RenderContext, Size, TextLayout, UpdateCtx, Widget,
+};
+use std::sync::Arc;
+
+use crate::{
+    axes::Scale,
+    canvas::ToCanvas,
+    commands::{PointSelected, RangeSelected, CHART_RANGE_SELECTED},
+    hit_test::HitInfo,
+    theme, Range,
+};
+
+/// How much a single unit of wheel delta zooms by (as a power of 2).
+const DEFAULT_ZOOM_SPEED: f64 = 1. / 500.;
+
+/// Mouse-wheel zoom and click-drag panning over a pair of x/y scales.
+///
+/// A chart opts in by owning one of these and forwarding its events to [`PanZoom::event`]. When
+/// the view changes the new `(x_range, y_range)` is returned, and the chart should store it as its
+/// effective range (invalidating its scales).
+#[derive(Debug, Clone)]
+pub struct PanZoom {
+    zoom_speed: f64,
+    pan_x: bool,
+    pan_y: bool,
+    // retained
+    drag: Option<Drag>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Drag {
+    /// Where the mouse was pressed.
+    start: Point,
+    /// The ranges when the mouse was pressed.
+    x_range: Range,
+    y_range: Range,
+}
+
+impl PanZoom {
+    pub fn new() -> Self {
+        PanZoom {
+            zoom_speed: DEFAULT_ZOOM_SPEED,
+            pan_x: true,
+            pan_y: true,
+            drag: None,
+        }
+    }
+
+    /// How fast the mouse wheel zooms. The default is `1 / 500`.
+    pub fn with_zoom_speed(mut self, zoom_speed: f64) -> Self {
+        self.zoom_speed = zoom_speed;
+        self
+    }
+
+    /// Only allow panning and zooming along the x axis.
+    pub fn x_only(mut self) -> Self {
+        self.pan_x = true;
+        self.pan_y = false;
+        self
+    }
+
+    /// Only allow panning and zooming along the y axis.
+    pub fn y_only(mut self) -> Self {
+        self.pan_x = false;
+        self.pan_y = true;
+        self
+    }
+
+    /// Whether a drag is in progress.
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Handle an event. Returns the new `(x_range, y_range)` if the view changed.
+    pub fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        x_scale: &Scale,
+        y_scale: &Scale,
+    ) -> Option<(Range, Range)> {
+        let graph_bounds = x_scale.graph_bounds();
+        match event {
+            Event::MouseDown(mouse)
+                if mouse.button == MouseButton::Left && graph_bounds.contains(mouse.pos) =>
+            {
+                ctx.set_active(true);
+                ctx.set_handled();
+                self.drag = Some(Drag {
+                    start: mouse.pos,
+                    x_range: x_scale.data_range(),
+                    y_range: y_scale.data_range(),
+                });
+                None
+            }
+            Event::MouseMove(mouse) if ctx.is_active() => {
+                let drag = self.drag?;
+                // Work from the ranges at the start of the drag so rounding errors don't
+                // accumulate.
+                let delta = mouse.pos - drag.start;
+                let mut x_range = drag.x_range;
+                let mut y_range = drag.y_range;
+                if self.pan_x {
+                    x_range = x_range.translate(-delta.x * x_range.size() / graph_bounds.width());
+                }
+                if self.pan_y {
+                    // pixel y goes down, data y goes up.
+                    y_range = y_range.translate(delta.y * y_range.size() / graph_bounds.height());
+                }
+                ctx.set_handled();
+                Some((x_range, y_range))
+            }
+            Event::MouseUp(mouse) if mouse.button == MouseButton::Left && ctx.is_active() => {
+                ctx.set_active(false);
+                self.drag = None;
+                None
+            }
+            Event::Wheel(mouse) if graph_bounds.contains(mouse.pos) => {
+                let factor = 2f64.powf(mouse.wheel_delta.y * self.zoom_speed);
+                let mut x_range = x_scale.data_range();
+                let mut y_range = y_scale.data_range();
+                if self.pan_x {
+                    x_range = x_range.zoom(x_scale.data_location(mouse.pos.x), factor);
+                }
+                if self.pan_y {
+                    y_range = y_range.zoom(y_scale.data_location(mouse.pos.y), factor);
+                }
+                ctx.set_handled();
+                Some((x_range, y_range))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Click-drag to select a rectangle of the plot area (or a range of x values).
+///
+/// A chart opts in by owning one of these and forwarding its events to [`Brush::event`]. When the
+/// user lets go, the selected rectangle is returned, and the chart can work out which data is in
+/// it. Draw the rectangle while it is being dragged with [`Brush::draw`].
+#[derive(Debug, Clone)]
+pub struct Brush {
+    x_only: bool,
+    zoom: bool,
+    color: KeyOrValue<Color>,
+    // retained
+    /// Where the mouse was pressed, and where it is now.
+    drag: Option<(Point, Point)>,
+}
+
+impl Brush {
+    pub fn new() -> Self {
+        Brush {
+            x_only: false,
+            zoom: false,
+            color: theme::SELECTION_COLOR.into(),
+            drag: None,
+        }
+    }
+
+    /// Only select a range of x values. The selection always covers the full height of the graph.
+    pub fn x_only(mut self) -> Self {
+        self.x_only = true;
+        self
+    }
+
+    /// Zoom in to the selection, instead of telling the app about it. Double-click to zoom back
+    /// out.
+    pub fn zoom_to_selection(mut self) -> Self {
+        self.zoom = true;
+        self
+    }
+
+    /// Set the fill of the selection rectangle. The default is `theme::SELECTION_COLOR`.
+    pub fn with_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    /// Whether only x values are selected.
+    pub fn is_x_only(&self) -> bool {
+        self.x_only
+    }
+
+    /// Whether the chart should zoom in to the selection.
+    pub fn is_zoom(&self) -> bool {
+        self.zoom
+    }
+
+    /// Whether a selection is being dragged out.
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Handle an event. Returns the selected rectangle (in pixels) when the user lets go.
+    ///
+    /// A click without a drag doesn't select anything.
+    pub fn event(&mut self, ctx: &mut EventCtx, event: &Event, graph_bounds: Rect) -> Option<Rect> {
+        match event {
+            Event::MouseDown(mouse)
+                if mouse.button == MouseButton::Left && graph_bounds.contains(mouse.pos) =>
+            {
+                ctx.set_active(true);
+                ctx.set_handled();
+                self.drag = Some((mouse.pos, mouse.pos));
+                None
+            }
+            Event::MouseMove(mouse) if ctx.is_active() => {
+                let (start, _) = self.drag?;
+                let old_selection = self.selection(graph_bounds);
+                self.drag = Some((start, mouse.pos));
+                // only the area covered by the old or new selection changes.
+                if let (Some(old), Some(new)) = (old_selection, self.selection(graph_bounds)) {
+                    ctx.request_paint_rect(old.union(new).inflate(1., 1.));
+                }
+                ctx.set_handled();
+                None
+            }
+            Event::MouseUp(mouse) if mouse.button == MouseButton::Left && ctx.is_active() => {
+                ctx.set_active(false);
+                let selection = self.selection(graph_bounds);
+                if let Some(selection) = selection {
+                    ctx.request_paint_rect(selection.inflate(1., 1.));
+                }
+                self.drag = None;
+                selection.filter(|rect| rect.width() >= 1. && rect.height() >= 1.)
+            }
+            _ => None,
+        }
+    }
+
+    /// The rectangle being dragged out, if there is one, limited to the graph.
+    pub fn selection(&self, graph_bounds: Rect) -> Option<Rect> {
+        let (start, end) = self.drag?;
+        let rect = Rect::from_points(start, end).intersect(graph_bounds);
+        Some(if self.x_only {
+            Rect::new(rect.x0, graph_bounds.y0, rect.x1, graph_bounds.y1)
+        } else {
+            rect
+        })
+    }
+
+    /// Draw the selection, if one is being dragged out.
+    pub fn draw(&self, ctx: &mut impl ToCanvas, env: &Env, graph_bounds: Rect) {
+        ctx.with_canvas(env, |ctx| {
+            if let Some(selection) = self.selection(graph_bounds) {
+                ctx.fill(selection, &self.color.resolve(env));
+            }
+        });
+    }
+}
+
+/// A chart with x and y axes, that the interaction wrappers can work with.
+pub trait CartesianChart<T>: Widget<T> {
+    /// The x and y scales, once the chart has been laid out.
+    fn scales(&self) -> Option<(&Scale, &Scale)>;
+
+    /// Show just `x_range` and `y_range`, e.g. by writing them into the data. An axis without a
+    /// range stays as it is. The caller requests a new layout afterwards.
+    fn set_view(&mut self, data: &mut T, x_range: Option<Range>, y_range: Option<Range>);
+
+    /// Show all the data again.
+    fn reset_view(&mut self, data: &mut T);
+
+    /// The data point under `pos`, if there is one.
+    fn hit_test(&self, pos: Point, data: &T) -> Option<HitInfo>;
+
+    /// The data points inside `rect` (in pixels), in series order.
+    fn points_in(&self, rect: Rect, data: &T) -> Vec<PointSelected>;
+}
+
+/// Wraps a chart so the mouse wheel zooms it and dragging pans it, using a [`PanZoom`].
+/// Double-click to zoom back out.
+pub struct Zoomable<W> {
+    inner: W,
+    pan_zoom: PanZoom,
+}
+
+impl<W> Zoomable<W> {
+    pub fn new(inner: W) -> Self {
+        Zoomable {
+            inner,
+            pan_zoom: PanZoom::new(),
+        }
+    }
+
+    /// Set how the chart pans and zooms, e.g. `PanZoom::new().x_only()`.
+    pub fn with_pan_zoom(mut self, pan_zoom: PanZoom) -> Self {
+        self.pan_zoom = pan_zoom;
+        self
+    }
+}
+
+impl<T: Data, W: CartesianChart<T>> Widget<T> for Zoomable<W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        // the chart gets first go, so clicking a point still selects it.
+        self.inner.event(ctx, event, data, env);
+        if ctx.is_handled() {
+            return;
+        }
+        let (x_scale, y_scale) = match self.inner.scales() {
+            Some(scales) => scales,
+            None => return,
+        };
+        match event {
+            Event::MouseDown(mouse)
+                if mouse.count == 2 && x_scale.graph_bounds().contains(mouse.pos) =>
+            {
+                self.inner.reset_view(data);
+                ctx.request_layout();
+                ctx.set_handled();
+            }
+            _ => {
+                if let Some((x_range, y_range)) = self.pan_zoom.event(ctx, event, x_scale, y_scale)
+                {
+                    self.inner.set_view(data, Some(x_range), Some(y_range));
+                    ctx.request_layout();
+                }
+            }
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.inner.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.inner.update(ctx, old_data, data, env)
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.inner.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.inner.paint(ctx, data, env)
+    }
+}
+
+/// Wraps a chart so click-dragging selects part of it, using a [`Brush`].
+///
+/// The selection is sent as a [`CHART_RANGE_SELECTED`] notification, or if the brush zooms, the
+/// chart shows just the selection.
+pub struct Selectable<W> {
+    inner: W,
+    brush: Brush,
+    shift_to_select: bool,
+}
+
+impl<W> Selectable<W> {
+    pub fn new(inner: W) -> Self {
+        Selectable {
+            inner,
+            brush: Brush::new(),
+            shift_to_select: false,
+        }
+    }
+
+    /// Set how the selection works, e.g. `Brush::new().x_only()`.
+    pub fn with_brush(mut self, brush: Brush) -> Self {
+        self.brush = brush;
+        self
+    }
+
+    /// Only start a selection when shift is held, so a plain drag goes to the chart (e.g. to pan
+    /// a `Zoomable` inside this).
+    pub fn shift_to_select(mut self) -> Self {
+        self.shift_to_select = true;
+        self
+    }
+}
+
+impl<T: Data, W: CartesianChart<T>> Widget<T> for Selectable<W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        let starts_selection = match event {
+            // clicking on a point selects it instead.
+            Event::MouseDown(mouse) => {
+                (!self.shift_to_select || mouse.mods.shift())
+                    && self.inner.hit_test(mouse.pos, data).is_none()
+            }
+            _ => self.brush.is_dragging(),
+        };
+        if let (true, Some((x_scale, y_scale))) = (starts_selection, self.inner.scales()) {
+            if let Some(selection) = self.brush.event(ctx, event, x_scale.graph_bounds()) {
+                let points = self.inner.points_in(selection, data);
+                let selected =
+                    range_selected(x_scale, y_scale, selection, self.brush.is_x_only(), points);
+                if self.brush.is_zoom() {
+                    self.inner
+                        .set_view(data, Some(selected.x_range), selected.y_range);
+                    ctx.request_layout();
+                } else {
+                    ctx.submit_notification(CHART_RANGE_SELECTED.with(selected));
+                }
+            }
+            if ctx.is_handled() {
+                return;
+            }
+        }
+        match event {
+            // double-click to zoom back out.
+            Event::MouseDown(mouse) if mouse.count == 2 && self.brush.is_zoom() => {
+                self.inner.reset_view(data);
+                ctx.request_layout();
+                ctx.set_handled();
+            }
+            _ => self.inner.event(ctx, event, data, env),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.inner.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.inner.update(ctx, old_data, data, env)
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.inner.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.inner.paint(ctx, data, env);
+        if let Some((x_scale, _)) = self.inner.scales() {
+            self.brush.draw(ctx, env, x_scale.graph_bounds());
+        }
+    }
+}
+
+/// Wraps a chart to show a box of text next to the data point under the mouse.
+pub struct Tooltip<T, W> {
+    inner: W,
+    /// Writes the text for the point that was hit.
+    text: Arc<dyn Fn(&T, &HitInfo) -> String>,
+    // retained
+    hovered: Option<HitInfo>,
+    layout: TextLayout<ArcStr>,
+}
+
+impl<T, W> Tooltip<T, W> {
+    /// Show the text from `text` for the point under the mouse.
+    pub fn new(inner: W, text: impl Fn(&T, &HitInfo) -> String + 'static) -> Self {
+        let mut layout = TextLayout::new();
+        layout.set_text_size(theme::TICK_LABEL_TEXT_SIZE);
+        Tooltip {
+            inner,
+            text: Arc::new(text),
+            hovered: None,
+            layout,
+        }
+    }
+
+    /// Where the tooltip box goes: just above and right of the point, but kept inside `bounds`.
+    fn bounds(&self, bounds: Rect, env: &Env) -> Option<Rect> {
+        let hovered = self.hovered?;
+        let margin = env.get(theme::MARGIN);
+        let size = self.layout.size() + Size::new(2. * margin, 2. * margin);
+        let origin = hovered.position + Vec2::new(margin, -margin - size.height);
+        let origin = Point::new(
+            origin.x.min(bounds.x1 - size.width).max(bounds.x0),
+            origin.y.min(bounds.y1 - size.height).max(bounds.y0),
+        );
+        Some(Rect::from_origin_size(origin, size))
+    }
+}
+
+impl<T: Data, W: CartesianChart<T>> Widget<T> for Tooltip<T, W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if let Event::MouseMove(mouse) = event {
+            let hovered = if ctx.is_hot() {
+                self.inner.hit_test(mouse.pos, data)
+            } else {
+                None
+            };
+            if hovered != self.hovered {
+                self.hovered = hovered;
+                if let Some(hovered) = hovered.as_ref() {
+                    self.layout.set_text((self.text)(data, hovered).into());
+                }
+                ctx.request_paint();
+            }
+        }
+        self.inner.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::HotChanged(false) = event {
+            if self.hovered.take().is_some() {
+                ctx.request_paint();
+            }
+        }
+        self.inner.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        if !old_data.same(data) {
+            // the point may have moved, or gone.
+            self.hovered = None;
+        }
+        self.inner.update(ctx, old_data, data, env)
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.layout.rebuild_if_needed(ctx.text(), env);
+        self.inner.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.inner.paint(ctx, data, env);
+        if self.hovered.is_none() {
+            return;
+        }
+        // the text changes between layouts.
+        self.layout.rebuild_if_needed(ctx.text(), env);
+        let margin = env.get(theme::MARGIN);
+        if let Some(bounds) = self.bounds(ctx.size().to_rect(), env) {
+            ctx.fill(bounds, &env.get(WINDOW_BACKGROUND_COLOR));
+            ctx.stroke(bounds, &env.get(LABEL_COLOR), env.get(theme::SIZE_SCALE));
+            self.layout
+                .draw(ctx, (bounds.x0 + margin, bounds.y0 + margin));
+        }
+    }
+}
+
+/// The range of data values between two pixel locations on `scale`.
+pub(crate) fn selected_range(scale: &Scale, p0: f64, p1: f64) -> Range {
+    let (v0, v1) = (scale.data_location(p0), scale.data_location(p1));
+    Range::new(v0.min(v1), v0.max(v1))
+}
+
+/// The ranges covered by `selection` (in pixels), with the points inside it.
+pub(crate) fn range_selected(
+    x_scale: &Scale,
+    y_scale: &Scale,
+    selection: Rect,
+    x_only: bool,
+    points: Vec<PointSelected>,
+) -> RangeSelected {
+    RangeSelected {
+        x_range: selected_range(x_scale, selection.x0, selection.x1),
+        y_range: if x_only {
+            None
+        } else {
+            Some(selected_range(y_scale, selection.y0, selection.y1))
+        },
+        points,
+    }
+}
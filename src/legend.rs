@@ -0,0 +1,311 @@
+//! A legend (or key) that can be shared between the different chart types.
+use druid::{
+    kurbo::{Point, Rect},
+    piet::PietText,
+    theme::{LABEL_COLOR, WINDOW_BACKGROUND_COLOR},
+    ArcStr, Color, Data, Env, KeyOrValue, RenderContext, Size, TextLayout, UpdateCtx,
+};
+use itertools::izip;
+use std::iter;
+
+use crate::{
+    canvas::{Canvas, ToCanvas},
+    fill::Fill,
+    theme,
+};
+
+/// Where a legend should be drawn.
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub enum LegendPlacement {
+    /// To the right of the graph, centered vertically. The graph is shrunk to make room.
+    Right,
+    /// Below the graph, centered horizontally. The graph is shrunk to make room.
+    Bottom,
+    /// On top of the graph area, in the given corner.
+    Overlay(Corner),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    /// A rectangle of the given size, tucked into this corner of `bounds`.
+    pub(crate) fn place(self, bounds: Rect, size: Size) -> Rect {
+        let origin = match self {
+            Corner::TopLeft => Point::new(bounds.x0, bounds.y0),
+            Corner::TopRight => Point::new(bounds.x1 - size.width, bounds.y0),
+            Corner::BottomLeft => Point::new(bounds.x0, bounds.y1 - size.height),
+            Corner::BottomRight => Point::new(bounds.x1 - size.width, bounds.y1 - size.height),
+        };
+        Rect::from_origin_size(origin, size)
+    }
+
+    /// Whether boxes stacked in this corner grow downwards.
+    pub(crate) fn is_top(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::TopRight)
+    }
+}
+
+/// A list of colored entries with labels.
+///
+/// This isn't a widget: charts own a `Legend`, tell it the labels in `update`, let it take the
+/// space it needs when calculating their graph area, and then draw it in `paint`.
+#[derive(Clone)]
+pub struct Legend {
+    placement: LegendPlacement,
+    title_layout: Option<TextLayout<ArcStr>>,
+    entry_layouts: Vec<TextLayout<ArcStr>>,
+    /// The number of layouts in `entry_layouts` that are in use.
+    entry_count: usize,
+    // theme stuff
+    stroke_color: KeyOrValue<Color>,
+    margin: KeyOrValue<f64>,
+    // retained
+    /// Where the legend will be drawn.
+    bounds: Rect,
+}
+
+impl Legend {
+    pub fn new(placement: LegendPlacement) -> Self {
+        Legend {
+            placement,
+            title_layout: None,
+            entry_layouts: vec![],
+            entry_count: 0,
+            stroke_color: LABEL_COLOR.into(),
+            margin: theme::MARGIN.into(),
+            bounds: Rect::ZERO,
+        }
+    }
+
+    /// Draw a title at the top of the legend.
+    pub fn with_title(mut self, title: impl Into<ArcStr>) -> Self {
+        let mut title_layout = TextLayout::from_text(title.into());
+        title_layout.set_text_size(theme::TITLE_TEXT_SIZE);
+        self.title_layout = Some(title_layout);
+        self
+    }
+
+    /// Set the color of the border around the legend, and around each entry's color swatch.
+    pub fn with_border_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.stroke_color = color.into();
+        self
+    }
+
+    pub fn placement(&self) -> LegendPlacement {
+        self.placement
+    }
+
+    pub fn set_placement(&mut self, placement: LegendPlacement) {
+        self.placement = placement;
+    }
+
+    /// Set the labels for the entries. Entry `n` will be drawn using color `n`.
+    pub fn set_labels(&mut self, labels: impl IntoIterator<Item = ArcStr>) {
+        let labels: Vec<ArcStr> = labels.into_iter().collect();
+        // Reuse the layouts we already have, and only add more if we need them.
+        //
+        // Note that we might have too many. That is why we only take the first `entry_count`
+        // items when measuring and drawing.
+        if self.entry_layouts.len() < labels.len() {
+            self.entry_layouts.extend(
+                iter::repeat(TextLayout::new()).take(labels.len() - self.entry_layouts.len()),
+            );
+        }
+        for (label, layout) in izip!(labels.iter(), self.entry_layouts.iter_mut()) {
+            layout.set_text(label.clone());
+        }
+        self.entry_count = labels.len();
+    }
+
+    pub fn needs_rebuild_after_update(&mut self, ctx: &mut UpdateCtx) -> bool {
+        let mut needs_rebuild = false;
+        if let Some(title_layout) = self.title_layout.as_mut() {
+            needs_rebuild |= title_layout.needs_rebuild_after_update(ctx);
+        }
+        for layout in self.entry_layouts.iter_mut() {
+            needs_rebuild |= layout.needs_rebuild_after_update(ctx);
+        }
+        needs_rebuild
+    }
+
+    pub fn rebuild_if_needed(&mut self, text: &mut PietText, env: &Env) {
+        if let Some(title_layout) = self.title_layout.as_mut() {
+            title_layout.rebuild_if_needed(text, env);
+        }
+        for layout in self.entry_layouts.iter_mut() {
+            layout.rebuild_if_needed(text, env);
+        }
+    }
+
+    fn entries(&self) -> impl Iterator<Item = &TextLayout<ArcStr>> {
+        self.entry_layouts.iter().take(self.entry_count)
+    }
+
+    fn is_horizontal(&self) -> bool {
+        self.placement == LegendPlacement::Bottom
+    }
+
+    /// The size the legend will take up. Layouts must have been built.
+    pub fn size(&self, env: &Env) -> Size {
+        let margin = self.margin.resolve(env);
+        let title_size = self
+            .title_layout
+            .as_ref()
+            .map(|layout| layout.size())
+            .unwrap_or(Size::ZERO);
+        if self.is_horizontal() {
+            // m [title m] color m label m color m label m ...
+            let mut width = margin;
+            let mut height = title_size.height;
+            if self.title_layout.is_some() {
+                width += title_size.width + margin;
+            }
+            for layout in self.entries() {
+                let size = layout.size();
+                width += size.height + size.width + 2. * margin;
+                height = height.max(size.height);
+            }
+            Size::new(width, height + 2. * margin)
+        } else {
+            let mut width = title_size.width + 2. * margin;
+            let mut height = margin;
+            if self.title_layout.is_some() {
+                height += title_size.height + margin;
+            }
+            for layout in self.entries() {
+                let size = layout.size();
+                // m color m label m
+                width = width.max(size.width + size.height + 3. * margin);
+                height += size.height + margin;
+            }
+            Size::new(width, height)
+        }
+    }
+
+    /// Take the space the legend needs from `bounds`, returning what is left for the graph.
+    ///
+    /// Overlay legends don't take any space; their position is set by `set_graph_bounds`.
+    pub fn reserve_space(&mut self, bounds: Rect, env: &Env) -> Rect {
+        let margin = self.margin.resolve(env);
+        let size = self.size(env);
+        match self.placement {
+            LegendPlacement::Right => {
+                let x0 = (bounds.x1 - size.width - margin).max(bounds.x0);
+                let y0 = bounds.y0 + (bounds.height() - size.height) * 0.5;
+                self.bounds = Rect::from_origin_size((x0, y0), size);
+                Rect::new(
+                    bounds.x0,
+                    bounds.y0,
+                    (x0 - margin).max(bounds.x0),
+                    bounds.y1,
+                )
+            }
+            LegendPlacement::Bottom => {
+                let x0 = bounds.x0 + (bounds.width() - size.width) * 0.5;
+                let y0 = (bounds.y1 - size.height - margin).max(bounds.y0);
+                self.bounds = Rect::from_origin_size((x0, y0), size);
+                Rect::new(
+                    bounds.x0,
+                    bounds.y0,
+                    bounds.x1,
+                    (y0 - margin).max(bounds.y0),
+                )
+            }
+            LegendPlacement::Overlay(_) => bounds,
+        }
+    }
+
+    /// Tell an overlay legend where the graph is, so it can position itself in a corner.
+    pub fn set_graph_bounds(&mut self, graph_bounds: Rect, env: &Env) {
+        let margin = self.margin.resolve(env);
+        let size = self.size(env);
+        let inner = graph_bounds.inset(-margin);
+        if let LegendPlacement::Overlay(corner) = self.placement {
+            self.bounds = corner.place(inner, size);
+        }
+    }
+
+    /// Where the legend will be drawn.
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    /// Draw the legend, using `color(n)` as the color for entry `n`.
+    pub fn draw(&mut self, ctx: &mut impl ToCanvas, env: &Env, color: impl Fn(usize) -> Color) {
+        ctx.with_canvas(env, |ctx| {
+            self.draw_with_fills(ctx, env, color, |_| Fill::Solid)
+        });
+    }
+
+    /// Draw the legend, filling the square for entry `n` with `color(n)` in the style `fill(n)`.
+    pub(crate) fn draw_with_fills(
+        &mut self,
+        ctx: &mut Canvas,
+        env: &Env,
+        color: impl Fn(usize) -> Color,
+        fill: impl Fn(usize) -> Fill,
+    ) {
+        let margin = self.margin.resolve(env);
+        let stroke_brush = ctx.solid_brush(self.stroke_color.resolve(env));
+        let bounds = self.bounds;
+        if let LegendPlacement::Overlay(_) = self.placement {
+            // don't let the data show through.
+            ctx.fill(bounds, &env.get(WINDOW_BACKGROUND_COLOR));
+        }
+        let size_scale = env.get(theme::SIZE_SCALE);
+        ctx.stroke(bounds, &stroke_brush, 2.0 * size_scale);
+
+        let horizontal = self.is_horizontal();
+        let mut next_loc = if horizontal {
+            bounds.x0 + margin
+        } else {
+            bounds.y0 + margin
+        };
+        if let Some(title_layout) = self.title_layout.as_mut() {
+            let title_size = title_layout.size();
+            if horizontal {
+                ctx.draw_layout(title_layout, (next_loc, bounds.y0 + margin));
+                next_loc += title_size.width + margin;
+            } else {
+                ctx.draw_layout(
+                    title_layout,
+                    (
+                        bounds.x0 + (bounds.width() - title_size.width) * 0.5,
+                        next_loc,
+                    ),
+                );
+                next_loc += title_size.height + margin;
+            }
+        }
+        // important: only take the right amount of layouts here.
+        for (idx, layout) in self
+            .entry_layouts
+            .iter_mut()
+            .take(self.entry_count)
+            .enumerate()
+        {
+            let size = layout.size();
+            let origin = if horizontal {
+                Point::new(next_loc, bounds.y0 + margin)
+            } else {
+                Point::new(bounds.x0 + margin, next_loc)
+            };
+            // use the text's height to make the color square match the text.
+            let color_rect = Rect::from_origin_size(origin, (size.height, size.height));
+            fill(idx).paint(ctx, &color_rect, &color(idx), env);
+            ctx.stroke(color_rect, &stroke_brush, size_scale);
+            ctx.draw_layout(layout, (origin.x + size.height + margin, origin.y)); // m color m label
+            if horizontal {
+                next_loc += size.height + size.width + 2. * margin;
+            } else {
+                next_loc += size.height + margin;
+            }
+        }
+    }
+}
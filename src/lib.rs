@@ -1,46 +1,222 @@
 //! Some graph widgets for use with druid
-use druid::{kurbo::Rect, Color, Insets};
+use druid::{kurbo::Rect, BoxConstraints, Color, Data, Env, KeyOrValue, Size};
 
-mod axes;
+mod animation;
+mod annotations;
+pub mod axes;
+mod bar_chart;
+mod binning;
 mod box_plot;
+mod canvas;
+mod chart_grid;
+mod chart_stack;
+mod colormap;
+pub mod commands;
+mod context_menu;
+mod density;
+mod describe;
+mod downsample;
+mod ecdf;
+mod empty;
+mod error_bars;
+mod fill;
+mod focus;
+mod format;
 mod histogram;
+mod hit_test;
+mod interaction;
+mod legend;
 mod line_chart;
+mod offscreen;
+mod panels;
+mod pareto;
 mod pie_chart;
+mod qq;
 mod range;
+mod rose_chart;
+mod scatter;
+mod snap;
+mod stacked_area;
+pub mod stats;
+mod swarm;
 pub mod theme;
+mod title;
+mod trend;
+mod value;
+mod watermark;
 
 pub use crate::{
-    box_plot::{BoxPlot, BoxPlotData},
-    histogram::{Histogram, HistogramData},
-    line_chart::{LineChart, LineChartData},
-    pie_chart::{PieChart, PieChartData},
+    annotations::{Annotation, AnnotationShape},
+    bar_chart::{BarChart, BarChartData, BarMode, BarSeries},
+    binning::{BinStrategy, Bins},
+    box_plot::{BoxPlot, BoxPlotData, RawPoints},
+    canvas::{Canvas, ToCanvas},
+    chart_grid::{ChartGrid, GridData},
+    chart_stack::{BarLayer, ChartStack, LineLayer, PlotLayer, RegressionLayer, ScatterLayer},
+    colormap::Colormap,
+    density::{CellShape, DensityPlot, DensityPlotData},
+    ecdf::{Ecdf, EcdfData},
+    error_bars::ErrorBar,
+    fill::{Fill, Hatch},
+    format::Formatter,
+    histogram::{
+        BinnedHistogram, BinnedHistogramData, CategoryLabels, Histogram, HistogramData,
+        HistogramSeries, Normalization,
+    },
+    hit_test::HitInfo,
+    interaction::{Brush, CartesianChart, PanZoom, Selectable, Tooltip, Zoomable},
+    legend::{Corner, Legend, LegendPlacement},
+    line_chart::{
+        AreaFill, Callouts, ConfidenceBand, Interpolation, LineChart, LineChartData, LineSeries,
+        MissingValues, PointLabels, ThresholdColors, YAxis,
+    },
+    offscreen::{render_chart_offscreen, render_offscreen, OffscreenChart},
+    panels::{Panel, SharedXPanels},
+    pareto::{ParetoChart, ParetoChartData},
+    pie_chart::{PieChart, PieChartData, SliceLabels, SweepDirection},
+    qq::{QqPlot, QqPlotData},
     range::Range,
+    rose_chart::{RoseChart, RoseChartData},
+    scatter::{ScatterPlot, ScatterPlotData, SizeMapping},
+    stacked_area::{AreaSeries, Baseline, StackedArea, StackedAreaData},
+    stats::{Bandwidth, Distribution, QuantileMethod},
     theme::add_to_env,
+    title::TitleAlignment,
+    trend::{RegressionStats, Trend, TrendLine},
+    value::{SeriesData, ToF64},
+    watermark::Watermark,
 };
 
-const GRAPH_INSETS: Insets = Insets::new(-200.0, -100.0, -40.0, -60.0);
+/// Which way the bars or boxes of a chart go.
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub enum Orientation {
+    /// Values go up the y axis.
+    Vertical,
+    /// Values go along the x axis.
+    Horizontal,
+}
+
+impl Orientation {
+    /// The direction of the axis that shows the values.
+    fn value_direction(self) -> axes::Direction {
+        match self {
+            Orientation::Vertical => axes::Direction::Y,
+            Orientation::Horizontal => axes::Direction::X,
+        }
+    }
+
+    /// The direction of the axis that the groups or categories are spread along.
+    fn category_direction(self) -> axes::Direction {
+        match self {
+            Orientation::Vertical => axes::Direction::X,
+            Orientation::Horizontal => axes::Direction::Y,
+        }
+    }
+}
 
-fn new_color(idx: usize) -> Color {
+/// The color for series `idx`, using the lightness and chroma from the theme.
+fn new_color(idx: usize, env: &Env) -> Color {
     let idx = idx as f64;
     // use a number that is fairly coprime with 360.
-    Color::hlc(idx * 140.0, 50.0, 50.0)
+    Color::hlc(
+        idx * 140.0,
+        env.get(theme::SERIES_LIGHTNESS),
+        env.get(theme::SERIES_CHROMA),
+    )
+}
+
+/// Colors chosen for some of the series (or slices) of a chart. The rest use `new_color`.
+#[derive(Clone, Default)]
+struct SeriesColors(Vec<Option<KeyOrValue<Color>>>);
+
+impl SeriesColors {
+    fn set(&mut self, idx: usize, color: impl Into<KeyOrValue<Color>>) {
+        if self.0.len() <= idx {
+            self.0.resize(idx + 1, None);
+        }
+        self.0[idx] = Some(color.into());
+    }
+
+    fn resolve(&self, idx: usize, env: &Env) -> Color {
+        match self.0.get(idx) {
+            Some(Some(color)) => color.resolve(env),
+            _ => new_color(idx, env),
+        }
+    }
+}
+
+/// The size a chart takes when its constraints are unbounded, unless told otherwise.
+const DEFAULT_CHART_SIZE: Size = Size::new(400., 300.);
+/// Pie charts are round, so they default to a square.
+const DEFAULT_PIE_SIZE: Size = Size::new(300., 300.);
+
+/// The size for a chart: fill any bounded direction, and use `preferred` in unbounded ones (e.g.
+/// inside a `Scroll`).
+fn chart_size(bc: &BoxConstraints, preferred: Size) -> Size {
+    let width = if bc.is_width_bounded() {
+        bc.max().width
+    } else {
+        preferred.width
+    };
+    let height = if bc.is_height_bounded() {
+        bc.max().height
+    } else {
+        preferred.height
+    };
+    bc.constrain((width, height))
 }
 
 /// Take a rect and shrink it to a square centered within the original rectangle.
 fn square(input: Rect) -> Rect {
+    fit_aspect_ratio(input, 1.0)
+}
+
+/// Take a rect and shrink it to the given width:height ratio, centered within the original
+/// rectangle.
+fn fit_aspect_ratio(input: Rect, ratio: f64) -> Rect {
     let (width, height) = (input.width(), input.height());
-    assert!(width >= 0.0 && height >= 0.0);
-    if width == height {
-        input
-    } else if width < height {
-        let half_overlap = 0.5 * (height - width);
+    assert!(ratio > 0.0, "aspect ratio must be positive");
+    // Nothing to fit in a window too small for the chart.
+    if width <= 0.0 || height <= 0.0 {
+        return input;
+    }
+    if width > height * ratio {
+        let half_overlap = 0.5 * (width - height * ratio);
+        let x0 = input.x0 + half_overlap;
+        let x1 = input.x1 - half_overlap;
+        Rect::new(x0, input.y0, x1, input.y1)
+    } else if width < height * ratio {
+        let half_overlap = 0.5 * (height - width / ratio);
         let y0 = input.y0 + half_overlap;
         let y1 = input.y1 - half_overlap;
         Rect::new(input.x0, y0, input.x1, y1)
     } else {
-        let half_overlap = 0.5 * (width - height);
-        let x0 = input.x0 + half_overlap;
-        let x1 = input.x1 - half_overlap;
-        Rect::new(x0, input.y0, x1, input.y1)
+        input
     }
 }
+
+#[test]
+fn test_chart_size() {
+    let preferred = Size::new(400., 300.);
+    let bounded = BoxConstraints::new(Size::ZERO, Size::new(800., 600.));
+    assert_eq!(chart_size(&bounded, preferred), Size::new(800., 600.));
+    let unbounded_height = BoxConstraints::new(Size::ZERO, Size::new(800., f64::INFINITY));
+    assert_eq!(
+        chart_size(&unbounded_height, preferred),
+        Size::new(800., 300.)
+    );
+    let min_bigger = BoxConstraints::new(
+        Size::new(500., 350.),
+        Size::new(f64::INFINITY, f64::INFINITY),
+    );
+    assert_eq!(chart_size(&min_bigger, preferred), Size::new(500., 350.));
+}
+
+#[test]
+fn test_fit_aspect_ratio() {
+    let input = Rect::new(0., 0., 400., 100.);
+    assert_eq!(square(input), Rect::new(150., 0., 250., 100.));
+    assert_eq!(fit_aspect_ratio(input, 2.), Rect::new(100., 0., 300., 100.));
+    assert_eq!(fit_aspect_ratio(input, 8.), Rect::new(0., 25., 400., 75.));
+    assert_eq!(fit_aspect_ratio(input, 4.), input);
+}
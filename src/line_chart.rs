@@ -1,22 +1,51 @@
 use druid::{
     im::Vector,
-    kurbo::{Affine, Line, Point, Rect},
+    kurbo::{
+        Affine, BezPath, Circle, Line, ParamCurve, ParamCurveExtrema, PathEl, PathSeg, Point, Rect,
+        Shape, Vec2,
+    },
+    piet::PietText,
     text::TextStorage,
     ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, Insets, KeyOrValue, LayoutCtx, Lens,
-    LifeCycle, LifeCycleCtx, PaintCtx, RenderContext, Size, TextLayout, UpdateCtx, Widget,
+    LifeCycle, LifeCycleCtx, MouseButton, PaintCtx, RenderContext, Size, TextLayout, UpdateCtx,
+    Widget,
 };
 use druid_lens_compose::ComposeLens;
-use itertools::{izip, Itertools};
-use std::{iter, sync::Arc};
+use std::{f64::consts::PI, iter, sync::Arc, time::Duration};
 
 use crate::{
-    axes::{calc_tick_spacing, Scale},
-    theme, Range,
+    animation::{self, Entrance},
+    annotations::{Annotation, Annotations},
+    axes::Direction,
+    axes::{calc_tick_spacing, CustomTick, PositionedLayout, Scale, TickLocator},
+    canvas::Canvas,
+    chart_grid::GridData,
+    chart_size,
+    commands::{
+        PointSelected, CHART_POINT_SELECTED, CHART_RANGE_SELECTED, COPY_CHART_DATA,
+        COPY_CHART_IMAGE, EXPORT_IMAGE, RESET_CHART_VIEW, TOGGLE_CHART_GRID, ZOOM_TO_RANGE,
+    },
+    context_menu::{copy_csv, copy_image, csv_cell, ChartMenu, MenuItems},
+    describe::{count, describe, min_max},
+    downsample::downsample,
+    empty::EmptyState,
+    error_bars::{add_error_bar, ErrorBar, CAP_WIDTH},
+    fit_aspect_ratio,
+    focus::KeyboardFocus,
+    format::Formatter,
+    hit_test::{HitInfo, HIT_RADIUS},
+    interaction::{range_selected, Brush, CartesianChart, PanZoom},
+    legend::{Corner, Legend, LegendPlacement},
+    offscreen::{export_image, OffscreenChart},
+    theme,
+    title::{ChartTitle, TitleAlignment},
+    trend::TrendLine,
+    Range, SeriesColors, SeriesData, DEFAULT_CHART_SIZE,
 };
 
 /// A histogram of equal width categories
 #[derive(Debug, Clone, Data, ComposeLens)]
-pub struct LineChartData<Title, XLabel> {
+pub struct LineChartData<Title, XLabel, S = Vector<f64>> {
     pub title: Title,
     // x axis
     pub x_axis_label: XLabel,
@@ -24,18 +53,515 @@ pub struct LineChartData<Title, XLabel> {
     pub x_range: Option<Range>,
     pub draw_x_tick_labels: bool,
     pub draw_x_axis: bool,
-    pub x_data: Option<Vector<f64>>,
+    pub draw_x_grid: bool,
+    /// Ticks to show on the x axis instead of the automatic ones, each with its own label.
+    pub x_ticks: Option<Vector<CustomTick>>,
+    pub x_data: Option<S>,
     // y axis
+    /// Drawn rotated, to the left of the y axis.
+    pub y_axis_label: ArcStr,
     pub y_range: Option<Range>,
     pub draw_y_tick_labels: bool,
     pub draw_y_axis: bool,
-    pub y_data: Vector<f64>,
+    pub draw_y_grid: bool,
+    /// Ticks to show on the (left hand) y axis instead of the automatic ones, e.g. the points of
+    /// a Likert scale.
+    pub y_ticks: Option<Vector<CustomTick>>,
+    pub y_data: S,
+    /// The uncertainty of each value in `y_data`, if known.
+    pub y_errors: Option<Vector<ErrorBar>>,
+    /// A band (like a confidence interval) to shade around `y_data`.
+    pub y_band: Option<ConfidenceBand<S>>,
+    // secondary y axis
+    /// The range of the right hand y axis, used by any series assigned to `YAxis::Right`. If
+    /// `None`, the range of those series is used.
+    pub y2_range: Option<Range>,
+    // series
+    /// The name of `y_data`, used in the legend.
+    pub y_data_label: ArcStr,
+    /// Extra series to draw on the same axes as `y_data`.
+    pub extra_series: Vector<LineSeries<S>>,
+}
+
+/// A series of y values drawn on a line chart, sharing the chart's `x_data`.
+#[derive(Debug, Clone, Data, Lens)]
+pub struct LineSeries<S = Vector<f64>> {
+    /// The name of the series, used in the legend.
+    pub label: ArcStr,
+    pub y_data: S,
+    /// The uncertainty of each value in `y_data`, if known.
+    pub errors: Option<Vector<ErrorBar>>,
+    /// A band (like a confidence interval) to shade around the series.
+    pub band: Option<ConfidenceBand<S>>,
+    /// Which y axis the series is plotted against.
+    pub axis: YAxis,
+}
+
+impl<S: SeriesData> LineSeries<S> {
+    pub fn new(label: impl Into<ArcStr>, y_data: S) -> Self {
+        LineSeries {
+            label: label.into(),
+            y_data,
+            errors: None,
+            band: None,
+            axis: YAxis::Left,
+        }
+    }
+
+    /// Draw error bars showing the uncertainty of each value.
+    pub fn with_errors(mut self, errors: Vector<ErrorBar>) -> Self {
+        self.errors = Some(errors);
+        self
+    }
+
+    /// Shade a band around the series, e.g. mean ± standard deviation.
+    pub fn with_band(mut self, band: ConfidenceBand<S>) -> Self {
+        self.band = Some(band);
+        self
+    }
+
+    /// Plot the series against the given y axis. The default is `YAxis::Left`.
+    pub fn with_axis(mut self, axis: YAxis) -> Self {
+        self.axis = axis;
+        self
+    }
+}
+
+/// A translucent band drawn between a lower and upper bound, like a confidence or prediction
+/// interval.
+///
+/// The bounds share the chart's `x_data`. The band is broken wherever either bound is missing.
+#[derive(Debug, Clone, Data, Lens)]
+pub struct ConfidenceBand<S = Vector<f64>> {
+    pub lower: S,
+    pub upper: S,
+}
+
+impl<S: SeriesData> ConfidenceBand<S> {
+    pub fn new(lower: S, upper: S) -> Self {
+        ConfidenceBand { lower, upper }
+    }
+}
+
+/// The y axes of a line chart.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Data)]
+pub enum YAxis {
+    Left,
+    /// The secondary axis. It is only drawn if a series uses it.
+    Right,
+}
+
+impl<Title, XLabel, S: SeriesData> LineChartData<Title, XLabel, S> {
+    /// All the y series, starting with `y_data`.
+    fn all_y_data(&self) -> impl Iterator<Item = &S> {
+        iter::once(&self.y_data).chain(self.extra_series.iter().map(|series| &series.y_data))
+    }
+
+    /// All the y series along with their axes, starting with `y_data` (always on the left).
+    fn all_series(&self) -> impl Iterator<Item = (YAxis, &S)> {
+        iter::once((YAxis::Left, &self.y_data)).chain(
+            self.extra_series
+                .iter()
+                .map(|series| (series.axis, &series.y_data)),
+        )
+    }
+
+    /// The y data of the series plotted against `axis`.
+    fn axis_y_data(&self, axis: YAxis) -> impl Iterator<Item = &S> {
+        self.all_series()
+            .filter(move |(series_axis, _)| *series_axis == axis)
+            .map(|(_, y_data)| y_data)
+    }
+
+    /// The error bars for each series, starting with `y_errors`.
+    fn all_errors(&self) -> impl Iterator<Item = Option<&Vector<ErrorBar>>> {
+        iter::once(self.y_errors.as_ref()).chain(
+            self.extra_series
+                .iter()
+                .map(|series| series.errors.as_ref()),
+        )
+    }
+
+    /// The confidence bands for each series, starting with `y_band`.
+    fn all_bands(&self) -> impl Iterator<Item = Option<&ConfidenceBand<S>>> {
+        iter::once(self.y_band.as_ref())
+            .chain(self.extra_series.iter().map(|series| series.band.as_ref()))
+    }
+
+    /// All the y values plotted against `axis`, including the ends of any error bars and bands.
+    fn axis_values(&self, axis: YAxis) -> impl Iterator<Item = f64> + '_ {
+        self.all_series()
+            .zip(self.all_errors())
+            .zip(self.all_bands())
+            .filter(move |(((series_axis, _), _), _)| *series_axis == axis)
+            .flat_map(|(((_, y_data), errors), band)| {
+                let error_bounds = errors.into_iter().flat_map(move |errors| {
+                    y_data.values().zip(errors.iter()).flat_map(|(y, error)| {
+                        let (low, high) = error.bounds(y);
+                        iter::once(low).chain(iter::once(high))
+                    })
+                });
+                let band_bounds = band
+                    .into_iter()
+                    .flat_map(|band| band.lower.values().chain(band.upper.values()));
+                y_data.values().chain(error_bounds).chain(band_bounds)
+            })
+    }
+
+    /// Whether any series is plotted against the right y axis.
+    fn has_right_axis(&self) -> bool {
+        self.extra_series
+            .iter()
+            .any(|series| series.axis == YAxis::Right)
+    }
+
+    /// All the series labels, starting with `y_data_label`.
+    fn all_labels(&self) -> impl Iterator<Item = ArcStr> + '_ {
+        iter::once(self.y_data_label.clone())
+            .chain(self.extra_series.iter().map(|series| series.label.clone()))
+    }
+
+    /// Go back to showing all the data, after the user has panned or zoomed the chart.
+    pub fn reset_view(&mut self) {
+        self.x_range = None;
+        self.y_range = None;
+    }
+
+    /// The length of the longest series.
+    fn max_len(&self) -> usize {
+        self.all_y_data()
+            .map(|y_data| y_data.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The data as a table, with a column for the x values and then one for each series.
+    fn csv_table(&self) -> Vec<Vec<String>> {
+        let mut header = vec!["x".to_string(), self.y_data_label.to_string()];
+        header.extend(
+            self.extra_series
+                .iter()
+                .map(|series| series.label.to_string()),
+        );
+        let mut table = vec![header];
+        for idx in 0..self.max_len() {
+            let x = match self.x_data.as_ref() {
+                Some(x_data) => x_data.get(idx),
+                None => Some(idx as f64),
+            };
+            let mut row = vec![csv_cell(x)];
+            row.extend(self.all_y_data().map(|y_data| csv_cell(y_data.get(idx))));
+            table.push(row);
+        }
+        table
+    }
+}
+
+impl<Title, XLabel> LineChartData<Title, XLabel> {
+    /// Append a point to every series, for streaming data.
+    ///
+    /// `x` is only used if there is `x_data`. `ys` are the new values for `y_data` followed by
+    /// each of the `extra_series`. If `capacity` is given, the oldest points are dropped so that
+    /// there are never more than `capacity`, making the data behave like a ring buffer.
+    ///
+    /// This is only available when the data is kept in `Vector`s, which can grow without being
+    /// copied.
+    pub fn push_point(
+        &mut self,
+        x: f64,
+        ys: impl IntoIterator<Item = f64>,
+        capacity: Option<usize>,
+    ) {
+        if let Some(x_data) = self.x_data.as_mut() {
+            push_bounded(x_data, x, capacity);
+        }
+        let extra_y_data = self
+            .extra_series
+            .iter_mut()
+            .map(|series| &mut series.y_data);
+        for (y_data, y) in iter::once(&mut self.y_data).chain(extra_y_data).zip(ys) {
+            push_bounded(y_data, y, capacity);
+        }
+    }
+}
+
+impl<Title: Data, XLabel: Data, S: SeriesData> GridData for LineChartData<Title, XLabel, S> {
+    fn data_x_range(&self) -> Option<Range> {
+        self.x_range
+            .or_else(|| Range::from_finite(resolve_x_data(self.x_data.as_ref(), self.max_len())))
+    }
+
+    fn data_y_range(&self) -> Option<Range> {
+        self.y_range
+            .or_else(|| Range::from_finite(self.axis_values(YAxis::Left)))
+    }
+
+    fn set_x_range(&mut self, range: Range) {
+        self.x_range = Some(range);
+    }
+
+    fn set_y_range(&mut self, range: Range) {
+        self.y_range = Some(range);
+    }
+
+    fn set_x_tick_labels(&mut self, visible: bool) {
+        self.draw_x_tick_labels = visible;
+    }
+
+    fn set_y_tick_labels(&mut self, visible: bool) {
+        self.draw_y_tick_labels = visible;
+    }
+
+    fn restore_x(&mut self, original: &Self) {
+        self.x_range = original.x_range;
+        self.draw_x_tick_labels = original.draw_x_tick_labels;
+    }
+
+    fn restore_y(&mut self, original: &Self) {
+        self.y_range = original.y_range;
+        self.draw_y_tick_labels = original.draw_y_tick_labels;
+    }
+}
+
+/// How much of the series color shows through in filled areas.
+const AREA_ALPHA: f64 = 0.3;
+
+/// How much of the series color shows through in confidence bands.
+const BAND_ALPHA: f64 = 0.2;
+
+/// Space between a point and its label, at the normal size.
+const POINT_LABEL_GAP: f64 = 4.0;
+
+/// The radius of the marker on a called out point, at the normal size.
+const CALLOUT_RADIUS: f64 = 3.0;
+
+/// What to do with missing (NaN or infinite) values in a series.
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub enum MissingValues {
+    /// Break the line, leaving a gap where the missing values are.
+    Gap,
+    /// Leave out the missing values, joining up the points either side of them.
+    Skip,
+    /// Fill in the missing values by interpolating linearly between the points either side of
+    /// them. Missing values at the start or end of a series are left out.
+    Interpolate,
+}
+
+/// How the line between two points is drawn.
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub enum Interpolation {
+    /// A straight line.
+    Linear,
+    /// Jump to the new value at the start of the step, so each value is drawn to its left.
+    StepBefore,
+    /// Keep the old value until the next point, so each value is drawn to its right. This suits
+    /// counters and states that hold until they change.
+    StepAfter,
+    /// A smooth curve (a monotone cubic spline). The curve never overshoots the points, so it
+    /// doesn't invent peaks and troughs that aren't in the data.
+    Smooth,
+}
+
+/// Which region under (or between) lines to fill in.
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub enum AreaFill {
+    /// Fill between each series and the x axis (or `y = 0` if it is visible).
+    ToAxis,
+    /// Fill between two series, given as indices (`0` is `y_data`, `1` is the first of the
+    /// `extra_series`, etc.).
+    Between(usize, usize),
+}
+
+/// Colors for a line series that change where the line crosses threshold values, e.g. red above
+/// an alert level.
+#[derive(Clone)]
+pub struct ThresholdColors {
+    pub(crate) series: usize,
+    /// The thresholds in increasing order, each with the color of the line above it (up to the next
+    /// threshold).
+    pub(crate) thresholds: Vec<(f64, KeyOrValue<Color>)>,
+}
+
+impl ThresholdColors {
+    /// Color `series` by its thresholds (`0` is `y_data`, `1` is the first of the `extra_series`,
+    /// etc.). Below the lowest threshold the line has the color of the series.
+    pub fn new(series: usize) -> Self {
+        ThresholdColors {
+            series,
+            thresholds: Vec::new(),
+        }
+    }
+
+    /// Draw the line in `color` where it is above `threshold` (and below any higher threshold).
+    pub fn above(mut self, threshold: f64, color: impl Into<KeyOrValue<Color>>) -> Self {
+        let idx = self
+            .thresholds
+            .iter()
+            .position(|(value, _)| *value > threshold)
+            .unwrap_or(self.thresholds.len());
+        self.thresholds.insert(idx, (threshold, color.into()));
+        self
+    }
+
+    /// Which color band `value` is in: `0` is below all the thresholds, `1` is above the first,
+    /// and so on.
+    fn band(&self, value: f64) -> usize {
+        self.thresholds
+            .iter()
+            .take_while(|(threshold, _)| value >= *threshold)
+            .count()
+    }
+}
+
+/// Which points of a series to label with their values.
+#[derive(Debug, Clone, PartialEq, Data)]
+pub enum PointLabels {
+    /// Every point.
+    All,
+    /// The first point, and every `n`th point after it.
+    EveryNth(usize),
+    /// The highest and lowest points.
+    MinMax,
+    /// The points at these indices.
+    Indices(Vector<usize>),
+}
+
+impl PointLabels {
+    /// The indices of the points to label, given the values of the series.
+    fn indices(&self, values: &[f64]) -> Vec<usize> {
+        match self {
+            PointLabels::All => (0..values.len()).collect(),
+            PointLabels::EveryNth(n) => (0..values.len()).step_by((*n).max(1)).collect(),
+            PointLabels::MinMax => match extreme_indices(values) {
+                Some((min, max)) if min == max => vec![min],
+                Some((min, max)) => vec![min, max],
+                None => vec![],
+            },
+            PointLabels::Indices(indices) => indices
+                .iter()
+                .copied()
+                .filter(|idx| *idx < values.len())
+                .collect(),
+        }
+    }
+}
+
+/// Which values of a series to point out with a marker and a label, as is common on monitoring
+/// dashboards.
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub struct Callouts {
+    min: bool,
+    max: bool,
+    latest: bool,
+}
+
+impl Callouts {
+    /// Call out the minimum, maximum and most recent (last) values.
+    pub fn new() -> Self {
+        Callouts {
+            min: true,
+            max: true,
+            latest: true,
+        }
+    }
+
+    /// Whether to call out the minimum value. The default is `true`.
+    pub fn with_min(mut self, min: bool) -> Self {
+        self.min = min;
+        self
+    }
+
+    /// Whether to call out the maximum value. The default is `true`.
+    pub fn with_max(mut self, max: bool) -> Self {
+        self.max = max;
+        self
+    }
+
+    /// Whether to call out the most recent (last) value. The default is `true`.
+    pub fn with_latest(mut self, latest: bool) -> Self {
+        self.latest = latest;
+        self
+    }
+
+    /// The indices of the points to call out, given the values of the series, along with what to
+    /// write before each value. Missing values are never called out.
+    fn points(&self, values: &[f64]) -> Vec<(usize, String)> {
+        let mut points: Vec<(usize, String)> = Vec::new();
+        let mut add = |idx: usize, name: &str| match points.iter_mut().find(|(i, _)| *i == idx) {
+            Some((_, prefix)) => {
+                prefix.push_str(", ");
+                prefix.push_str(name);
+            }
+            None => points.push((idx, name.to_string())),
+        };
+        if let Some((min, max)) = extreme_indices(values) {
+            if self.max {
+                add(max, "max");
+            }
+            if self.min {
+                add(min, "min");
+            }
+        }
+        if self.latest {
+            if let Some(idx) = values.iter().rposition(|value| value.is_finite()) {
+                add(idx, "latest");
+            }
+        }
+        points
+    }
+}
+
+impl Default for Callouts {
+    fn default() -> Self {
+        Callouts::new()
+    }
+}
+
+/// A value label for a point, and whether the point has a callout marker.
+struct PointLabel {
+    series: usize,
+    point: Point,
+    marker: bool,
+    label: PositionedLayout<ArcStr>,
 }
 
 pub struct LineChart<Title, XLabel> {
+    x_formatter: Formatter,
+    y_formatter: Formatter,
+    y2_formatter: Formatter,
+    /// If `None`, the x axis uses the default ticks.
+    x_tick_locator: Option<Arc<dyn TickLocator>>,
+    /// Whether larger y values are drawn lower down.
+    y_inverted: bool,
+    /// Whether each axis shows the power of 10 of big or small values once, at its end.
+    shared_exponent: bool,
+    /// Values to leave out of the y axis.
+    y_axis_break: Option<Range>,
+    area_fill: Option<AreaFill>,
+    missing_values: MissingValues,
+    interpolation: Interpolation,
+    annotations: Annotations,
+    trends: Vec<TrendLine>,
+    threshold_colors: Vec<ThresholdColors>,
+    /// Which points to label, with the index of their series.
+    point_labels: Vec<(usize, PointLabels)>,
+    /// Which values to call out, with the index of their series.
+    callouts: Vec<(usize, Callouts)>,
+    series_colors: SeriesColors,
+    stroke_width: KeyOrValue<f64>,
+    /// Whether to draw the axes at all. The data can hide parts of them as well.
+    draw_axes: bool,
+    /// If `None`, we measure the axes and labels.
+    graph_insets: Option<KeyOrValue<Insets>>,
+    preferred_size: Size,
+    aspect_ratio: Option<f64>,
+    /// How much to grow the ranges calculated from the data, as a fraction of their size.
+    range_padding: f64,
+    zebra_bands: bool,
     // retained state
-    title_layout: TextLayout<Title>,
+    title: ChartTitle<Title>,
     x_label_layout: TextLayout<XLabel>,
+    y_label_layout: TextLayout<ArcStr>,
     // we keep axes separate as we have to do less invalidation that way.
     // x axis
     /// We only need to calculate this if we aren't using a fixed range.
@@ -44,6 +570,39 @@ pub struct LineChart<Title, XLabel> {
     // y axis
     data_range_y: Option<Range>,
     y_scale: Option<Scale>,
+    // secondary y axis (only if a series uses it)
+    data_range_y2: Option<Range>,
+    y2_scale: Option<Scale>,
+    /// The area where the data is drawn.
+    graph_bounds: Rect,
+    /// The line for each series, downsampled so it never has more than a few points per pixel
+    /// column.
+    lines: Option<Vec<BezPath>>,
+    /// Paths for the filled areas, if we are drawing any.
+    area_paths: Option<Vec<(usize, BezPath)>>,
+    /// The value of each trend at each point, in data space.
+    trend_values: Option<Vec<Vec<f64>>>,
+    /// The line for each trend. These are rebuilt along with `lines`.
+    trend_paths: Vec<BezPath>,
+    /// The text of the stats box for each trend that has one, with the index of the trend.
+    trend_stats_layouts: Option<Vec<(usize, Corner, TextLayout<ArcStr>)>>,
+    /// The outline of each confidence band, along with the index of its series. These are
+    /// rebuilt along with `lines`.
+    band_paths: Vec<(usize, BezPath)>,
+    /// The lines of the series with threshold colors, split into one path per color band (in the
+    /// same order as `threshold_colors`). These are rebuilt along with `lines`.
+    threshold_paths: Vec<Vec<BezPath>>,
+    /// The value labels for points and callouts, placed clear of the lines. These are rebuilt
+    /// along with `lines`.
+    point_label_layouts: Option<Vec<PointLabel>>,
+    legend: Option<Legend>,
+    empty_state: EmptyState,
+    // interaction
+    pan_zoom: Option<PanZoom>,
+    brush: Option<Brush>,
+    entrance: Option<Entrance>,
+    keyboard: Option<KeyboardFocus>,
+    context_menu: Option<ChartMenu>,
 }
 
 impl<Title, XLabel> LineChart<Title, XLabel>
@@ -52,135 +611,1554 @@ where
     XLabel: TextStorage,
 {
     pub fn new() -> Self {
-        let mut title_layout = TextLayout::new();
-        title_layout.set_text_size(20.);
         LineChart {
-            title_layout,
+            x_formatter: Formatter::default(),
+            y_formatter: Formatter::default(),
+            y2_formatter: Formatter::default(),
+            x_tick_locator: None,
+            y_inverted: false,
+            shared_exponent: false,
+            y_axis_break: None,
+            area_fill: None,
+            missing_values: MissingValues::Gap,
+            interpolation: Interpolation::Linear,
+            annotations: Annotations::new(),
+            trends: Vec::new(),
+            threshold_colors: Vec::new(),
+            point_labels: Vec::new(),
+            callouts: Vec::new(),
+            series_colors: SeriesColors::default(),
+            stroke_width: theme::LINE_STROKE_WIDTH.into(),
+            draw_axes: true,
+            graph_insets: None,
+            preferred_size: DEFAULT_CHART_SIZE,
+            aspect_ratio: None,
+            range_padding: 0.,
+            zebra_bands: false,
+            title: ChartTitle::new(),
             x_label_layout: TextLayout::new(),
+            y_label_layout: TextLayout::new(),
             data_range_x: None,
             data_range_y: None,
             x_scale: None,
             y_scale: None,
+            data_range_y2: None,
+            y2_scale: None,
+            graph_bounds: Rect::ZERO,
+            lines: None,
+            area_paths: None,
+            trend_values: None,
+            trend_paths: Vec::new(),
+            trend_stats_layouts: None,
+            band_paths: Vec::new(),
+            threshold_paths: Vec::new(),
+            point_label_layouts: None,
+            legend: None,
+            empty_state: EmptyState::new(),
+            pan_zoom: None,
+            brush: None,
+            entrance: None,
+            keyboard: None,
+            context_menu: None,
+        }
+    }
+
+    /// Set how the x axis tick labels are formatted.
+    pub fn with_x_formatter(mut self, formatter: Formatter) -> Self {
+        self.x_formatter = formatter;
+        self
+    }
+
+    /// Choose where the ticks on the x axis go, e.g. `FixedTicker` for ticks at specific values.
+    pub fn with_x_tick_locator(mut self, tick_locator: impl TickLocator + 'static) -> Self {
+        self.x_tick_locator = Some(Arc::new(tick_locator));
+        self
+    }
+
+    /// Set how the y axis tick labels are formatted.
+    pub fn with_y_formatter(mut self, formatter: Formatter) -> Self {
+        self.y_formatter = formatter;
+        self
+    }
+
+    /// Flip the y axis, so larger values are drawn lower down, e.g. for depths or rankings.
+    pub fn with_y_inverted(mut self, inverted: bool) -> Self {
+        self.y_inverted = inverted;
+        self
+    }
+
+    /// Leave the values in `axis_break` out of the y axis, e.g. so one outlier doesn't squash the
+    /// rest of the data. See [`Scale::set_axis_break`].
+    pub fn with_y_axis_break(mut self, axis_break: impl Into<Range>) -> Self {
+        self.y_axis_break = Some(axis_break.into());
+        self
+    }
+
+    /// Show the power of 10 of very big or small tick values once at the end of each axis (e.g.
+    /// `×10⁶`), instead of in every label. See [`Scale::set_shared_exponent`].
+    pub fn with_shared_exponent(mut self, shared_exponent: bool) -> Self {
+        self.shared_exponent = shared_exponent;
+        self
+    }
+
+    /// Set how the right hand y axis tick labels are formatted.
+    pub fn with_y2_formatter(mut self, formatter: Formatter) -> Self {
+        self.y2_formatter = formatter;
+        self
+    }
+
+    /// Fill in the area under the lines (or between two of them), making an area chart.
+    pub fn with_area_fill(mut self, area_fill: AreaFill) -> Self {
+        self.area_fill = Some(area_fill);
+        self
+    }
+
+    /// Choose how missing values in the data are drawn. The default is to leave a gap.
+    pub fn with_missing_values(mut self, missing_values: MissingValues) -> Self {
+        self.missing_values = missing_values;
+        self
+    }
+
+    /// Choose how the points are joined up. The default is straight lines.
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Add a reference line or band, drawn behind the data.
+    pub fn with_annotation(mut self, annotation: Annotation) -> Self {
+        self.annotations.push(annotation);
+        self
+    }
+
+    /// Set the color of one of the lines (`0` is `y_data`, `1` is the first of the
+    /// `extra_series`, etc.). Lines without a color get one picked for them.
+    pub fn with_line_color(mut self, series: usize, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.series_colors.set(series, color);
+        self
+    }
+
+    /// Set the width of the lines. The default is `theme::LINE_STROKE_WIDTH`.
+    pub fn with_stroke_width(mut self, width: impl Into<KeyOrValue<f64>>) -> Self {
+        self.stroke_width = width.into();
+        self
+    }
+
+    /// Whether to draw the axes, with their tick labels. Hidden axes don't take up any room.
+    ///
+    /// The `draw_*` flags of the data can hide the axis lines or tick labels of visible axes.
+    pub fn with_axes_visible(mut self, visible: bool) -> Self {
+        self.draw_axes = visible;
+        self
+    }
+
+    /// Set the font size of the title. The default is `theme::TITLE_TEXT_SIZE`.
+    pub fn with_title_size(mut self, size: impl Into<KeyOrValue<f64>>) -> Self {
+        self.title.set_text_size(size);
+        self
+    }
+
+    /// Where to put the title (and subtitle). Defaults to the center.
+    pub fn with_title_alignment(mut self, alignment: TitleAlignment) -> Self {
+        self.title.set_alignment(alignment);
+        self
+    }
+
+    /// Draw a line of smaller text under the title.
+    pub fn with_subtitle(mut self, subtitle: impl Into<ArcStr>) -> Self {
+        self.title.set_subtitle(subtitle.into());
+        self
+    }
+
+    /// Draw a trend (like a moving average) for one of the series, over the data.
+    pub fn with_trend(mut self, trend: TrendLine) -> Self {
+        self.trends.push(trend);
+        self
+    }
+
+    /// Color a series differently where it crosses thresholds, e.g. red above an alert level.
+    pub fn with_threshold_colors(mut self, threshold_colors: ThresholdColors) -> Self {
+        self.threshold_colors.push(threshold_colors);
+        self
+    }
+
+    /// Label some of the points of a series (`0` is `y_data`, `1` is the first of the
+    /// `extra_series`, etc.) with their values, formatted like the tick labels of their axis.
+    ///
+    /// Labels go above, below or beside their point, wherever they don't cover the line.
+    pub fn with_point_labels(mut self, series: usize, labels: PointLabels) -> Self {
+        self.point_labels.push((series, labels));
+        self
+    }
+
+    /// Mark the minimum, maximum and/or latest values of a series (`0` is `y_data`, `1` is the
+    /// first of the `extra_series`, etc.) and label them with their values.
+    pub fn with_callouts(mut self, series: usize, callouts: Callouts) -> Self {
+        self.callouts.push((series, callouts));
+        self
+    }
+
+    /// Use fixed insets around the plot area, instead of measuring the axes (see
+    /// `theme::GRAPH_INSETS`).
+    pub fn with_graph_insets(mut self, insets: impl Into<KeyOrValue<Insets>>) -> Self {
+        self.graph_insets = Some(insets.into());
+        self
+    }
+
+    /// Set the size to take when the space isn't bounded, e.g. inside a `Scroll`. Otherwise the
+    /// chart fills the space it is given. The default is 400×300.
+    pub fn with_preferred_size(mut self, size: impl Into<Size>) -> Self {
+        self.preferred_size = size.into();
+        self
+    }
+
+    /// Keep the plot area at the given width:height ratio, centered in the space left over from
+    /// the title, legend and axes. By default the plot area fills that space.
+    pub fn with_aspect_ratio(mut self, ratio: f64) -> Self {
+        assert!(ratio > 0.0, "aspect ratio must be positive");
+        self.aspect_ratio = Some(ratio);
+        self
+    }
+
+    /// Leave a gap of `fraction` (e.g. `0.05` for 5%) of the range at each end of the axes, so the
+    /// lines don't sit right on the axis lines. Ranges given in the data aren't changed.
+    pub fn with_range_padding(mut self, fraction: f64) -> Self {
+        self.range_padding = fraction;
+        self
+    }
+
+    /// Shade every other band between the y grid lines, to help read values across a wide
+    /// chart. The color is `theme::ZEBRA_COLOR`.
+    pub fn with_zebra_bands(mut self, zebra_bands: bool) -> Self {
+        self.zebra_bands = zebra_bands;
+        self
+    }
+
+    /// Show a legend with an entry for each series.
+    pub fn with_legend(mut self, placement: LegendPlacement) -> Self {
+        self.legend = Some(Legend::new(placement));
+        self
+    }
+
+    /// Allow the user to zoom with the mouse wheel and pan by dragging.
+    ///
+    /// The chart writes the new view into `x_range` and `y_range`, so these should be bound to
+    /// your app state. Setting them back to `None` resets the view. The right hand y axis (if
+    /// there is one) is left as it is.
+    pub fn with_pan_zoom(mut self, pan_zoom: PanZoom) -> Self {
+        self.pan_zoom = Some(pan_zoom);
+        self
+    }
+
+    /// Allow the user to select part of the chart by dragging out a rectangle.
+    ///
+    /// When they let go, `CHART_RANGE_SELECTED` is sent as a notification, with the selected
+    /// ranges and the points inside them, or if the brush zooms the chart shows just the
+    /// selection. If the chart also has pan and zoom, hold shift to select instead of panning.
+    pub fn with_brush(mut self, brush: Brush) -> Self {
+        self.brush = Some(brush);
+        self
+    }
+
+    /// Draw the data in from left to right when the chart is first shown.
+    pub fn with_entrance_animation(mut self, duration: Duration) -> Self {
+        self.entrance = Some(Entrance::new(duration));
+        self
+    }
+
+    /// Let the user move between the points with the arrow keys (up and down change series), and
+    /// select one with Enter. The chart takes part in tab focus.
+    pub fn with_keyboard_navigation(mut self, enabled: bool) -> Self {
+        self.keyboard = if enabled {
+            Some(KeyboardFocus::new())
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Show a menu when the chart is right-clicked, to reset the zoom (if the chart can zoom),
+    /// toggle the grid, copy the chart as an image, or copy or save the data as CSV. `T` is the
+    /// type of your app's data, which druid needs to show a menu.
+    pub fn with_context_menu<T: Data>(mut self) -> Self {
+        self.context_menu = Some(ChartMenu::new::<T>());
+        self
+    }
+
+    /// Calculate the x range from the data. If there is no data, the range and scale are cleared
+    /// (likewise for the y axes), and we draw the empty state instead.
+    fn calc_x_data_range<S: SeriesData>(&mut self, data: &LineChartData<Title, XLabel, S>) {
+        match Range::from_finite(resolve_x_data(data.x_data.as_ref(), data.max_len())) {
+            Some(range) => self.set_data_range_x(range),
+            None => {
+                self.data_range_x = None;
+                self.x_scale = None;
+            }
+        }
+    }
+
+    fn calc_y_data_range<S: SeriesData>(&mut self, data: &LineChartData<Title, XLabel, S>) {
+        match Range::from_finite(data.axis_values(YAxis::Left)) {
+            Some(range) => self.set_data_range_y(range),
+            None => {
+                self.data_range_y = None;
+                self.y_scale = None;
+            }
+        }
+    }
+
+    fn calc_y2_data_range<S: SeriesData>(&mut self, data: &LineChartData<Title, XLabel, S>) {
+        if !data.has_right_axis() {
+            self.data_range_y2 = None;
+            self.y2_scale = None;
+            return;
+        }
+        match Range::from_finite(data.axis_values(YAxis::Right)) {
+            Some(range) => self.set_data_range_y2(range),
+            None => {
+                self.data_range_y2 = None;
+                self.y2_scale = None;
+            }
+        }
+    }
+
+    fn set_data_range_x(&mut self, range: Range) {
+        self.data_range_x = Some(range);
+        // Keep the scale if we have one, so the ticks are only rebuilt if the range changed.
+        if let Some(x_scale) = self.x_scale.as_mut() {
+            x_scale.set_data_range(range.pad(self.range_padding));
+        }
+    }
+
+    fn set_data_range_y(&mut self, range: Range) {
+        self.data_range_y = Some(range);
+        if let Some(y_scale) = self.y_scale.as_mut() {
+            y_scale.set_data_range(range.pad(self.range_padding));
+        }
+    }
+
+    fn set_data_range_y2(&mut self, range: Range) {
+        self.data_range_y2 = Some(range);
+        if let Some(y2_scale) = self.y2_scale.as_mut() {
+            y2_scale.set_data_range(range.pad(self.range_padding));
+        }
+    }
+
+    /// Update the data ranges after points were streamed in, and repaint as little as possible.
+    fn update_streamed<S: SeriesData>(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        streamed: Streamed,
+        old_data: &LineChartData<Title, XLabel, S>,
+        data: &LineChartData<Title, XLabel, S>,
+        env: &Env,
+    ) {
+        let old_ranges = (self.data_range_x, self.data_range_y, self.data_range_y2);
+        if data.x_range.is_none() {
+            let range = match (self.data_range_x, &old_data.x_data, &data.x_data) {
+                (Some(range), Some(old_x_data), Some(x_data)) => {
+                    streamed.update_range(range, old_x_data, x_data)
+                }
+                _ => None,
+            };
+            match range {
+                Some(range) => self.set_data_range_x(range),
+                None => self.calc_x_data_range(data),
+            }
+        }
+        if data.y_range.is_none() {
+            let range = self
+                .data_range_y
+                .and_then(|range| streamed.update_axis_range(range, YAxis::Left, old_data, data));
+            match range {
+                Some(range) => self.set_data_range_y(range),
+                None => self.calc_y_data_range(data),
+            }
+        }
+        if data.y2_range.is_none() && data.has_right_axis() {
+            let range = self
+                .data_range_y2
+                .and_then(|range| streamed.update_axis_range(range, YAxis::Right, old_data, data));
+            match range {
+                Some(range) => self.set_data_range_y2(range),
+                None => self.calc_y2_data_range(data),
+            }
+        }
+
+        // If nothing moved, we only need to paint the new segments.
+        let unmoved = streamed.dropped == 0
+            && self.area_fill.is_none()
+            && self.x_scale.is_some()
+            && self.y_scale.is_some()
+            && (self.y2_scale.is_some() || !data.has_right_axis())
+            && (self.data_range_x, self.data_range_y, self.data_range_y2) == old_ranges;
+        if !unmoved {
+            self.lines = None;
+            self.area_paths = None;
+            self.trend_values = None;
+            ctx.request_layout();
+            return;
+        }
+        // nothing else needs laying out again, so the new points can be added to the lines
+        // straight away.
+        if !self.trends.is_empty() {
+            // the trends are fitted to all the points.
+            self.trend_values = None;
+        }
+        let dirty = self.extend_lines(data, streamed.added);
+        if dirty.is_none() {
+            self.lines = None;
+        }
+        self.rebuild_paths(data, env);
+        // the point labels and trend stats went with the old lines and trends.
+        if self.point_label_layouts.is_none() {
+            self.point_label_layouts = Some(self.build_point_labels(ctx.text(), data, env));
+        }
+        if self.trend_stats_layouts.is_none() {
+            self.trend_stats_layouts = Some(self.build_trend_stats(ctx.text(), data, env));
+        }
+        match dirty {
+            // allow for the width of the line.
+            Some(dirty) if !self.streamed_repaints_all() => {
+                ctx.request_paint_rect(dirty.inflate(2., 2.))
+            }
+            _ => ctx.request_paint(),
+        }
+    }
+
+    /// Add the last `added` points of each series to the ends of the lines (and their threshold
+    /// colors), rather than building them all again, and return the area the new segments cover.
+    ///
+    /// Returns `None` if the lines have to be built from scratch: if they haven't been built yet,
+    /// or are smooth curves, where the end of the old line bends towards the new points.
+    fn extend_lines<S: SeriesData>(
+        &mut self,
+        data: &LineChartData<Title, XLabel, S>,
+        added: usize,
+    ) -> Option<Rect> {
+        if self.lines.is_none() || self.interpolation == Interpolation::Smooth {
+            return None;
+        }
+        let tails: Vec<BezPath> = data
+            .all_series()
+            .map(|(axis, y_data)| self.line_tail(data, axis, y_data, added))
+            .collect();
+        let threshold_tails = self.split_thresholds(data, &tails);
+        for (line, tail) in self.lines.as_mut().unwrap().iter_mut().zip(tails.iter()) {
+            append_path(line, tail);
+        }
+        for (paths, tails) in self.threshold_paths.iter_mut().zip(threshold_tails.iter()) {
+            for (path, tail) in paths.iter_mut().zip(tails.iter()) {
+                append_path(path, tail);
+            }
+        }
+        self.point_label_layouts = None;
+        let dirty = tails
+            .iter()
+            .filter(|tail| !tail.elements().is_empty())
+            .map(|tail| tail.bounding_box())
+            .fold(None, |dirty: Option<Rect>, rect| {
+                Some(dirty.map_or(rect, |dirty| dirty.union(rect)))
+            });
+        Some(dirty.unwrap_or(Rect::ZERO))
+    }
+
+    /// The path from the end of the line of a series through its last `added` points, without
+    /// looking at the rest of the series.
+    fn line_tail<S: SeriesData>(
+        &self,
+        data: &LineChartData<Title, XLabel, S>,
+        axis: YAxis,
+        y_data: &S,
+        added: usize,
+    ) -> BezPath {
+        let x_scale = self.x_scale.as_ref().unwrap();
+        let y_scale = self.y_scale_for(axis);
+        let point_at = |idx: usize| {
+            let x = match data.x_data.as_ref() {
+                Some(x_data) => x_data.get(idx).unwrap_or(f64::NAN),
+                None => idx as f64,
+            };
+            let y = y_data.get(idx).unwrap_or(f64::NAN);
+            Point::new(x_scale.pixel_location(x), y_scale.pixel_location(y))
+        };
+        // start from the last old point, so the new segments join on to the line.
+        let mut start = (y_data.len() - added).saturating_sub(1);
+        if self.missing_values != MissingValues::Gap {
+            // missing points at the end of the line were left out of it (for now).
+            while start > 0 && is_missing(point_at(start)) {
+                start -= 1;
+            }
+        }
+        let points = (start..y_data.len()).map(point_at).collect();
+        line_path(
+            downsample(finish_line(points, self.missing_values, self.interpolation)),
+            self.interpolation,
+        )
+    }
+
+    /// Whether streamed points can change things anywhere on the chart, rather than just next to
+    /// the new segments. Point labels (e.g. on the highest point) can move to any point, and
+    /// trend lines are fitted to all the points.
+    fn streamed_repaints_all(&self) -> bool {
+        !self.point_labels.is_empty() || !self.callouts.is_empty() || !self.trends.is_empty()
+    }
+
+    fn x_range<S: SeriesData>(&self, data: &LineChartData<Title, XLabel, S>) -> Option<Range> {
+        data.x_range
+            .or(self.data_range_x.map(|range| range.pad(self.range_padding)))
+    }
+
+    fn y_range<S: SeriesData>(&self, data: &LineChartData<Title, XLabel, S>) -> Option<Range> {
+        data.y_range
+            .or(self.data_range_y.map(|range| range.pad(self.range_padding)))
+    }
+
+    fn y2_range<S: SeriesData>(&self, data: &LineChartData<Title, XLabel, S>) -> Option<Range> {
+        data.y2_range.or(self
+            .data_range_y2
+            .map(|range| range.pad(self.range_padding)))
+    }
+
+    /// The scale a series assigned to `axis` is plotted against.
+    fn y_scale_for(&self, axis: YAxis) -> &Scale {
+        match axis {
+            YAxis::Left => self.y_scale.as_ref().unwrap(),
+            YAxis::Right => self.y2_scale.as_ref().unwrap(),
+        }
+    }
+
+    /// A short description of the chart for screen readers, e.g. "Line chart, Blood pressure, 200
+    /// points, min 90, max 180".
+    pub fn describe<S: SeriesData>(&self, data: &LineChartData<Title, XLabel, S>) -> String {
+        let series_count = data.all_y_data().count();
+        let mut facts = Vec::new();
+        if series_count > 1 {
+            facts.push(count(series_count, "series", "series"));
+        }
+        facts.push(count(data.max_len(), "point", "points"));
+        facts.extend(min_max(
+            data.all_y_data().flat_map(|y_data| y_data.values()),
+            &self.y_formatter,
+        ));
+        describe("Line chart", data.title.as_str(), facts)
+    }
+
+    /// Whether there is nothing to plot, so we draw the empty state instead.
+    fn is_empty<S: SeriesData>(&self, data: &LineChartData<Title, XLabel, S>) -> bool {
+        self.x_range(data).is_none()
+            || self.y_range(data).is_none()
+            || (data.has_right_axis() && self.y2_range(data).is_none())
+    }
+
+    /// Take the labels and data ranges from `data`, when the chart is added.
+    fn set_data<S: SeriesData>(&mut self, data: &LineChartData<Title, XLabel, S>) {
+        self.title.set_text(data.title.clone());
+        self.x_label_layout.set_text(data.x_axis_label.clone());
+        self.y_label_layout.set_text(data.y_axis_label.clone());
+        if let Some(legend) = self.legend.as_mut() {
+            legend.set_labels(data.all_labels());
+        }
+        if data.x_range.is_none() {
+            self.calc_x_data_range(data);
+        }
+        if data.y_range.is_none() {
+            self.calc_y_data_range(data);
+        }
+        if data.y2_range.is_none() {
+            self.calc_y2_data_range(data);
+        }
+    }
+
+    /// Lay out the chart, or the empty state if there is nothing to draw.
+    fn lay_out<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &LineChartData<Title, XLabel, S>,
+        env: &Env,
+    ) {
+        if self.is_empty(data) {
+            self.empty_state.rebuild_if_needed(text, env);
+        } else {
+            self.rebuild_if_needed(text, size, data, env);
+        }
+    }
+
+    /// Rebuild the layouts, scales and paths for a chart of size `size`, as needed.
+    fn rebuild_if_needed<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &LineChartData<Title, XLabel, S>,
+        env: &Env,
+    ) {
+        let margin = env.get(theme::MARGIN);
+        let scale_margin = env.get(theme::SCALE_MARGIN);
+        let has_right_axis = data.has_right_axis();
+        if self.x_scale.is_none()
+            || self.y_scale.is_none()
+            || (has_right_axis && self.y2_scale.is_none())
+        {
+            self.lines = None;
+            self.area_paths = None;
+        }
+
+        self.title.rebuild_if_needed(text, env);
+        self.x_label_layout.rebuild_if_needed(text, env);
+        self.y_label_layout.rebuild_if_needed(text, env);
+        self.annotations.rebuild_if_needed(text, env);
+        if let Some(legend) = self.legend.as_mut() {
+            legend.rebuild_if_needed(text, env);
+        }
+        if self.x_scale.is_none() {
+            let mut x_scale = Scale::new_x(self.x_range(data).unwrap());
+            x_scale.set_formatter(self.x_formatter.clone());
+            if let Some(tick_locator) = self.x_tick_locator.clone() {
+                x_scale.set_shared_tick_locator(tick_locator);
+            }
+            x_scale.set_custom_ticks(data.x_ticks.clone());
+            x_scale.set_shared_exponent(self.shared_exponent);
+            self.x_scale = Some(x_scale);
+        }
+        if self.y_scale.is_none() {
+            let mut y_scale = Scale::new_y(self.y_range(data).unwrap());
+            y_scale.set_formatter(self.y_formatter.clone());
+            y_scale.set_inverted(self.y_inverted);
+            y_scale.set_custom_ticks(data.y_ticks.clone());
+            y_scale.set_shared_exponent(self.shared_exponent);
+            y_scale.set_axis_break(self.y_axis_break);
+            self.y_scale = Some(y_scale);
+        }
+        if !has_right_axis {
+            self.y2_scale = None;
+        } else if self.y2_scale.is_none() {
+            let mut y2_scale = Scale::new(self.y2_range(data).unwrap(), Direction::YRight);
+            y2_scale.set_formatter(self.y2_formatter.clone());
+            y2_scale.set_shared_exponent(self.shared_exponent);
+            self.y2_scale = Some(y2_scale);
+        }
+
+        // build twice because we want to check the size
+        // Firstly try laying out with no size restriction
+        //
+        // There is a bit of a dance here because the borrow checker won't let us borrow both parts
+        // of the struct at the same time.
+        let mut draw_area = self.title.reserve_space(size.to_rect(), env);
+        if let Some(legend) = self.legend.as_mut() {
+            draw_area = legend.reserve_space(draw_area, env);
+        }
+        let x_scale = self.x_scale.as_mut().unwrap();
+        x_scale.set_graph_bounds(draw_area);
+        x_scale.rebuild_if_needed(text, env);
+        let y_scale = self.y_scale.as_mut().unwrap();
+        y_scale.set_graph_bounds(draw_area);
+        y_scale.rebuild_if_needed(text, env);
+        if let Some(y2_scale) = self.y2_scale.as_mut() {
+            y2_scale.set_graph_bounds(draw_area);
+            y2_scale.rebuild_if_needed(text, env);
+        }
+
+        // space for an axis and its tick labels, if the axes are drawn.
+        let draw_axes = self.draw_axes;
+        let axis_room = |labels: f64| {
+            if draw_axes {
+                labels + scale_margin
+            } else {
+                0.
+            }
+        };
+        // space for the y axis and tick labels
+        let mut x0 = margin + axis_room(self.y_scale.as_ref().unwrap().max_layout().width);
+        // add space for the y axis label (if it's there). It's rotated, so we need its height.
+        if !data.y_axis_label.is_empty() {
+            x0 += margin + self.y_label_layout.size().height;
+        }
+        // space for the right y axis and tick labels (if it's there)
+        let x1 = match self.y2_scale.as_ref() {
+            Some(y2_scale) => margin + axis_room(y2_scale.max_layout().width),
+            None => margin,
+        };
+        // the title has its own space above the draw area.
+        let y0 = margin;
+        // space for the x axis and tick labels
+        let mut y1 = margin + axis_room(self.x_scale.as_ref().unwrap().max_layout().height);
+        // add space for the x axis label (if it's there)
+        if !data.x_axis_label.as_str().is_empty() {
+            y1 += margin + self.x_label_layout.size().height;
+        }
+
+        let graph_insets = match self.graph_insets.as_ref() {
+            Some(graph_insets) => -graph_insets.resolve(env),
+            None => Insets {
+                x0: -x0,
+                y0: -y0,
+                x1: -x1,
+                y1: -y1,
+            },
+        };
+        let mut graph_bounds = draw_area.inset(graph_insets);
+        if let Some(ratio) = self.aspect_ratio {
+            graph_bounds = fit_aspect_ratio(graph_bounds, ratio);
+        }
+
+        // now build again using the info we calculated.
+        let x_scale = self.x_scale.as_mut().unwrap();
+        x_scale.set_graph_bounds(graph_bounds);
+        x_scale.rebuild_if_needed(text, env);
+        let y_scale = self.y_scale.as_mut().unwrap();
+        y_scale.set_graph_bounds(graph_bounds);
+        y_scale.rebuild_if_needed(text, env);
+        if let Some(y2_scale) = self.y2_scale.as_mut() {
+            y2_scale.set_graph_bounds(graph_bounds);
+            y2_scale.rebuild_if_needed(text, env);
+        }
+        if let Some(legend) = self.legend.as_mut() {
+            legend.set_graph_bounds(graph_bounds, env);
+        }
+        if self.graph_bounds != graph_bounds {
+            self.graph_bounds = graph_bounds;
+            self.lines = None;
+            self.area_paths = None;
+        }
+        self.rebuild_paths(data, env);
+        if self.point_label_layouts.is_none() {
+            self.point_label_layouts = Some(self.build_point_labels(text, data, env));
+        }
+        if self.trend_stats_layouts.is_none() {
+            self.trend_stats_layouts = Some(self.build_trend_stats(text, data, env));
+        }
+    }
+
+    /// Rebuild the lines, trends and areas, as needed. The scales must have been built.
+    fn rebuild_paths<S: SeriesData>(&mut self, data: &LineChartData<Title, XLabel, S>, env: &Env) {
+        let mut rebuild_trend_paths = false;
+        if self.trend_values.is_none() {
+            self.trend_values = Some(
+                self.trends
+                    .iter()
+                    .map(
+                        |trend_line| match data.all_series().nth(trend_line.series) {
+                            Some((_, y_data)) => {
+                                let xs: Vec<f64> =
+                                    resolve_x_data(data.x_data.as_ref(), y_data.len())
+                                        .take(y_data.len())
+                                        .collect();
+                                let ys: Vec<f64> = y_data.values().collect();
+                                trend_line.trend.calculate(&xs, &ys)
+                            }
+                            None => vec![],
+                        },
+                    )
+                    .collect(),
+            );
+            self.trend_stats_layouts = None;
+            rebuild_trend_paths = true;
+        }
+        if self.lines.is_none() {
+            let cap_width = CAP_WIDTH * env.get(theme::SIZE_SCALE);
+            rebuild_trend_paths = true;
+            self.band_paths = self.build_band_paths(data);
+            self.lines = Some(
+                data.all_series()
+                    .zip(data.all_errors())
+                    .map(|((axis, y_data), errors)| {
+                        let mut path = line_path(
+                            downsample(self.series_line(data, axis, y_data)),
+                            self.interpolation,
+                        );
+                        if let Some(errors) = errors {
+                            self.add_error_bars(&mut path, data, axis, y_data, errors, cap_width);
+                        }
+                        path
+                    })
+                    .collect(),
+            );
+            self.threshold_paths = self.split_thresholds(data, self.lines.as_ref().unwrap());
+            self.point_label_layouts = None;
+        }
+        if rebuild_trend_paths {
+            self.trend_paths = self.build_trend_paths(data);
+        }
+        if self.area_paths.is_none() {
+            if let Some(area_fill) = self.area_fill {
+                self.area_paths = Some(self.build_area_paths(area_fill, data));
+            }
+        }
+    }
+
+    fn build_trend_paths<S: SeriesData>(
+        &self,
+        data: &LineChartData<Title, XLabel, S>,
+    ) -> Vec<BezPath> {
+        let x_scale = self.x_scale.as_ref().unwrap();
+        self.trends
+            .iter()
+            .zip(self.trend_values.as_ref().unwrap())
+            .map(|(trend_line, values)| {
+                let axis = data
+                    .all_series()
+                    .nth(trend_line.series)
+                    .map_or(YAxis::Left, |(axis, _)| axis);
+                let y_scale = self.y_scale_for(axis);
+                let points = resolve_x_data(data.x_data.as_ref(), values.len())
+                    .zip(values.iter().copied())
+                    .map(|(x, y)| Point::new(x_scale.pixel_location(x), y_scale.pixel_location(y)));
+                line_path(downsample(points), Interpolation::Linear)
+            })
+            .collect()
+    }
+
+    fn build_trend_stats<S: SeriesData>(
+        &self,
+        text: &mut PietText,
+        data: &LineChartData<Title, XLabel, S>,
+        env: &Env,
+    ) -> Vec<(usize, Corner, TextLayout<ArcStr>)> {
+        self.trends
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, trend_line)| {
+                let corner = trend_line.stats_corner?;
+                let (_, y_data) = data.all_series().nth(trend_line.series)?;
+                let xs: Vec<f64> = resolve_x_data(data.x_data.as_ref(), y_data.len())
+                    .take(y_data.len())
+                    .collect();
+                let ys: Vec<f64> = y_data.values().collect();
+                let mut layout =
+                    TextLayout::from_text(ArcStr::from(trend_line.stats_text(&xs, &ys)?));
+                layout.set_text_size(theme::TICK_LABEL_TEXT_SIZE);
+                layout.rebuild_if_needed(text, env);
+                Some((idx, corner, layout))
+            })
+            .collect()
+    }
+
+    /// Split the `lines` of the series with threshold colors where they cross each threshold.
+    fn split_thresholds<S: SeriesData>(
+        &self,
+        data: &LineChartData<Title, XLabel, S>,
+        lines: &[BezPath],
+    ) -> Vec<Vec<BezPath>> {
+        self.threshold_colors
+            .iter()
+            .map(|threshold_colors| {
+                let (axis, line) = match (
+                    data.all_series().nth(threshold_colors.series),
+                    lines.get(threshold_colors.series),
+                ) {
+                    (Some((axis, _)), Some(line)) => (axis, line),
+                    _ => return vec![],
+                };
+                let y_scale = self.y_scale_for(axis);
+                let levels: Vec<f64> = threshold_colors
+                    .thresholds
+                    .iter()
+                    .map(|(value, _)| y_scale.pixel_location(*value))
+                    .collect();
+                split_at_levels(line, &levels, threshold_colors.thresholds.len() + 1, |y| {
+                    threshold_colors.band(y_scale.data_location(y))
+                })
+            })
+            .collect()
+    }
+
+    /// Lay out the labels for the points in `self.point_labels` and `self.callouts`, and find
+    /// somewhere to put each one.
+    fn build_point_labels<S: SeriesData>(
+        &self,
+        text: &mut PietText,
+        data: &LineChartData<Title, XLabel, S>,
+        env: &Env,
+    ) -> Vec<PointLabel> {
+        if self.point_labels.is_empty() && self.callouts.is_empty() {
+            return vec![];
+        }
+        let gap = POINT_LABEL_GAP * env.get(theme::SIZE_SCALE);
+        let all_series: Vec<(YAxis, Vec<f64>, Vec<Point>)> = data
+            .all_series()
+            .map(|(axis, y_data)| {
+                let values = y_data.values().collect();
+                let points = self.series_points(data, axis, y_data).collect();
+                (axis, values, points)
+            })
+            .collect();
+        // (series, index, what to write before the value). Callouts go first so they get the
+        // best places.
+        let mut wanted: Vec<(usize, usize, Option<String>)> = Vec::new();
+        for (series, callouts) in self.callouts.iter() {
+            if let Some((_, values, _)) = all_series.get(*series) {
+                for (idx, prefix) in callouts.points(values) {
+                    wanted.push((*series, idx, Some(prefix)));
+                }
+            }
+        }
+        for (series, point_labels) in self.point_labels.iter() {
+            if let Some((_, values, _)) = all_series.get(*series) {
+                for idx in point_labels.indices(values) {
+                    if !wanted.iter().any(|(s, i, _)| (*s, *i) == (*series, idx)) {
+                        wanted.push((*series, idx, None));
+                    }
+                }
+            }
+        }
+
+        let mut placed: Vec<Rect> = Vec::new();
+        let mut labels = Vec::new();
+        for (series, idx, prefix) in wanted {
+            let (axis, values, points) = &all_series[series];
+            let point = points[idx];
+            if is_missing(point) {
+                continue;
+            }
+            let formatter = match axis {
+                YAxis::Left => &self.y_formatter,
+                YAxis::Right => &self.y2_formatter,
+            };
+            // the line only goes to the points either side.
+            let lines: Vec<Line> = points[..idx]
+                .iter()
+                .rev()
+                .find(|point| !is_missing(**point))
+                .into_iter()
+                .chain(points[idx + 1..].iter().find(|point| !is_missing(**point)))
+                .map(|other| Line::new(point, *other))
+                .collect();
+            let value = formatter.format(values[idx]);
+            let label = match prefix.as_ref() {
+                Some(prefix) => format!("{} {}", prefix, value),
+                None => value,
+            };
+            let mut layout = TextLayout::from_text(ArcStr::from(label));
+            layout.set_text_size(theme::TICK_LABEL_TEXT_SIZE);
+            layout.rebuild_if_needed(text, env);
+            let rect = place_label(
+                point,
+                layout.size(),
+                gap,
+                &lines,
+                &placed,
+                self.graph_bounds,
+            );
+            placed.push(rect);
+            labels.push(PointLabel {
+                series,
+                point,
+                marker: prefix.is_some(),
+                label: PositionedLayout {
+                    position: rect.origin(),
+                    layout,
+                },
+            });
+        }
+        labels
+    }
+
+    fn build_band_paths<S: SeriesData>(
+        &self,
+        data: &LineChartData<Title, XLabel, S>,
+    ) -> Vec<(usize, BezPath)> {
+        data.all_series()
+            .zip(data.all_bands())
+            .enumerate()
+            .filter_map(|(idx, ((axis, _), band))| {
+                let band = band?;
+                let lower: Vec<Point> = self.series_points(data, axis, &band.lower).collect();
+                let upper: Vec<Point> = self.series_points(data, axis, &band.upper).collect();
+                Some((idx, band_path(&lower, &upper, self.interpolation)))
+            })
+            .collect()
+    }
+
+    /// The pixel locations of the points in a series.
+    fn series_points<'a, S: SeriesData>(
+        &'a self,
+        data: &'a LineChartData<Title, XLabel, S>,
+        axis: YAxis,
+        y_data: &'a S,
+    ) -> impl Iterator<Item = Point> + 'a {
+        let x_scale = self.x_scale.as_ref().unwrap();
+        let y_scale = self.y_scale_for(axis);
+        resolve_x_data(data.x_data.as_ref(), y_data.len())
+            .zip(y_data.values())
+            .map(move |(x, y)| Point::new(x_scale.pixel_location(x), y_scale.pixel_location(y)))
+    }
+
+    /// The points of a series to draw, with missing values dealt with according to
+    /// `self.missing_values`, and any extra corners needed by `self.interpolation`.
+    fn series_line<S: SeriesData>(
+        &self,
+        data: &LineChartData<Title, XLabel, S>,
+        axis: YAxis,
+        y_data: &S,
+    ) -> Vec<Point> {
+        finish_line(
+            self.series_points(data, axis, y_data).collect(),
+            self.missing_values,
+            self.interpolation,
+        )
+    }
+
+    /// Add the error bars for a series to its path.
+    fn add_error_bars<S: SeriesData>(
+        &self,
+        path: &mut BezPath,
+        data: &LineChartData<Title, XLabel, S>,
+        axis: YAxis,
+        y_data: &S,
+        errors: &Vector<ErrorBar>,
+        cap_width: f64,
+    ) {
+        let y_scale = self.y_scale_for(axis);
+        for ((point, y), error) in self
+            .series_points(data, axis, y_data)
+            .zip(y_data.values())
+            .zip(errors.iter().copied())
+        {
+            if is_missing(point) {
+                continue;
+            }
+            let (low, high) = error.bounds(y);
+            add_error_bar(
+                path,
+                point.x,
+                y_scale.pixel_location(low),
+                y_scale.pixel_location(high),
+                cap_width,
+            );
+        }
+    }
+
+    /// The data point under `pos`: the closest one, if it is within a few pixels.
+    ///
+    /// `pos` is in the chart's coordinates, like the position of a mouse event. This returns
+    /// `None` before the chart has been laid out.
+    pub fn hit_test<S: SeriesData>(
+        &self,
+        pos: Point,
+        data: &LineChartData<Title, XLabel, S>,
+    ) -> Option<HitInfo> {
+        if !self.graph_bounds.contains(pos) {
+            return None;
+        }
+        self.nearest_point(pos, data)
+            .filter(|hit| hit.distance <= HIT_RADIUS)
+    }
+
+    /// The data point closest to `pos`, however far away it is, e.g. for showing a crosshair
+    /// that snaps to the data.
+    ///
+    /// Points that are missing or outside the graph area are ignored.
+    pub fn nearest_point<S: SeriesData>(
+        &self,
+        pos: Point,
+        data: &LineChartData<Title, XLabel, S>,
+    ) -> Option<HitInfo> {
+        if self.x_scale.is_none() || self.y_scale.is_none() {
+            return None;
+        }
+        let mut closest: Option<HitInfo> = None;
+        for (series, (axis, y_data)) in data.all_series().enumerate() {
+            for (index, point) in self.series_points(data, axis, y_data).enumerate() {
+                if is_missing(point) || !self.graph_bounds.contains(point) {
+                    continue;
+                }
+                let distance = point.distance(pos);
+                if closest.map_or(true, |closest| distance <= closest.distance) {
+                    closest = Some(HitInfo {
+                        series,
+                        index,
+                        position: point,
+                        distance,
+                    });
+                }
+            }
+        }
+        closest
+    }
+
+    /// Whether the user can pan or zoom the chart.
+    fn can_zoom(&self) -> bool {
+        self.pan_zoom.is_some() || self.brush.as_ref().map_or(false, Brush::is_zoom)
+    }
+
+    /// The points inside `rect` (in pixels), in series order.
+    fn points_in<S: SeriesData>(
+        &self,
+        rect: Rect,
+        data: &LineChartData<Title, XLabel, S>,
+    ) -> Vec<PointSelected> {
+        let mut points = Vec::new();
+        for (series, (axis, y_data)) in data.all_series().enumerate() {
+            for (index, point) in self.series_points(data, axis, y_data).enumerate() {
+                if rect.contains(point) {
+                    points.push(PointSelected::new(series, index));
+                }
+            }
+        }
+        points
+    }
+
+    /// Build the outlines of the areas to fill, along with the index of the series whose color
+    /// they should be filled with.
+    fn build_area_paths<S: SeriesData>(
+        &self,
+        area_fill: AreaFill,
+        data: &LineChartData<Title, XLabel, S>,
+    ) -> Vec<(usize, BezPath)> {
+        let all_series: Vec<_> = data.all_series().collect();
+        match area_fill {
+            AreaFill::ToAxis => {
+                all_series
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(idx, (axis, y_data))| {
+                        // Fill to y = 0 if we can see it, otherwise to the bottom of the graph.
+                        let baseline = self
+                            .y_scale_for(*axis)
+                            .pixel_location(0.)
+                            .max(self.graph_bounds.y0)
+                            .min(self.graph_bounds.y1);
+                        let points = self.series_line(data, *axis, y_data);
+                        let mut path = BezPath::new();
+                        // a separate area for each unbroken part of the line.
+                        for run in points.split(|point| is_missing(*point)) {
+                            let (first, last) = match (run.first(), run.last()) {
+                                (Some(first), Some(last)) => (first, last),
+                                _ => continue,
+                            };
+                            path.move_to((first.x, baseline));
+                            path.line_to(*first);
+                            extend_path(&mut path, run, self.interpolation);
+                            path.line_to((last.x, baseline));
+                            path.close_path();
+                        }
+                        if path.elements().is_empty() {
+                            None
+                        } else {
+                            Some((idx, path))
+                        }
+                    })
+                    .collect()
+            }
+            AreaFill::Between(a, b) => {
+                let ((axis_a, y_data_a), (axis_b, y_data_b)) =
+                    match (all_series.get(a), all_series.get(b)) {
+                        (Some(series_a), Some(series_b)) => (*series_a, *series_b),
+                        _ => return vec![],
+                    };
+                let mut path = BezPath::new();
+                // we can't fill a gap in only one of the lines, so join them up.
+                let points_a: Vec<Point> = self
+                    .series_line(data, axis_a, y_data_a)
+                    .into_iter()
+                    .filter(|point| !is_missing(*point))
+                    .collect();
+                let first = match points_a.first() {
+                    Some(first) => *first,
+                    None => return vec![],
+                };
+                path.move_to(first);
+                extend_path(&mut path, &points_a, self.interpolation);
+                // go back along the other line.
+                let points_b: Vec<Point> = self
+                    .series_line(data, axis_b, y_data_b)
+                    .into_iter()
+                    .rev()
+                    .filter(|point| !is_missing(*point))
+                    .collect();
+                if let Some(first) = points_b.first() {
+                    path.line_to(*first);
+                    extend_path(&mut path, &points_b, self.interpolation);
+                }
+                path.close_path();
+                vec![(a, path)]
+            }
+        }
+    }
+
+    /// Draw the chart. Call `lay_out` first.
+    fn draw<S: SeriesData>(
+        &mut self,
+        ctx: &mut Canvas,
+        data: &LineChartData<Title, XLabel, S>,
+        env: &Env,
+    ) {
+        if self.is_empty(data) {
+            self.empty_state.draw(ctx);
+            return;
+        }
+        let size = ctx.size();
+        let bounds = size.to_rect();
+        let margin = env.get(theme::MARGIN);
+
+        let x_scale = self.x_scale.as_ref().unwrap();
+        let y_scale = self.y_scale.as_ref().unwrap();
+        let series_colors = &self.series_colors;
+        let stroke_width = self.stroke_width.resolve(env);
+
+        // grid
+        if self.zebra_bands {
+            y_scale.draw_bands(ctx, env);
+        }
+        if data.draw_x_grid {
+            x_scale.draw_grid(ctx, env);
+        }
+        if data.draw_y_grid {
+            y_scale.draw_grid(ctx, env);
+        }
+
+        // data
+        let progress = animation::progress(self.entrance.as_ref());
+        ctx.with_save(|ctx| {
+            // When zoomed in, the data can go outside the graph area. While the chart is animating
+            // in, only part of it is shown.
+            let mut clip = x_scale.graph_bounds();
+            clip.x1 = clip.x0 + clip.width() * progress;
+            ctx.clip(clip);
+            self.annotations.draw(ctx, env, &[x_scale, y_scale]);
+            for (idx, path) in self.band_paths.iter() {
+                ctx.fill(
+                    path,
+                    &series_colors.resolve(*idx, env).with_alpha(BAND_ALPHA),
+                );
+            }
+            if let Some(area_paths) = self.area_paths.as_ref() {
+                for (idx, path) in area_paths.iter() {
+                    ctx.fill(
+                        path,
+                        &series_colors.resolve(*idx, env).with_alpha(AREA_ALPHA),
+                    );
+                }
+            }
+            for (idx, path) in self.lines.as_ref().unwrap().iter().enumerate() {
+                if self.threshold_colors.iter().any(|t| t.series == idx) {
+                    continue;
+                }
+                ctx.stroke(path, &series_colors.resolve(idx, env), stroke_width);
+            }
+            for (threshold_colors, paths) in self
+                .threshold_colors
+                .iter()
+                .zip(self.threshold_paths.iter())
+            {
+                for (band, path) in paths.iter().enumerate() {
+                    let color = match band {
+                        0 => series_colors.resolve(threshold_colors.series, env),
+                        _ => threshold_colors.thresholds[band - 1].1.resolve(env),
+                    };
+                    ctx.stroke(path, &color, stroke_width);
+                }
+            }
+            for (trend_line, path) in self.trends.iter().zip(self.trend_paths.iter()) {
+                let color = match trend_line.color.as_ref() {
+                    Some(color) => color.resolve(env),
+                    None => series_colors.resolve(trend_line.series, env),
+                };
+                ctx.stroke(path, &color, trend_line.stroke_width.resolve(env));
+            }
+            let callout_radius = CALLOUT_RADIUS * env.get(theme::SIZE_SCALE);
+            let background = env.get(druid::theme::WINDOW_BACKGROUND_COLOR);
+            for point_label in self.point_label_layouts.iter().flatten() {
+                if point_label.marker {
+                    let marker = Circle::new(point_label.point, callout_radius);
+                    ctx.fill(marker, &series_colors.resolve(point_label.series, env));
+                    ctx.stroke(marker, &background, 1.);
+                }
+                let label = &point_label.label;
+                ctx.draw_layout(&label.layout, label.position);
+            }
+        });
+
+        // trend stats, stacked away from their corner.
+        let size_scale = env.get(theme::SIZE_SCALE);
+        let background = env.get(druid::theme::WINDOW_BACKGROUND_COLOR);
+        let mut stacked: Vec<(Corner, f64)> = Vec::new();
+        for (idx, corner, layout) in self.trend_stats_layouts.iter().flatten() {
+            let pos = match stacked.iter().position(|(c, _)| c == corner) {
+                Some(pos) => pos,
+                None => {
+                    stacked.push((*corner, 0.));
+                    stacked.len() - 1
+                }
+            };
+            let offset = &mut stacked[pos].1;
+            let size = layout.size() + Size::new(2. * margin, 2. * margin);
+            let mut bounds = self.graph_bounds.inset(-margin);
+            if corner.is_top() {
+                bounds.y0 += *offset;
+            } else {
+                bounds.y1 -= *offset;
+            }
+            *offset += size.height + margin;
+            let bounds = corner.place(bounds, size);
+            let trend_line = &self.trends[*idx];
+            let color = match trend_line.color.as_ref() {
+                Some(color) => color.resolve(env),
+                None => self.series_colors.resolve(trend_line.series, env),
+            };
+            ctx.fill(bounds, &background);
+            ctx.stroke(bounds, &color, size_scale);
+            ctx.draw_layout(layout, (bounds.x0 + margin, bounds.y0 + margin));
+        }
+
+        // title
+        self.title.draw(ctx, env);
+
+        // x axis
+        if self.draw_axes {
+            self.x_scale.as_mut().unwrap().draw(
+                ctx,
+                env,
+                data.draw_x_axis,
+                data.draw_x_tick_labels,
+            );
+        }
+        if !data.x_axis_label.as_str().is_empty() {
+            let label_size = self.x_label_layout.size();
+            ctx.draw_layout(
+                &self.x_label_layout,
+                (
+                    (size.width - label_size.width) * 0.5,
+                    size.height - label_size.height - margin,
+                ),
+            );
         }
-    }
 
-    fn calc_x_data_range(&mut self, data: &LineChartData<Title, XLabel>) {
-        self.data_range_x = Some(Range::from_iter(resolve_x_data(
-            data.x_data.as_ref(),
-            data.y_data.len(),
-        )));
-        self.x_scale = None;
-    }
+        // y axis
+        if self.draw_axes {
+            self.y_scale.as_mut().unwrap().draw(
+                ctx,
+                env,
+                data.draw_y_axis,
+                data.draw_y_tick_labels,
+            );
+            if let Some(y2_scale) = self.y2_scale.as_mut() {
+                y2_scale.draw(ctx, env, data.draw_y_axis, data.draw_y_tick_labels);
+            }
+        }
+        if !data.y_axis_label.is_empty() {
+            let label_size = self.y_label_layout.size();
+            let center_y = self.graph_bounds.center().y;
+            let y_label_layout = &self.y_label_layout;
+            ctx.with_save(|ctx| {
+                // rotate so the text reads from bottom to top.
+                ctx.transform(
+                    Affine::translate((margin, center_y + label_size.width * 0.5))
+                        * Affine::rotate(-0.5 * PI),
+                );
+                ctx.draw_layout(y_label_layout, Point::ORIGIN);
+            });
+        }
 
-    fn calc_y_data_range(&mut self, data: &LineChartData<Title, XLabel>) {
-        self.data_range_y = Some(Range::from_iter(data.y_data.iter().copied()));
-        self.y_scale = None;
-    }
+        // focus ring
+        if let Some(keyboard) = self.keyboard.as_ref() {
+            let highlighted = keyboard.highlighted().and_then(|highlighted| {
+                let (axis, y_data) = data.all_series().nth(highlighted.series)?;
+                self.series_points(data, axis, y_data)
+                    .nth(highlighted.index)
+                    .filter(|point| self.graph_bounds.contains(*point))
+            });
+            if let Some(point) = highlighted {
+                keyboard.draw_ring(ctx, env, Circle::new(point, HIT_RADIUS * 0.5));
+            }
+        }
+
+        // selection
+        if let Some(brush) = self.brush.as_ref() {
+            brush.draw(ctx, env, self.graph_bounds);
+        }
 
-    fn x_range(&self, data: &LineChartData<Title, XLabel>) -> Option<Range> {
-        data.x_range.or(self.data_range_x)
+        // legend
+        if let Some(legend) = self.legend.as_mut() {
+            let series_colors = &self.series_colors;
+            legend.draw(ctx, env, |idx| series_colors.resolve(idx, env));
+        }
     }
+}
 
-    fn y_range(&self, data: &LineChartData<Title, XLabel>) -> Option<Range> {
-        data.y_range.or(self.data_range_y)
+impl<Title, XLabel, S> CartesianChart<LineChartData<Title, XLabel, S>> for LineChart<Title, XLabel>
+where
+    Title: TextStorage,
+    XLabel: TextStorage,
+    S: SeriesData,
+{
+    fn scales(&self) -> Option<(&Scale, &Scale)> {
+        Some((self.x_scale.as_ref()?, self.y_scale.as_ref()?))
     }
 
-    fn rebuild_if_needed(
+    fn set_view(
         &mut self,
-        ctx: &mut PaintCtx,
-        data: &LineChartData<Title, XLabel>,
-        env: &Env,
+        data: &mut LineChartData<Title, XLabel, S>,
+        x_range: Option<Range>,
+        y_range: Option<Range>,
     ) {
-        let margin = env.get(theme::MARGIN);
-        let scale_margin = env.get(theme::SCALE_MARGIN);
-
-        self.title_layout.rebuild_if_needed(ctx.text(), env);
-        self.x_label_layout.rebuild_if_needed(ctx.text(), env);
-        if self.x_scale.is_none() {
-            self.x_scale = Some(Scale::new_x(self.x_range(data).unwrap()));
+        if let Some(x_range) = x_range {
+            data.x_range = Some(x_range);
         }
-        if self.y_scale.is_none() {
-            self.y_scale = Some(Scale::new_y(self.y_range(data).unwrap()));
+        if let Some(y_range) = y_range {
+            data.y_range = Some(y_range);
         }
+    }
 
-        // build twice because we want to check the size
-        // Firstly try laying out with no size restriction
-        //
-        // There is a bit of a dance here because the borrow checker won't let us borrow both parts
-        // of the struct at the same time.
-        let draw_area = ctx.size().to_rect();
-        let x_scale = self.x_scale.as_mut().unwrap();
-        x_scale.set_graph_bounds(draw_area);
-        x_scale.rebuild_if_needed(ctx, env);
-        let y_scale = self.y_scale.as_mut().unwrap();
-        y_scale.set_graph_bounds(draw_area);
-        y_scale.rebuild_if_needed(ctx, env);
-
-        // space for the y axis and tick labels
-        let x0 = margin + self.y_scale.as_ref().unwrap().max_layout().width + scale_margin;
-        // space for the chart title (if needed)
-        let mut y0 = if data.title.as_str().is_empty() {
-            margin
-        } else {
-            2. * margin + self.title_layout.size().height
-        };
-        // space for the x axis and tick labels
-        let mut y1 = margin + self.x_scale.as_ref().unwrap().max_layout().height + scale_margin;
-        // add space for the x axis label (if it's there)
-        if !data.x_axis_label.as_str().is_empty() {
-            y1 += margin + self.x_label_layout.size().height;
-        }
+    fn reset_view(&mut self, data: &mut LineChartData<Title, XLabel, S>) {
+        data.reset_view();
+    }
 
-        let graph_insets = Insets {
-            x0: -x0,
-            y0: -y0,
-            x1: -margin,
-            y1: -y1,
-        };
-        let graph_bounds = draw_area.inset(graph_insets);
+    fn hit_test(&self, pos: Point, data: &LineChartData<Title, XLabel, S>) -> Option<HitInfo> {
+        LineChart::hit_test(self, pos, data)
+    }
 
-        // now build again using the info we calculated.
-        let x_scale = self.x_scale.as_mut().unwrap();
-        x_scale.set_graph_bounds(graph_bounds);
-        x_scale.rebuild_if_needed(ctx, env);
-        let y_scale = self.y_scale.as_mut().unwrap();
-        y_scale.set_graph_bounds(graph_bounds);
-        y_scale.rebuild_if_needed(ctx, env);
+    fn points_in(&self, rect: Rect, data: &LineChartData<Title, XLabel, S>) -> Vec<PointSelected> {
+        LineChart::points_in(self, rect, data)
     }
 }
 
-impl<Title, XLabel> Widget<LineChartData<Title, XLabel>> for LineChart<Title, XLabel>
+impl<Title, XLabel, S> Widget<LineChartData<Title, XLabel, S>> for LineChart<Title, XLabel>
 where
     Title: TextStorage,
     XLabel: TextStorage,
+    S: SeriesData,
 {
     fn event(
         &mut self,
         ctx: &mut EventCtx,
         event: &Event,
-        data: &mut LineChartData<Title, XLabel>,
+        data: &mut LineChartData<Title, XLabel, S>,
         env: &Env,
     ) {
+        if let Some(entrance) = self.entrance.as_mut() {
+            entrance.event(ctx, event);
+        }
+        if let Some(keyboard) = self.keyboard.as_mut() {
+            let series_lens: Vec<usize> = data.all_y_data().map(|y_data| y_data.len()).collect();
+            keyboard.event(ctx, event, &series_lens);
+        }
+        let items = MenuItems {
+            reset_zoom: self.can_zoom(),
+            grid: true,
+            data: true,
+        };
+        if let Some(context_menu) = self.context_menu.as_mut() {
+            context_menu.event(ctx, event, items, || data.csv_table());
+            if ctx.is_handled() {
+                return;
+            }
+        }
+        match event {
+            Event::Command(cmd) if cmd.is(RESET_CHART_VIEW) => {
+                data.reset_view();
+                ctx.set_handled();
+                return;
+            }
+            Event::Command(cmd) if cmd.is(TOGGLE_CHART_GRID) => {
+                let draw_grid = !(data.draw_x_grid || data.draw_y_grid);
+                data.draw_x_grid = draw_grid;
+                data.draw_y_grid = draw_grid;
+                ctx.set_handled();
+                return;
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_DATA) => {
+                copy_csv(data.csv_table());
+                ctx.set_handled();
+                return;
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_IMAGE) => {
+                copy_image(ctx, env, |ctx| self.draw(ctx, data, env));
+                return;
+            }
+            Event::Command(cmd) if cmd.is(EXPORT_IMAGE) => {
+                let scale = *cmd.get_unchecked(EXPORT_IMAGE);
+                export_image(ctx, scale, env, |ctx| self.draw(ctx, data, env));
+                return;
+            }
+            Event::Command(cmd) if cmd.is(ZOOM_TO_RANGE) => {
+                let zoom = cmd.get_unchecked(ZOOM_TO_RANGE);
+                if let Some(x_range) = zoom.x_range {
+                    data.x_range = Some(x_range);
+                }
+                if let Some(y_range) = zoom.y_range {
+                    data.y_range = Some(y_range);
+                }
+                ctx.set_handled();
+                return;
+            }
+            // double-click to zoom back out.
+            Event::MouseDown(mouse)
+                if mouse.count == 2 && self.can_zoom() && self.graph_bounds.contains(mouse.pos) =>
+            {
+                data.reset_view();
+                ctx.set_handled();
+                return;
+            }
+            _ => (),
+        }
+        if let Event::MouseDown(mouse) = event {
+            if mouse.button == MouseButton::Left {
+                if let Some(selected) = self.hit_test(mouse.pos, data).map(|hit| hit.point()) {
+                    ctx.submit_notification(CHART_POINT_SELECTED.with(selected));
+                    ctx.set_handled();
+                    return;
+                }
+            }
+        }
+        if let Some(brush) = self.brush.as_mut() {
+            // with pan and zoom as well, a drag only selects if shift is held.
+            let starts_selection = match event {
+                Event::MouseDown(mouse) => self.pan_zoom.is_none() || mouse.mods.shift(),
+                _ => brush.is_dragging(),
+            };
+            if starts_selection && self.x_scale.is_some() {
+                let (x_only, zoom) = (brush.is_x_only(), brush.is_zoom());
+                if let Some(selection) = brush.event(ctx, event, self.graph_bounds) {
+                    let selected = range_selected(
+                        self.x_scale.as_ref().unwrap(),
+                        self.y_scale.as_ref().unwrap(),
+                        selection,
+                        x_only,
+                        self.points_in(selection, data),
+                    );
+                    if zoom {
+                        data.x_range = Some(selected.x_range);
+                        if let Some(y_range) = selected.y_range {
+                            data.y_range = Some(y_range);
+                        }
+                    } else {
+                        ctx.submit_notification(CHART_RANGE_SELECTED.with(selected));
+                    }
+                }
+                if ctx.is_handled() {
+                    return;
+                }
+            }
+        }
+        if let (Some(pan_zoom), Some(x_scale), Some(y_scale)) = (
+            self.pan_zoom.as_mut(),
+            self.x_scale.as_ref(),
+            self.y_scale.as_ref(),
+        ) {
+            if let Some((x_range, y_range)) = pan_zoom.event(ctx, event, x_scale, y_scale) {
+                data.x_range = Some(x_range);
+                data.y_range = Some(y_range);
+            }
+        }
     }
 
     fn lifecycle(
         &mut self,
         ctx: &mut LifeCycleCtx,
         event: &LifeCycle,
-        data: &LineChartData<Title, XLabel>,
+        data: &LineChartData<Title, XLabel, S>,
         env: &Env,
     ) {
+        if let Some(entrance) = self.entrance.as_mut() {
+            entrance.lifecycle(ctx, event);
+        }
+        if let Some(keyboard) = self.keyboard.as_mut() {
+            keyboard.lifecycle(ctx, event);
+        }
         match event {
-            LifeCycle::WidgetAdded => {
-                self.title_layout.set_text(data.title.clone());
-                self.x_label_layout.set_text(data.x_axis_label.clone());
-                if data.x_range.is_none() {
-                    self.calc_x_data_range(data);
-                }
-                if data.y_range.is_none() {
-                    self.calc_y_data_range(data);
-                }
-            }
+            LifeCycle::WidgetAdded => self.set_data(data),
             _ => (),
         }
     }
@@ -188,123 +2166,651 @@ where
     fn update(
         &mut self,
         ctx: &mut UpdateCtx,
-        old_data: &LineChartData<Title, XLabel>,
-        data: &LineChartData<Title, XLabel>,
+        old_data: &LineChartData<Title, XLabel, S>,
+        data: &LineChartData<Title, XLabel, S>,
         env: &Env,
     ) {
         // the job of this method is to invalidate parts of the retained state that are no longer
         // valid, and to request a repaint/relayout if necessary.
         if !old_data.title.same(&data.title) {
-            self.title_layout.set_text(data.title.clone());
+            self.title.set_text(data.title.clone());
+        }
+        if self.title.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
         }
-        self.title_layout.needs_rebuild_after_update(ctx);
 
         // x axis
         if !old_data.x_axis_label.same(&data.x_axis_label) {
             self.x_label_layout.set_text(data.x_axis_label.clone());
         }
-        self.x_label_layout.needs_rebuild_after_update(ctx);
+        if self.x_label_layout.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
         if data.draw_x_tick_labels != old_data.draw_x_tick_labels {
             ctx.request_layout();
         }
-        if data.draw_x_axis != old_data.draw_x_axis {
+        if data.draw_x_axis != old_data.draw_x_axis || data.draw_x_grid != old_data.draw_x_grid {
             // don't need to re-layout in this case.
             ctx.request_paint();
         }
-        if (!Data::same(&old_data.x_data, &data.x_data) || self.data_range_x.is_none())
-            && data.x_range.is_none()
-        {
+        if self.data_range_x.is_none() && data.x_range.is_none() {
             self.calc_x_data_range(data);
             ctx.request_layout();
         }
-        if !Data::same(&old_data.x_data, &data.x_data) {
+        if old_data.x_range != data.x_range || !old_data.x_ticks.same(&data.x_ticks) {
+            self.x_scale = None;
             ctx.request_layout();
         }
 
         // y axis
-        if (!Data::same(&old_data.y_data, &data.y_data) || self.data_range_y.is_none())
-            && data.y_range.is_none()
-        {
+        if !old_data.y_axis_label.same(&data.y_axis_label) {
+            self.y_label_layout.set_text(data.y_axis_label.clone());
+        }
+        if self.y_label_layout.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if self.data_range_y.is_none() && data.y_range.is_none() {
             self.calc_y_data_range(data);
             ctx.request_layout();
         }
         if data.draw_y_tick_labels != old_data.draw_y_tick_labels {
             ctx.request_layout();
         }
-        if data.draw_y_axis != old_data.draw_y_axis {
+        if data.draw_y_axis != old_data.draw_y_axis || data.draw_y_grid != old_data.draw_y_grid {
             // don't need to re-layout in this case.
             ctx.request_paint();
         }
-        if !Data::same(&old_data.y_data, &data.y_data) {
+        if old_data.y_range != data.y_range || !old_data.y_ticks.same(&data.y_ticks) {
+            self.y_scale = None;
+            ctx.request_layout();
+        }
+
+        // secondary y axis
+        if self.data_range_y2.is_none() && data.y2_range.is_none() && data.has_right_axis() {
+            self.calc_y2_data_range(data);
+            ctx.request_layout();
+        }
+        if old_data.y2_range != data.y2_range {
+            self.y2_scale = None;
             ctx.request_layout();
         }
+
+        // series
+        if !Data::same(&old_data.x_data, &data.x_data)
+            || !Data::same(&old_data.y_data, &data.y_data)
+            || !Data::same(&old_data.y_errors, &data.y_errors)
+            || !Data::same(&old_data.y_band, &data.y_band)
+            || !Data::same(&old_data.extra_series, &data.extra_series)
+        {
+            match Streamed::between_data(old_data, data) {
+                Some(streamed) => self.update_streamed(ctx, streamed, old_data, data, env),
+                None => {
+                    self.lines = None;
+                    self.area_paths = None;
+                    self.trend_values = None;
+                    if data.x_range.is_none() {
+                        self.calc_x_data_range(data);
+                    }
+                    if data.y_range.is_none() {
+                        self.calc_y_data_range(data);
+                    }
+                    if data.y2_range.is_none() {
+                        self.calc_y2_data_range(data);
+                    }
+                    ctx.request_layout();
+                }
+            }
+        }
+        if let Some(legend) = self.legend.as_mut() {
+            if !old_data.y_data_label.same(&data.y_data_label)
+                || !Data::same(&old_data.extra_series, &data.extra_series)
+            {
+                legend.set_labels(data.all_labels());
+                ctx.request_layout();
+            }
+            if legend.needs_rebuild_after_update(ctx) {
+                ctx.request_layout();
+            }
+        }
     }
 
     fn layout(
         &mut self,
         ctx: &mut LayoutCtx,
         bc: &BoxConstraints,
-        data: &LineChartData<Title, XLabel>,
+        data: &LineChartData<Title, XLabel, S>,
         env: &Env,
     ) -> Size {
-        bc.max() // or costrain to some size.
+        let size = chart_size(bc, self.preferred_size);
+        self.lay_out(ctx.text(), size, data, env);
+        size
     }
 
-    fn paint(&mut self, ctx: &mut PaintCtx, data: &LineChartData<Title, XLabel>, env: &Env) {
-        self.rebuild_if_needed(ctx, data, env);
-        let line_brush = ctx.solid_brush(Color::hlc(0.0, 50.0, 50.0));
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &LineChartData<Title, XLabel, S>, env: &Env) {
+        self.draw(&mut Canvas::new(ctx, env), data, env);
+    }
+}
+
+impl<Title, XLabel, S> OffscreenChart<LineChartData<Title, XLabel, S>> for LineChart<Title, XLabel>
+where
+    Title: TextStorage,
+    XLabel: TextStorage,
+    S: SeriesData,
+{
+    fn draw_offscreen(
+        &mut self,
+        ctx: &mut Canvas,
+        data: &LineChartData<Title, XLabel, S>,
+        env: &Env,
+    ) {
+        self.entrance = None;
+        self.set_data(data);
         let size = ctx.size();
-        let bounds = size.to_rect();
-        let margin = env.get(theme::MARGIN);
+        self.lay_out(ctx.text(), size, data, env);
+        self.draw(ctx, data, env);
+    }
+}
 
-        // data
-        for ((x0, x1), (y0, y1)) in izip!(
-            resolve_x_data(data.x_data.as_ref(), data.y_data.len()).tuple_windows(),
-            data.y_data.iter().tuple_windows()
-        ) {
-            let x_scale = self.x_scale.as_ref().unwrap();
-            let y_scale = self.y_scale.as_ref().unwrap();
-            let x0 = x_scale.pixel_location(x0);
-            let x1 = x_scale.pixel_location(x1);
-            let y0 = y_scale.pixel_location(*y0);
-            let y1 = y_scale.pixel_location(*y1);
-            ctx.stroke(Line::new((x0, y0), (x1, y1)), &line_brush, 1.);
+/// Deal with the missing values in `points` according to `missing_values`, and add any extra
+/// corners needed by `interpolation`.
+pub(crate) fn finish_line(
+    mut points: Vec<Point>,
+    missing_values: MissingValues,
+    interpolation: Interpolation,
+) -> Vec<Point> {
+    match missing_values {
+        // the missing points break the line when we build the path.
+        MissingValues::Gap => (),
+        MissingValues::Skip => points.retain(|point| !is_missing(*point)),
+        MissingValues::Interpolate => {
+            interpolate_missing(&mut points);
+            points.retain(|point| !is_missing(*point));
         }
+    }
+    match interpolation {
+        Interpolation::Linear | Interpolation::Smooth => points,
+        Interpolation::StepBefore | Interpolation::StepAfter => add_steps(points, interpolation),
+    }
+}
 
-        // title
-        let title_width = self.title_layout.size().width;
-        self.title_layout
-            .draw(ctx, ((size.width - title_width) * 0.5, 10.0));
+/// Join the points up into a single path, which is much quicker to draw than separate lines.
+///
+/// The line is broken at any missing points.
+pub(crate) fn line_path(
+    points: impl IntoIterator<Item = Point>,
+    interpolation: Interpolation,
+) -> BezPath {
+    let points: Vec<Point> = points.into_iter().collect();
+    let mut path = BezPath::new();
+    for run in points.split(|point| is_missing(*point)) {
+        if let Some(first) = run.first() {
+            path.move_to(*first);
+            extend_path(&mut path, run, interpolation);
+        }
+    }
+    path
+}
 
-        // x axis
-        self.x_scale
-            .as_mut()
-            .unwrap()
-            .draw(ctx, env, data.draw_x_axis, data.draw_x_tick_labels);
-        if !data.x_axis_label.as_str().is_empty() {
-            let label_size = self.x_label_layout.size();
-            self.x_label_layout.draw(
-                ctx,
-                (
-                    (size.width - label_size.width) * 0.5,
-                    size.height - label_size.height - margin,
-                ),
-            );
+/// Split `path` into `bands` paths, cutting it wherever it crosses one of the horizontal `levels`
+/// (in pixels). Each piece goes in the path given by `band_of` the y position of its middle.
+fn split_at_levels(
+    path: &BezPath,
+    levels: &[f64],
+    bands: usize,
+    band_of: impl Fn(f64) -> usize,
+) -> Vec<BezPath> {
+    let mut paths = vec![BezPath::new(); bands];
+    let mut ends: Vec<Option<Point>> = vec![None; bands];
+    for seg in path.segments() {
+        let bounds = seg.bounding_box();
+        let mut cuts: Vec<f64> = levels
+            .iter()
+            .filter(|level| **level > bounds.y0 && **level < bounds.y1)
+            .flat_map(|level| {
+                let line = Line::new((bounds.x0 - 1., *level), (bounds.x1 + 1., *level));
+                seg.intersect_line(line)
+                    .into_iter()
+                    .map(|hit| hit.segment_t)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        cuts.push(0.);
+        cuts.push(1.);
+        cuts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        cuts.dedup();
+        for range in cuts.windows(2) {
+            let piece = seg.subsegment(range[0]..range[1]);
+            let band = band_of(piece.eval(0.5).y).min(bands - 1);
+            let path = &mut paths[band];
+            if ends[band] != Some(piece.start()) {
+                path.move_to(piece.start());
+            }
+            match piece {
+                PathSeg::Line(line) => path.line_to(line.p1),
+                PathSeg::Quad(quad) => path.quad_to(quad.p1, quad.p2),
+                PathSeg::Cubic(cubic) => path.curve_to(cubic.p1, cubic.p2, cubic.p3),
+            }
+            ends[band] = Some(piece.end());
         }
+    }
+    paths
+}
 
-        // y axis
-        self.y_scale
-            .as_mut()
-            .unwrap()
-            .draw(ctx, env, data.draw_y_axis, data.draw_y_tick_labels);
+/// The indices of the smallest and largest values, ignoring any that are missing.
+fn extreme_indices(values: &[f64]) -> Option<(usize, usize)> {
+    let mut extremes: Option<(usize, usize)> = None;
+    for (idx, value) in values.iter().enumerate() {
+        if !value.is_finite() {
+            continue;
+        }
+        extremes = Some(match extremes {
+            Some((min, max)) => (
+                if *value < values[min] { idx } else { min },
+                if *value > values[max] { idx } else { max },
+            ),
+            None => (idx, idx),
+        });
+    }
+    extremes
+}
+
+/// Where to put a label of `size` for `point`.
+///
+/// We try above, below, right and left of the point, in that order, and take the first place that
+/// covers neither the `lines` through the point nor any of the `placed` labels. Failing that we
+/// take the first place that at least misses the lines, and failing that, above. Labels are kept
+/// inside `bounds`.
+fn place_label(
+    point: Point,
+    size: Size,
+    gap: f64,
+    lines: &[Line],
+    placed: &[Rect],
+    bounds: Rect,
+) -> Rect {
+    let candidates: Vec<Rect> = [
+        (point.x - size.width * 0.5, point.y - gap - size.height),
+        (point.x - size.width * 0.5, point.y + gap),
+        (point.x + gap, point.y - size.height * 0.5),
+        (point.x - gap - size.width, point.y - size.height * 0.5),
+    ]
+    .iter()
+    .map(|origin| {
+        let rect = Rect::from_origin_size(*origin, size);
+        // nudge the label back inside the bounds.
+        let dx = (bounds.x0 - rect.x0).max(0.) + (bounds.x1 - rect.x1).min(0.);
+        let dy = (bounds.y0 - rect.y0).max(0.) + (bounds.y1 - rect.y1).min(0.);
+        rect + Vec2::new(dx, dy)
+    })
+    .collect();
+    let misses_lines = |rect: &&Rect| lines.iter().all(|line| !line_hits_rect(*line, **rect));
+    let misses_labels = |rect: &&Rect| {
+        placed
+            .iter()
+            .all(|other| other.intersect(**rect).area() == 0.)
+    };
+    candidates
+        .iter()
+        .find(|rect| misses_lines(rect) && misses_labels(rect))
+        .or_else(|| candidates.iter().find(misses_lines))
+        .copied()
+        .unwrap_or(candidates[0])
+}
+
+/// Whether any part of `line` is inside `rect` (using Liang-Barsky clipping).
+fn line_hits_rect(line: Line, rect: Rect) -> bool {
+    let d = line.p1 - line.p0;
+    let (mut t0, mut t1) = (0., 1.);
+    for (p, q) in [
+        (-d.x, line.p0.x - rect.x0),
+        (d.x, rect.x1 - line.p0.x),
+        (-d.y, line.p0.y - rect.y0),
+        (d.y, rect.y1 - line.p0.y),
+    ]
+    .iter()
+    {
+        if *p == 0. {
+            // parallel to this edge, so either all outside or we learn nothing.
+            if *q < 0. {
+                return false;
+            }
+        } else if *p < 0. {
+            t0 = f64::max(t0, q / p);
+        } else {
+            t1 = f64::min(t1, q / p);
+        }
+    }
+    t0 <= t1
+}
+
+/// The outline of the band between `lower` and `upper`, which are the pixel locations of the
+/// bounds at the same x positions.
+///
+/// The band is broken wherever either bound is missing.
+fn band_path(lower: &[Point], upper: &[Point], interpolation: Interpolation) -> BezPath {
+    let pairs: Vec<(Point, Point)> = lower.iter().copied().zip(upper.iter().copied()).collect();
+    let mut path = BezPath::new();
+    for run in pairs.split(|(low, high)| is_missing(*low) || is_missing(*high)) {
+        let (low, high): (Vec<Point>, Vec<Point>) = run.iter().copied().unzip();
+        let (low, mut high) = match interpolation {
+            Interpolation::StepBefore | Interpolation::StepAfter => (
+                add_steps(low, interpolation),
+                add_steps(high, interpolation),
+            ),
+            Interpolation::Linear | Interpolation::Smooth => (low, high),
+        };
+        // go along the bottom, then back along the top.
+        high.reverse();
+        if let (Some(first), Some(turn)) = (low.first(), high.first()) {
+            path.move_to(*first);
+            extend_path(&mut path, &low, interpolation);
+            path.line_to(*turn);
+            extend_path(&mut path, &high, interpolation);
+            path.close_path();
+        }
+    }
+    path
+}
+
+/// Whether a point is missing a value (or has an infinite one), so can't be drawn.
+fn is_missing(point: Point) -> bool {
+    !(point.x.is_finite() && point.y.is_finite())
+}
+
+/// Add `tail` to the end of `path`, carrying straight on if `tail` starts where `path` ends.
+fn append_path(path: &mut BezPath, tail: &BezPath) {
+    let end = path.elements().last().and_then(|el| match el {
+        PathEl::MoveTo(p) | PathEl::LineTo(p) | PathEl::QuadTo(_, p) | PathEl::CurveTo(_, _, p) => {
+            Some(*p)
+        }
+        PathEl::ClosePath => None,
+    });
+    let mut elements = tail.elements().iter().peekable();
+    if let (Some(end), Some(PathEl::MoveTo(start))) = (end, elements.peek()) {
+        if end == *start {
+            elements.next();
+        }
+    }
+    for el in elements {
+        path.push(*el);
+    }
+}
+
+/// Continue `path`, whose current point is the first of `run`, through the rest of `run`.
+///
+/// `run` must not contain any missing points.
+fn extend_path(path: &mut BezPath, run: &[Point], interpolation: Interpolation) {
+    if interpolation != Interpolation::Smooth {
+        for point in run.iter().skip(1) {
+            path.line_to(*point);
+        }
+        return;
+    }
+    let tangents = monotone_tangents(run);
+    for (idx, pair) in run.windows(2).enumerate() {
+        let (p0, p1) = (pair[0], pair[1]);
+        let third = (p1.x - p0.x) / 3.;
+        if third == 0. {
+            path.line_to(p1);
+            continue;
+        }
+        path.curve_to(
+            (p0.x + third, p0.y + tangents[idx] * third),
+            (p1.x - third, p1.y - tangents[idx + 1] * third),
+            p1,
+        );
+    }
+}
+
+/// The gradient of the curve at each point, for a monotone cubic spline (using the
+/// Fritsch–Carlson method).
+///
+/// Between two points the curve stays between their y values, so there is no overshoot.
+fn monotone_tangents(points: &[Point]) -> Vec<f64> {
+    let secants: Vec<f64> = points
+        .windows(2)
+        .map(|pair| {
+            let dx = pair[1].x - pair[0].x;
+            if dx == 0. {
+                0.
+            } else {
+                (pair[1].y - pair[0].y) / dx
+            }
+        })
+        .collect();
+    let mut tangents: Vec<f64> = (0..points.len())
+        .map(|idx| {
+            match (
+                idx.checked_sub(1).and_then(|prev| secants.get(prev)),
+                secants.get(idx),
+            ) {
+                // at a peak or trough the curve is flat.
+                (Some(before), Some(after)) if before * after <= 0. => 0.,
+                (Some(before), Some(after)) => (before + after) * 0.5,
+                (Some(secant), None) | (None, Some(secant)) => *secant,
+                (None, None) => 0.,
+            }
+        })
+        .collect();
+    for (idx, secant) in secants.iter().copied().enumerate() {
+        if secant == 0. {
+            tangents[idx] = 0.;
+            tangents[idx + 1] = 0.;
+            continue;
+        }
+        let a = tangents[idx] / secant;
+        let b = tangents[idx + 1] / secant;
+        let size = a.hypot(b);
+        // keep the tangents small enough that the curve can't overshoot.
+        if size > 3. {
+            tangents[idx] = 3. / size * a * secant;
+            tangents[idx + 1] = 3. / size * b * secant;
+        }
+    }
+    tangents
+}
+
+/// Add a corner between each pair of neighbouring points, so that straight lines through the
+/// result make steps.
+fn add_steps(points: Vec<Point>, interpolation: Interpolation) -> Vec<Point> {
+    let mut out = Vec::with_capacity(points.len() * 2);
+    let mut prev: Option<Point> = None;
+    for point in points {
+        if let Some(prev) = prev.filter(|_| !is_missing(point)) {
+            out.push(match interpolation {
+                Interpolation::StepBefore => Point::new(prev.x, point.y),
+                _ => Point::new(point.x, prev.y),
+            });
+        }
+        out.push(point);
+        prev = Some(point).filter(|point| !is_missing(*point));
+    }
+    out
+}
+
+/// Fill in missing y values by interpolating linearly between the points either side.
+///
+/// Points with a missing x value, or missing values at the start or end, are left as they are.
+fn interpolate_missing(points: &mut [Point]) {
+    let mut prev: Option<Point> = None;
+    let mut idx = 0;
+    while idx < points.len() {
+        if !is_missing(points[idx]) {
+            prev = Some(points[idx]);
+            idx += 1;
+            continue;
+        }
+        // find the end of the run of missing values.
+        let end = (idx..points.len())
+            .find(|end| !is_missing(points[*end]))
+            .unwrap_or(points.len());
+        if let (Some(start), Some(next)) = (prev, points.get(end).copied()) {
+            for point in points[idx..end].iter_mut() {
+                if point.x.is_finite() {
+                    let t = (point.x - start.x) / (next.x - start.x);
+                    point.y = start.y + t * (next.y - start.y);
+                }
+            }
+        }
+        idx = end;
+    }
+}
+
+/// Push `value` onto the end of `data`, dropping values from the start to stay within `capacity`.
+fn push_bounded(data: &mut Vector<f64>, value: f64, capacity: Option<usize>) {
+    data.push_back(value);
+    if let Some(capacity) = capacity {
+        while data.len() > capacity {
+            data.pop_front();
+        }
+    }
+}
+
+/// How many times [`Streamed::between`] compares all the kept points before giving up.
+const MAX_STREAM_COMPARISONS: usize = 4;
+
+/// How a series changed, if points were only dropped from the start and/or added to the end, as
+/// happens when streaming.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Streamed {
+    dropped: usize,
+    added: usize,
+}
+
+impl Streamed {
+    /// Check if `new` is `old` with points dropped from the start and/or added to the end.
+    ///
+    /// Only the ends of the kept points are checked for each number dropped, and the kept points
+    /// are compared in full at most `MAX_STREAM_COMPARISONS` times, so data with long runs of the
+    /// same value (e.g. a flat signal) doesn't take quadratic time. If that isn't enough to find
+    /// the overlap, this gives up and the chart is laid out from scratch.
+    fn between<S: SeriesData>(old: &S, new: &S) -> Option<Self> {
+        let last = old.len().checked_sub(1).and_then(|idx| old.get(idx));
+        let mut comparisons = 0;
+        for dropped in 0..=old.len() {
+            let kept = old.len() - dropped;
+            if kept > new.len() {
+                continue;
+            }
+            // check the first and last kept points before comparing the lot.
+            if kept > 0
+                && !(same_value(old.get(dropped), new.get(0))
+                    && same_value(last, new.get(kept - 1)))
+            {
+                continue;
+            }
+            if comparisons == MAX_STREAM_COMPARISONS {
+                return None;
+            }
+            comparisons += 1;
+            if old
+                .values()
+                .skip(dropped)
+                .zip(new.values().take(kept))
+                .all(|(old_value, value)| same_value(Some(old_value), Some(value)))
+            {
+                return Some(Streamed {
+                    dropped,
+                    added: new.len() - kept,
+                });
+            }
+        }
+        None
+    }
+
+    /// Check that every series (and the x data) was streamed in the same way.
+    fn between_data<Title, XLabel, S: SeriesData>(
+        old: &LineChartData<Title, XLabel, S>,
+        new: &LineChartData<Title, XLabel, S>,
+    ) -> Option<Self> {
+        // the error bars and bands can change the range in ways we don't track.
+        if old
+            .all_errors()
+            .chain(new.all_errors())
+            .any(|errors| errors.is_some())
+            || old
+                .all_bands()
+                .chain(new.all_bands())
+                .any(|band| band.is_some())
+        {
+            return None;
+        }
+        let same_axes = old.extra_series.len() == new.extra_series.len()
+            && old
+                .extra_series
+                .iter()
+                .zip(new.extra_series.iter())
+                .all(|(old_series, series)| old_series.axis == series.axis);
+        if !same_axes {
+            return None;
+        }
+        let streamed = Streamed::between(&old.y_data, &new.y_data)?;
+        for (old_y_data, y_data) in old.all_y_data().zip(new.all_y_data()).skip(1) {
+            if Streamed::between(old_y_data, y_data)? != streamed {
+                return None;
+            }
+        }
+        match (&old.x_data, &new.x_data) {
+            (Some(old_x_data), Some(x_data)) => {
+                Streamed::between(old_x_data, x_data).filter(|x| *x == streamed)
+            }
+            (None, None) => Some(streamed),
+            _ => None,
+        }
+    }
+
+    /// Update the range of all the series plotted against `axis`, or return `None` if it needs
+    /// calculating from scratch.
+    fn update_axis_range<Title, XLabel, S: SeriesData>(
+        self,
+        range: Range,
+        axis: YAxis,
+        old: &LineChartData<Title, XLabel, S>,
+        new: &LineChartData<Title, XLabel, S>,
+    ) -> Option<Range> {
+        old.axis_y_data(axis)
+            .zip(new.axis_y_data(axis))
+            .try_fold(range, |range, (old_y_data, y_data)| {
+                self.update_range(range, old_y_data, y_data)
+            })
+    }
+
+    /// Update the range of a series for the streamed points, or return `None` if it needs
+    /// calculating from scratch.
+    fn update_range<S: SeriesData>(self, mut range: Range, old: &S, new: &S) -> Option<Range> {
+        // dropping a point only changes the range if it was at one of the ends.
+        let dropped_end = old
+            .values()
+            .take(self.dropped)
+            .any(|v| v <= range.min() || v >= range.max());
+        if dropped_end {
+            return None;
+        }
+        for v in new.values().skip(new.len() - self.added) {
+            if v.is_finite() {
+                range.extend_to(v);
+            }
+        }
+        Some(range)
+    }
+}
+
+/// Whether two values are the same, counting missing (NaN) values as the same as each other.
+fn same_value(a: Option<f64>, b: Option<f64>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a == b || (a.is_nan() && b.is_nan()),
+        (a, b) => a.is_none() && b.is_none(),
     }
 }
 
 /// return either the data or a range
-fn resolve_x_data<'a>(data: Option<&'a Vector<f64>>, len: usize) -> impl Iterator<Item = f64> + 'a {
+fn resolve_x_data<'a, S: SeriesData>(
+    data: Option<&'a S>,
+    len: usize,
+) -> impl Iterator<Item = f64> + 'a {
     let len = len as f64;
     match data {
-        Some(data) => Either::Left(data.iter().copied()),
+        Some(data) => Either::Left(data.values()),
         None => Either::Right(iter::successors(Some(0.0f64), move |n| {
             if *n <= len {
                 Some(n + 1.)
@@ -330,3 +2836,257 @@ impl<T: Copy, I1: Iterator<Item = T>, I2: Iterator<Item = T>> Iterator for Eithe
         }
     }
 }
+
+#[test]
+fn test_interpolate_missing() {
+    let mut points = vec![
+        Point::new(0., f64::NAN),
+        Point::new(1., 1.),
+        Point::new(2., f64::NAN),
+        Point::new(3., f64::INFINITY),
+        Point::new(4., 4.),
+        Point::new(5., f64::NAN),
+    ];
+    interpolate_missing(&mut points);
+    assert!(points[0].y.is_nan());
+    assert_eq!(
+        points[1..5],
+        [
+            Point::new(1., 1.),
+            Point::new(2., 2.),
+            Point::new(3., 3.),
+            Point::new(4., 4.),
+        ]
+    );
+    assert!(points[5].y.is_nan());
+}
+
+#[test]
+fn test_add_steps() {
+    let points = vec![
+        Point::new(0., 0.),
+        Point::new(1., 2.),
+        Point::new(2., f64::NAN),
+        Point::new(3., 1.),
+    ];
+    let after = add_steps(points.clone(), Interpolation::StepAfter);
+    assert_eq!(
+        &after[..3],
+        &[(0., 0.).into(), (1., 0.).into(), (1., 2.).into()]
+    );
+    // no step across a gap.
+    assert!(after[3].y.is_nan());
+    assert_eq!(after[4], Point::new(3., 1.));
+    let before = add_steps(points, Interpolation::StepBefore);
+    assert_eq!(before[1], Point::new(0., 2.));
+}
+
+#[test]
+fn test_monotone_tangents() {
+    let points = [
+        Point::new(0., 0.),
+        Point::new(1., 1.),
+        Point::new(2., 1.),
+        Point::new(3., 4.),
+        Point::new(4., 0.),
+    ];
+    let tangents = monotone_tangents(&points);
+    // flat where the data is flat, or at a peak.
+    assert_eq!(tangents[1], 0.);
+    assert_eq!(tangents[2], 0.);
+    assert_eq!(tangents[3], 0.);
+    assert_eq!(tangents[0], 1.);
+    // no overshoot: the control points stay within the y range of each segment.
+    for (idx, pair) in points.windows(2).enumerate() {
+        let third = (pair[1].x - pair[0].x) / 3.;
+        let (low, high) = (pair[0].y.min(pair[1].y), pair[0].y.max(pair[1].y));
+        for y in [
+            pair[0].y + tangents[idx] * third,
+            pair[1].y - tangents[idx + 1] * third,
+        ]
+        .iter()
+        {
+            assert!(*y >= low && *y <= high);
+        }
+    }
+}
+
+#[test]
+fn test_split_at_levels() {
+    // up from y = 0 to y = 10, then back down, split at y = 5.
+    let path = line_path(
+        vec![
+            Point::new(0., 0.),
+            Point::new(10., 10.),
+            Point::new(20., 0.),
+        ],
+        Interpolation::Linear,
+    );
+    let paths = split_at_levels(&path, &[5.], 2, |y| if y >= 5. { 1 } else { 0 });
+    assert_eq!(paths.len(), 2);
+    // below: two separate pieces.
+    let below: Vec<PathSeg> = paths[0].segments().collect();
+    assert_eq!(below.len(), 2);
+    assert!((below[0].end().y - 5.).abs() < 1e-9);
+    assert!((below[1].start().x - 15.).abs() < 1e-9);
+    // above: one joined-up piece through the peak.
+    let above: Vec<PathSeg> = paths[1].segments().collect();
+    assert_eq!(above.len(), 2);
+    assert!((above[0].start() - Point::new(5., 5.)).hypot() < 1e-9);
+    assert_eq!(above[0].end(), Point::new(10., 10.));
+    assert_eq!(above[1].start(), Point::new(10., 10.));
+}
+
+#[test]
+fn test_line_hits_rect() {
+    let rect = Rect::new(0., 0., 10., 10.);
+    assert!(line_hits_rect(Line::new((-5., 5.), (15., 5.)), rect));
+    assert!(line_hits_rect(Line::new((2., 2.), (3., 3.)), rect));
+    assert!(!line_hits_rect(Line::new((-5., -5.), (15., -5.)), rect));
+    // passes by the corner.
+    assert!(!line_hits_rect(Line::new((5., -10.), (20., 5.)), rect));
+}
+
+#[test]
+fn test_place_label() {
+    let bounds = Rect::new(-100., -100., 100., 100.);
+    let size = Size::new(20., 10.);
+    // a peak: the label goes above.
+    let peak = Point::new(10., 0.);
+    let lines = [Line::new(peak, (0., 10.)), Line::new(peak, (20., 10.))];
+    let rect = place_label(peak, size, 4., &lines, &[], bounds);
+    assert_eq!(rect, Rect::new(0., -14., 20., -4.));
+    // a trough: the label goes below.
+    let trough = Point::new(10., 10.);
+    let lines = [Line::new(trough, (0., 0.)), Line::new(trough, (20., 0.))];
+    let rect = place_label(trough, size, 4., &lines, &[], bounds);
+    assert_eq!(rect, Rect::new(0., 14., 20., 24.));
+    // labels are kept inside the bounds.
+    let rect = place_label(Point::new(-100., 0.), size, 4., &[], &[], bounds);
+    assert_eq!(rect.x0, -100.);
+    assert_eq!(
+        PointLabels::MinMax.indices(&[3., f64::NAN, 1., 5.]),
+        vec![2, 3]
+    );
+    assert_eq!(PointLabels::EveryNth(2).indices(&[0.; 5]), vec![0, 2, 4]);
+}
+
+#[test]
+fn test_callout_points() {
+    let values = [3., 1., 5., 2., f64::NAN];
+    assert_eq!(
+        Callouts::new().points(&values),
+        vec![
+            (2, "max".to_string()),
+            (1, "min".to_string()),
+            (3, "latest".to_string())
+        ]
+    );
+    // the same point can be more than one callout.
+    assert_eq!(
+        Callouts::new().with_max(false).points(&[2., 1.]),
+        vec![(1, "min, latest".to_string())]
+    );
+    assert!(Callouts::new().points(&[f64::NAN]).is_empty());
+}
+
+#[test]
+fn test_streamed_between() {
+    let old: Vector<f64> = (0..10).map(f64::from).collect();
+    let new: Vector<f64> = (3..12).map(f64::from).collect();
+    assert_eq!(
+        Streamed::between(&old, &new),
+        Some(Streamed {
+            dropped: 3,
+            added: 2
+        })
+    );
+    // a flat signal, with the newest points different.
+    let old: Vector<f64> = iter::repeat(0.).take(1000).collect();
+    let mut new = old.clone();
+    for _ in 0..3 {
+        new.pop_front();
+        new.push_back(1.);
+    }
+    assert_eq!(
+        Streamed::between(&old, &new),
+        Some(Streamed {
+            dropped: 3,
+            added: 3
+        })
+    );
+    // the points in the middle changed.
+    let mut changed = old.clone();
+    changed.set(500, 1.);
+    assert_eq!(Streamed::between(&old, &changed), None);
+    // missing values are kept like any other.
+    let old: Vector<f64> = vec![1., f64::NAN, 2.].into();
+    let new: Vector<f64> = vec![f64::NAN, 2., 3.].into();
+    assert_eq!(
+        Streamed::between(&old, &new),
+        Some(Streamed {
+            dropped: 1,
+            added: 1
+        })
+    );
+}
+
+#[test]
+fn test_append_path() {
+    let mut path = line_path(
+        vec![Point::new(0., 0.), Point::new(1., 1.)],
+        Interpolation::Linear,
+    );
+    // the tail starts where the line ends, so carries it on.
+    let tail = line_path(
+        vec![Point::new(1., 1.), Point::new(2., 0.)],
+        Interpolation::Linear,
+    );
+    append_path(&mut path, &tail);
+    let expected = line_path(
+        vec![Point::new(0., 0.), Point::new(1., 1.), Point::new(2., 0.)],
+        Interpolation::Linear,
+    );
+    assert_eq!(path.elements(), expected.elements());
+    // after a gap, the tail starts a new line.
+    let gap = line_path(
+        vec![
+            Point::new(f64::NAN, f64::NAN),
+            Point::new(3., 0.),
+            Point::new(4., 1.),
+        ],
+        Interpolation::Linear,
+    );
+    append_path(&mut path, &gap);
+    assert_eq!(path.elements().len(), 5);
+    assert_eq!(path.elements()[3], PathEl::MoveTo(Point::new(3., 0.)));
+}
+
+#[test]
+fn test_streamed_point_labels() {
+    let chart = LineChart::<ArcStr, ArcStr>::new();
+    assert!(!chart.streamed_repaints_all());
+    // the highest point can be anywhere, so adding points can move its label.
+    let chart = chart.with_point_labels(0, PointLabels::MinMax);
+    assert!(chart.streamed_repaints_all());
+    let old: Vector<f64> = vec![1., 3., 2.].into();
+    let mut new = old.clone();
+    new.push_back(4.);
+    assert_eq!(
+        Streamed::between(&old, &new),
+        Some(Streamed {
+            dropped: 0,
+            added: 1
+        })
+    );
+    assert_eq!(PointLabels::MinMax.indices(&[1., 3., 2., 4.]), [0, 3]);
+}
+
+#[test]
+fn test_streamed_trend() {
+    use crate::trend::Trend;
+    // the whole trend line moves when points are added.
+    let chart = LineChart::<ArcStr, ArcStr>::new()
+        .with_trend(TrendLine::new(0, Trend::LinearRegression).with_stats(Corner::TopLeft));
+    assert!(chart.streamed_repaints_all());
+}
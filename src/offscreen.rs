@@ -0,0 +1,181 @@
+//! Drawing big images (e.g. 8K figures for print) on a worker thread, so the UI doesn't freeze.
+use druid::{
+    piet::{self, Device, ImageFormat, Piet, RenderContext},
+    theme::WINDOW_BACKGROUND_COLOR,
+    Env, EventCtx, ExtEventSink, ImageBuf, Size, Target,
+};
+use std::thread::{self, JoinHandle};
+
+use crate::{
+    canvas::Canvas,
+    commands::{CHART_IMAGE_EXPORTED, OFFSCREEN_RENDER_DONE},
+    theme,
+};
+
+/// A chart that can draw itself without being in a window, e.g. onto an image.
+pub trait OffscreenChart<T> {
+    /// Lay the chart out to fill `ctx`, and draw it fully (without any entrance animation).
+    fn draw_offscreen(&mut self, ctx: &mut Canvas, data: &T, env: &Env);
+}
+
+/// Draw a chart onto an image `size` display points big, at `scale` pixels per point, on a new
+/// thread. When it is done, the image is sent to `target` in an [`OFFSCREEN_RENDER_DONE`]
+/// command.
+///
+/// Chart widgets can't be sent between threads, so `make_chart` builds the chart on the worker
+/// thread. It is given the environment the chart is drawn with, with the crate's theme already
+/// added, to change as it likes. Get `sink` and `target` as for [`render_offscreen`].
+pub fn render_chart_offscreen<T, W>(
+    sink: ExtEventSink,
+    target: impl Into<Target>,
+    size: Size,
+    scale: f64,
+    data: T,
+    make_chart: impl FnOnce(&mut Env) -> W + Send + 'static,
+) -> JoinHandle<()>
+where
+    T: Send + 'static,
+    W: OffscreenChart<T>,
+{
+    let target = target.into();
+    thread::spawn(move || {
+        let mut env = Env::default();
+        theme::add_to_env(&mut env);
+        let mut chart = make_chart(&mut env);
+        let image =
+            render_chart(&mut chart, &data, &env, size, scale).map_err(|err| err.to_string());
+        send(sink, target, image);
+    })
+}
+
+/// Draw an image `size` display points big, at `scale` pixels per point, on a new thread. When it
+/// is done, the image is sent to `target` in an [`OFFSCREEN_RENDER_DONE`] command.
+///
+/// `draw` is given the image's render context and its size in points. It runs on the worker
+/// thread, so it can't use a widget's `PaintCtx`: give it what it needs to draw (e.g. a copy of the
+/// chart data) and draw with the render context. To draw one of this crate's charts, use
+/// [`render_chart_offscreen`].
+///
+/// Get `sink` from `ctx.get_external_handle()`, and the target from `ctx.widget_id()` of the
+/// widget that should receive the image.
+pub fn render_offscreen(
+    sink: ExtEventSink,
+    target: impl Into<Target>,
+    size: Size,
+    scale: f64,
+    draw: impl FnOnce(&mut Piet, Size) + Send + 'static,
+) -> JoinHandle<()> {
+    let target = target.into();
+    thread::spawn(move || {
+        let image = render(size, scale, draw).map_err(|err| err.to_string());
+        send(sink, target, image);
+    })
+}
+
+fn send(sink: ExtEventSink, target: Target, image: Result<ImageBuf, String>) {
+    if sink
+        .submit_command(OFFSCREEN_RENDER_DONE, image, target)
+        .is_err()
+    {
+        log::warn!("the app closed before an offscreen render finished");
+    }
+}
+
+/// Handle an [`EXPORT_IMAGE`](crate::commands::EXPORT_IMAGE) command: draw the widget with
+/// `draw` onto an image at `scale` pixels per point, and send it up in a [`CHART_IMAGE_EXPORTED`]
+/// notification.
+pub(crate) fn export_image(
+    ctx: &mut EventCtx,
+    scale: f64,
+    env: &Env,
+    draw: impl FnOnce(&mut Canvas),
+) {
+    let image = render_canvas(ctx.size(), scale, env, draw).map_err(|err| err.to_string());
+    ctx.submit_notification(CHART_IMAGE_EXPORTED.with(image));
+    ctx.set_handled();
+}
+
+/// Draw `chart` onto an image on this thread.
+fn render_chart<T>(
+    chart: &mut impl OffscreenChart<T>,
+    data: &T,
+    env: &Env,
+    size: Size,
+    scale: f64,
+) -> Result<ImageBuf, piet::Error> {
+    render_canvas(size, scale, env, |ctx| chart.draw_offscreen(ctx, data, env))
+}
+
+/// Draw onto an image on this thread, over the window background color.
+pub(crate) fn render_canvas(
+    size: Size,
+    scale: f64,
+    env: &Env,
+    draw: impl FnOnce(&mut Canvas),
+) -> Result<ImageBuf, piet::Error> {
+    render(size, scale, |rc, size| {
+        let mut ctx = Canvas::for_image(rc, size, scale, env);
+        ctx.fill(size.to_rect(), &env.get(WINDOW_BACKGROUND_COLOR));
+        draw(&mut ctx);
+    })
+}
+
+/// Draw the image on this thread.
+fn render(
+    size: Size,
+    scale: f64,
+    draw: impl FnOnce(&mut Piet, Size),
+) -> Result<ImageBuf, piet::Error> {
+    let (width, height) = pixel_size(size, scale);
+    let mut device = Device::new()?;
+    let mut bitmap = device.bitmap_target(width, height, scale)?;
+    {
+        let mut rc = bitmap.render_context();
+        draw(&mut rc, size);
+        rc.finish()?;
+    }
+    bitmap.to_image_buf(ImageFormat::RgbaPremul)
+}
+
+/// The size in pixels of an image `size` points big, at `scale` pixels per point. Part of a pixel
+/// counts as a whole one, so nothing is cut off.
+fn pixel_size(size: Size, scale: f64) -> (usize, usize) {
+    let pixels = (size * scale).expand();
+    (
+        pixels.width.max(1.) as usize,
+        pixels.height.max(1.) as usize,
+    )
+}
+
+#[test]
+fn test_render_chart() {
+    use crate::{BarChart, BarChartData, BarMode, BarSeries};
+    use druid::im::Vector;
+
+    let mut env = Env::default();
+    theme::add_to_env(&mut env);
+    let data = BarChartData {
+        title: "Sales".into(),
+        x_axis_label: "Month".into(),
+        categories: vec!["Jan".into(), "Feb".into()].into(),
+        series: vec![BarSeries::new("Shop", Vector::from(vec![3., 5.]))].into(),
+        draw_y_grid: true,
+    };
+    let mut chart = BarChart::new(BarMode::Grouped);
+    let image = render_chart(&mut chart, &data, &env, Size::new(300., 200.), 2.).unwrap();
+    assert_eq!((image.width(), image.height()), (600, 400));
+    // the bars are drawn, in the first series' color.
+    let (r, g, b, _) = crate::new_color(0, &env).as_rgba8();
+    let close = |a: u8, b: u8| (a as i16 - b as i16).abs() <= 1;
+    assert!(image
+        .raw_pixels()
+        .chunks(4)
+        .any(|px| close(px[0], r) && close(px[1], g) && close(px[2], b) && px[3] == 255));
+}
+
+#[test]
+fn test_pixel_size() {
+    // 8K
+    assert_eq!(pixel_size(Size::new(3840., 2160.), 2.), (7680, 4320));
+    assert_eq!(pixel_size(Size::new(100.5, 0.), 1.), (101, 1));
+}
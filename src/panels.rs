@@ -0,0 +1,408 @@
+//! Plots stacked on top of each other, sharing one x axis.
+use druid::{
+    kurbo::Rect, piet::PietText, ArcStr, BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, RenderContext, Size, UpdateCtx, Widget,
+};
+
+use crate::{
+    axes::Scale,
+    canvas::Canvas,
+    chart_size,
+    chart_stack::{layer_color, union, PlotLayer},
+    commands::{COPY_CHART_IMAGE, EXPORT_IMAGE, RESET_CHART_VIEW, ZOOM_TO_RANGE},
+    context_menu::{copy_image, ChartMenu, MenuItems},
+    empty::EmptyState,
+    format::Formatter,
+    offscreen::{export_image, OffscreenChart},
+    theme,
+    title::ChartTitle,
+    Range, DEFAULT_CHART_SIZE,
+};
+
+/// One of the plots in a [`SharedXPanels`], with its own y axis.
+pub struct Panel<T> {
+    layers: Vec<Box<dyn PlotLayer<T>>>,
+    /// How much of the height this panel gets, compared to the others.
+    weight: f64,
+    y_formatter: Formatter,
+    /// If `None`, the y axis fits the layers.
+    y_range: Option<Range>,
+    draw_grid: bool,
+}
+
+impl<T> Panel<T> {
+    pub fn new() -> Self {
+        Panel {
+            layers: Vec::new(),
+            weight: 1.,
+            y_formatter: Formatter::default(),
+            y_range: None,
+            draw_grid: false,
+        }
+    }
+
+    /// Add a layer on top of the ones already added.
+    pub fn with_layer(mut self, layer: impl PlotLayer<T> + 'static) -> Self {
+        self.layers.push(Box::new(layer));
+        self
+    }
+
+    /// Set how tall the panel is compared to the others, e.g. `3.` for a price panel over a
+    /// volume panel of weight `1.`. The default is `1`.
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        self.weight = weight.max(0.);
+        self
+    }
+
+    /// Set how the numbers on the y axis are formatted.
+    pub fn with_y_formatter(mut self, formatter: Formatter) -> Self {
+        self.y_formatter = formatter;
+        self
+    }
+
+    /// Show `range` on the y axis, instead of fitting it to the layers.
+    pub fn with_y_range(mut self, range: impl Into<Range>) -> Self {
+        self.y_range = Some(range.into());
+        self
+    }
+
+    /// Draw horizontal grid lines behind the layers.
+    pub fn with_grid(mut self, draw_grid: bool) -> Self {
+        self.draw_grid = draw_grid;
+        self
+    }
+
+    /// The y values to show. A panel with nothing in it shows 0 to 1.
+    fn y_range(&self, data: &T) -> Range {
+        let fitted = self
+            .layers
+            .iter()
+            .fold(None, |range, layer| union(range, layer.data_range(data).1));
+        self.y_range
+            .or(fitted)
+            .unwrap_or_else(|| Range::new(0., 1.))
+    }
+}
+
+/// Several panels stacked vertically, sharing one x axis that is only drawn under the bottom
+/// panel, e.g. a price over the trading volume, or a signal over a value derived from it.
+///
+/// Each panel has its own y axis. The y axes are lined up, so the x values line up between the
+/// panels.
+pub struct SharedXPanels<T> {
+    panels: Vec<Panel<T>>,
+    title: ChartTitle,
+    x_formatter: Formatter,
+    /// If `None`, the x axis fits all the layers.
+    x_range: Option<Range>,
+    /// The gap between panels, at the normal size.
+    spacing: f64,
+    preferred_size: Size,
+    // retained
+    /// The part of the x axis zoomed in to with `ZOOM_TO_RANGE`.
+    x_view: Option<Range>,
+    x_scale: Option<Scale>,
+    /// The y axis of each panel.
+    y_scales: Option<Vec<Scale>>,
+    /// Whether the layers have been rebuilt since the data or scales changed.
+    layers_built: bool,
+    empty_state: EmptyState,
+    context_menu: Option<ChartMenu>,
+}
+
+impl<T: Data> SharedXPanels<T> {
+    pub fn new() -> Self {
+        SharedXPanels {
+            panels: Vec::new(),
+            title: ChartTitle::new(),
+            x_formatter: Formatter::default(),
+            x_range: None,
+            spacing: 8.,
+            preferred_size: DEFAULT_CHART_SIZE,
+            x_view: None,
+            x_scale: None,
+            y_scales: None,
+            layers_built: false,
+            empty_state: EmptyState::new(),
+            context_menu: None,
+        }
+    }
+
+    /// Add a panel under the ones already added.
+    pub fn with_panel(mut self, panel: Panel<T>) -> Self {
+        self.panels.push(panel);
+        self
+    }
+
+    /// Draw a title above the top panel.
+    pub fn with_title(mut self, title: impl Into<ArcStr>) -> Self {
+        self.title.set_text(title.into());
+        self
+    }
+
+    /// Set how the numbers on the x axis are formatted.
+    pub fn with_x_formatter(mut self, formatter: Formatter) -> Self {
+        self.x_formatter = formatter;
+        self
+    }
+
+    /// Show `range` on the x axis, instead of fitting it to the layers.
+    pub fn with_x_range(mut self, range: impl Into<Range>) -> Self {
+        self.x_range = Some(range.into());
+        self
+    }
+
+    /// Set the gap between the panels. The default is `8`.
+    pub fn with_spacing(mut self, spacing: f64) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Set the size the chart would like to be, when it can be any size.
+    pub fn with_preferred_size(mut self, size: impl Into<Size>) -> Self {
+        self.preferred_size = size.into();
+        self
+    }
+
+    /// Show a menu when the chart is right-clicked, to reset the zoom or copy the chart as an
+    /// image. `A` is the type of your app's data, which druid needs to show a menu.
+    pub fn with_context_menu<A: Data>(mut self) -> Self {
+        self.context_menu = Some(ChartMenu::new::<A>());
+        self
+    }
+
+    /// The x values to show: where the axis has been zoomed to, or else the fixed range, or else
+    /// a range that covers all the layers. `None` if there is nothing to show.
+    fn x_range(&self, data: &T) -> Option<Range> {
+        self.x_view.or(self.x_range).or_else(|| {
+            self.panels
+                .iter()
+                .flat_map(|panel| panel.layers.iter())
+                .fold(None, |range, layer| union(range, layer.data_range(data).0))
+        })
+    }
+
+    /// Lay out the chart, or the empty state if there is no data.
+    fn lay_out(&mut self, text: &mut PietText, size: Size, data: &T, env: &Env) {
+        if self.x_range(data).is_none() {
+            self.empty_state.rebuild_if_needed(text, env);
+        } else {
+            self.rebuild_if_needed(text, size, data, env);
+        }
+    }
+
+    fn rebuild_if_needed(&mut self, text: &mut PietText, size: Size, data: &T, env: &Env) {
+        let margin = env.get(theme::MARGIN);
+        let scale_margin = env.get(theme::SCALE_MARGIN);
+        let spacing = self.spacing * env.get(theme::SIZE_SCALE);
+        self.title.rebuild_if_needed(text, env);
+        if self.x_scale.is_none() || self.y_scales.is_none() {
+            let mut x_scale = Scale::new_x(self.x_range(data).unwrap());
+            x_scale.set_formatter(self.x_formatter.clone());
+            self.x_scale = Some(x_scale);
+            self.y_scales = Some(
+                self.panels
+                    .iter()
+                    .map(|panel| {
+                        let mut y_scale = Scale::new_y(panel.y_range(data));
+                        y_scale.set_formatter(panel.y_formatter.clone());
+                        y_scale
+                    })
+                    .collect(),
+            );
+            self.layers_built = false;
+        }
+
+        // Lay out the scales for the whole area first, so we know how big their labels are. The
+        // widest y labels decide where all the panels start.
+        let draw_area = self.title.reserve_space(size.to_rect(), env);
+        let x_scale = self.x_scale.as_mut().unwrap();
+        x_scale.set_graph_bounds(draw_area);
+        x_scale.rebuild_if_needed(text, env);
+        let x_labels = x_scale.max_layout();
+        let y_scales = self.y_scales.as_mut().unwrap();
+        let mut y_label_width: f64 = 0.;
+        for y_scale in y_scales.iter_mut() {
+            y_scale.set_graph_bounds(draw_area);
+            y_scale.rebuild_if_needed(text, env);
+            y_label_width = y_label_width.max(y_scale.max_layout().width);
+        }
+
+        // the title has its own space above the draw area.
+        let x0 = draw_area.x0 + margin + y_label_width + scale_margin;
+        let x1 = draw_area.x1 - margin;
+        let y0 = draw_area.y0 + margin;
+        let y1 = draw_area.y1 - margin - x_labels.height - scale_margin;
+        let weights: Vec<f64> = self.panels.iter().map(|panel| panel.weight).collect();
+        for (y_scale, (panel_y0, panel_y1)) in y_scales
+            .iter_mut()
+            .zip(panel_extents(y0, y1, &weights, spacing))
+        {
+            let bounds = Rect::new(x0, panel_y0, x1, panel_y1);
+            if y_scale.graph_bounds() != bounds {
+                self.layers_built = false;
+            }
+            y_scale.set_graph_bounds(bounds);
+            y_scale.rebuild_if_needed(text, env);
+        }
+        // the x axis runs under all the panels.
+        let x_scale = self.x_scale.as_mut().unwrap();
+        let x_bounds = Rect::new(x0, y0, x1, y1.max(y0));
+        if x_scale.graph_bounds() != x_bounds {
+            self.layers_built = false;
+        }
+        x_scale.set_graph_bounds(x_bounds);
+        x_scale.rebuild_if_needed(text, env);
+
+        if !self.layers_built {
+            let x_scale = self.x_scale.as_ref().unwrap();
+            for (panel, y_scale) in self.panels.iter_mut().zip(y_scales.iter()) {
+                for layer in panel.layers.iter_mut() {
+                    layer.rebuild(text, data, env, x_scale, y_scale);
+                }
+            }
+            self.layers_built = true;
+        }
+    }
+
+    /// Draw the chart, as laid out by `layout`.
+    fn draw(&mut self, ctx: &mut Canvas, data: &T, env: &Env) {
+        if !self.layers_built || self.x_range(data).is_none() {
+            self.empty_state.draw(ctx);
+            return;
+        }
+        let y_scales = self.y_scales.as_mut().unwrap();
+        for (panel, y_scale) in self.panels.iter().zip(y_scales.iter_mut()) {
+            if panel.draw_grid {
+                y_scale.draw_grid(ctx, env);
+            }
+            let layers = &panel.layers;
+            ctx.with_save(|ctx| {
+                ctx.clip(y_scale.graph_bounds());
+                for (idx, layer) in layers.iter().enumerate() {
+                    layer.paint(ctx, env, &layer_color(&**layer, idx, env));
+                }
+            });
+            y_scale.draw(ctx, env, true, true);
+        }
+
+        self.title.draw(ctx, env);
+        self.x_scale.as_mut().unwrap().draw(ctx, env, true, true);
+    }
+}
+
+impl<T: Data> Widget<T> for SharedXPanels<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        let items = MenuItems {
+            reset_zoom: self.x_view.is_some(),
+            ..MenuItems::default()
+        };
+        if let Some(context_menu) = self.context_menu.as_mut() {
+            context_menu.event(ctx, event, items, Vec::new);
+        }
+        match event {
+            // each panel has its own y axis, so only the x axis can be zoomed.
+            Event::Command(cmd) if cmd.is(ZOOM_TO_RANGE) => {
+                if let Some(x_range) = cmd.get_unchecked(ZOOM_TO_RANGE).x_range {
+                    self.x_view = Some(x_range);
+                    self.x_scale = None;
+                    ctx.request_layout();
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(RESET_CHART_VIEW) => {
+                self.x_view = None;
+                self.x_scale = None;
+                ctx.request_layout();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_IMAGE) => {
+                copy_image(ctx, env, |ctx| self.draw(ctx, data, env));
+            }
+            Event::Command(cmd) if cmd.is(EXPORT_IMAGE) => {
+                let scale = *cmd.get_unchecked(EXPORT_IMAGE);
+                export_image(ctx, scale, env, |ctx| self.draw(ctx, data, env));
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &T, _env: &Env) {}
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, _env: &Env) {
+        if self.title.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !old_data.same(data) {
+            self.x_scale = None;
+            self.y_scales = None;
+            ctx.request_layout();
+        } else {
+            let mut needs_rebuild = false;
+            for scale in self
+                .x_scale
+                .iter_mut()
+                .chain(self.y_scales.iter_mut().flatten())
+            {
+                needs_rebuild |= scale.needs_rebuild_after_update(ctx);
+            }
+            if needs_rebuild {
+                self.layers_built = false;
+                ctx.request_layout();
+            }
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let size = chart_size(bc, self.preferred_size);
+        self.lay_out(ctx.text(), size, data, env);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.draw(&mut Canvas::new(ctx, env), data, env);
+    }
+}
+
+impl<T: Data> OffscreenChart<T> for SharedXPanels<T> {
+    fn draw_offscreen(&mut self, ctx: &mut Canvas, data: &T, env: &Env) {
+        let size = ctx.size();
+        self.lay_out(ctx.text(), size, data, env);
+        self.draw(ctx, data, env);
+    }
+}
+
+/// The `(top, bottom)` of each panel between `y0` and `y1`, sharing the height by weight with
+/// `spacing` between them.
+fn panel_extents(y0: f64, y1: f64, weights: &[f64], spacing: f64) -> Vec<(f64, f64)> {
+    let gaps = spacing * weights.len().saturating_sub(1) as f64;
+    let height = (y1 - y0 - gaps).max(0.);
+    let total: f64 = weights.iter().sum();
+    let mut top = y0;
+    weights
+        .iter()
+        .map(|weight| {
+            let panel_height = if total > 0. {
+                height * weight / total
+            } else {
+                height / weights.len() as f64
+            };
+            let extent = (top, top + panel_height);
+            top += panel_height + spacing;
+            extent
+        })
+        .collect()
+}
+
+#[test]
+fn test_panel_extents() {
+    assert_eq!(
+        panel_extents(0., 110., &[3., 1.], 10.),
+        vec![(0., 75.), (85., 110.)]
+    );
+    // no weight at all shares the height out evenly.
+    assert_eq!(
+        panel_extents(0., 100., &[0., 0.], 0.),
+        vec![(0., 50.), (50., 100.)]
+    );
+}
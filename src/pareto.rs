@@ -0,0 +1,596 @@
+use druid::{
+    im::Vector,
+    kurbo::{BezPath, Circle, Point, Rect},
+    piet::PietText,
+    ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, Insets, KeyOrValue, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, RenderContext, Size, TextLayout, UpdateCtx, Widget,
+};
+use druid_lens_compose::ComposeLens;
+
+use crate::{
+    axes::{Direction, Scale},
+    canvas::Canvas,
+    chart_size,
+    commands::{COPY_CHART_DATA, COPY_CHART_IMAGE, EXPORT_IMAGE, TOGGLE_CHART_GRID},
+    context_menu::{copy_csv, copy_image, csv_cell, ChartMenu, MenuItems},
+    describe::{count, describe},
+    empty::EmptyState,
+    fit_aspect_ratio,
+    format::Formatter,
+    new_color,
+    offscreen::{export_image, OffscreenChart},
+    theme,
+    title::{ChartTitle, TitleAlignment},
+    Range, SeriesData, DEFAULT_CHART_SIZE,
+};
+
+/// The radius of the dot at each point of the cumulative line, at the normal size.
+const POINT_RADIUS: f64 = 3.;
+
+/// The data for a Pareto chart: a value (e.g. the number of defects) for each category, in any
+/// order. The values can be any [`SeriesData`], e.g. a `Vector<i32>` or an `Arc<Vec<f64>>`.
+#[derive(Debug, Clone, Data, ComposeLens)]
+pub struct ParetoChartData<S = Vector<f64>> {
+    pub title: ArcStr,
+    pub x_axis_label: ArcStr,
+    /// The name of each category.
+    pub categories: Vector<ArcStr>,
+    /// The value of each category. Missing values are left out.
+    pub values: S,
+    pub draw_y_grid: bool,
+}
+
+impl<S: SeriesData> ParetoChartData<S> {
+    /// The data as a table, with a row for each category, in the order of the data.
+    fn csv_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![vec!["category".to_string(), "value".to_string()]];
+        for (idx, category) in self.categories.iter().enumerate() {
+            table.push(vec![category.to_string(), csv_cell(self.values.get(idx))]);
+        }
+        table
+    }
+}
+
+/// One bar of a Pareto chart.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct ParetoBar {
+    /// Which category this is, in the data.
+    idx: usize,
+    value: f64,
+    /// The total of this bar and all the bigger ones, as a percentage of the total of all of them.
+    cumulative: f64,
+}
+
+/// A Pareto chart: a bar for each category, biggest first, with a line showing the running total
+/// as a percentage on a second y axis on the right. It shows which few categories account for
+/// most of the total.
+pub struct ParetoChart {
+    bar_spacing: KeyOrValue<f64>,
+    axis_color: KeyOrValue<Color>,
+    bar_color: KeyOrValue<Color>,
+    /// If `None`, we use the second series color.
+    line_color: Option<KeyOrValue<Color>>,
+    stroke_width: KeyOrValue<f64>,
+    draw_axes: bool,
+    /// If `None`, we measure the axes and labels.
+    graph_insets: Option<KeyOrValue<Insets>>,
+    preferred_size: Size,
+    aspect_ratio: Option<f64>,
+    y_formatter: Formatter,
+    // retained state
+    title: ChartTitle,
+    x_label_layout: TextLayout<ArcStr>,
+    bars: Option<Vec<ParetoBar>>,
+    /// The categories, in the order of the bars.
+    x_scale: Option<Scale>,
+    y_scale: Option<Scale>,
+    /// The cumulative percentage, from 0 to 100.
+    y2_scale: Option<Scale>,
+    /// The running total, above the middle of each bar.
+    cumulative_points: Vec<Point>,
+    cumulative_line: BezPath,
+    /// The area the bars are drawn in.
+    graph_bounds: Rect,
+    empty_state: EmptyState,
+    context_menu: Option<ChartMenu>,
+}
+
+impl ParetoChart {
+    pub fn new() -> Self {
+        ParetoChart {
+            bar_spacing: theme::BAR_SPACING.into(),
+            axis_color: theme::AXES_COLOR.into(),
+            bar_color: theme::BAR_COLOR.into(),
+            line_color: None,
+            stroke_width: theme::LINE_STROKE_WIDTH.into(),
+            draw_axes: true,
+            graph_insets: None,
+            preferred_size: DEFAULT_CHART_SIZE,
+            aspect_ratio: None,
+            y_formatter: Formatter::default(),
+            title: ChartTitle::new(),
+            x_label_layout: TextLayout::new(),
+            bars: None,
+            x_scale: None,
+            y_scale: None,
+            y2_scale: None,
+            cumulative_points: Vec::new(),
+            cumulative_line: BezPath::new(),
+            graph_bounds: Rect::ZERO,
+            empty_state: EmptyState::new(),
+            context_menu: None,
+        }
+    }
+
+    /// Set how the tick labels of the (left) value axis are formatted.
+    pub fn with_y_formatter(mut self, formatter: Formatter) -> Self {
+        self.y_formatter = formatter;
+        self
+    }
+
+    /// Set the color of the bars. The default is `theme::BAR_COLOR`.
+    pub fn with_bar_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.bar_color = color.into();
+        self
+    }
+
+    /// Set the color of the cumulative percentage line.
+    pub fn with_line_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.line_color = Some(color.into());
+        self
+    }
+
+    /// Set the width of the cumulative percentage line. The default is
+    /// `theme::LINE_STROKE_WIDTH`.
+    pub fn with_stroke_width(mut self, width: impl Into<KeyOrValue<f64>>) -> Self {
+        self.stroke_width = width.into();
+        self
+    }
+
+    /// Set the color of the axes.
+    pub fn with_axis_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.axis_color = color.into();
+        self
+    }
+
+    /// Whether to draw the axis lines. The tick labels are drawn either way.
+    pub fn with_axes_visible(mut self, visible: bool) -> Self {
+        self.draw_axes = visible;
+        self
+    }
+
+    /// Set the font size of the title. The default is `theme::TITLE_TEXT_SIZE`.
+    pub fn with_title_size(mut self, size: impl Into<KeyOrValue<f64>>) -> Self {
+        self.title.set_text_size(size);
+        self
+    }
+
+    /// Where to put the title (and subtitle). Defaults to the center.
+    pub fn with_title_alignment(mut self, alignment: TitleAlignment) -> Self {
+        self.title.set_alignment(alignment);
+        self
+    }
+
+    /// Draw a line of smaller text under the title.
+    pub fn with_subtitle(mut self, subtitle: impl Into<ArcStr>) -> Self {
+        self.title.set_subtitle(subtitle.into());
+        self
+    }
+
+    /// Use fixed insets around the plot area, instead of measuring the axes (see
+    /// `theme::GRAPH_INSETS`).
+    pub fn with_graph_insets(mut self, insets: impl Into<KeyOrValue<Insets>>) -> Self {
+        self.graph_insets = Some(insets.into());
+        self
+    }
+
+    /// Set the size to take when the space isn't bounded, e.g. inside a `Scroll`. Otherwise the
+    /// chart fills the space it is given. The default is 400×300.
+    pub fn with_preferred_size(mut self, size: impl Into<Size>) -> Self {
+        self.preferred_size = size.into();
+        self
+    }
+
+    /// Show a menu when the chart is right-clicked, to toggle the grid, copy the chart as an image,
+    /// or copy or save the data as CSV. `T` is the type of your app's data, which druid needs to
+    /// show a menu.
+    pub fn with_context_menu<T: Data>(mut self) -> Self {
+        self.context_menu = Some(ChartMenu::new::<T>());
+        self
+    }
+
+    /// Keep the plot area at the given width:height ratio, centered in the space left over from
+    /// the title and axes. By default the plot area fills that space.
+    pub fn with_aspect_ratio(mut self, ratio: f64) -> Self {
+        assert!(ratio > 0.0, "aspect ratio must be positive");
+        self.aspect_ratio = Some(ratio);
+        self
+    }
+
+    /// A short description of the chart for screen readers, e.g. "Pareto chart, Defects, 6
+    /// categories, largest Scratches (40%)".
+    pub fn describe<S: SeriesData>(&self, data: &ParetoChartData<S>) -> String {
+        let bars = pareto_bars(&data.values);
+        let mut facts = vec![count(bars.len(), "category", "categories")];
+        if let Some(largest) = bars.first() {
+            let label = data
+                .categories
+                .get(largest.idx)
+                .map(|label| label.as_ref())
+                .unwrap_or("");
+            facts.push(format!(
+                "largest {} ({})",
+                label,
+                Formatter::percent(0).format(largest.cumulative)
+            ));
+        }
+        describe("Pareto chart", &data.title, facts)
+    }
+
+    /// Take the title and axis label from `data`, when the chart is added.
+    fn set_data<S: SeriesData>(&mut self, data: &ParetoChartData<S>) {
+        self.title.set_text(data.title.clone());
+        self.x_label_layout.set_text(data.x_axis_label.clone());
+    }
+
+    /// Lay out the chart, or the empty state if there is no data.
+    fn lay_out<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &ParetoChartData<S>,
+        env: &Env,
+    ) {
+        let bars = self.bars.get_or_insert_with(|| pareto_bars(&data.values));
+        if bars.is_empty() {
+            self.empty_state.rebuild_if_needed(text, env);
+        } else {
+            self.rebuild_if_needed(text, size, data, env);
+        }
+    }
+
+    /// Rebuild the layouts, scales and line for a chart of size `size`, as needed.
+    fn rebuild_if_needed<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &ParetoChartData<S>,
+        env: &Env,
+    ) {
+        let margin = env.get(theme::MARGIN);
+        let scale_margin = env.get(theme::SCALE_MARGIN);
+        self.title.rebuild_if_needed(text, env);
+        self.x_label_layout.rebuild_if_needed(text, env);
+        if self.bars.is_none() {
+            self.bars = Some(pareto_bars(&data.values));
+        }
+        let bars = self.bars.as_ref().unwrap();
+        if self.x_scale.is_none() {
+            let categories = bars.iter().map(|bar| {
+                data.categories
+                    .get(bar.idx)
+                    .cloned()
+                    .unwrap_or_else(|| "".into())
+            });
+            let mut x_scale = Scale::new_categorical(categories, Direction::X);
+            x_scale.set_axis_color(self.axis_color.clone());
+            self.x_scale = Some(x_scale);
+        }
+        if self.y_scale.is_none() {
+            // the bars are biggest first, and grow from 0, whichever way they go.
+            let max = bars.first().map_or(0., |bar| bar.value).max(0.);
+            let min = bars.last().map_or(0., |bar| bar.value).min(0.);
+            let y_range = if min == max {
+                Range::new(0., 1.)
+            } else {
+                Range::new(min, max)
+            };
+            let mut y_scale = Scale::new_y(y_range);
+            y_scale.set_axis_color(self.axis_color.clone());
+            y_scale.set_formatter(self.y_formatter.clone());
+            self.y_scale = Some(y_scale);
+        }
+        if self.y2_scale.is_none() {
+            let mut y2_scale = Scale::new((0., 100.), Direction::YRight);
+            y2_scale.set_axis_color(self.axis_color.clone());
+            y2_scale.set_formatter(Formatter::percent(0));
+            self.y2_scale = Some(y2_scale);
+        }
+
+        // Lay out the scales for the whole area first, so we know how big their labels are.
+        let draw_area = self.title.reserve_space(size.to_rect(), env);
+        let mut label_sizes = Vec::with_capacity(3);
+        for scale in [&mut self.x_scale, &mut self.y_scale, &mut self.y2_scale].iter_mut() {
+            let scale = scale.as_mut().unwrap();
+            scale.set_graph_bounds(draw_area);
+            scale.rebuild_if_needed(text, env);
+            label_sizes.push(scale.max_layout());
+        }
+
+        // space for the value axes and their tick labels either side, and the category labels
+        // (and the x axis label, if it's there) underneath.
+        let x0 = margin + label_sizes[1].width + scale_margin;
+        let x1 = margin + label_sizes[2].width + scale_margin;
+        // the title has its own space above the draw area.
+        let y0 = margin;
+        let mut y1 = margin + label_sizes[0].height + scale_margin;
+        if !data.x_axis_label.is_empty() {
+            y1 += margin + self.x_label_layout.size().height;
+        }
+        self.graph_bounds = draw_area.inset(match self.graph_insets.as_ref() {
+            Some(graph_insets) => -graph_insets.resolve(env),
+            None => Insets {
+                x0: -x0,
+                y0: -y0,
+                x1: -x1,
+                y1: -y1,
+            },
+        });
+        if let Some(ratio) = self.aspect_ratio {
+            self.graph_bounds = fit_aspect_ratio(self.graph_bounds, ratio);
+        }
+        for scale in [&mut self.x_scale, &mut self.y_scale, &mut self.y2_scale].iter_mut() {
+            let scale = scale.as_mut().unwrap();
+            scale.set_graph_bounds(self.graph_bounds);
+            scale.rebuild_if_needed(text, env);
+        }
+
+        let x_scale = self.x_scale.as_ref().unwrap();
+        let y2_scale = self.y2_scale.as_ref().unwrap();
+        self.cumulative_points = bars
+            .iter()
+            .enumerate()
+            .map(|(idx, bar)| {
+                Point::new(
+                    x_scale.category_center(idx),
+                    y2_scale.pixel_location(bar.cumulative),
+                )
+            })
+            .collect();
+        self.cumulative_line = BezPath::new();
+        for (idx, point) in self.cumulative_points.iter().copied().enumerate() {
+            if idx == 0 {
+                self.cumulative_line.move_to(point);
+            } else {
+                self.cumulative_line.line_to(point);
+            }
+        }
+    }
+
+    /// Draw the chart, as laid out by `layout`.
+    fn draw<S: SeriesData>(&mut self, ctx: &mut Canvas, data: &ParetoChartData<S>, env: &Env) {
+        if self.bars.as_ref().map_or(true, |bars| bars.is_empty()) {
+            self.empty_state.draw(ctx);
+            return;
+        }
+        let size = ctx.size();
+        let bar_brush = ctx.solid_brush(self.bar_color.resolve(env));
+        let line_color = match self.line_color.as_ref() {
+            Some(color) => color.resolve(env),
+            None => new_color(1, env),
+        };
+        let line_brush = ctx.solid_brush(line_color);
+        let stroke_width = self.stroke_width.resolve(env);
+        let bar_spacing = self.bar_spacing.resolve(env);
+
+        // bars
+        if data.draw_y_grid {
+            self.y_scale.as_ref().unwrap().draw_grid(ctx, env);
+        }
+        let x_scale = self.x_scale.as_ref().unwrap();
+        let y_scale = self.y_scale.as_ref().unwrap();
+        let bar_width = x_scale.category_width() - bar_spacing;
+        // negative values hang down from 0.
+        let zero = y_scale.pixel_location(0.);
+        let snap = ctx.snap();
+        if bar_width > 0. {
+            for (idx, bar) in self.bars.iter().flatten().enumerate() {
+                let center = x_scale.category_center(idx);
+                ctx.fill(
+                    snap.rect(
+                        Rect::new(
+                            center - bar_width * 0.5,
+                            y_scale.pixel_location(bar.value),
+                            center + bar_width * 0.5,
+                            zero,
+                        )
+                        .abs(),
+                    ),
+                    &bar_brush,
+                );
+            }
+        }
+
+        // cumulative line
+        let point_radius = POINT_RADIUS * env.get(theme::SIZE_SCALE);
+        ctx.stroke(&self.cumulative_line, &line_brush, stroke_width);
+        for point in self.cumulative_points.iter().copied() {
+            ctx.fill(Circle::new(point, point_radius), &line_brush);
+        }
+
+        // title
+        self.title.draw(ctx, env);
+
+        // axes
+        self.x_scale
+            .as_mut()
+            .unwrap()
+            .draw(ctx, env, self.draw_axes, true);
+        if !data.x_axis_label.is_empty() {
+            let label_size = self.x_label_layout.size();
+            let margin = env.get(theme::MARGIN);
+            ctx.draw_layout(
+                &self.x_label_layout,
+                (
+                    (size.width - label_size.width) * 0.5,
+                    size.height - label_size.height - margin,
+                ),
+            );
+        }
+        self.y_scale
+            .as_mut()
+            .unwrap()
+            .draw(ctx, env, self.draw_axes, true);
+        self.y2_scale
+            .as_mut()
+            .unwrap()
+            .draw(ctx, env, self.draw_axes, true);
+    }
+}
+
+impl<S: SeriesData> Widget<ParetoChartData<S>> for ParetoChart {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ParetoChartData<S>,
+        env: &Env,
+    ) {
+        let items = MenuItems {
+            grid: true,
+            data: true,
+            ..MenuItems::default()
+        };
+        if let Some(context_menu) = self.context_menu.as_mut() {
+            context_menu.event(ctx, event, items, || data.csv_table());
+        }
+        match event {
+            Event::Command(cmd) if cmd.is(TOGGLE_CHART_GRID) => {
+                data.draw_y_grid = !data.draw_y_grid;
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_DATA) => {
+                copy_csv(data.csv_table());
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_IMAGE) => {
+                copy_image(ctx, env, |ctx| self.draw(ctx, data, env));
+            }
+            Event::Command(cmd) if cmd.is(EXPORT_IMAGE) => {
+                let scale = *cmd.get_unchecked(EXPORT_IMAGE);
+                export_image(ctx, scale, env, |ctx| self.draw(ctx, data, env));
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &ParetoChartData<S>,
+        env: &Env,
+    ) {
+        match event {
+            LifeCycle::WidgetAdded => self.set_data(data),
+            _ => (),
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &ParetoChartData<S>,
+        data: &ParetoChartData<S>,
+        env: &Env,
+    ) {
+        if !old_data.title.same(&data.title) {
+            self.title.set_text(data.title.clone());
+        }
+        if self.title.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !old_data.x_axis_label.same(&data.x_axis_label) {
+            self.x_label_layout.set_text(data.x_axis_label.clone());
+        }
+        if self.x_label_layout.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !old_data.values.same(&data.values) {
+            self.bars = None;
+            self.y_scale = None;
+            // the bars may be in a different order.
+            self.x_scale = None;
+            ctx.request_layout();
+        } else if !old_data.categories.same(&data.categories) {
+            self.x_scale = None;
+            ctx.request_layout();
+        }
+        let mut needs_rebuild = false;
+        for scale in [&mut self.x_scale, &mut self.y_scale, &mut self.y2_scale]
+            .iter_mut()
+            .filter_map(|scale| scale.as_mut())
+        {
+            needs_rebuild |= scale.needs_rebuild_after_update(ctx);
+        }
+        if needs_rebuild {
+            ctx.request_layout();
+        }
+        if old_data.draw_y_grid != data.draw_y_grid {
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &ParetoChartData<S>,
+        env: &Env,
+    ) -> Size {
+        let size = chart_size(bc, self.preferred_size);
+        self.lay_out(ctx.text(), size, data, env);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &ParetoChartData<S>, env: &Env) {
+        self.draw(&mut Canvas::new(ctx, env), data, env);
+    }
+}
+
+impl<S: SeriesData> OffscreenChart<ParetoChartData<S>> for ParetoChart {
+    fn draw_offscreen(&mut self, ctx: &mut Canvas, data: &ParetoChartData<S>, env: &Env) {
+        self.set_data(data);
+        let size = ctx.size();
+        self.lay_out(ctx.text(), size, data, env);
+        self.draw(ctx, data, env);
+    }
+}
+
+/// The bars of a Pareto chart of `values`, biggest first. Missing values are left out, and equal
+/// values keep the order of their categories.
+fn pareto_bars(values: &impl SeriesData) -> Vec<ParetoBar> {
+    let mut bars: Vec<ParetoBar> = values
+        .values()
+        .enumerate()
+        .filter(|(_, value)| value.is_finite())
+        .map(|(idx, value)| ParetoBar {
+            idx,
+            value,
+            cumulative: 0.,
+        })
+        .collect();
+    // the sort is stable, so ties stay in the order of the data.
+    bars.sort_by(|a, b| b.value.partial_cmp(&a.value).unwrap());
+    let total: f64 = bars.iter().map(|bar| bar.value).sum();
+    let mut running_total = 0.;
+    for bar in bars.iter_mut() {
+        running_total += bar.value;
+        bar.cumulative = if total == 0. {
+            0.
+        } else {
+            running_total / total * 100.
+        };
+    }
+    bars
+}
+
+#[test]
+fn test_pareto_bars() {
+    let values: Vector<f64> = vec![10., f64::NAN, 40., 20., 10., 20.].into();
+    let bars = pareto_bars(&values);
+    let order: Vec<usize> = bars.iter().map(|bar| bar.idx).collect();
+    assert_eq!(order, vec![2, 3, 5, 0, 4]);
+    let cumulative: Vec<f64> = bars.iter().map(|bar| bar.cumulative).collect();
+    assert_eq!(cumulative, vec![40., 60., 80., 90., 100.]);
+}
@@ -1,60 +1,733 @@
 use druid::{
     im::Vector,
-    kurbo::{Affine, CircleSegment, Line, Rect},
-    piet::{PietTextLayout, Text, TextLayoutBuilder},
+    kurbo::{Affine, CircleSegment, Line, Point, Rect, Vec2},
+    piet::{PietText, PietTextLayout, Text, TextLayoutBuilder},
     theme::LABEL_COLOR,
-    ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, Insets, KeyOrValue, LayoutCtx,
-    LifeCycle, LifeCycleCtx, PaintCtx, RenderContext, Size, TextLayout, UpdateCtx, Widget,
+    ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, Insets, KbKey, KeyOrValue,
+    LayoutCtx, LifeCycle, LifeCycleCtx, MouseButton, PaintCtx, RenderContext, Size, TextLayout,
+    UpdateCtx, Widget,
 };
 use druid_lens_compose::ComposeLens;
-use itertools::izip;
-use std::{cmp::Ordering, f64::consts::PI, iter};
+use std::{cmp::Ordering, f64::consts::PI, rc::Rc, time::Duration};
 
-use crate::{new_color, square, theme};
+use crate::{
+    animation::{self, Entrance, Transition},
+    canvas::Canvas,
+    chart_size,
+    commands::{
+        PointSelected, CHART_POINT_SELECTED, COPY_CHART_DATA, COPY_CHART_IMAGE, EXPORT_IMAGE,
+        HIGHLIGHT_POINT, PIE_OTHER_SLICE_SELECTED,
+    },
+    context_menu::{copy_csv, copy_image, ChartMenu, MenuItems},
+    describe::{count, describe},
+    empty::EmptyState,
+    focus::KeyboardFocus,
+    format::Formatter,
+    hit_test::HitInfo,
+    legend::{Legend, LegendPlacement},
+    offscreen::{export_image, OffscreenChart},
+    square, theme,
+    title::{ChartTitle, TitleAlignment},
+    SeriesColors, DEFAULT_PIE_SIZE,
+};
 
 #[derive(Debug, Clone, Data, ComposeLens)]
 pub struct PieChartData {
     pub title: ArcStr,
     pub category_labels: Vector<ArcStr>,
     pub counts: Vector<usize>,
+    /// Slices to pull out of the pie, to draw attention to them. Slices without an entry stay
+    /// where they are.
+    pub exploded: Vector<bool>,
+}
+
+impl PieChartData {
+    /// The data as a table, with a row for each category.
+    fn csv_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![vec!["category".to_string(), "count".to_string()]];
+        for (idx, count) in self.counts.iter().enumerate() {
+            let label = self
+                .category_labels
+                .get(idx)
+                .map_or_else(String::new, |label| label.to_string());
+            table.push(vec![label, count.to_string()]);
+        }
+        table
+    }
+}
+
+/// The length of the line joining a thin slice to its label, at the normal size.
+const LEADER_LENGTH: f64 = 16.0;
+
+/// How far an exploded slice is pulled out, as a fraction of the radius.
+const EXPLODE_OFFSET: f64 = 0.1;
+
+/// Which way round the pie the slices go.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Data)]
+pub enum SweepDirection {
+    Clockwise,
+    Anticlockwise,
+}
+
+/// What to write on each slice.
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub enum SliceLabels {
+    /// The slice's share of the total, as a percentage.
+    Percentage,
+    /// The count for the slice.
+    Value,
+}
+
+impl SliceLabels {
+    fn text(self, count: usize, total: usize, percent_formatter: &Formatter) -> String {
+        match self {
+            SliceLabels::Percentage => percent_formatter.format(count as f64 / total as f64 * 100.),
+            SliceLabels::Value => count.to_string(),
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct PieChart {
-    title_layout: TextLayout<ArcStr>,
-    key_title_layout: TextLayout<ArcStr>,
-    category_layouts: Vec<TextLayout<ArcStr>>,
-    // theme stuff
-    key_stroke_color: KeyOrValue<Color>,
-    key_margin: KeyOrValue<f64>,
+    /// The size of the hole in the middle, as a fraction of the radius.
+    inner_radius: f64,
+    slice_labels: Option<SliceLabels>,
+    /// What to write after each category in the key, if anything.
+    key_values: Option<SliceLabels>,
+    /// How percentages are written.
+    percent_formatter: Formatter,
+    slice_colors: SeriesColors,
+    /// Whether the slice under the mouse is pulled out.
+    explode_on_hover: bool,
+    /// Where the first slice starts, in radians clockwise from 3 o'clock.
+    start_angle: f64,
+    sweep_direction: SweepDirection,
+    /// Whether to draw the biggest slice first, rather than in the order of the data.
+    sort_slices: bool,
+    /// Slices smaller than this fraction of the total are put together in one slice with this
+    /// label.
+    other_slice: Option<(f64, ArcStr)>,
+    preferred_size: Size,
+    title: ChartTitle,
+    legend: Legend,
+    empty_state: EmptyState,
+    // retained
+    slice_label_layouts: Option<Vec<TextLayout<ArcStr>>>,
+    /// The `(center, inner radius, outer radius)` of the pie when it was last drawn.
+    ring: Option<(Point, f64, f64)>,
+    /// The space the pie and its slice labels were drawn in. Only this needs repainting when the
+    /// hovered slice changes.
+    pie_bounds: Rect,
+    /// The slice under the mouse, if we are exploding it.
+    hovered: Option<usize>,
+    entrance: Option<Entrance>,
+    transition: Option<Transition>,
+    /// The slice angles shown when the counts last changed, that the transition starts from.
+    transition_from: Option<Vec<(f64, f64)>>,
+    keyboard: Option<KeyboardFocus>,
+    /// The slices drawn, if some were put together in the other slice. This is worked out when the
+    /// data changes.
+    collapsed: Option<Rc<CollapsedSlices>>,
+    context_menu: Option<ChartMenu>,
 }
 
 impl PieChart {
     pub fn new() -> Self {
-        let mut key_title_layout = TextLayout::from_text("Key");
-        key_title_layout.set_text_size(20.);
-        let mut title_layout = TextLayout::new();
-        title_layout.set_text_size(20.);
         PieChart {
-            title_layout,
-            key_title_layout,
-            category_layouts: vec![],
-            key_stroke_color: LABEL_COLOR.into(),
-            key_margin: theme::MARGIN.into(),
+            inner_radius: 0.0,
+            slice_labels: None,
+            key_values: None,
+            percent_formatter: Formatter::percent(1),
+            slice_colors: SeriesColors::default(),
+            explode_on_hover: false,
+            start_angle: 0.0,
+            sweep_direction: SweepDirection::Clockwise,
+            sort_slices: false,
+            other_slice: None,
+            preferred_size: DEFAULT_PIE_SIZE,
+            title: ChartTitle::new(),
+            legend: Legend::new(LegendPlacement::Right)
+                .with_title("Key")
+                .with_border_color(theme::PIE_KEY_BORDER_COLOR),
+            empty_state: EmptyState::new(),
+            slice_label_layouts: None,
+            ring: None,
+            pie_bounds: Rect::ZERO,
+            hovered: None,
+            entrance: None,
+            transition: None,
+            transition_from: None,
+            keyboard: None,
+            collapsed: None,
+            context_menu: None,
+        }
+    }
+
+    /// Draw a donut chart, with a hole of the given fraction of the radius (between 0 and 1).
+    pub fn with_inner_radius(mut self, inner_radius: f64) -> Self {
+        assert!(
+            (0.0..1.0).contains(&inner_radius),
+            "inner radius must be between 0 and 1"
+        );
+        self.inner_radius = inner_radius;
+        self
+    }
+
+    /// Label each slice with its percentage or value.
+    pub fn with_slice_labels(mut self, slice_labels: SliceLabels) -> Self {
+        self.slice_labels = Some(slice_labels);
+        self
+    }
+
+    /// Write the percentage or value of each slice after its name in the key, e.g.
+    /// `Apples (12.5%)`.
+    pub fn with_key_values(mut self, key_values: SliceLabels) -> Self {
+        self.key_values = Some(key_values);
+        self
+    }
+
+    /// Set how percentages are written, on the slices and in the key. The default is
+    /// `Formatter::percent(1)`.
+    pub fn with_percent_formatter(mut self, formatter: Formatter) -> Self {
+        self.percent_formatter = formatter;
+        self
+    }
+
+    /// Set the color of one of the slices. Slices without a color get one picked for them.
+    pub fn with_slice_color(mut self, slice: usize, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.slice_colors.set(slice, color);
+        self
+    }
+
+    /// Set the font size of the title. The default is `theme::TITLE_TEXT_SIZE`.
+    pub fn with_title_size(mut self, size: impl Into<KeyOrValue<f64>>) -> Self {
+        self.title.set_text_size(size);
+        self
+    }
+
+    /// Where to put the title (and subtitle). Defaults to the center.
+    pub fn with_title_alignment(mut self, alignment: TitleAlignment) -> Self {
+        self.title.set_alignment(alignment);
+        self
+    }
+
+    /// Draw a line of smaller text under the title.
+    pub fn with_subtitle(mut self, subtitle: impl Into<ArcStr>) -> Self {
+        self.title.set_subtitle(subtitle.into());
+        self
+    }
+
+    /// Pull the slice under the mouse out of the pie.
+    pub fn with_explode_on_hover(mut self, explode_on_hover: bool) -> Self {
+        self.explode_on_hover = explode_on_hover;
+        self
+    }
+
+    /// Set where the first slice starts, in radians clockwise from 3 o'clock. Use `-PI / 2.` to
+    /// start at 12 o'clock. The default is `0`.
+    pub fn with_start_angle(mut self, start_angle: f64) -> Self {
+        self.start_angle = start_angle;
+        self
+    }
+
+    /// Set which way round the pie the slices go. The default is `SweepDirection::Clockwise`.
+    pub fn with_sweep_direction(mut self, sweep_direction: SweepDirection) -> Self {
+        self.sweep_direction = sweep_direction;
+        self
+    }
+
+    /// Draw the slices from biggest to smallest, rather than in the order of the data. The colors
+    /// and key stay in the order of the data.
+    pub fn with_sorted_slices(mut self, sort_slices: bool) -> Self {
+        self.sort_slices = sort_slices;
+        self
+    }
+
+    /// Put the slices smaller than `threshold` of the total (e.g. `0.02` for 2%) together in one
+    /// slice called `label`, at the end. This keeps the key from overflowing the chart when there
+    /// are lots of categories.
+    ///
+    /// The slice colors are given to the slices drawn, so the other slice has the last one.
+    /// Selections, hit tests and `HIGHLIGHT_POINT` use the indices of the categories in the data.
+    /// Selecting the other slice sends `PIE_OTHER_SLICE_SELECTED`, and hit tests don't find it.
+    pub fn with_other_slice(mut self, threshold: f64, label: impl Into<ArcStr>) -> Self {
+        self.other_slice = Some((threshold, label.into()));
+        self
+    }
+
+    /// Set the size to take when the space isn't bounded, e.g. inside a `Scroll`. Otherwise the
+    /// chart fills the space it is given. The default is 300×300.
+    pub fn with_preferred_size(mut self, size: impl Into<Size>) -> Self {
+        self.preferred_size = size.into();
+        self
+    }
+
+    /// Show a menu when the chart is right-clicked, to copy the chart as an image, or copy or save
+    /// the data as CSV. `T` is the type of your app's data, which druid needs to show a menu.
+    pub fn with_context_menu<T: Data>(mut self) -> Self {
+        self.context_menu = Some(ChartMenu::new::<T>());
+        self
+    }
+
+    /// Sweep the slices round from the start angle when the chart is first shown.
+    pub fn with_entrance_animation(mut self, duration: Duration) -> Self {
+        self.entrance = Some(Entrance::new(duration));
+        self
+    }
+
+    /// When the counts change (e.g. live vote tallies), sweep the slices round to their new sizes
+    /// over `duration` rather than jumping. If slices are added or removed, the pie still jumps.
+    pub fn with_value_transition(mut self, duration: Duration) -> Self {
+        self.transition = Some(Transition::new(duration));
+        self
+    }
+
+    /// Let the user move between the slices with the arrow keys, and select one with Enter. The
+    /// chart takes part in tab focus.
+    pub fn with_keyboard_navigation(mut self, enabled: bool) -> Self {
+        self.keyboard = if enabled {
+            Some(KeyboardFocus::new())
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Where to draw the key. Defaults to the right of the pie.
+    pub fn with_legend_placement(mut self, placement: LegendPlacement) -> Self {
+        self.legend.set_placement(placement);
+        self
+    }
+
+    /// A short description of the chart for screen readers, e.g. "Pie chart, Fruit, 4 slices,
+    /// largest Apples (40.0%)".
+    pub fn describe(&self, data: &PieChartData) -> String {
+        let collapsed = self.collapse(data);
+        let data = collapsed.as_ref().map_or(data, |collapsed| &collapsed.data);
+        let total: usize = data.counts.iter().sum();
+        let mut facts = vec![count(data.counts.len(), "slice", "slices")];
+        let largest = data
+            .counts
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, count)| *count > 0)
+            .max_by(|(a_idx, a), (b_idx, b)| a.cmp(b).then(b_idx.cmp(a_idx)));
+        if let Some((idx, largest)) = largest {
+            let percent = SliceLabels::Percentage.text(largest, total, &self.percent_formatter);
+            match data.category_labels.get(idx) {
+                Some(label) => facts.push(format!("largest {} ({})", label, percent)),
+                None => facts.push(format!("largest {}", percent)),
+            }
+        }
+        describe("Pie chart", &data.title, facts)
+    }
+
+    /// `data` with the small slices put together, if there is an other slice and any are small.
+    fn collapse(&self, data: &PieChartData) -> Option<Rc<CollapsedSlices>> {
+        let (threshold, label) = self.other_slice.as_ref()?;
+        collapse_small_slices(data, *threshold, label).map(Rc::new)
+    }
+
+    /// The index in the data of slice `idx` as drawn, or `None` for the other slice.
+    fn data_index(&self, idx: usize) -> Option<usize> {
+        match &self.collapsed {
+            Some(collapsed) => collapsed.data_index(idx),
+            None => Some(idx),
+        }
+    }
+
+    /// Send `CHART_POINT_SELECTED` for slice `idx` as drawn, or `PIE_OTHER_SLICE_SELECTED` if it
+    /// is the other slice.
+    fn select(&self, ctx: &mut EventCtx, idx: usize) {
+        match (self.data_index(idx), &self.collapsed) {
+            (Some(idx), _) => {
+                ctx.submit_notification(CHART_POINT_SELECTED.with(PointSelected::new(0, idx)))
+            }
+            (None, Some(collapsed)) => {
+                ctx.submit_notification(PIE_OTHER_SLICE_SELECTED.with(collapsed.other.clone()))
+            }
+            (None, None) => (),
+        }
+    }
+
+    /// Take the title and slices from `data`, when the chart is added.
+    fn set_data(&mut self, data: &PieChartData) {
+        self.title.set_text(data.title.clone());
+        self.collapsed = self.collapse(data);
+        let data = self
+            .collapsed
+            .as_ref()
+            .map_or(data, |collapsed| &collapsed.data);
+        let labels = self.key_labels(data);
+        self.legend.set_labels(labels);
+    }
+
+    /// Lay out the pie (after collapsing small slices), or the empty state if there is nothing to
+    /// divide up.
+    fn lay_out(&mut self, text: &mut PietText, size: Size, data: &PieChartData, env: &Env) {
+        let total: usize = data.counts.iter().copied().sum();
+        if total == 0 {
+            self.ring = None;
+            self.empty_state.rebuild_if_needed(text, env);
+        } else {
+            let collapsed = self.collapsed.clone();
+            let data = collapsed.as_ref().map_or(data, |collapsed| &collapsed.data);
+            self.rebuild_text_if_needed(text, size, data, env);
+        }
+    }
+
+    /// Rebuild the layouts, and work out where the pie goes in a chart of size `size`.
+    pub fn rebuild_if_needed(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        size: Size,
+        data: &PieChartData,
+        env: &Env,
+    ) {
+        self.rebuild_text_if_needed(ctx.text(), size, data, env);
+    }
+
+    /// [`rebuild_if_needed`](Self::rebuild_if_needed), with just the text factory, so it works
+    /// offscreen too.
+    fn rebuild_text_if_needed(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &PieChartData,
+        env: &Env,
+    ) {
+        self.title.rebuild_if_needed(text, env);
+        self.legend.rebuild_if_needed(text, env);
+        if let Some(slice_labels) = self.slice_labels {
+            if self.slice_label_layouts.is_none() {
+                let total: usize = data.counts.iter().copied().sum();
+                let percent_formatter = &self.percent_formatter;
+                self.slice_label_layouts = Some(
+                    data.counts
+                        .iter()
+                        .map(|count| {
+                            let label = slice_labels.text(*count, total, percent_formatter);
+                            let mut layout = TextLayout::from_text(ArcStr::from(label));
+                            layout.rebuild_if_needed(text, env);
+                            layout
+                        })
+                        .collect(),
+                );
+            }
+            for layout in self.slice_label_layouts.as_mut().unwrap() {
+                layout.rebuild_if_needed(text, env);
+            }
+        }
+
+        let bounds = self.title.reserve_space(size.to_rect(), env);
+        let pie_bounds = self.legend.reserve_space(bounds, env);
+        self.pie_bounds = pie_bounds;
+        // with a 10 px margin
+        let size_scale = env.get(theme::SIZE_SCALE);
+        let pie_area = square(pie_bounds.inset(-10.0 * size_scale));
+        self.legend.set_graph_bounds(pie_area, env);
+        let mut outer_radius = pie_area.width() * 0.5;
+        if let Some(layouts) = self.slice_label_layouts.as_ref() {
+            // leave space for labels outside thin slices.
+            let max_label_width = layouts
+                .iter()
+                .map(|layout| layout.size().width)
+                .fold(0., f64::max);
+            outer_radius = (outer_radius - LEADER_LENGTH * size_scale - max_label_width).max(0.);
+        }
+        if self.explode_on_hover || data.exploded.iter().any(|exploded| *exploded) {
+            // leave space for slices to be pulled out.
+            outer_radius /= 1. + EXPLODE_OFFSET;
+        }
+        let inner_radius = outer_radius * self.inner_radius;
+        self.ring = Some((pie_area.center(), inner_radius, outer_radius));
+    }
+
+    /// The text of each entry in the key.
+    fn key_labels(&self, data: &PieChartData) -> Vec<ArcStr> {
+        let key_values = match self.key_values {
+            Some(key_values) => key_values,
+            None => return data.category_labels.iter().cloned().collect(),
+        };
+        let total: usize = data.counts.iter().copied().sum();
+        data.category_labels
+            .iter()
+            .zip(data.counts.iter().copied())
+            .map(|(label, count)| {
+                let value = key_values.text(count, total, &self.percent_formatter);
+                format!("{} ({})", label, value).into()
+            })
+            .collect()
+    }
+
+    /// The slice under `pos`, which is in the chart's coordinates, like the position of a mouse
+    /// event. The position of the hit is the middle of the slice.
+    ///
+    /// This returns `None` before the chart has been laid out, and over the other slice (see
+    /// [`PieChart::with_other_slice`]).
+    pub fn hit_test(&self, pos: Point, data: &PieChartData) -> Option<HitInfo> {
+        let collapsed = self.collapsed.as_ref();
+        let data = collapsed.map_or(data, |collapsed| &collapsed.data);
+        let index = self.slice_at(pos, data)?;
+        let (center, inner_radius, outer_radius) = self.ring?;
+        let (start_angle, sweep_angle) = self.slice_angles(data)[index];
+        let mid_angle = start_angle + sweep_angle * 0.5;
+        let offset = self.explode_offsets(data, outer_radius)[index];
+        Some(HitInfo {
+            series: 0,
+            index: self.data_index(index)?,
+            position: center
+                + offset
+                + Vec2::from_angle(mid_angle) * (inner_radius + outer_radius) * 0.5,
+            distance: 0.,
+        })
+    }
+
+    /// The index of the slice under `pos`, if any.
+    ///
+    /// The hovered slice is tested where it would be without being pulled out, so it doesn't
+    /// flicker in and out of the hover when the mouse is near its edge.
+    fn slice_at(&self, pos: Point, data: &PieChartData) -> Option<usize> {
+        let (center, inner_radius, outer_radius) = self.ring?;
+        let explode_offsets = self.explode_offsets_for(data, outer_radius, None);
+        self.slice_angles(data).into_iter().enumerate().position(
+            |(idx, (start_angle, sweep_angle))| {
+                let segment = CircleSegment {
+                    center: center + explode_offsets[idx],
+                    outer_radius,
+                    inner_radius,
+                    start_angle,
+                    sweep_angle,
+                };
+                segment_contains(&segment, pos)
+            },
+        )
+    }
+
+    /// The `(start angle, sweep angle)` of each slice, as far as any transition from the old
+    /// counts has got.
+    fn current_angles(&self, data: &PieChartData) -> Vec<(f64, f64)> {
+        let counts: Vec<usize> = data.counts.iter().copied().collect();
+        let mut angles = slice_angles(
+            &counts,
+            self.start_angle,
+            self.sweep_direction,
+            self.sort_slices,
+        );
+        if let (Some(transition), Some(from)) = (&self.transition, &self.transition_from) {
+            interpolate_angles(from, &mut angles, transition.progress());
         }
+        angles
+    }
+
+    /// The `(start angle, sweep angle)` of each slice, as far as the animations have got.
+    fn slice_angles(&self, data: &PieChartData) -> Vec<(f64, f64)> {
+        let progress = animation::progress(self.entrance.as_ref());
+        self.current_angles(data)
+            .into_iter()
+            .map(|(start_angle, sweep_angle)| {
+                (
+                    self.start_angle + (start_angle - self.start_angle) * progress,
+                    sweep_angle * progress,
+                )
+            })
+            .collect()
+    }
+
+    /// How far each slice is moved from the center of the pie, when the pie has radius
+    /// `outer_radius`.
+    fn explode_offsets(&self, data: &PieChartData, outer_radius: f64) -> Vec<Vec2> {
+        self.explode_offsets_for(data, outer_radius, self.hovered)
     }
 
-    pub fn rebuild_if_needed(&mut self, ctx: &mut PaintCtx, env: &Env) {
-        self.title_layout.rebuild_if_needed(ctx.text(), env);
-        self.key_title_layout.rebuild_if_needed(ctx.text(), env);
-        for layout in self.category_layouts.iter_mut() {
-            layout.rebuild_if_needed(ctx.text(), env);
+    /// How far each slice is moved from the center of the pie, if the slice under the mouse is
+    /// `hovered`. Slices are pulled out by the data, or by being under the mouse.
+    fn explode_offsets_for(
+        &self,
+        data: &PieChartData,
+        outer_radius: f64,
+        hovered: Option<usize>,
+    ) -> Vec<Vec2> {
+        self.slice_angles(data)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, (start_angle, sweep_angle))| {
+                let exploded = data.exploded.get(idx).copied().unwrap_or(false);
+                if exploded || hovered == Some(idx) {
+                    let mid_angle = start_angle + sweep_angle * 0.5;
+                    Vec2::from_angle(mid_angle) * outer_radius * EXPLODE_OFFSET
+                } else {
+                    Vec2::ZERO
+                }
+            })
+            .collect()
+    }
+
+    /// Draw the chart. Call `lay_out` first.
+    fn draw(&mut self, ctx: &mut Canvas, data: &PieChartData, env: &Env) {
+        // we only have a ring if there is something to divide up.
+        let (center, inner_radius, outer_radius) = match self.ring {
+            Some(ring) => ring,
+            None => {
+                self.empty_state.draw(ctx);
+                return;
+            }
+        };
+        self.title.draw(ctx, env);
+        let size_scale = env.get(theme::SIZE_SCALE);
+        let collapsed = self.collapsed.clone();
+        let data = collapsed.as_ref().map_or(data, |collapsed| &collapsed.data);
+
+        // Pie
+        let explode_offsets = self.explode_offsets(data, outer_radius);
+        let slice_angles = self.slice_angles(data);
+        for (idx, (start_angle, sweep_angle)) in slice_angles.iter().copied().enumerate() {
+            ctx.fill(
+                CircleSegment {
+                    center: center + explode_offsets[idx],
+                    outer_radius,
+                    inner_radius,
+                    start_angle,
+                    sweep_angle,
+                },
+                &self.slice_colors.resolve(idx, env),
+            );
+        }
+
+        // focus ring
+        if let Some(keyboard) = self.keyboard.as_ref() {
+            let highlighted = keyboard.highlighted().map(|highlighted| highlighted.index);
+            if let Some(idx) = highlighted.filter(|idx| *idx < slice_angles.len()) {
+                let (start_angle, sweep_angle) = slice_angles[idx];
+                let segment = CircleSegment {
+                    center: center + explode_offsets[idx],
+                    outer_radius,
+                    inner_radius,
+                    start_angle,
+                    sweep_angle,
+                };
+                keyboard.draw_ring(ctx, env, segment);
+            }
+        }
+
+        // Slice labels
+        if let Some(layouts) = self.slice_label_layouts.as_mut() {
+            let leader_brush = ctx.solid_brush(env.get(LABEL_COLOR));
+            let label_radius = (inner_radius + outer_radius) * 0.5;
+            for (idx, (count, layout)) in data
+                .counts
+                .iter()
+                .copied()
+                .zip(layouts.iter_mut())
+                .enumerate()
+            {
+                let (start_angle, sweep_angle) = slice_angles[idx];
+                let mid_angle = start_angle + sweep_angle * 0.5;
+                if count == 0 {
+                    continue;
+                }
+                // labels move with their slice.
+                let center = center + explode_offsets[idx];
+                let direction = Vec2::from_angle(mid_angle);
+                let label_size = layout.size();
+                // The space along the arc at the label radius, and across the ring.
+                let fits = sweep_angle * label_radius >= label_size.width
+                    && outer_radius - inner_radius >= label_size.height;
+                if fits {
+                    let label_center = center + direction * label_radius;
+                    ctx.draw_layout(layout, label_center - label_size.to_vec2() * 0.5);
+                } else {
+                    // too thin: draw the label outside with a line to the slice.
+                    let edge = center + direction * outer_radius;
+                    let anchor = center + direction * (outer_radius + LEADER_LENGTH * size_scale);
+                    ctx.stroke(Line::new(edge, anchor), &leader_brush, size_scale);
+                    let x = if direction.x >= 0. {
+                        anchor.x
+                    } else {
+                        anchor.x - label_size.width
+                    };
+                    ctx.draw_layout(layout, Point::new(x, anchor.y - label_size.height * 0.5));
+                }
+            }
         }
+
+        // Key
+        let slice_colors = &self.slice_colors;
+        self.legend
+            .draw(ctx, env, |idx| slice_colors.resolve(idx, env));
     }
 }
 
 impl Widget<PieChartData> for PieChart {
-    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut PieChartData, env: &Env) {}
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut PieChartData, env: &Env) {
+        // the table has every category, not just the slices drawn.
+        if let Some(context_menu) = self.context_menu.as_mut() {
+            let items = MenuItems {
+                data: true,
+                ..MenuItems::default()
+            };
+            context_menu.event(ctx, event, items, || data.csv_table());
+        }
+        if let Event::Command(cmd) = event {
+            if cmd.is(COPY_CHART_DATA) {
+                copy_csv(data.csv_table());
+                ctx.set_handled();
+            }
+        }
+        let collapsed = self.collapsed.clone();
+        let data = collapsed
+            .as_ref()
+            .map_or(&*data, |collapsed| &collapsed.data);
+        if let Some(entrance) = self.entrance.as_mut() {
+            entrance.event(ctx, event);
+        }
+        if let Some(transition) = self.transition.as_mut() {
+            transition.event(ctx, event);
+        }
+        if let Some(keyboard) = self.keyboard.as_mut() {
+            // The keyboard moves between the slices drawn, but the app uses indices into the data.
+            match (event, &collapsed) {
+                (Event::Command(cmd), Some(collapsed)) if cmd.is(HIGHLIGHT_POINT) => {
+                    let highlighted = cmd
+                        .get_unchecked(HIGHLIGHT_POINT)
+                        .map(|point| PointSelected::new(0, collapsed.slice_index(point.index)));
+                    keyboard.highlight(ctx, highlighted);
+                    ctx.set_handled();
+                }
+                (Event::KeyDown(key), Some(_)) if key.key == KbKey::Enter && ctx.is_focused() => {
+                    if let Some(highlighted) = keyboard.highlighted() {
+                        self.select(ctx, highlighted.index);
+                        ctx.set_handled();
+                    }
+                }
+                _ => keyboard.event(ctx, event, &[data.counts.len()]),
+            }
+        }
+        match event {
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Left => {
+                if let Some(idx) = self.slice_at(mouse.pos, data) {
+                    self.select(ctx, idx);
+                    ctx.set_handled();
+                }
+            }
+            Event::MouseMove(mouse) if self.explode_on_hover => {
+                let hovered = self.slice_at(mouse.pos, data);
+                if hovered != self.hovered {
+                    self.hovered = hovered;
+                    ctx.request_paint_rect(self.pie_bounds);
+                }
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_IMAGE) => {
+                copy_image(ctx, env, |ctx| self.draw(ctx, data, env));
+            }
+            Event::Command(cmd) if cmd.is(EXPORT_IMAGE) => {
+                let scale = *cmd.get_unchecked(EXPORT_IMAGE);
+                export_image(ctx, scale, env, |ctx| self.draw(ctx, data, env));
+            }
+            _ => (),
+        }
+    }
 
     fn lifecycle(
         &mut self,
@@ -63,15 +736,17 @@ impl Widget<PieChartData> for PieChart {
         data: &PieChartData,
         env: &Env,
     ) {
+        if let Some(entrance) = self.entrance.as_mut() {
+            entrance.lifecycle(ctx, event);
+        }
+        if let Some(keyboard) = self.keyboard.as_mut() {
+            keyboard.lifecycle(ctx, event);
+        }
         match event {
-            LifeCycle::WidgetAdded => {
-                self.title_layout.set_text(data.title.clone());
-                self.category_layouts = data
-                    .category_labels
-                    .iter()
-                    .cloned()
-                    .map(|text| TextLayout::from_text(text))
-                    .collect()
+            LifeCycle::WidgetAdded => self.set_data(data),
+            LifeCycle::HotChanged(false) if self.hovered.is_some() => {
+                self.hovered = None;
+                ctx.request_paint_rect(self.pie_bounds);
             }
             _ => (),
         }
@@ -85,27 +760,56 @@ impl Widget<PieChartData> for PieChart {
         env: &Env,
     ) {
         if !Data::same(&old_data.title, &data.title) {
-            self.title_layout.set_text(data.title.clone());
-        }
-        self.title_layout.needs_rebuild_after_update(ctx);
-        self.key_title_layout.needs_rebuild_after_update(ctx);
-        if !Data::same(&old_data.category_labels, &data.category_labels) {
-            // If we don't have enough labels add some on the end.
-            //
-            // Note that we might have too many. That is why we only take the first
-            // `category_labels.len()` items during paint.
-            if self.category_layouts.len() < data.category_labels.len() {
-                self.category_layouts.extend(
-                    iter::repeat(TextLayout::new())
-                        .take(data.category_labels.len() - self.category_layouts.len()),
-                );
-            }
-            for (label_text, layout) in izip!(&data.category_labels, &mut self.category_layouts) {
-                layout.set_text(label_text.clone());
+            self.title.set_text(data.title.clone());
+        }
+        let old_collapsed = self.collapsed.clone();
+        if !old_data.same(data) {
+            self.collapsed = self.collapse(data);
+        }
+        let old_shown = old_collapsed
+            .as_ref()
+            .map_or(old_data, |collapsed| &collapsed.data);
+        let collapsed = self.collapsed.clone();
+        let shown = collapsed.as_ref().map_or(data, |collapsed| &collapsed.data);
+        if self.title.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !Data::same(&old_data.category_labels, &data.category_labels)
+            || ((self.key_values.is_some() || self.other_slice.is_some())
+                && !Data::same(&old_data.counts, &data.counts))
+        {
+            let labels = self.key_labels(shown);
+            self.legend.set_labels(labels);
+        }
+        if self.legend.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !Data::same(&old_data.counts, &data.counts) {
+            if self.transition.is_some() {
+                // there's nothing to move from if the pie was empty, or the slices don't match up.
+                let old_total: usize = old_shown.counts.iter().sum();
+                self.transition_from =
+                    if old_total > 0 && old_shown.counts.len() == shown.counts.len() {
+                        Some(self.current_angles(old_shown))
+                    } else {
+                        None
+                    };
+                self.transition.as_mut().unwrap().start(ctx);
             }
+            self.slice_label_layouts = None;
+            self.hovered = None;
+            ctx.request_layout();
         }
-        for layout in self.category_layouts.iter_mut() {
-            layout.needs_rebuild_after_update(ctx);
+        if !Data::same(&old_data.exploded, &data.exploded) {
+            // the pie might need to shrink to leave room for the exploded slices.
+            ctx.request_layout();
+        }
+        if let Some(layouts) = self.slice_label_layouts.as_mut() {
+            for layout in layouts.iter_mut() {
+                if layout.needs_rebuild_after_update(ctx) {
+                    ctx.request_layout();
+                }
+            }
         }
     }
 
@@ -116,107 +820,230 @@ impl Widget<PieChartData> for PieChart {
         data: &PieChartData,
         env: &Env,
     ) -> Size {
-        bc.constrain((f64::INFINITY, f64::INFINITY))
+        let size = chart_size(bc, self.preferred_size);
+        self.lay_out(ctx.text(), size, data, env);
+        size
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &PieChartData, env: &Env) {
-        self.rebuild_if_needed(ctx, env);
-        let bg_brush = ctx.solid_brush(Color::hlc(0.0, 90.0, 0.0));
-        let axes_brush = ctx.solid_brush(Color::hlc(0.0, 60.0, 0.0));
-        let text_brush = ctx.solid_brush(self.key_stroke_color.resolve(env));
-        let bar_brush = ctx.solid_brush(Color::hlc(0.0, 50.0, 50.0));
-        let size = ctx.size();
-        let bounds = size.to_rect();
-        let total: usize = data.counts.iter().copied().sum();
-        let categories_count = data.category_labels.len();
+        self.draw(&mut Canvas::new(ctx, env), data, env);
+    }
+}
 
-        // background & title
-        let title_width = self.title_layout.size().width;
-        self.title_layout
-            .draw(ctx, ((size.width - title_width) * 0.5, 40.0));
+impl OffscreenChart<PieChartData> for PieChart {
+    fn draw_offscreen(&mut self, ctx: &mut Canvas, data: &PieChartData, env: &Env) {
+        self.entrance = None;
+        self.set_data(data);
+        let size = ctx.size();
+        self.lay_out(ctx.text(), size, data, env);
+        self.draw(ctx, data, env);
+    }
+}
 
-        // Pie
-        let pie_area = square(
-            bounds
-                // the left 60% of the available area
-                .inset((0.0, -40.0, -bounds.width() * 0.4, 0.0))
-                // with a 10 px margin
-                .inset(-10.0),
-        );
-        let mut start_angle = 0.0;
-        for (idx, count) in data.counts.iter().copied().enumerate() {
-            let sweep_angle = count as f64 / total as f64 * 2.0 * PI;
-            ctx.fill(
-                CircleSegment {
-                    center: pie_area.center(),
-                    outer_radius: pie_area.width() * 0.5,
-                    inner_radius: 0.0,
-                    start_angle,
-                    sweep_angle,
-                },
-                &new_color(idx),
-            );
-            start_angle += sweep_angle;
+/// The `(start angle, sweep angle)` of the slice for each count, in the same order as `counts`.
+///
+/// The sweep angle is always positive, so going anticlockwise each slice starts where the next
+/// one ends.
+fn slice_angles(
+    counts: &[usize],
+    start_angle: f64,
+    sweep_direction: SweepDirection,
+    sort_slices: bool,
+) -> Vec<(f64, f64)> {
+    let total: usize = counts.iter().sum();
+    // the order the slices go round the pie.
+    let mut order: Vec<usize> = (0..counts.len()).collect();
+    if sort_slices {
+        // a stable sort, so equal slices stay in order.
+        order.sort_by(|a, b| counts[*b].cmp(&counts[*a]));
+    }
+    let mut angles = vec![(0.0, 0.0); counts.len()];
+    let mut angle = start_angle;
+    for idx in order {
+        let sweep_angle = counts[idx] as f64 / total as f64 * 2.0 * PI;
+        angles[idx] = match sweep_direction {
+            SweepDirection::Clockwise => (angle, sweep_angle),
+            SweepDirection::Anticlockwise => (angle - sweep_angle, sweep_angle),
+        };
+        match sweep_direction {
+            SweepDirection::Clockwise => angle += sweep_angle,
+            SweepDirection::Anticlockwise => angle -= sweep_angle,
         }
+    }
+    angles
+}
 
-        // Key
-        const COLOR_SIZE: f64 = 12.0;
-        // last 40% of the width
-        let key_bounds = bounds.inset((-bounds.width() * 0.6, 0.0, 0.0, 0.0));
-        // Calculate some stuff about label text layout:
-        let key_margin = self.key_margin.resolve(env);
-        let mut max_color_label_width = 0.;
-        let mut total_label_height = 0.;
-        for layout in self.category_layouts.iter().take(categories_count) {
-            let size = layout.size();
-            let new_width = size.width + size.height + 3. * key_margin; // m color m label m
-            if new_width > max_color_label_width {
-                max_color_label_width = new_width;
-            }
-            total_label_height += size.height;
-        }
-        let height = total_label_height
-            + self.key_title_layout.size().height
-            + (categories_count as f64 + 2.0) * key_margin;
-
-        let key_width =
-            (self.key_title_layout.size().width + 2.0 * key_margin).max(max_color_label_width);
-
-        let key_bounds = Rect::from_center_size(key_bounds.center(), (key_width, height));
-        ctx.stroke(key_bounds, &text_brush, 2.0);
-        self.key_title_layout.draw(
-            ctx,
-            (
-                key_bounds.x0 + (key_bounds.width() - self.key_title_layout.size().width) * 0.5,
-                key_bounds.y0 + key_margin,
-            ),
-        );
-        let mut next_loc = key_bounds.y0 + key_margin * 2. + self.key_title_layout.size().height;
-        // important: only take the right amount of layouts here.
-        for (idx, layout) in self
-            .category_layouts
+/// The slices of a pie, with the small ones put together in an other slice.
+#[derive(Debug)]
+struct CollapsedSlices {
+    /// The slices to draw, with the other slice last.
+    data: PieChartData,
+    /// The index in the chart's data of each slice before the other slice.
+    sources: Vec<usize>,
+    /// The indices in the chart's data of the categories in the other slice.
+    other: Vec<usize>,
+}
+
+impl CollapsedSlices {
+    /// The index in the chart's data of slice `idx`, or `None` for the other slice.
+    fn data_index(&self, idx: usize) -> Option<usize> {
+        self.sources.get(idx).copied()
+    }
+
+    /// The slice that category `data_idx` of the chart's data is drawn in.
+    fn slice_index(&self, data_idx: usize) -> usize {
+        self.sources
             .iter()
-            .take(categories_count)
-            .enumerate()
-        {
-            let height = layout.size().height;
-            let color_rect = Rect::new(
-                key_bounds.x0 + key_margin,
-                next_loc,
-                // use the text's height to make the color square match the text.
-                key_bounds.x0 + key_margin + height,
-                next_loc + height,
-            );
-            ctx.fill(color_rect, &new_color(idx));
-            ctx.stroke(color_rect, &text_brush, 1.0);
-            layout.draw(
-                ctx,
-                (
-                    key_bounds.x0 + key_margin + height + key_margin, // m color m label
-                    next_loc,
-                ),
-            );
-            next_loc += key_margin + height;
+            .position(|source| *source == data_idx)
+            .unwrap_or(self.sources.len())
+    }
+}
+
+/// `data` with the slices smaller than `threshold` of the total put together in one slice called
+/// `label`, after the others. Returns `None` if fewer than two slices are that small, as there is
+/// nothing to put together.
+fn collapse_small_slices(
+    data: &PieChartData,
+    threshold: f64,
+    label: &ArcStr,
+) -> Option<CollapsedSlices> {
+    let total: usize = data.counts.iter().sum();
+    let is_small = |count: usize| (count as f64) < threshold * total as f64;
+    if total == 0 || data.counts.iter().filter(|count| is_small(**count)).count() < 2 {
+        return None;
+    }
+    let mut collapsed = CollapsedSlices {
+        data: PieChartData {
+            title: data.title.clone(),
+            category_labels: Vector::new(),
+            counts: Vector::new(),
+            exploded: Vector::new(),
+        },
+        sources: Vec::new(),
+        other: Vec::new(),
+    };
+    let mut other = 0;
+    for (idx, count) in data.counts.iter().copied().enumerate() {
+        if is_small(count) {
+            other += count;
+            collapsed.other.push(idx);
+            continue;
         }
+        let category = data.category_labels.get(idx).cloned();
+        let slices = &mut collapsed.data;
+        slices
+            .category_labels
+            .push_back(category.unwrap_or_else(|| "".into()));
+        slices.counts.push_back(count);
+        slices
+            .exploded
+            .push_back(data.exploded.get(idx).copied().unwrap_or(false));
+        collapsed.sources.push(idx);
+    }
+    let slices = &mut collapsed.data;
+    slices.category_labels.push_back(label.clone());
+    slices.counts.push_back(other);
+    slices.exploded.push_back(false);
+    Some(collapsed)
+}
+
+/// Move each of `to` back towards the matching angles in `from`, so that they are only `t` (from
+/// 0 to 1) of the way there.
+fn interpolate_angles(from: &[(f64, f64)], to: &mut [(f64, f64)], t: f64) {
+    for ((from_start, from_sweep), (start, sweep)) in from.iter().copied().zip(to.iter_mut()) {
+        *start = from_start + (*start - from_start) * t;
+        *sweep = from_sweep + (*sweep - from_sweep) * t;
     }
 }
+
+/// Whether `pos` is inside `segment`. The segment is assumed to go clockwise from `start_angle`.
+fn segment_contains(segment: &CircleSegment, pos: Point) -> bool {
+    let offset = pos - segment.center;
+    let radius = offset.hypot();
+    if radius < segment.inner_radius || radius > segment.outer_radius {
+        return false;
+    }
+    let angle = (offset.atan2() - segment.start_angle).rem_euclid(2.0 * PI);
+    angle < segment.sweep_angle
+}
+
+#[test]
+fn test_segment_contains() {
+    let segment = CircleSegment {
+        center: Point::new(10., 10.),
+        outer_radius: 10.,
+        inner_radius: 2.,
+        start_angle: 1.5 * PI,
+        sweep_angle: PI,
+    };
+    // the segment covers the right hand half of the ring, crossing the x axis.
+    assert!(segment_contains(&segment, Point::new(15., 10.)));
+    assert!(segment_contains(&segment, Point::new(15., 8.)));
+    assert!(!segment_contains(&segment, Point::new(5., 10.)));
+    // in the hole, and outside the ring.
+    assert!(!segment_contains(&segment, Point::new(11., 10.)));
+    assert!(!segment_contains(&segment, Point::new(21., 10.)));
+}
+
+#[test]
+fn test_slice_angles() {
+    let counts = [1, 3];
+    let clockwise = slice_angles(&counts, 0.0, SweepDirection::Clockwise, false);
+    assert_eq!(clockwise, vec![(0.0, 0.5 * PI), (0.5 * PI, 1.5 * PI)]);
+    // the biggest slice goes first.
+    let sorted = slice_angles(&counts, 0.0, SweepDirection::Clockwise, true);
+    assert_eq!(sorted, vec![(1.5 * PI, 0.5 * PI), (0.0, 1.5 * PI)]);
+    let anticlockwise = slice_angles(&counts, 0.0, SweepDirection::Anticlockwise, false);
+    assert_eq!(
+        anticlockwise,
+        vec![(-0.5 * PI, 0.5 * PI), (-2.0 * PI, 1.5 * PI)]
+    );
+}
+
+#[test]
+fn test_interpolate_angles() {
+    let from = [(0.0, PI), (PI, PI)];
+    let mut angles = [(0.0, 0.5 * PI), (0.5 * PI, 1.5 * PI)];
+    interpolate_angles(&from, &mut angles, 0.5);
+    assert_eq!(angles, [(0.0, 0.75 * PI), (0.75 * PI, 1.25 * PI)]);
+    // at the end of the transition, the slices are where the counts put them.
+    let mut angles = [(0.0, 0.5 * PI), (0.5 * PI, 1.5 * PI)];
+    interpolate_angles(&from, &mut angles, 1.0);
+    assert_eq!(angles, [(0.0, 0.5 * PI), (0.5 * PI, 1.5 * PI)]);
+}
+
+#[test]
+fn test_collapse_small_slices() {
+    let data = PieChartData {
+        title: "".into(),
+        category_labels: vec!["a".into(), "b".into(), "c".into(), "d".into()].into(),
+        counts: vec![50, 1, 45, 4].into(),
+        exploded: vec![false, true, true].into(),
+    };
+    let collapsed = collapse_small_slices(&data, 0.05, &"Other".into()).unwrap();
+    let slices = &collapsed.data;
+    let labels: Vec<&str> = slices.category_labels.iter().map(|l| &**l).collect();
+    assert_eq!(labels, ["a", "c", "Other"]);
+    assert_eq!(slices.counts, vec![50, 45, 5].into());
+    assert_eq!(slices.exploded, vec![false, true, false].into());
+    assert_eq!(collapsed.other, [1, 3]);
+    // one small slice is left as it is.
+    assert!(collapse_small_slices(&data, 0.02, &"Other".into()).is_none());
+}
+
+#[test]
+fn test_collapsed_indices() {
+    let data = PieChartData {
+        title: "".into(),
+        category_labels: vec!["a".into(), "b".into(), "c".into(), "d".into()].into(),
+        counts: vec![50, 1, 45, 4].into(),
+        exploded: Vector::new(),
+    };
+    let collapsed = collapse_small_slices(&data, 0.05, &"Other".into()).unwrap();
+    // "c" is drawn second, but is the third category in the data.
+    assert_eq!(collapsed.data_index(0), Some(0));
+    assert_eq!(collapsed.data_index(1), Some(2));
+    assert_eq!(collapsed.data_index(2), None);
+    assert_eq!(collapsed.slice_index(2), 1);
+    assert_eq!(collapsed.slice_index(3), 2);
+}
@@ -0,0 +1,244 @@
+//! Quantile-quantile plots, for comparing a sample with the normal distribution or with another
+//! sample.
+use druid::{
+    im::Vector, ArcStr, BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, Size, UpdateCtx, Widget,
+};
+use druid_lens_compose::ComposeLens;
+
+use crate::{
+    canvas::Canvas,
+    offscreen::OffscreenChart,
+    scatter::{ScatterPlot, ScatterPlotData},
+    stats::{self, QuantileMethod},
+    SeriesData,
+};
+
+/// The data for a quantile-quantile plot. The values can be any [`SeriesData`], e.g. a
+/// `Vector<i32>` or an `Arc<Vec<f64>>`.
+#[derive(Debug, Clone, Data, ComposeLens)]
+pub struct QqPlotData<S = Vector<f64>> {
+    pub title: ArcStr,
+    pub x_axis_label: ArcStr,
+    /// The values to compare, whose quantiles go up the y axis.
+    pub sample: S,
+    /// Values to compare `sample` with, whose quantiles go along the x axis. If `None`, `sample`
+    /// is compared with the standard normal distribution.
+    pub reference: Option<S>,
+}
+
+/// A quantile-quantile plot: each quantile of a sample against the same quantile of the normal
+/// distribution (or of another sample). If the sample follows the distribution, the points lie
+/// along the reference line.
+///
+/// Against the normal distribution, the reference line goes through the quartiles, so it isn't
+/// pulled about by outliers. Against another sample, it is the diagonal `y = x`.
+///
+/// This is a [`ScatterPlot`] of the quantiles. They are recalculated only when the values change.
+pub struct QqPlot {
+    inner: ScatterPlot,
+    quantile_method: QuantileMethod,
+    // retained state
+    converted: Option<ScatterPlotData>,
+}
+
+impl QqPlot {
+    /// Wrap a scatter plot (so it can be configured first).
+    pub fn new(inner: ScatterPlot) -> Self {
+        QqPlot {
+            inner,
+            quantile_method: QuantileMethod::Type7,
+            converted: None,
+        }
+    }
+
+    /// Choose how quantiles are estimated from the values. The default is `QuantileMethod::Type7`,
+    /// which matches R's `qqplot` and `qqline`.
+    pub fn with_quantile_method(mut self, method: QuantileMethod) -> Self {
+        self.quantile_method = method;
+        self
+    }
+
+    /// A short description of the plot for screen readers. See [`ScatterPlot::describe`].
+    pub fn describe<S: SeriesData>(&self, data: &QqPlotData<S>) -> String {
+        self.inner.describe(&self.convert(data).0)
+    }
+
+    /// The quantiles to plot, and the `(slope, intercept)` of the reference line.
+    fn convert<S: SeriesData>(
+        &self,
+        data: &QqPlotData<S>,
+    ) -> (ScatterPlotData, Option<(f64, f64)>) {
+        let (pairs, reference_line) = quantile_pairs(
+            &sorted(&data.sample),
+            data.reference.as_ref().map(sorted).as_deref(),
+            self.quantile_method,
+        );
+        let converted = ScatterPlotData {
+            title: data.title.clone(),
+            x_axis_label: data.x_axis_label.clone(),
+            x: pairs.iter().map(|(x, _)| *x).collect(),
+            y: pairs.iter().map(|(_, y)| *y).collect(),
+            color_values: None,
+            size_values: None,
+        };
+        (converted, reference_line)
+    }
+
+    fn rebuild<S: SeriesData>(&mut self, data: &QqPlotData<S>) -> ScatterPlotData {
+        let (converted, reference_line) = self.convert(data);
+        self.inner.set_reference_line(reference_line);
+        converted
+    }
+}
+
+/// The finite values, sorted.
+fn sorted<S: SeriesData>(values: &S) -> Vec<f64> {
+    let mut sorted: Vec<f64> = values.values().filter(|v| v.is_finite()).collect();
+    sorted.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+    sorted
+}
+
+/// The `(x, y)` quantile pairs comparing `sample` with `reference` (or the standard normal
+/// distribution), both sorted, and the `(slope, intercept)` of the reference line.
+fn quantile_pairs(
+    sample: &[f64],
+    reference: Option<&[f64]>,
+    method: QuantileMethod,
+) -> (Vec<(f64, f64)>, Option<(f64, f64)>) {
+    match reference {
+        None => {
+            let pairs: Vec<(f64, f64)> = stats::plotting_positions(sample.len())
+                .into_iter()
+                .map(stats::normal_quantile)
+                .zip(sample.iter().copied())
+                .collect();
+            // the line through the quartiles.
+            let line = match (
+                stats::quantile(sample, 0.25, method),
+                stats::quantile(sample, 0.75, method),
+            ) {
+                (Some(q1), Some(q3)) => {
+                    let (x1, x3) = (stats::normal_quantile(0.25), stats::normal_quantile(0.75));
+                    let slope = (q3 - q1) / (x3 - x1);
+                    Some((slope, q1 - slope * x1))
+                }
+                _ => None,
+            };
+            (pairs, line)
+        }
+        Some(reference) => {
+            // as many points as the smaller sample, spread evenly through both.
+            let n = sample.len().min(reference.len());
+            let pairs = (0..n)
+                .filter_map(|idx| {
+                    let p = if n > 1 {
+                        idx as f64 / (n - 1) as f64
+                    } else {
+                        0.5
+                    };
+                    Some((
+                        stats::quantile(reference, p, method)?,
+                        stats::quantile(sample, p, method)?,
+                    ))
+                })
+                .collect();
+            (pairs, Some((1., 0.)))
+        }
+    }
+}
+
+impl<S: SeriesData> Widget<QqPlotData<S>> for QqPlot {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut QqPlotData<S>, env: &Env) {
+        if let Some(converted) = self.converted.as_ref() {
+            // the plot can't change the raw data.
+            let mut converted = converted.clone();
+            self.inner.event(ctx, event, &mut converted, env);
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &QqPlotData<S>,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            self.converted = Some(self.rebuild(data));
+        }
+        if let Some(converted) = self.converted.as_ref() {
+            self.inner.lifecycle(ctx, event, converted, env);
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &QqPlotData<S>,
+        data: &QqPlotData<S>,
+        env: &Env,
+    ) {
+        let old_converted = match self.converted.take() {
+            Some(old_converted) => old_converted,
+            None => return,
+        };
+        let converted =
+            if !old_data.sample.same(&data.sample) || !old_data.reference.same(&data.reference) {
+                self.rebuild(data)
+            } else {
+                // keep the quantiles we already have.
+                ScatterPlotData {
+                    title: data.title.clone(),
+                    x_axis_label: data.x_axis_label.clone(),
+                    ..old_converted.clone()
+                }
+            };
+        self.inner.update(ctx, &old_converted, &converted, env);
+        self.converted = Some(converted);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _data: &QqPlotData<S>,
+        env: &Env,
+    ) -> Size {
+        match self.converted.as_ref() {
+            Some(converted) => self.inner.layout(ctx, bc, converted, env),
+            None => bc.min(),
+        }
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &QqPlotData<S>, env: &Env) {
+        if let Some(converted) = self.converted.as_ref() {
+            self.inner.paint(ctx, converted, env);
+        }
+    }
+}
+
+impl<S: SeriesData> OffscreenChart<QqPlotData<S>> for QqPlot {
+    fn draw_offscreen(&mut self, ctx: &mut Canvas, data: &QqPlotData<S>, env: &Env) {
+        let converted = self.rebuild(data);
+        self.inner.draw_offscreen(ctx, &converted, env);
+        self.converted = Some(converted);
+    }
+}
+
+#[test]
+fn test_quantile_pairs() {
+    // a symmetric sample against the normal distribution.
+    let sample = [-2., -1., 0., 1., 2.];
+    let (pairs, line) = quantile_pairs(&sample, None, QuantileMethod::Type7);
+    assert_eq!(pairs.len(), 5);
+    assert_eq!(pairs[2], (0., 0.));
+    assert!(pairs[0].0 < 0. && pairs[4].0 > 0.);
+    let (slope, intercept) = line.unwrap();
+    assert!(slope > 0. && intercept.abs() < 1e-12);
+    // against a bigger sample, the bigger one is interpolated.
+    let reference = [0., 1., 2., 3., 4.];
+    let (pairs, line) = quantile_pairs(&[10., 20., 30.], Some(&reference), QuantileMethod::Type7);
+    assert_eq!(pairs, vec![(0., 10.), (2., 20.), (4., 30.)]);
+    assert_eq!(line, Some((1., 0.)));
+}
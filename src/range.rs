@@ -59,6 +59,43 @@ impl Range {
         self.max - self.min
     }
 
+    /// Scale the range by `factor` about `center`, so `center` stays in the same relative position.
+    ///
+    /// A factor less than 1 zooms in, greater than 1 zooms out. If the result would not be a valid
+    /// range the range is returned unchanged.
+    pub fn zoom(self, center: f64, factor: f64) -> Self {
+        let min = center - (center - self.min) * factor;
+        let max = center + (self.max - center) * factor;
+        if min.is_finite() && max.is_finite() && min < max {
+            Range { min, max }
+        } else {
+            self
+        }
+    }
+
+    /// Shift the range by `delta`.
+    pub fn translate(self, delta: f64) -> Self {
+        let (min, max) = (self.min + delta, self.max + delta);
+        if min.is_finite() && max.is_finite() {
+            Range { min, max }
+        } else {
+            self
+        }
+    }
+
+    /// Grow the range by `fraction` of its size at each end, e.g. `0.05` for 5%, so the data
+    /// doesn't sit right on the edges of the graph.
+    ///
+    /// A range with no size (or a negative fraction) is returned unchanged.
+    pub fn pad(self, fraction: f64) -> Self {
+        let padding = self.size() * fraction;
+        if padding > 0. && padding.is_finite() {
+            Range::new(self.min - padding, self.max + padding)
+        } else {
+            self
+        }
+    }
+
     /// Returns true if the range changed.
     pub fn extend_to(&mut self, val: f64) -> bool {
         // NaN will be ignored.
@@ -89,6 +126,17 @@ impl Range {
         }
         Range::new(min, max)
     }
+
+    /// The smallest range containing all the finite values of `iter`, or `None` if there aren't
+    /// any (e.g. before any data has arrived).
+    pub fn from_finite<I>(iter: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = f64>,
+    {
+        let mut finite = iter.into_iter().filter(|v| v.is_finite()).peekable();
+        finite.peek()?;
+        Some(Range::from_iter(finite))
+    }
 }
 
 impl From<(f64, f64)> for Range {
@@ -108,3 +156,33 @@ impl From<std::ops::Range<f64>> for Range {
         Self::new(range.start, range.end)
     }
 }
+
+#[test]
+fn test_zoom() {
+    let range = Range::new(0., 10.);
+    assert_eq!(range.zoom(5., 0.5), Range::new(2.5, 7.5));
+    assert_eq!(range.zoom(0., 2.), Range::new(0., 20.));
+    // zooming to nothing is refused
+    assert_eq!(range.zoom(5., 0.), range);
+}
+
+#[test]
+fn test_from_finite() {
+    assert_eq!(Range::from_finite(vec![]), None);
+    assert_eq!(Range::from_finite(vec![f64::NAN, f64::INFINITY]), None);
+    assert_eq!(
+        Range::from_finite(vec![3., f64::NAN, -1.]),
+        Some(Range::new(-1., 3.))
+    );
+}
+
+#[test]
+fn test_translate() {
+    assert_eq!(Range::new(0., 10.).translate(-2.), Range::new(-2., 8.));
+}
+
+#[test]
+fn test_pad() {
+    assert_eq!(Range::new(0., 10.).pad(0.1), Range::new(-1., 11.));
+    assert_eq!(Range::new(3., 3.).pad(0.1), Range::new(3., 3.));
+}
@@ -0,0 +1,359 @@
+use druid::{
+    im::Vector, kurbo::CircleSegment, piet::PietText, ArcStr, BoxConstraints, Color, Data, Env,
+    Event, EventCtx, KeyOrValue, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, RenderContext, Size,
+    UpdateCtx, Widget,
+};
+use druid_lens_compose::ComposeLens;
+
+use crate::{
+    axes::PolarScale,
+    canvas::Canvas,
+    chart_size,
+    commands::{COPY_CHART_DATA, COPY_CHART_IMAGE, EXPORT_IMAGE, TOGGLE_CHART_GRID},
+    context_menu::{copy_csv, copy_image, csv_cell, ChartMenu, MenuItems},
+    describe::{count, describe},
+    empty::EmptyState,
+    format::Formatter,
+    offscreen::{export_image, OffscreenChart},
+    theme,
+    title::{ChartTitle, TitleAlignment},
+    Range, SeriesData, DEFAULT_PIE_SIZE,
+};
+
+/// The data for a rose chart: a value for each sector, e.g. how often the wind blows from each
+/// direction. The values can be any [`SeriesData`], e.g. a `Vector<i32>` or an `Arc<Vec<f64>>`.
+#[derive(Debug, Clone, Data, ComposeLens)]
+pub struct RoseChartData<S = Vector<f64>> {
+    pub title: ArcStr,
+    /// The name of each sector, clockwise from the top, e.g. `N`, `NE`, `E`...
+    pub sectors: Vector<ArcStr>,
+    /// The value of each sector. Missing values are left out.
+    pub values: S,
+    pub draw_grid: bool,
+}
+
+impl<S: SeriesData> RoseChartData<S> {
+    /// The data as a table, with a row for each sector.
+    fn csv_table(&self) -> Vec<Vec<String>> {
+        let mut table = vec![vec!["sector".to_string(), "value".to_string()]];
+        for (idx, sector) in self.sectors.iter().enumerate() {
+            table.push(vec![sector.to_string(), csv_cell(self.values.get(idx))]);
+        }
+        table
+    }
+}
+
+/// A polar bar chart (or wind rose): a wedge for each sector, going further out from the center
+/// the bigger its value is.
+pub struct RoseChart {
+    bar_color: KeyOrValue<Color>,
+    axis_color: KeyOrValue<Color>,
+    /// The gap between neighbouring wedges, as a fraction of their angle.
+    wedge_spacing: f64,
+    draw_axis: bool,
+    preferred_size: Size,
+    value_formatter: Formatter,
+    // retained state
+    title: ChartTitle,
+    scale: Option<PolarScale>,
+    wedges: Vec<CircleSegment>,
+    empty_state: EmptyState,
+    context_menu: Option<ChartMenu>,
+}
+
+impl RoseChart {
+    pub fn new() -> Self {
+        RoseChart {
+            bar_color: theme::BAR_COLOR.into(),
+            axis_color: theme::AXES_COLOR.into(),
+            wedge_spacing: 0.1,
+            draw_axis: true,
+            preferred_size: DEFAULT_PIE_SIZE,
+            value_formatter: Formatter::default(),
+            title: ChartTitle::new(),
+            scale: None,
+            wedges: Vec::new(),
+            empty_state: EmptyState::new(),
+            context_menu: None,
+        }
+    }
+
+    /// Set the color of the wedges. The default is `theme::BAR_COLOR`.
+    pub fn with_bar_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.bar_color = color.into();
+        self
+    }
+
+    /// Set the color of the outer circle.
+    pub fn with_axis_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.axis_color = color.into();
+        self
+    }
+
+    /// Leave a gap of `fraction` (e.g. `0.1` for 10%) of each sector's angle between the wedges.
+    /// The default is `0.1`.
+    pub fn with_wedge_spacing(mut self, fraction: f64) -> Self {
+        self.wedge_spacing = fraction;
+        self
+    }
+
+    /// Whether to draw the outer circle. The labels are drawn either way.
+    pub fn with_axis_visible(mut self, visible: bool) -> Self {
+        self.draw_axis = visible;
+        self
+    }
+
+    /// Set how the value labels on the grid circles are formatted.
+    pub fn with_value_formatter(mut self, formatter: Formatter) -> Self {
+        self.value_formatter = formatter;
+        self
+    }
+
+    /// Set the font size of the title. The default is `theme::TITLE_TEXT_SIZE`.
+    pub fn with_title_size(mut self, size: impl Into<KeyOrValue<f64>>) -> Self {
+        self.title.set_text_size(size);
+        self
+    }
+
+    /// Where to put the title (and subtitle). Defaults to the center.
+    pub fn with_title_alignment(mut self, alignment: TitleAlignment) -> Self {
+        self.title.set_alignment(alignment);
+        self
+    }
+
+    /// Draw a line of smaller text under the title.
+    pub fn with_subtitle(mut self, subtitle: impl Into<ArcStr>) -> Self {
+        self.title.set_subtitle(subtitle.into());
+        self
+    }
+
+    /// Set the size to take when the space isn't bounded, e.g. inside a `Scroll`. Otherwise the
+    /// chart fills the space it is given. The default is 300×300.
+    pub fn with_preferred_size(mut self, size: impl Into<Size>) -> Self {
+        self.preferred_size = size.into();
+        self
+    }
+
+    /// Show a menu when the chart is right-clicked, to toggle the grid, copy the chart as an image,
+    /// or copy or save the data as CSV. `T` is the type of your app's data, which druid needs to
+    /// show a menu.
+    pub fn with_context_menu<T: Data>(mut self) -> Self {
+        self.context_menu = Some(ChartMenu::new::<T>());
+        self
+    }
+
+    /// A short description of the chart for screen readers, e.g. "Rose chart, Wind direction, 8
+    /// sectors, largest SW".
+    pub fn describe<S: SeriesData>(&self, data: &RoseChartData<S>) -> String {
+        let mut facts = vec![count(data.sectors.len(), "sector", "sectors")];
+        // the first of the largest, if there are any values at all.
+        let largest = data
+            .values
+            .values()
+            .enumerate()
+            .filter(|(_, value)| value.is_finite())
+            .fold(
+                None,
+                |largest: Option<(usize, f64)>, (idx, value)| match largest {
+                    Some((_, largest_value)) if largest_value >= value => largest,
+                    _ => Some((idx, value)),
+                },
+            )
+            .and_then(|(idx, _)| data.sectors.get(idx));
+        if let Some(label) = largest {
+            facts.push(format!("largest {}", label));
+        }
+        describe("Rose chart", &data.title, facts)
+    }
+
+    /// Take the title from `data`, when the chart is added.
+    fn set_data<S: SeriesData>(&mut self, data: &RoseChartData<S>) {
+        self.title.set_text(data.title.clone());
+    }
+
+    /// Lay out the chart, or the empty state if there is no data.
+    fn lay_out<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &RoseChartData<S>,
+        env: &Env,
+    ) {
+        if is_empty(data) {
+            self.empty_state.rebuild_if_needed(text, env);
+        } else {
+            self.rebuild_if_needed(text, size, data, env);
+        }
+    }
+
+    /// Rebuild the layouts, scale and wedges for a chart of size `size`, as needed.
+    fn rebuild_if_needed<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &RoseChartData<S>,
+        env: &Env,
+    ) {
+        let margin = env.get(theme::MARGIN);
+        self.title.rebuild_if_needed(text, env);
+        if self.scale.is_none() {
+            // the wedges all start from the center, and the circle can't be empty.
+            let max = value_range(data)
+                .map(|range| range.max())
+                .filter(|max| *max > 0.)
+                .unwrap_or(1.);
+            let mut scale = PolarScale::new((0., max), data.sectors.iter().cloned());
+            scale.set_axis_color(self.axis_color.clone());
+            scale.set_formatter(self.value_formatter.clone());
+            self.scale = Some(scale);
+        }
+        let draw_area = self.title.reserve_space(size.to_rect(), env);
+        let scale = self.scale.as_mut().unwrap();
+        scale.set_graph_bounds(draw_area.inset(-margin));
+        scale.rebuild_if_needed(text, env);
+
+        let center = scale.center();
+        let wedge_spacing = self.wedge_spacing;
+        self.wedges = data
+            .values
+            .values()
+            .take(data.sectors.len())
+            .enumerate()
+            .filter(|(_, value)| value.is_finite())
+            .map(|(idx, value)| {
+                let (start_angle, sweep_angle) = scale.sector_angles(idx);
+                let gap = sweep_angle * wedge_spacing;
+                CircleSegment {
+                    center,
+                    outer_radius: scale.pixel_radius(value).max(0.),
+                    inner_radius: 0.,
+                    start_angle: start_angle + 0.5 * gap,
+                    sweep_angle: sweep_angle - gap,
+                }
+            })
+            .collect();
+    }
+
+    /// Draw the chart, as laid out by `layout`.
+    fn draw<S: SeriesData>(&mut self, ctx: &mut Canvas, data: &RoseChartData<S>, env: &Env) {
+        if is_empty(data) {
+            self.empty_state.draw(ctx);
+            return;
+        }
+        let bar_brush = ctx.solid_brush(self.bar_color.resolve(env));
+        let scale = self.scale.as_mut().unwrap();
+        if data.draw_grid {
+            scale.draw_grid(ctx, env);
+        }
+        for wedge in self.wedges.iter() {
+            ctx.fill(wedge, &bar_brush);
+        }
+        scale.draw(ctx, env, self.draw_axis, true);
+        self.title.draw(ctx, env);
+    }
+}
+
+impl<S: SeriesData> Widget<RoseChartData<S>> for RoseChart {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut RoseChartData<S>, env: &Env) {
+        let items = MenuItems {
+            grid: true,
+            data: true,
+            ..MenuItems::default()
+        };
+        if let Some(context_menu) = self.context_menu.as_mut() {
+            context_menu.event(ctx, event, items, || data.csv_table());
+        }
+        match event {
+            Event::Command(cmd) if cmd.is(TOGGLE_CHART_GRID) => {
+                data.draw_grid = !data.draw_grid;
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_DATA) => {
+                copy_csv(data.csv_table());
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_IMAGE) => {
+                copy_image(ctx, env, |ctx| self.draw(ctx, data, env));
+            }
+            Event::Command(cmd) if cmd.is(EXPORT_IMAGE) => {
+                let scale = *cmd.get_unchecked(EXPORT_IMAGE);
+                export_image(ctx, scale, env, |ctx| self.draw(ctx, data, env));
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &RoseChartData<S>,
+        env: &Env,
+    ) {
+        match event {
+            LifeCycle::WidgetAdded => self.set_data(data),
+            _ => (),
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &RoseChartData<S>,
+        data: &RoseChartData<S>,
+        env: &Env,
+    ) {
+        if !old_data.title.same(&data.title) {
+            self.title.set_text(data.title.clone());
+        }
+        if self.title.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !old_data.sectors.same(&data.sectors) || !old_data.values.same(&data.values) {
+            self.scale = None;
+            ctx.request_layout();
+        } else if let Some(scale) = self.scale.as_mut() {
+            if scale.needs_rebuild_after_update(ctx) {
+                ctx.request_layout();
+            }
+        }
+        if old_data.draw_grid != data.draw_grid {
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &RoseChartData<S>,
+        env: &Env,
+    ) -> Size {
+        let size = chart_size(bc, self.preferred_size);
+        self.lay_out(ctx.text(), size, data, env);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &RoseChartData<S>, env: &Env) {
+        self.draw(&mut Canvas::new(ctx, env), data, env);
+    }
+}
+
+impl<S: SeriesData> OffscreenChart<RoseChartData<S>> for RoseChart {
+    fn draw_offscreen(&mut self, ctx: &mut Canvas, data: &RoseChartData<S>, env: &Env) {
+        self.set_data(data);
+        let size = ctx.size();
+        self.lay_out(ctx.text(), size, data, env);
+        self.draw(ctx, data, env);
+    }
+}
+
+/// The range of the values, or `None` if there aren't any.
+fn value_range<S: SeriesData>(data: &RoseChartData<S>) -> Option<Range> {
+    Range::from_finite(data.values.values().take(data.sectors.len()))
+}
+
+/// Whether there is nothing to draw.
+fn is_empty<S: SeriesData>(data: &RoseChartData<S>) -> bool {
+    value_range(data).is_none()
+}
@@ -0,0 +1,807 @@
+use druid::{
+    im::Vector,
+    kurbo::{Circle, Point, Rect},
+    piet::PietText,
+    ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, Insets, KeyOrValue, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, RenderContext, Size, TextLayout, UpdateCtx, Widget,
+};
+use druid_lens_compose::ComposeLens;
+
+use crate::{
+    axes::{Direction, LinearTicker, Scale, ScaleType, TickLocator},
+    canvas::Canvas,
+    chart_size,
+    colormap::{draw_color_bar, Colormap, COLOR_BAR_WIDTH},
+    commands::{COPY_CHART_DATA, COPY_CHART_IMAGE, EXPORT_IMAGE, RESET_CHART_VIEW, ZOOM_TO_RANGE},
+    context_menu::{copy_csv, copy_image, csv_cell, ChartMenu, MenuItems},
+    describe::{count, describe},
+    empty::EmptyState,
+    format::Formatter,
+    new_color,
+    offscreen::{export_image, OffscreenChart},
+    theme,
+    title::{ChartTitle, TitleAlignment},
+    trend::line_across,
+    Range, SeriesData, DEFAULT_CHART_SIZE,
+};
+
+/// The radius of each point without a size value, at the normal size.
+pub(crate) const POINT_RADIUS: f64 = 3.;
+/// How many bubbles the size legend shows, at most.
+const SIZE_LEGEND_ENTRIES: usize = 3;
+/// How much of the color shows through in bubbles, so the ones behind can be seen.
+const BUBBLE_ALPHA: f64 = 0.7;
+
+/// `(x, y)` points to draw as dots, optionally colored and sized by more values. The values can
+/// be any [`SeriesData`], e.g. a `Vector<i32>` or an `Arc<Vec<f64>>`.
+#[derive(Debug, Clone, Data, ComposeLens)]
+pub struct ScatterPlotData<S = Vector<f64>> {
+    pub title: ArcStr,
+    pub x_axis_label: ArcStr,
+    /// The x value of each point.
+    pub x: S,
+    /// The y value of each point. Points missing either value are left out.
+    pub y: S,
+    /// A value for each point that picks its color from the colormap, e.g. the age of each
+    /// person. Points without one are drawn in the plain point color.
+    pub color_values: Option<S>,
+    /// A value for each point that picks its size, making a bubble chart. Points without one are
+    /// drawn as small dots.
+    pub size_values: Option<S>,
+}
+
+impl<S: SeriesData> ScatterPlotData<S> {
+    /// The data as a table, with a row for each point: its x and y values, and then its color
+    /// and size values if there are any.
+    fn csv_table(&self) -> Vec<Vec<String>> {
+        let mut header = vec!["x".to_string(), "y".to_string()];
+        if self.color_values.is_some() {
+            header.push("color".to_string());
+        }
+        if self.size_values.is_some() {
+            header.push("size".to_string());
+        }
+        let extra: Vec<&S> = self
+            .color_values
+            .iter()
+            .chain(self.size_values.iter())
+            .collect();
+        let mut table = vec![header];
+        for idx in 0..self.x.len().max(self.y.len()) {
+            let mut row = vec![csv_cell(self.x.get(idx)), csv_cell(self.y.get(idx))];
+            row.extend(extra.iter().map(|values| csv_cell(values.get(idx))));
+            table.push(row);
+        }
+        table
+    }
+}
+
+/// How size values are turned into the sizes of bubbles.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Data)]
+pub enum SizeMapping {
+    /// The area of the bubble grows in step with the value. This is how people compare bubbles,
+    /// so it doesn't exaggerate the differences.
+    Area,
+    /// The radius of the bubble grows in step with the value.
+    Radius,
+}
+
+impl SizeMapping {
+    /// The radius for `t` (from `0` for the smallest value to `1` for the largest), between
+    /// `min_radius` and `max_radius`.
+    fn radius(self, t: f64, min_radius: f64, max_radius: f64) -> f64 {
+        let t = if t.is_nan() { 0.5 } else { t.max(0.).min(1.) };
+        match self {
+            SizeMapping::Area => {
+                (min_radius.powi(2) + t * (max_radius.powi(2) - min_radius.powi(2))).sqrt()
+            }
+            SizeMapping::Radius => min_radius + t * (max_radius - min_radius),
+        }
+    }
+}
+
+/// A dot of a scatter plot.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Dot {
+    pub center: Point,
+    pub color_value: Option<f64>,
+    pub radius: f64,
+}
+
+impl Dot {
+    /// Fill the dot with `color`.
+    pub fn paint(&self, ctx: &mut Canvas, color: &Color) {
+        ctx.fill(Circle::new(self.center, self.radius), color);
+    }
+}
+
+/// A scatter plot: a dot for each point, which can be colored by a third value with a color bar
+/// alongside to show which color is which value.
+///
+/// With size values it becomes a bubble chart, with a legend of example bubble sizes alongside.
+pub struct ScatterPlot {
+    colormap: Colormap,
+    /// Whether to draw the color bar, when the points have color values.
+    color_bar: bool,
+    /// The color of points without a color value. If `None`, the first series color is used.
+    point_color: Option<KeyOrValue<Color>>,
+    x_formatter: Formatter,
+    y_formatter: Formatter,
+    color_formatter: Formatter,
+    size_mapping: SizeMapping,
+    /// The smallest and largest bubbles, at the normal size.
+    size_radii: (f64, f64),
+    size_formatter: Formatter,
+    /// The `(slope, intercept)` of a line to draw behind the points.
+    reference_line: Option<(f64, f64)>,
+    preferred_size: Size,
+    // retained state
+    title: ChartTitle,
+    x_label_layout: TextLayout<ArcStr>,
+    x_scale: Option<Scale>,
+    y_scale: Option<Scale>,
+    /// The part of the x axis zoomed in to with `ZOOM_TO_RANGE`.
+    x_view: Option<Range>,
+    /// The part of the y axis zoomed in to with `ZOOM_TO_RANGE`.
+    y_view: Option<Range>,
+    /// The color values, by the color bar.
+    color_scale: Option<Scale>,
+    /// The range of the color values, if there are any.
+    color_range: Option<Range>,
+    /// The range of the size values, if there are any.
+    size_range: Option<Range>,
+    /// The values shown in the size legend, with their labels.
+    size_legend: Option<Vec<(f64, TextLayout<ArcStr>)>>,
+    /// The dots, biggest first so the small ones aren't hidden.
+    points: Option<Vec<Dot>>,
+    /// The area the points are drawn in.
+    graph_bounds: Rect,
+    empty_state: EmptyState,
+    context_menu: Option<ChartMenu>,
+}
+
+impl ScatterPlot {
+    pub fn new() -> Self {
+        ScatterPlot {
+            colormap: Colormap::Viridis,
+            color_bar: true,
+            point_color: None,
+            x_formatter: Formatter::default(),
+            y_formatter: Formatter::default(),
+            color_formatter: Formatter::precision(3),
+            size_mapping: SizeMapping::Area,
+            size_radii: (2., 15.),
+            size_formatter: Formatter::precision(3),
+            reference_line: None,
+            preferred_size: DEFAULT_CHART_SIZE,
+            title: ChartTitle::new(),
+            x_label_layout: TextLayout::new(),
+            x_scale: None,
+            y_scale: None,
+            x_view: None,
+            y_view: None,
+            color_scale: None,
+            color_range: None,
+            size_range: None,
+            size_legend: None,
+            points: None,
+            graph_bounds: Rect::ZERO,
+            empty_state: EmptyState::new(),
+            context_menu: None,
+        }
+    }
+
+    /// Choose the colors for the color values. The default is `Colormap::Viridis`.
+    pub fn with_colormap(mut self, colormap: Colormap) -> Self {
+        self.colormap = colormap;
+        self
+    }
+
+    /// Whether to draw a bar to the right of the plot showing which color is which value, when
+    /// the points have color values. On by default.
+    pub fn with_color_bar(mut self, color_bar: bool) -> Self {
+        self.color_bar = color_bar;
+        self
+    }
+
+    /// Set the color of points without a color value. The default is the first series color.
+    pub fn with_point_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.point_color = Some(color.into());
+        self
+    }
+
+    /// Set how the x axis tick labels are formatted.
+    pub fn with_x_formatter(mut self, formatter: Formatter) -> Self {
+        self.x_formatter = formatter;
+        self
+    }
+
+    /// Set how the y axis tick labels are formatted.
+    pub fn with_y_formatter(mut self, formatter: Formatter) -> Self {
+        self.y_formatter = formatter;
+        self
+    }
+
+    /// Set how the labels on the color bar are formatted.
+    pub fn with_color_formatter(mut self, formatter: Formatter) -> Self {
+        self.color_formatter = formatter;
+        self
+    }
+
+    /// Choose how size values are turned into bubble sizes. The default is `SizeMapping::Area`.
+    pub fn with_size_mapping(mut self, mapping: SizeMapping) -> Self {
+        self.size_mapping = mapping;
+        self
+    }
+
+    /// Set the radius of the bubbles for the smallest and largest size values. The default is
+    /// 2 to 15.
+    pub fn with_size_radii(mut self, min_radius: f64, max_radius: f64) -> Self {
+        assert!(
+            0. <= min_radius && min_radius <= max_radius,
+            "the radii must be in order, and not negative"
+        );
+        self.size_radii = (min_radius, max_radius);
+        self
+    }
+
+    /// Set how the values in the size legend are formatted.
+    pub fn with_size_formatter(mut self, formatter: Formatter) -> Self {
+        self.size_formatter = formatter;
+        self
+    }
+
+    /// Draw the line `y = slope × x + intercept` behind the points, e.g. `y = x` to compare two
+    /// measurements of the same thing.
+    pub fn with_reference_line(mut self, slope: f64, intercept: f64) -> Self {
+        self.reference_line = Some((slope, intercept));
+        self
+    }
+
+    /// Change the reference line, e.g. when it is fitted to the data.
+    pub(crate) fn set_reference_line(&mut self, reference_line: Option<(f64, f64)>) {
+        self.reference_line = reference_line;
+    }
+
+    /// Set the font size of the title. The default is `theme::TITLE_TEXT_SIZE`.
+    pub fn with_title_size(mut self, size: impl Into<KeyOrValue<f64>>) -> Self {
+        self.title.set_text_size(size);
+        self
+    }
+
+    /// Where to put the title (and subtitle). Defaults to the center.
+    pub fn with_title_alignment(mut self, alignment: TitleAlignment) -> Self {
+        self.title.set_alignment(alignment);
+        self
+    }
+
+    /// Draw a line of smaller text under the title.
+    pub fn with_subtitle(mut self, subtitle: impl Into<ArcStr>) -> Self {
+        self.title.set_subtitle(subtitle.into());
+        self
+    }
+
+    /// Set the size to take when the space isn't bounded, e.g. inside a `Scroll`. Otherwise the
+    /// chart fills the space it is given. The default is 400×300.
+    pub fn with_preferred_size(mut self, size: impl Into<Size>) -> Self {
+        self.preferred_size = size.into();
+        self
+    }
+
+    /// Show a menu when the chart is right-clicked, to reset the zoom, copy the chart as an image,
+    /// or copy or save the data as CSV. `T` is the type of your app's data, which druid needs to
+    /// show a menu.
+    pub fn with_context_menu<T: Data>(mut self) -> Self {
+        self.context_menu = Some(ChartMenu::new::<T>());
+        self
+    }
+
+    /// A short description of the chart for screen readers, e.g. "Scatter plot, Blood pressure,
+    /// 500 points".
+    pub fn describe<S: SeriesData>(&self, data: &ScatterPlotData<S>) -> String {
+        let points = points(data).count();
+        describe(
+            "Scatter plot",
+            &data.title,
+            vec![count(points, "point", "points")],
+        )
+    }
+
+    /// Whether there is a color bar to make room for.
+    fn has_color_bar(&self) -> bool {
+        self.color_bar && self.color_range.is_some()
+    }
+
+    /// The radius of a bubble for `value`, or of a plain dot if there isn't a value.
+    fn radius(&self, value: Option<f64>, env: &Env) -> f64 {
+        let size_scale = env.get(theme::SIZE_SCALE);
+        match (value, self.size_range) {
+            (Some(value), Some(range)) => {
+                let (min_radius, max_radius) = self.size_radii;
+                let t = if range.size() > 0. {
+                    (value - range.min()) / range.size()
+                } else {
+                    0.5
+                };
+                self.size_mapping.radius(t, min_radius, max_radius) * size_scale
+            }
+            _ => POINT_RADIUS * size_scale,
+        }
+    }
+
+    /// The width of the size legend: the biggest bubble and the widest label.
+    fn size_legend_width(&self, env: &Env) -> f64 {
+        let size_legend = match self.size_legend.as_ref() {
+            Some(size_legend) => size_legend,
+            None => return 0.,
+        };
+        let label_width = size_legend
+            .iter()
+            .map(|(_, layout)| layout.size().width)
+            .fold(0., f64::max);
+        2. * self.size_radii.1 * env.get(theme::SIZE_SCALE)
+            + env.get(theme::SCALE_MARGIN)
+            + label_width
+    }
+
+    /// Show `x_view` and `y_view` of the axes, or all of an axis if its view is `None`.
+    fn set_view(&mut self, x_view: Option<Range>, y_view: Option<Range>) {
+        self.x_view = x_view;
+        self.y_view = y_view;
+        self.x_scale = None;
+        self.y_scale = None;
+        self.points = None;
+    }
+
+    /// Take the title and axis label from `data`, when the chart is added.
+    fn set_data<S: SeriesData>(&mut self, data: &ScatterPlotData<S>) {
+        self.title.set_text(data.title.clone());
+        self.x_label_layout.set_text(data.x_axis_label.clone());
+    }
+
+    /// Lay out the chart, or the empty state if there is no data.
+    fn lay_out<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &ScatterPlotData<S>,
+        env: &Env,
+    ) {
+        if points(data).next().is_none() {
+            self.empty_state.rebuild_if_needed(text, env);
+        } else {
+            self.rebuild_if_needed(text, size, data, env);
+        }
+    }
+
+    /// Rebuild the layouts, scales and points for a chart of size `size`, as needed.
+    fn rebuild_if_needed<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &ScatterPlotData<S>,
+        env: &Env,
+    ) {
+        let margin = env.get(theme::MARGIN);
+        let scale_margin = env.get(theme::SCALE_MARGIN);
+        self.title.rebuild_if_needed(text, env);
+        self.x_label_layout.rebuild_if_needed(text, env);
+        if self.x_scale.is_none() || self.y_scale.is_none() {
+            let x_range = self
+                .x_view
+                .unwrap_or_else(|| Range::from_finite(points(data).map(|(x, _, _, _)| x)).unwrap());
+            let y_range = self
+                .y_view
+                .unwrap_or_else(|| Range::from_finite(points(data).map(|(_, y, _, _)| y)).unwrap());
+            let mut x_scale = Scale::new_x(x_range);
+            x_scale.set_formatter(self.x_formatter.clone());
+            self.x_scale = Some(x_scale);
+            let mut y_scale = Scale::new_y(y_range);
+            y_scale.set_formatter(self.y_formatter.clone());
+            self.y_scale = Some(y_scale);
+            self.color_range = Range::from_finite(points(data).filter_map(|(_, _, c, _)| c));
+            self.color_scale = None;
+            self.size_range = Range::from_finite(points(data).filter_map(|(_, _, _, s)| s));
+            self.size_legend = None;
+        }
+        if self.size_legend.is_none() {
+            if let Some(size_range) = self.size_range {
+                let mut values: Vec<f64> = LinearTicker
+                    .ticks(size_range, SIZE_LEGEND_ENTRIES, ScaleType::Linear)
+                    .into_iter()
+                    .map(|tick| tick.value)
+                    .collect();
+                if values.is_empty() {
+                    values = vec![size_range.min(), size_range.max()];
+                }
+                let formatter = &self.size_formatter;
+                self.size_legend = Some(
+                    values
+                        .into_iter()
+                        .map(|value| {
+                            let mut layout =
+                                TextLayout::from_text(ArcStr::from(formatter.format(value)));
+                            layout.set_text_size(theme::TICK_LABEL_TEXT_SIZE);
+                            (value, layout)
+                        })
+                        .collect(),
+                );
+            }
+        }
+        if let Some(size_legend) = self.size_legend.as_mut() {
+            for (_, layout) in size_legend.iter_mut() {
+                layout.rebuild_if_needed(text, env);
+            }
+        }
+        if self.color_scale.is_none() {
+            if let Some(color_range) = self.color_range {
+                let mut color_scale = Scale::new(color_range, Direction::YRight);
+                color_scale.set_formatter(self.color_formatter.clone());
+                self.color_scale = Some(color_scale);
+            }
+        }
+
+        // Lay out the scales for the whole area first, so we know how big their labels are.
+        let draw_area = self.title.reserve_space(size.to_rect(), env);
+        let mut label_sizes = Vec::with_capacity(3);
+        for scale in [&mut self.x_scale, &mut self.y_scale, &mut self.color_scale]
+            .iter_mut()
+            .filter_map(|scale| scale.as_mut())
+        {
+            scale.set_graph_bounds(draw_area);
+            scale.rebuild_if_needed(text, env);
+            label_sizes.push(scale.max_layout());
+        }
+        let x0 = margin + label_sizes[1].width + scale_margin;
+        // space for the color bar and its labels, and the size legend.
+        let mut side_width: f64 = 0.;
+        if self.has_color_bar() {
+            side_width = COLOR_BAR_WIDTH + scale_margin + label_sizes[2].width;
+        }
+        side_width = side_width.max(self.size_legend_width(env));
+        let x1 = if side_width > 0. {
+            margin + side_width + margin
+        } else {
+            margin
+        };
+        // the title has its own space above the draw area.
+        let y0 = margin;
+        let mut y1 = margin + label_sizes[0].height + scale_margin;
+        if !data.x_axis_label.is_empty() {
+            y1 += margin + self.x_label_layout.size().height;
+        }
+        let graph_bounds = draw_area.inset(Insets {
+            x0: -x0,
+            y0: -y0,
+            x1: -x1,
+            y1: -y1,
+        });
+        if self.graph_bounds != graph_bounds {
+            self.graph_bounds = graph_bounds;
+            self.points = None;
+        }
+        let x_scale = self.x_scale.as_mut().unwrap();
+        x_scale.set_graph_bounds(graph_bounds);
+        x_scale.rebuild_if_needed(text, env);
+        let y_scale = self.y_scale.as_mut().unwrap();
+        y_scale.set_graph_bounds(graph_bounds);
+        y_scale.rebuild_if_needed(text, env);
+
+        if self.points.is_none() {
+            let x_scale = self.x_scale.as_ref().unwrap();
+            let y_scale = self.y_scale.as_ref().unwrap();
+            let mut dots: Vec<Dot> = points(data)
+                .map(|(x, y, color_value, size_value)| Dot {
+                    center: Point::new(x_scale.pixel_location(x), y_scale.pixel_location(y)),
+                    color_value,
+                    radius: self.radius(size_value, env),
+                })
+                .collect();
+            dots.sort_by(|a, b| {
+                b.radius
+                    .partial_cmp(&a.radius)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+            self.points = Some(dots);
+        }
+        if self.has_color_bar() {
+            let color_bar_bounds = self.color_bar_bounds(env);
+            let color_scale = self.color_scale.as_mut().unwrap();
+            color_scale.set_graph_bounds(color_bar_bounds);
+            color_scale.rebuild_if_needed(text, env);
+        }
+    }
+
+    /// Where the color bar goes, to the right of the graph. It shares the space with the size
+    /// legend, if there is one.
+    fn color_bar_bounds(&self, env: &Env) -> Rect {
+        let margin = env.get(theme::MARGIN);
+        let x0 = self.graph_bounds.x1 + margin;
+        let y1 = if self.size_legend.is_some() {
+            self.graph_bounds.center().y - 0.5 * margin
+        } else {
+            self.graph_bounds.y1
+        };
+        Rect::new(x0, self.graph_bounds.y0, x0 + COLOR_BAR_WIDTH, y1)
+    }
+
+    /// Draw the size legend: an example bubble for each value, with its label beside it. It goes
+    /// under the color bar if there is one.
+    fn draw_size_legend(&mut self, ctx: &mut Canvas, color: &Color, env: &Env) {
+        let margin = env.get(theme::MARGIN);
+        let scale_margin = env.get(theme::SCALE_MARGIN);
+        let max_radius = self.size_radii.1 * env.get(theme::SIZE_SCALE);
+        let x0 = self.graph_bounds.x1 + margin;
+        let mut y = if self.has_color_bar() {
+            self.graph_bounds.center().y + 0.5 * margin
+        } else {
+            self.graph_bounds.y0
+        };
+        let radii: Vec<f64> = self
+            .size_legend
+            .iter()
+            .flatten()
+            .map(|(value, _)| self.radius(Some(*value), env))
+            .collect();
+        for ((_, layout), radius) in self.size_legend.iter_mut().flatten().zip(radii) {
+            let label_size = layout.size();
+            let height = (2. * radius).max(label_size.height);
+            ctx.stroke(
+                Circle::new((x0 + max_radius, y + 0.5 * height), radius),
+                color,
+                env.get(theme::SIZE_SCALE),
+            );
+            ctx.draw_layout(
+                layout,
+                (
+                    x0 + 2. * max_radius + scale_margin,
+                    y + 0.5 * (height - label_size.height),
+                ),
+            );
+            y += height + scale_margin;
+        }
+    }
+
+    /// Draw the chart, as laid out by `layout`.
+    fn draw<S: SeriesData>(&mut self, ctx: &mut Canvas, data: &ScatterPlotData<S>, env: &Env) {
+        if points(data).next().is_none() {
+            self.empty_state.draw(ctx);
+            return;
+        }
+        let size = ctx.size();
+
+        // points, cut off at the edges of the graph.
+        let point_color = match self.point_color.as_ref() {
+            Some(color) => color.resolve(env),
+            None => new_color(0, env),
+        };
+        let graph_bounds = self.graph_bounds;
+        let colormap = self.colormap;
+        let color_range = self.color_range;
+        let alpha = if self.size_range.is_some() {
+            BUBBLE_ALPHA
+        } else {
+            1.
+        };
+        let points = self.points.as_ref().unwrap();
+        // the reference line goes across the whole x axis.
+        let reference_line = self.reference_line.map(|(slope, intercept)| {
+            let x_scale = self.x_scale.as_ref().unwrap();
+            let y_scale = self.y_scale.as_ref().unwrap();
+            line_across(x_scale, y_scale, slope, intercept)
+        });
+        ctx.with_save(|ctx| {
+            ctx.clip(graph_bounds);
+            if let Some(line) = reference_line {
+                ctx.stroke(
+                    line,
+                    &env.get(theme::ANNOTATION_COLOR),
+                    env.get(theme::SIZE_SCALE),
+                );
+            }
+            for dot in points.iter() {
+                let color = match (dot.color_value, color_range) {
+                    (Some(value), Some(range)) => {
+                        colormap.map_value(value, range.min(), range.max())
+                    }
+                    _ => point_color.clone(),
+                };
+                dot.paint(ctx, &color.with_alpha(alpha));
+            }
+        });
+
+        // color bar, from the smallest value at the bottom to the largest at the top.
+        if self.has_color_bar() {
+            draw_color_bar(ctx, self.colormap, self.color_bar_bounds(env));
+            self.color_scale
+                .as_mut()
+                .unwrap()
+                .draw(ctx, env, false, true);
+        }
+
+        // size legend
+        if self.size_legend.is_some() {
+            let color = env.get(theme::AXES_COLOR);
+            self.draw_size_legend(ctx, &color, env);
+        }
+
+        // title
+        self.title.draw(ctx, env);
+
+        // axes
+        self.x_scale.as_mut().unwrap().draw(ctx, env, true, true);
+        if !data.x_axis_label.is_empty() {
+            let label_size = self.x_label_layout.size();
+            let margin = env.get(theme::MARGIN);
+            ctx.draw_layout(
+                &self.x_label_layout,
+                (
+                    (size.width - label_size.width) * 0.5,
+                    size.height - label_size.height - margin,
+                ),
+            );
+        }
+        self.y_scale.as_mut().unwrap().draw(ctx, env, true, true);
+    }
+}
+
+impl<S: SeriesData> Widget<ScatterPlotData<S>> for ScatterPlot {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ScatterPlotData<S>,
+        env: &Env,
+    ) {
+        let items = MenuItems {
+            reset_zoom: self.x_view.is_some() || self.y_view.is_some(),
+            data: true,
+            ..MenuItems::default()
+        };
+        if let Some(context_menu) = self.context_menu.as_mut() {
+            context_menu.event(ctx, event, items, || data.csv_table());
+        }
+        match event {
+            Event::Command(cmd) if cmd.is(ZOOM_TO_RANGE) => {
+                let zoom = cmd.get_unchecked(ZOOM_TO_RANGE);
+                self.set_view(zoom.x_range.or(self.x_view), zoom.y_range.or(self.y_view));
+                ctx.request_layout();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(RESET_CHART_VIEW) => {
+                self.set_view(None, None);
+                ctx.request_layout();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_DATA) => {
+                copy_csv(data.csv_table());
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_IMAGE) => {
+                copy_image(ctx, env, |ctx| self.draw(ctx, data, env));
+            }
+            Event::Command(cmd) if cmd.is(EXPORT_IMAGE) => {
+                let scale = *cmd.get_unchecked(EXPORT_IMAGE);
+                export_image(ctx, scale, env, |ctx| self.draw(ctx, data, env));
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &ScatterPlotData<S>,
+        env: &Env,
+    ) {
+        match event {
+            LifeCycle::WidgetAdded => self.set_data(data),
+            _ => (),
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &ScatterPlotData<S>,
+        data: &ScatterPlotData<S>,
+        env: &Env,
+    ) {
+        if !old_data.title.same(&data.title) {
+            self.title.set_text(data.title.clone());
+        }
+        if self.title.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !old_data.x_axis_label.same(&data.x_axis_label) {
+            self.x_label_layout.set_text(data.x_axis_label.clone());
+        }
+        if self.x_label_layout.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !old_data.x.same(&data.x)
+            || !old_data.y.same(&data.y)
+            || !old_data.color_values.same(&data.color_values)
+            || !old_data.size_values.same(&data.size_values)
+        {
+            self.x_scale = None;
+            self.y_scale = None;
+            self.color_scale = None;
+            self.size_legend = None;
+            self.points = None;
+            ctx.request_layout();
+        } else {
+            let mut needs_rebuild = false;
+            for scale in [&mut self.x_scale, &mut self.y_scale, &mut self.color_scale]
+                .iter_mut()
+                .filter_map(|scale| scale.as_mut())
+            {
+                needs_rebuild |= scale.needs_rebuild_after_update(ctx);
+            }
+            if needs_rebuild {
+                ctx.request_layout();
+            }
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &ScatterPlotData<S>,
+        env: &Env,
+    ) -> Size {
+        let size = chart_size(bc, self.preferred_size);
+        self.lay_out(ctx.text(), size, data, env);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &ScatterPlotData<S>, env: &Env) {
+        self.draw(&mut Canvas::new(ctx, env), data, env);
+    }
+}
+
+impl<S: SeriesData> OffscreenChart<ScatterPlotData<S>> for ScatterPlot {
+    fn draw_offscreen(&mut self, ctx: &mut Canvas, data: &ScatterPlotData<S>, env: &Env) {
+        self.set_data(data);
+        let size = ctx.size();
+        self.lay_out(ctx.text(), size, data, env);
+        self.draw(ctx, data, env);
+    }
+}
+
+/// The points with both an x and a y value, along with their color and size values if they have
+/// them.
+fn points<S: SeriesData>(
+    data: &ScatterPlotData<S>,
+) -> impl Iterator<Item = (f64, f64, Option<f64>, Option<f64>)> + '_ {
+    let value = move |values: Option<&S>, idx: usize| {
+        values
+            .and_then(|values| values.get(idx))
+            .filter(|value| value.is_finite())
+    };
+    data.x
+        .values()
+        .zip(data.y.values())
+        .enumerate()
+        .filter(|(_, (x, y))| x.is_finite() && y.is_finite())
+        .map(move |(idx, (x, y))| {
+            (
+                x,
+                y,
+                value(data.color_values.as_ref(), idx),
+                value(data.size_values.as_ref(), idx),
+            )
+        })
+}
+
+#[test]
+fn test_size_mapping() {
+    assert_eq!(SizeMapping::Radius.radius(0.5, 2., 10.), 6.);
+    // half way by area.
+    assert_eq!(SizeMapping::Area.radius(0.5, 0., 10.), 50f64.sqrt());
+    assert_eq!(SizeMapping::Area.radius(1., 2., 10.), 10.);
+    // out of range values are clamped.
+    assert_eq!(SizeMapping::Area.radius(-1., 2., 10.), 2.);
+}
@@ -0,0 +1,110 @@
+//! Lining shapes up with the pixels of the screen, so their edges are drawn crisply rather than
+//! blurred across 2 pixels.
+use druid::{
+    kurbo::{Line, Rect, Vec2},
+    Env, PaintCtx,
+};
+
+use crate::theme;
+
+/// Moves lines and rectangles (in the widget's coordinates) onto the pixel grid of the window.
+///
+/// Charts get one from their [`Canvas`](crate::canvas::Canvas). If `theme::SNAP_TO_PIXELS` is
+/// off, shapes are left alone.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct PixelSnap {
+    /// Where the widget is in the window. The pixel grid lines up with the window, not the
+    /// widget.
+    origin: Vec2,
+    /// Device pixels per display point, across and down.
+    scale_x: f64,
+    scale_y: f64,
+    enabled: bool,
+}
+
+impl PixelSnap {
+    pub fn new(ctx: &PaintCtx, env: &Env) -> Self {
+        let scale = ctx.scale();
+        PixelSnap {
+            origin: ctx.window_origin().to_vec2(),
+            scale_x: scale.x(),
+            scale_y: scale.y(),
+            enabled: env.get(theme::SNAP_TO_PIXELS),
+        }
+    }
+
+    /// Snap to the pixels of an image with `scale` pixels per point, drawn from its top left
+    /// corner.
+    pub fn for_image(scale: f64, env: &Env) -> Self {
+        PixelSnap {
+            origin: Vec2::ZERO,
+            scale_x: scale,
+            scale_y: scale,
+            enabled: env.get(theme::SNAP_TO_PIXELS),
+        }
+    }
+
+    /// Move a horizontal or vertical line that will be stroked `width` wide, so both sides of the
+    /// stroke are on pixel boundaries. Other lines are left alone.
+    pub fn line(&self, line: Line, width: f64) -> Line {
+        if !self.enabled {
+            return line;
+        }
+        let Line { mut p0, mut p1 } = line;
+        if p0.x == p1.x {
+            p0.x = snap_center(p0.x, width, self.origin.x, self.scale_x);
+            p1.x = p0.x;
+        } else if p0.y == p1.y {
+            p0.y = snap_center(p0.y, width, self.origin.y, self.scale_y);
+            p1.y = p0.y;
+        }
+        Line::new(p0, p1)
+    }
+
+    /// Move the edges of `rect` to the nearest pixel boundaries. Rectangles that share an edge
+    /// still share it afterwards.
+    pub fn rect(&self, rect: Rect) -> Rect {
+        if !self.enabled {
+            return rect;
+        }
+        Rect::new(
+            snap_edge(rect.x0, self.origin.x, self.scale_x),
+            snap_edge(rect.y0, self.origin.y, self.scale_y),
+            snap_edge(rect.x1, self.origin.x, self.scale_x),
+            snap_edge(rect.y1, self.origin.y, self.scale_y),
+        )
+    }
+}
+
+/// The nearest pixel boundary to `pos`, for a widget at `origin` in a window with `scale` pixels
+/// per point.
+fn snap_edge(pos: f64, origin: f64, scale: f64) -> f64 {
+    ((pos + origin) * scale).round() / scale - origin
+}
+
+/// Where to put the center of a line `width` wide near `pos`, so it covers whole pixels. Lines an
+/// odd number of pixels wide go through the middle of a pixel.
+fn snap_center(pos: f64, width: f64, origin: f64, scale: f64) -> f64 {
+    let pixels = (width * scale).round().max(1.);
+    let offset = if pixels % 2. == 1. { 0.5 } else { 0. };
+    (((pos + origin) * scale - offset).round() + offset) / scale - origin
+}
+
+#[test]
+fn test_snap_edge() {
+    assert_eq!(snap_edge(10.3, 0., 1.), 10.);
+    // the pixel grid is relative to the window.
+    assert_eq!(snap_edge(10.3, 0.5, 1.), 10.5);
+    assert_eq!(snap_edge(10.3, 0., 2.), 10.5);
+}
+
+#[test]
+fn test_snap_center() {
+    // a 1px line goes through the middle of a pixel, and a 2px line between 2 pixels.
+    assert_eq!(snap_center(10.2, 1., 0., 1.), 10.5);
+    assert_eq!(snap_center(10.2, 2., 0., 1.), 10.);
+    // at 2x, a 1 point line is 2 pixels wide.
+    assert_eq!(snap_center(10.2, 1., 0., 2.), 10.);
+    // at 1.5x, a 2 point line is 3 pixels wide.
+    assert_eq!(snap_center(10.2, 2., 0., 1.5), 15.5 / 1.5);
+}
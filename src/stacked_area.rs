@@ -0,0 +1,638 @@
+use druid::{
+    im::Vector,
+    kurbo::{BezPath, Point, Rect},
+    piet::PietText,
+    ArcStr, BoxConstraints, Data, Env, Event, EventCtx, Insets, KeyOrValue, LayoutCtx, Lens,
+    LifeCycle, LifeCycleCtx, PaintCtx, Size, TextLayout, UpdateCtx, Widget,
+};
+use druid_lens_compose::ComposeLens;
+
+use crate::{
+    axes::Scale,
+    canvas::Canvas,
+    chart_size,
+    commands::{
+        COPY_CHART_DATA, COPY_CHART_IMAGE, EXPORT_IMAGE, RESET_CHART_VIEW, TOGGLE_CHART_GRID,
+        ZOOM_TO_RANGE,
+    },
+    context_menu::{copy_csv, copy_image, csv_cell, ChartMenu, MenuItems},
+    describe::{count, describe},
+    empty::EmptyState,
+    fill::{Fill, SeriesFills},
+    fit_aspect_ratio,
+    format::Formatter,
+    legend::{Legend, LegendPlacement},
+    new_color,
+    offscreen::{export_image, OffscreenChart},
+    theme,
+    title::{ChartTitle, TitleAlignment},
+    Range, SeriesData, DEFAULT_CHART_SIZE,
+};
+
+/// Several series stacked on top of each other, showing how a total is made up over time.
+#[derive(Debug, Clone, Data, ComposeLens)]
+pub struct StackedAreaData<S = Vector<f64>> {
+    pub title: ArcStr,
+    pub x_axis_label: ArcStr,
+    /// If `None`, then the index of each value is used.
+    pub x_data: Option<Vector<f64>>,
+    /// The series, from the bottom of the stack to the top.
+    pub series: Vector<AreaSeries<S>>,
+    pub draw_y_grid: bool,
+}
+
+impl<S: SeriesData> StackedAreaData<S> {
+    /// The data as a table, with a column for the x values and then one for each series.
+    fn csv_table(&self) -> Vec<Vec<String>> {
+        let mut header = vec!["x".to_string()];
+        header.extend(self.series.iter().map(|series| series.label.to_string()));
+        let len = self
+            .series
+            .iter()
+            .map(|series| series.values.len())
+            .max()
+            .unwrap_or(0);
+        let mut table = vec![header];
+        for idx in 0..len {
+            let x = match self.x_data.as_ref() {
+                Some(x_data) => x_data.get(idx).copied(),
+                None => Some(idx as f64),
+            };
+            let mut row = vec![csv_cell(x)];
+            row.extend(
+                self.series
+                    .iter()
+                    .map(|series| csv_cell(series.values.get(idx))),
+            );
+            table.push(row);
+        }
+        table
+    }
+}
+
+/// One layer of a stacked area chart. The values can be any [`SeriesData`], e.g. a `Vector<i32>`
+/// or an `Arc<Vec<f64>>`.
+#[derive(Debug, Clone, Data, Lens)]
+pub struct AreaSeries<S = Vector<f64>> {
+    /// The name of the series, used in the legend.
+    pub label: ArcStr,
+    /// The thickness of the layer at each x value. Missing values count as 0.
+    pub values: S,
+}
+
+impl<S: SeriesData> AreaSeries<S> {
+    pub fn new(label: impl Into<ArcStr>, values: S) -> Self {
+        AreaSeries {
+            label: label.into(),
+            values,
+        }
+    }
+}
+
+/// Where the bottom of the stack is drawn.
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub enum Baseline {
+    /// At zero, giving a normal stacked area chart.
+    Zero,
+    /// Centered about zero, giving a symmetric "ThemeRiver" shape.
+    Silhouette,
+    /// Chosen to minimize how much the layers wiggle up and down, giving a streamgraph (see Byron
+    /// & Wattenberg, "Stacked Graphs – Geometry & Aesthetics").
+    Wiggle,
+}
+
+impl Baseline {
+    /// Stack the layers, returning the edges between them at each point, from the baseline up.
+    ///
+    /// There is always one more edge than there are layers.
+    pub fn stack(self, layers: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        let len = layers.iter().map(|layer| layer.len()).max().unwrap_or(0);
+        let value = |layer: &Vec<f64>, idx: usize| match layer.get(idx) {
+            Some(v) if v.is_finite() => *v,
+            _ => 0.,
+        };
+        let totals: Vec<f64> = (0..len)
+            .map(|idx| layers.iter().map(|layer| value(layer, idx)).sum())
+            .collect();
+        let baseline: Vec<f64> = match self {
+            Baseline::Zero => vec![0.; len],
+            Baseline::Silhouette => totals.iter().map(|total| -0.5 * total).collect(),
+            Baseline::Wiggle => {
+                // g₀' = -1/(n+1) Σ (n - i) fᵢ', integrated starting from the silhouette.
+                let n = layers.len() as f64;
+                let mut baseline = Vec::with_capacity(len);
+                for idx in 0..len {
+                    let g0 = match idx.checked_sub(1) {
+                        Some(prev) => {
+                            let weighted_change: f64 = layers
+                                .iter()
+                                .enumerate()
+                                .map(|(i, layer)| {
+                                    (n - i as f64) * (value(layer, idx) - value(layer, prev))
+                                })
+                                .sum();
+                            baseline[prev] - weighted_change / (n + 1.)
+                        }
+                        None => -0.5 * totals[0],
+                    };
+                    baseline.push(g0);
+                }
+                baseline
+            }
+        };
+        let mut edges = vec![baseline];
+        for layer in layers {
+            let prev = edges.last().unwrap();
+            let edge = (0..len).map(|idx| prev[idx] + value(layer, idx)).collect();
+            edges.push(edge);
+        }
+        edges
+    }
+}
+
+pub struct StackedArea {
+    baseline: Baseline,
+    graph_insets: KeyOrValue<Insets>,
+    preferred_size: Size,
+    aspect_ratio: Option<f64>,
+    x_formatter: Formatter,
+    y_formatter: Formatter,
+    fills: SeriesFills,
+    // retained state
+    title: ChartTitle,
+    x_label_layout: TextLayout<ArcStr>,
+    /// The edges between the layers, from `Baseline::stack`.
+    edges: Option<Vec<Vec<f64>>>,
+    x_scale: Option<Scale>,
+    y_scale: Option<Scale>,
+    /// The part of the x axis zoomed in to with `ZOOM_TO_RANGE`.
+    x_view: Option<Range>,
+    /// The part of the y axis zoomed in to with `ZOOM_TO_RANGE`.
+    y_view: Option<Range>,
+    /// The outline of each layer.
+    band_paths: Option<Vec<BezPath>>,
+    legend: Legend,
+    empty_state: EmptyState,
+    /// The area where the data is drawn.
+    graph_bounds: Rect,
+    context_menu: Option<ChartMenu>,
+}
+
+impl StackedArea {
+    pub fn new() -> Self {
+        StackedArea {
+            baseline: Baseline::Zero,
+            graph_insets: theme::GRAPH_INSETS.into(),
+            preferred_size: DEFAULT_CHART_SIZE,
+            aspect_ratio: None,
+            x_formatter: Formatter::default(),
+            y_formatter: Formatter::default(),
+            fills: SeriesFills::default(),
+            title: ChartTitle::new(),
+            x_label_layout: TextLayout::new(),
+            edges: None,
+            x_scale: None,
+            y_scale: None,
+            x_view: None,
+            y_view: None,
+            band_paths: None,
+            legend: Legend::new(LegendPlacement::Right),
+            empty_state: EmptyState::new(),
+            graph_bounds: Rect::ZERO,
+            context_menu: None,
+        }
+    }
+
+    /// Fill the layers with `fill` instead of a flat color, e.g. a gradient or hatch pattern.
+    pub fn with_fill(mut self, fill: Fill) -> Self {
+        self.fills.set_all(fill);
+        self
+    }
+
+    /// Fill one layer with `fill`, where `0` is the first series. This overrides `with_fill`.
+    pub fn with_series_fill(mut self, series: usize, fill: Fill) -> Self {
+        self.fills.set(series, fill);
+        self
+    }
+
+    /// Choose where the bottom of the stack goes. The default is `Baseline::Zero`.
+    pub fn with_baseline(mut self, baseline: Baseline) -> Self {
+        self.baseline = baseline;
+        self
+    }
+
+    /// Set how the x axis tick labels are formatted.
+    pub fn with_x_formatter(mut self, formatter: Formatter) -> Self {
+        self.x_formatter = formatter;
+        self
+    }
+
+    /// Set how the y axis tick labels are formatted.
+    pub fn with_y_formatter(mut self, formatter: Formatter) -> Self {
+        self.y_formatter = formatter;
+        self
+    }
+
+    /// Where to draw the legend. Defaults to the right of the chart.
+    pub fn with_legend_placement(mut self, placement: LegendPlacement) -> Self {
+        self.legend.set_placement(placement);
+        self
+    }
+
+    /// Set the space around the plot area. The default is `theme::GRAPH_INSETS`.
+    pub fn with_graph_insets(mut self, insets: impl Into<KeyOrValue<Insets>>) -> Self {
+        self.graph_insets = insets.into();
+        self
+    }
+
+    /// Set the size to take when the space isn't bounded, e.g. inside a `Scroll`. Otherwise the
+    /// chart fills the space it is given. The default is 400×300.
+    pub fn with_preferred_size(mut self, size: impl Into<Size>) -> Self {
+        self.preferred_size = size.into();
+        self
+    }
+
+    /// Show a menu when the chart is right-clicked, to reset the zoom, toggle the grid, copy the
+    /// chart as an image, or copy or save the data as CSV. `T` is the type of your app's data,
+    /// which druid needs to show a menu.
+    pub fn with_context_menu<T: Data>(mut self) -> Self {
+        self.context_menu = Some(ChartMenu::new::<T>());
+        self
+    }
+
+    /// Keep the plot area at the given width:height ratio, centered in the space left over from
+    /// the title, legend and axes. By default the plot area fills that space.
+    pub fn with_aspect_ratio(mut self, ratio: f64) -> Self {
+        assert!(ratio > 0.0, "aspect ratio must be positive");
+        self.aspect_ratio = Some(ratio);
+        self
+    }
+
+    /// Where to put the title (and subtitle). Defaults to the center.
+    pub fn with_title_alignment(mut self, alignment: TitleAlignment) -> Self {
+        self.title.set_alignment(alignment);
+        self
+    }
+
+    /// Draw a line of smaller text under the title.
+    pub fn with_subtitle(mut self, subtitle: impl Into<ArcStr>) -> Self {
+        self.title.set_subtitle(subtitle.into());
+        self
+    }
+
+    /// A short description of the chart for screen readers, e.g. "Stacked area chart, Energy use,
+    /// 3 series, 50 points".
+    pub fn describe<S: SeriesData>(&self, data: &StackedAreaData<S>) -> String {
+        let points = data
+            .series
+            .iter()
+            .map(|series| series.values.len())
+            .max()
+            .unwrap_or(0);
+        let facts = vec![
+            count(data.series.len(), "series", "series"),
+            count(points, "point", "points"),
+        ];
+        describe("Stacked area chart", &data.title, facts)
+    }
+
+    /// Show `x_view` and `y_view` of the axes, or all of an axis if its view is `None`.
+    fn set_view(&mut self, x_view: Option<Range>, y_view: Option<Range>) {
+        self.x_view = x_view;
+        self.y_view = y_view;
+        self.x_scale = None;
+        self.y_scale = None;
+    }
+
+    /// Take the title, axis label and legend labels from `data`, when the chart is added.
+    fn set_data<S: SeriesData>(&mut self, data: &StackedAreaData<S>) {
+        self.title.set_text(data.title.clone());
+        self.x_label_layout.set_text(data.x_axis_label.clone());
+        self.legend
+            .set_labels(data.series.iter().map(|series| series.label.clone()));
+    }
+
+    /// Lay out the chart, or the empty state if there is no data.
+    fn lay_out<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &StackedAreaData<S>,
+        env: &Env,
+    ) {
+        if is_empty(data) {
+            self.empty_state.rebuild_if_needed(text, env);
+        } else {
+            self.rebuild_if_needed(text, size, data, env);
+        }
+    }
+
+    /// Rebuild the layouts, scales and bands for a chart of size `size`, as needed.
+    fn rebuild_if_needed<S: SeriesData>(
+        &mut self,
+        text: &mut PietText,
+        size: Size,
+        data: &StackedAreaData<S>,
+        env: &Env,
+    ) {
+        self.title.rebuild_if_needed(text, env);
+        self.x_label_layout.rebuild_if_needed(text, env);
+        self.legend.rebuild_if_needed(text, env);
+        if self.edges.is_none() {
+            let layers: Vec<Vec<f64>> = data
+                .series
+                .iter()
+                .map(|series| series.values.values().collect())
+                .collect();
+            self.edges = Some(self.baseline.stack(&layers));
+            self.x_scale = None;
+            self.y_scale = None;
+        }
+        let edges = self.edges.as_ref().unwrap();
+        if self.x_scale.is_none() {
+            let len = edges[0].len();
+            let range = self.x_view.unwrap_or_else(|| x_range(&x_values(data, len)));
+            let mut x_scale = Scale::new_x(range);
+            x_scale.set_formatter(self.x_formatter.clone());
+            self.x_scale = Some(x_scale);
+            self.band_paths = None;
+        }
+        if self.y_scale.is_none() {
+            let mut y_range = Range::from_iter(edges.iter().flatten().copied().chain(Some(0.)));
+            if y_range.size() == 0. {
+                y_range.set_max(y_range.min() + 1.);
+            }
+            let y_range = self.y_view.unwrap_or(y_range);
+            let mut y_scale = Scale::new_y(y_range);
+            y_scale.set_formatter(self.y_formatter.clone());
+            self.y_scale = Some(y_scale);
+            self.band_paths = None;
+        }
+
+        let bounds = Rect::from_origin_size(Point::ZERO, size);
+        let draw_area = self
+            .legend
+            .reserve_space(self.title.reserve_space(bounds, env), env);
+        let mut graph_bounds = draw_area.inset(-self.graph_insets.resolve(env));
+        if let Some(ratio) = self.aspect_ratio {
+            graph_bounds = fit_aspect_ratio(graph_bounds, ratio);
+        }
+        self.legend.set_graph_bounds(graph_bounds, env);
+        if self.graph_bounds != graph_bounds {
+            self.graph_bounds = graph_bounds;
+            self.band_paths = None;
+        }
+        for scale in [
+            self.x_scale.as_mut().unwrap(),
+            self.y_scale.as_mut().unwrap(),
+        ]
+        .iter_mut()
+        {
+            scale.set_graph_bounds(graph_bounds);
+            scale.rebuild_if_needed(text, env);
+        }
+
+        if self.band_paths.is_none() {
+            let x_scale = self.x_scale.as_ref().unwrap();
+            let y_scale = self.y_scale.as_ref().unwrap();
+            let xs: Vec<f64> = x_values(data, edges[0].len())
+                .into_iter()
+                .map(|x| x_scale.pixel_location(x))
+                .collect();
+            let to_points = |edge: &Vec<f64>| -> Vec<Point> {
+                xs.iter()
+                    .zip(edge.iter())
+                    .map(|(x, y)| Point::new(*x, y_scale.pixel_location(*y)))
+                    .collect()
+            };
+            self.band_paths = Some(
+                edges
+                    .windows(2)
+                    .map(|pair| {
+                        let lower = to_points(&pair[0]);
+                        let upper = to_points(&pair[1]);
+                        let mut path = BezPath::new();
+                        let mut points = upper.into_iter().chain(lower.into_iter().rev());
+                        if let Some(first) = points.next() {
+                            path.move_to(first);
+                            for point in points {
+                                path.line_to(point);
+                            }
+                            path.close_path();
+                        }
+                        path
+                    })
+                    .collect(),
+            );
+        }
+    }
+
+    /// Draw the chart, as laid out by `layout`.
+    fn draw<S: SeriesData>(&mut self, ctx: &mut Canvas, data: &StackedAreaData<S>, env: &Env) {
+        if is_empty(data) {
+            self.empty_state.draw(ctx);
+            return;
+        }
+        let size = ctx.size();
+
+        // grid
+        if data.draw_y_grid {
+            self.y_scale.as_ref().unwrap().draw_grid(ctx, env);
+        }
+
+        // data, cut off at the edges of the graph.
+        let graph_bounds = self.graph_bounds;
+        let fills = &self.fills;
+        let band_paths = self.band_paths.as_ref().unwrap();
+        ctx.with_save(|ctx| {
+            ctx.clip(graph_bounds);
+            for (idx, path) in band_paths.iter().enumerate() {
+                fills
+                    .resolve(idx, env)
+                    .paint(ctx, path, &new_color(idx, env), env);
+            }
+        });
+
+        // title
+        self.title.draw(ctx, env);
+
+        // axes
+        self.x_scale.as_mut().unwrap().draw(ctx, env, true, true);
+        let x_label_width = self.x_label_layout.size().width;
+        ctx.draw_layout(
+            &self.x_label_layout,
+            ((size.width - x_label_width) * 0.5, size.height - 40.0),
+        );
+        self.y_scale.as_mut().unwrap().draw(ctx, env, true, true);
+
+        // legend
+        self.legend.draw_with_fills(
+            ctx,
+            env,
+            |idx| new_color(idx, env),
+            |idx| fills.resolve(idx, env),
+        );
+    }
+}
+
+impl<S: SeriesData> Widget<StackedAreaData<S>> for StackedArea {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut StackedAreaData<S>,
+        env: &Env,
+    ) {
+        let items = MenuItems {
+            reset_zoom: self.x_view.is_some() || self.y_view.is_some(),
+            grid: true,
+            data: true,
+        };
+        if let Some(context_menu) = self.context_menu.as_mut() {
+            context_menu.event(ctx, event, items, || data.csv_table());
+        }
+        match event {
+            Event::Command(cmd) if cmd.is(ZOOM_TO_RANGE) => {
+                let zoom = cmd.get_unchecked(ZOOM_TO_RANGE);
+                self.set_view(zoom.x_range.or(self.x_view), zoom.y_range.or(self.y_view));
+                ctx.request_layout();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(RESET_CHART_VIEW) => {
+                self.set_view(None, None);
+                ctx.request_layout();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(TOGGLE_CHART_GRID) => {
+                data.draw_y_grid = !data.draw_y_grid;
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_DATA) => {
+                copy_csv(data.csv_table());
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(COPY_CHART_IMAGE) => {
+                copy_image(ctx, env, |ctx| self.draw(ctx, data, env));
+            }
+            Event::Command(cmd) if cmd.is(EXPORT_IMAGE) => {
+                let scale = *cmd.get_unchecked(EXPORT_IMAGE);
+                export_image(ctx, scale, env, |ctx| self.draw(ctx, data, env));
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &StackedAreaData<S>,
+        env: &Env,
+    ) {
+        match event {
+            LifeCycle::WidgetAdded => self.set_data(data),
+            _ => (),
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &StackedAreaData<S>,
+        data: &StackedAreaData<S>,
+        env: &Env,
+    ) {
+        if !old_data.title.same(&data.title) {
+            self.title.set_text(data.title.clone());
+        }
+        if self.title.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !old_data.x_axis_label.same(&data.x_axis_label) {
+            self.x_label_layout.set_text(data.x_axis_label.clone());
+        }
+        if self.x_label_layout.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !old_data.series.same(&data.series) {
+            self.legend
+                .set_labels(data.series.iter().map(|series| series.label.clone()));
+        }
+        if self.legend.needs_rebuild_after_update(ctx) {
+            ctx.request_layout();
+        }
+        if !old_data.series.same(&data.series) || !old_data.x_data.same(&data.x_data) {
+            self.edges = None;
+            ctx.request_layout();
+        }
+        if old_data.draw_y_grid != data.draw_y_grid {
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &StackedAreaData<S>,
+        env: &Env,
+    ) -> Size {
+        let size = chart_size(bc, self.preferred_size);
+        self.lay_out(ctx.text(), size, data, env);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &StackedAreaData<S>, env: &Env) {
+        self.draw(&mut Canvas::new(ctx, env), data, env);
+    }
+}
+
+impl<S: SeriesData> OffscreenChart<StackedAreaData<S>> for StackedArea {
+    fn draw_offscreen(&mut self, ctx: &mut Canvas, data: &StackedAreaData<S>, env: &Env) {
+        self.set_data(data);
+        let size = ctx.size();
+        self.lay_out(ctx.text(), size, data, env);
+        self.draw(ctx, data, env);
+    }
+}
+
+/// Whether there is nothing to plot, so we draw the empty state instead.
+fn is_empty<S: SeriesData>(data: &StackedAreaData<S>) -> bool {
+    data.series.iter().all(|series| series.values.is_empty())
+}
+
+/// The x value of each point: either `x_data` or the indices.
+fn x_values<S>(data: &StackedAreaData<S>, len: usize) -> Vec<f64> {
+    match &data.x_data {
+        Some(x_data) => x_data.iter().copied().take(len).collect(),
+        None => (0..len).map(|idx| idx as f64).collect(),
+    }
+}
+
+/// The range of the x values, widened if there are fewer than two distinct values.
+fn x_range(xs: &[f64]) -> Range {
+    let mut range = Range::from_finite(xs.iter().copied()).unwrap_or(Range::new(0., 1.));
+    if range.size() == 0. {
+        range.set_max(range.min() + 1.);
+    }
+    range
+}
+
+#[test]
+fn test_stack_zero() {
+    let edges = Baseline::Zero.stack(&[vec![1., 2.], vec![3., 4.]]);
+    assert_eq!(edges, vec![vec![0., 0.], vec![1., 2.], vec![4., 6.]]);
+}
+
+#[test]
+fn test_stack_silhouette() {
+    let edges = Baseline::Silhouette.stack(&[vec![1., 2.], vec![3., f64::NAN]]);
+    assert_eq!(edges, vec![vec![-2., -1.], vec![-1., 1.], vec![2., 1.]]);
+}
+
+#[test]
+fn test_stack_wiggle() {
+    // a single layer that doesn't change shouldn't move.
+    let edges = Baseline::Wiggle.stack(&[vec![2., 2., 2.]]);
+    assert_eq!(edges[0], vec![-1., -1., -1.]);
+}
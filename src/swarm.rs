@@ -0,0 +1,89 @@
+//! Spreading data points across a category, so they can be drawn without hiding each other.
+
+/// The golden ratio conjugate. Multiples of it (mod 1) never repeat and stay evenly spread.
+const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_895;
+
+/// Offsets in `-0.5..0.5` for `count` points, to scale by the width to jitter over.
+///
+/// These look random, but are the same every time, so the points don't jump about when the chart
+/// is redrawn. Neighbouring points always get quite different offsets.
+pub fn jitter(count: usize) -> Vec<f64> {
+    (0..count)
+        .map(|idx| (idx as f64 * GOLDEN_RATIO_CONJUGATE).fract() - 0.5)
+        .collect()
+}
+
+/// Offsets across the category for points at `positions` along the value axis (in pixels), so
+/// that no 2 circles of the given `diameter` overlap (a "bee swarm").
+///
+/// `positions` must be in order (either way), which is the case for sorted data. Each point goes
+/// as close to the center as it can without touching the points before it. Positions that aren't
+/// finite get an offset of `0`, and don't push other points aside.
+pub fn bee_swarm(positions: &[f64], diameter: f64) -> Vec<f64> {
+    let mut offsets: Vec<f64> = Vec::with_capacity(positions.len());
+    for (idx, position) in positions.iter().copied().enumerate() {
+        if !position.is_finite() {
+            offsets.push(0.);
+            continue;
+        }
+        // the offsets each earlier point close enough to touch rules out.
+        let mut blocked = Vec::new();
+        for prev in (0..idx).rev() {
+            if !positions[prev].is_finite() {
+                continue;
+            }
+            let distance = (position - positions[prev]).abs();
+            if distance >= diameter {
+                // the positions are in order, so all earlier points are further away.
+                break;
+            }
+            let half_width = (diameter * diameter - distance * distance).sqrt();
+            blocked.push((offsets[prev] - half_width, offsets[prev] + half_width));
+        }
+        let mut candidates = vec![0.];
+        for (start, end) in blocked.iter().copied() {
+            candidates.push(end);
+            candidates.push(start);
+        }
+        candidates.sort_by(|a: &f64, b: &f64| a.abs().total_cmp(&b.abs()));
+        let offset = candidates
+            .into_iter()
+            .find(|&offset| {
+                blocked
+                    .iter()
+                    .all(|&(start, end)| offset <= start || offset >= end)
+            })
+            .unwrap_or(0.);
+        offsets.push(offset);
+    }
+    offsets
+}
+
+#[test]
+fn test_jitter() {
+    let offsets = jitter(100);
+    assert!(offsets.iter().all(|&offset| offset >= -0.5 && offset < 0.5));
+    assert_eq!(offsets, jitter(100));
+}
+
+#[test]
+fn test_bee_swarm() {
+    // far apart points all sit in the middle.
+    assert_eq!(bee_swarm(&[0., 10., 20.], 5.), vec![0., 0., 0.]);
+    // points at the same value sit side by side.
+    assert_eq!(bee_swarm(&[0., 0., 0.], 5.), vec![0., 5., -5.]);
+    // points that can't be placed stay in the middle, and don't move the others.
+    assert_eq!(
+        bee_swarm(&[f64::NAN, 0., f64::NAN, 0.], 5.),
+        vec![0., 0., 0., 5.]
+    );
+    // close points never overlap.
+    let positions: Vec<f64> = (0..50).map(|idx| 100. - idx as f64 * 0.5).collect();
+    let offsets = bee_swarm(&positions, 4.);
+    for i in 0..positions.len() {
+        for j in 0..i {
+            let (dx, dy) = (offsets[i] - offsets[j], positions[i] - positions[j]);
+            assert!((dx * dx + dy * dy).sqrt() >= 4. - 1e-9);
+        }
+    }
+}
@@ -1,15 +1,143 @@
-use druid::{Color, Env, Key};
+//! Env keys for styling the charts, and palettes for light and dark backgrounds.
+use druid::{ArcStr, Color, Env, Insets, Key};
 
 /// Used in a number of places to give small spacing.
 pub const SCALE_MARGIN: Key<f64> = Key::new("org.derekdreery.druid-graphs.theme.scale_margin");
 pub const MARGIN: Key<f64> = Key::new("org.derekdreery.druid-graphs.theme.margin");
 pub const BAR_SPACING: Key<f64> = Key::new("org.derekdreery.druid-graphs.theme.bar_spacing");
 pub const AXES_COLOR: Key<Color> = Key::new("org.derekdreery.druid-graphs.theme.axes_color");
+pub const GRID_COLOR: Key<Color> = Key::new("org.derekdreery.druid-graphs.theme.grid_color");
+pub const ANNOTATION_COLOR: Key<Color> =
+    Key::new("org.derekdreery.druid-graphs.theme.annotation_color");
+pub const BAR_COLOR: Key<Color> = Key::new("org.derekdreery.druid-graphs.theme.bar_color");
+/// The color of bars that go down from the baseline, in charts given one with `with_baseline`.
+pub const BELOW_BASELINE_COLOR: Key<Color> =
+    Key::new("org.derekdreery.druid-graphs.theme.below_baseline_color");
+pub const LINE_STROKE_WIDTH: Key<f64> =
+    Key::new("org.derekdreery.druid-graphs.theme.line_stroke_width");
+pub const TITLE_TEXT_SIZE: Key<f64> =
+    Key::new("org.derekdreery.druid-graphs.theme.title_text_size");
+/// The text size of chart subtitles, drawn under the title.
+pub const SUBTITLE_TEXT_SIZE: Key<f64> =
+    Key::new("org.derekdreery.druid-graphs.theme.subtitle_text_size");
+pub const TICK_LABEL_TEXT_SIZE: Key<f64> =
+    Key::new("org.derekdreery.druid-graphs.theme.tick_label_text_size");
+/// The color of the outliers in box plots.
+pub const OUTLIER_COLOR: Key<Color> = Key::new("org.derekdreery.druid-graphs.theme.outlier_color");
+/// The color of the border around the pie chart key, and its color swatches.
+pub const PIE_KEY_BORDER_COLOR: Key<Color> =
+    Key::new("org.derekdreery.druid-graphs.theme.pie_key_border_color");
+/// The lightness (0 to 100) of the colors picked for each series.
+pub const SERIES_LIGHTNESS: Key<f64> =
+    Key::new("org.derekdreery.druid-graphs.theme.series_lightness");
+/// The chroma (colorfulness) of the colors picked for each series.
+pub const SERIES_CHROMA: Key<f64> = Key::new("org.derekdreery.druid-graphs.theme.series_chroma");
+/// The space around the plot area, for the axes and labels.
+///
+/// The bar chart and stacked area chart use these insets unless they are given their own with
+/// `with_graph_insets`. The other charts measure their axes and labels instead, but can be given
+/// fixed insets with `with_graph_insets` too, e.g. to line up several charts or to leave room for
+/// your own decorations. The title and subtitle take their own space above the insets.
+pub const GRAPH_INSETS: Key<Insets> = Key::new("org.derekdreery.druid-graphs.theme.graph_insets");
+/// The shading of every other band between the grid lines, for charts drawn with zebra bands.
+/// It should be faint, so the data stands out.
+pub const ZEBRA_COLOR: Key<Color> = Key::new("org.derekdreery.druid-graphs.theme.zebra_color");
+/// The fill of the rectangle the user drags out to select part of a chart. It should be mostly
+/// transparent, so the data shows through.
+pub const SELECTION_COLOR: Key<Color> =
+    Key::new("org.derekdreery.druid-graphs.theme.selection_color");
+/// The ring drawn around the point, bar or slice highlighted with the keyboard.
+pub const FOCUS_COLOR: Key<Color> = Key::new("org.derekdreery.druid-graphs.theme.focus_color");
+/// How much bigger than normal to draw lines, markers, gaps and text. Use [`set_size_scale`] to
+/// change it, which scales the other sizes in the env to match. The default is `1`.
+pub const SIZE_SCALE: Key<f64> = Key::new("org.derekdreery.druid-graphs.theme.size_scale");
+/// Whether to line up the axes, grid lines and bar edges with the pixels of the screen, so they
+/// are crisp rather than blurred across 2 pixels. On by default.
+pub const SNAP_TO_PIXELS: Key<bool> = Key::new("org.derekdreery.druid-graphs.theme.snap_to_pixels");
+/// Whether to fill the bars and areas of each series with a different hatch pattern instead of a
+/// flat color, so the series can be told apart when printed in black and white. Series given their
+/// own fill keep it. Off by default.
+pub const HATCH_FILLS: Key<bool> = Key::new("org.derekdreery.druid-graphs.theme.hatch_fills");
+/// The gap between the lines of hatch patterns.
+pub const HATCH_SPACING: Key<f64> = Key::new("org.derekdreery.druid-graphs.theme.hatch_spacing");
+/// The message shown in place of a chart when there is no data to plot.
+pub const EMPTY_MESSAGE: Key<ArcStr> = Key::new("org.derekdreery.druid-graphs.theme.empty_message");
 
 /// Important: call this before doing anything else.
+///
+/// This uses the dark palette, which suits druid's default (dark) theme.
 pub fn add_to_env(env: &mut Env) {
-    env.set(SCALE_MARGIN, 6.);
-    env.set(MARGIN, 10.);
-    env.set(BAR_SPACING, 10.);
+    add_to_env_dark(env);
+}
+
+/// Set up the env for charts on a dark background.
+///
+/// Only the chart keys are set: druid's own colors (like `druid::theme::WINDOW_BACKGROUND_COLOR`
+/// and `TEXT_COLOR`) are left for the app to choose.
+pub fn add_to_env_dark(env: &mut Env) {
+    add_sizes(env, 1.);
     env.set(AXES_COLOR, Color::grey(0.8));
+    env.set(GRID_COLOR, Color::grey(0.3));
+    env.set(ZEBRA_COLOR, Color::rgba8(0xff, 0xff, 0xff, 0x0c));
+    env.set(ANNOTATION_COLOR, Color::rgb8(0xf0, 0x90, 0x30));
+    env.set(BAR_COLOR, Color::hlc(0.0, 50.0, 50.0));
+    env.set(BELOW_BASELINE_COLOR, Color::hlc(220.0, 50.0, 50.0));
+    env.set(OUTLIER_COLOR, Color::rgb8(0xf0, 0xf0, 0xea));
+    env.set(PIE_KEY_BORDER_COLOR, Color::rgb8(0xf0, 0xf0, 0xea));
+    env.set(SELECTION_COLOR, Color::rgba8(0x90, 0xb8, 0xf0, 0x40));
+    env.set(FOCUS_COLOR, Color::rgb8(0x90, 0xb8, 0xf0));
+    env.set(SERIES_LIGHTNESS, 50.);
+    env.set(SERIES_CHROMA, 50.);
+}
+
+/// Set up the env for charts on a light background.
+///
+/// Only the chart keys are set, so give druid a light theme too: the charts are drawn on
+/// `druid::theme::WINDOW_BACKGROUND_COLOR`, and their text is `TEXT_COLOR`.
+pub fn add_to_env_light(env: &mut Env) {
+    add_sizes(env, 1.);
+    env.set(AXES_COLOR, Color::grey(0.25));
+    env.set(GRID_COLOR, Color::grey(0.85));
+    env.set(ZEBRA_COLOR, Color::rgba8(0x00, 0x00, 0x00, 0x0a));
+    env.set(ANNOTATION_COLOR, Color::rgb8(0xc8, 0x5a, 0x00));
+    env.set(BAR_COLOR, Color::hlc(0.0, 45.0, 60.0));
+    env.set(BELOW_BASELINE_COLOR, Color::hlc(220.0, 45.0, 60.0));
+    env.set(OUTLIER_COLOR, Color::rgb8(0x20, 0x20, 0x24));
+    env.set(PIE_KEY_BORDER_COLOR, Color::rgb8(0x20, 0x20, 0x24));
+    env.set(SELECTION_COLOR, Color::rgba8(0x20, 0x60, 0xc0, 0x30));
+    env.set(FOCUS_COLOR, Color::rgb8(0x20, 0x60, 0xc0));
+    // darker and more colorful, so thin lines stand out against white.
+    env.set(SERIES_LIGHTNESS, 45.);
+    env.set(SERIES_CHROMA, 60.);
+}
+
+/// Draw the charts `scale` times their normal size: their lines, markers, gaps and text.
+///
+/// Druid already scales everything for high DPI displays that the OS reports, so this is for
+/// screens where the charts still look too small (e.g. a 4K monitor at 100%), or for
+/// presentations. Call it after [`add_to_env`], as it sets the sizes back to their defaults times
+/// `scale`, and then change any sizes of your own.
+pub fn set_size_scale(env: &mut Env, scale: f64) {
+    assert!(scale > 0., "size scale must be positive");
+    add_sizes(env, scale);
+}
+
+/// The settings that don't depend on the background, with the sizes multiplied by `scale`.
+fn add_sizes(env: &mut Env, scale: f64) {
+    env.set(SIZE_SCALE, scale);
+    env.set(SCALE_MARGIN, 6. * scale);
+    env.set(MARGIN, 10. * scale);
+    env.set(BAR_SPACING, 10. * scale);
+    env.set(
+        GRAPH_INSETS,
+        Insets::new(200. * scale, 100. * scale, 40. * scale, 60. * scale),
+    );
+    env.set(LINE_STROKE_WIDTH, 1. * scale);
+    env.set(SNAP_TO_PIXELS, true);
+    env.set(HATCH_FILLS, false);
+    env.set(HATCH_SPACING, 6. * scale);
+    env.set(TITLE_TEXT_SIZE, 20. * scale);
+    env.set(SUBTITLE_TEXT_SIZE, 14. * scale);
+    env.set(TICK_LABEL_TEXT_SIZE, 15. * scale);
+    env.set(EMPTY_MESSAGE, ArcStr::from("No data"));
 }
@@ -0,0 +1,153 @@
+//! The title (and optional subtitle) drawn across the top of a chart.
+use druid::{
+    kurbo::Rect, piet::PietText, text::TextStorage, ArcStr, Data, Env, KeyOrValue, TextLayout,
+    UpdateCtx,
+};
+
+use crate::{canvas::Canvas, theme};
+
+/// Where the title and subtitle go across the top of a chart.
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub enum TitleAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+impl Default for TitleAlignment {
+    fn default() -> Self {
+        TitleAlignment::Center
+    }
+}
+
+impl TitleAlignment {
+    /// The x position of the left of some text `width` wide, when placed in `x0..x1`.
+    fn x(self, x0: f64, x1: f64, width: f64) -> f64 {
+        match self {
+            TitleAlignment::Left => x0,
+            TitleAlignment::Center => x0 + (x1 - x0 - width) * 0.5,
+            TitleAlignment::Right => x1 - width,
+        }
+    }
+}
+
+/// A chart title, with an optional subtitle in smaller text underneath.
+///
+/// Like the legend, it takes space from the top of the chart with
+/// [`ChartTitle::reserve_space`], and is then drawn in that space.
+pub(crate) struct ChartTitle<T = ArcStr> {
+    layout: TextLayout<T>,
+    subtitle_layout: Option<TextLayout<ArcStr>>,
+    alignment: TitleAlignment,
+    margin: KeyOrValue<f64>,
+    // retained
+    /// Where the title will be drawn.
+    bounds: Rect,
+}
+
+impl<T: TextStorage> ChartTitle<T> {
+    pub fn new() -> Self {
+        let mut layout = TextLayout::new();
+        layout.set_text_size(theme::TITLE_TEXT_SIZE);
+        ChartTitle {
+            layout,
+            subtitle_layout: None,
+            alignment: TitleAlignment::default(),
+            margin: theme::MARGIN.into(),
+            bounds: Rect::ZERO,
+        }
+    }
+
+    pub fn set_text(&mut self, text: T) {
+        self.layout.set_text(text);
+    }
+
+    pub fn set_text_size(&mut self, size: impl Into<KeyOrValue<f64>>) {
+        self.layout.set_text_size(size);
+    }
+
+    pub fn set_subtitle(&mut self, subtitle: ArcStr) {
+        let mut layout = TextLayout::from_text(subtitle);
+        layout.set_text_size(theme::SUBTITLE_TEXT_SIZE);
+        self.subtitle_layout = Some(layout);
+    }
+
+    pub fn set_alignment(&mut self, alignment: TitleAlignment) {
+        self.alignment = alignment;
+    }
+
+    pub fn needs_rebuild_after_update(&mut self, ctx: &mut UpdateCtx) -> bool {
+        let mut needs_rebuild = self.layout.needs_rebuild_after_update(ctx);
+        if let Some(layout) = self.subtitle_layout.as_mut() {
+            needs_rebuild |= layout.needs_rebuild_after_update(ctx);
+        }
+        needs_rebuild
+    }
+
+    pub fn rebuild_if_needed(&mut self, text: &mut PietText, env: &Env) {
+        self.layout.rebuild_if_needed(text, env);
+        if let Some(layout) = self.subtitle_layout.as_mut() {
+            layout.rebuild_if_needed(text, env);
+        }
+    }
+
+    fn has_title(&self) -> bool {
+        self.layout
+            .text()
+            .map_or(false, |text| !text.as_str().is_empty())
+    }
+
+    /// The height the title and subtitle take up, including the margin above them. Layouts must
+    /// have been built.
+    pub fn height(&self, env: &Env) -> f64 {
+        let margin = self.margin.resolve(env);
+        let title_height = if self.has_title() {
+            self.layout.size().height
+        } else {
+            0.
+        };
+        let subtitle_height = self
+            .subtitle_layout
+            .as_ref()
+            .map(|layout| layout.size().height)
+            .unwrap_or(0.);
+        match (title_height > 0., subtitle_height > 0.) {
+            (false, false) => 0.,
+            (true, true) => margin + title_height + 0.5 * margin + subtitle_height,
+            _ => margin + title_height + subtitle_height,
+        }
+    }
+
+    /// Take the space for the title from the top of `bounds`, and return what is left.
+    pub fn reserve_space(&mut self, bounds: Rect, env: &Env) -> Rect {
+        let height = self.height(env).min(bounds.height());
+        self.bounds = Rect::new(bounds.x0, bounds.y0, bounds.x1, bounds.y0 + height);
+        Rect::new(bounds.x0, bounds.y0 + height, bounds.x1, bounds.y1)
+    }
+
+    /// Draw the title in the space we reserved.
+    pub fn draw(&mut self, ctx: &mut Canvas, env: &Env) {
+        if self.bounds.height() <= 0. {
+            return;
+        }
+        let margin = self.margin.resolve(env);
+        let (x0, x1) = (self.bounds.x0 + margin, self.bounds.x1 - margin);
+        let mut y = self.bounds.y0 + margin;
+        if self.has_title() {
+            let size = self.layout.size();
+            ctx.draw_layout(&self.layout, (self.alignment.x(x0, x1, size.width), y));
+            y += size.height + 0.5 * margin;
+        }
+        if let Some(layout) = self.subtitle_layout.as_mut() {
+            let size = layout.size();
+            ctx.draw_layout(layout, (self.alignment.x(x0, x1, size.width), y));
+        }
+    }
+}
+
+#[test]
+fn test_title_alignment() {
+    assert_eq!(TitleAlignment::Left.x(10., 110., 40.), 10.);
+    assert_eq!(TitleAlignment::Center.x(10., 110., 40.), 40.);
+    assert_eq!(TitleAlignment::Right.x(10., 110., 40.), 70.);
+}
@@ -0,0 +1,280 @@
+//! Series derived from the data, like moving averages, drawn over a line chart.
+use druid::{
+    kurbo::{Line, Point},
+    Color, Data, KeyOrValue,
+};
+use std::sync::Arc;
+
+use crate::{axes::Scale, format::Formatter, legend::Corner};
+
+/// A way of summarizing a series to show its trend.
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub enum Trend {
+    /// The mean of the last `n` values (a simple moving average).
+    MovingAverage(usize),
+    /// An exponential moving average, where each new value has weight `alpha` (between 0 and 1).
+    ExponentialMovingAverage(f64),
+    /// The least-squares straight line through the points.
+    LinearRegression,
+}
+
+impl Trend {
+    /// The value of the trend at each point, or NaN where it isn't defined (e.g. where a value is
+    /// missing, or before there are enough values for a moving average).
+    pub fn calculate(self, xs: &[f64], ys: &[f64]) -> Vec<f64> {
+        match self {
+            Trend::MovingAverage(n) => moving_average(ys, n.max(1)),
+            Trend::ExponentialMovingAverage(alpha) => {
+                exponential_moving_average(ys, alpha.max(0.).min(1.))
+            }
+            Trend::LinearRegression => linear_regression(xs, ys),
+        }
+    }
+}
+
+/// The least-squares straight line through some points, and how well it fits them.
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub struct RegressionStats {
+    pub slope: f64,
+    pub intercept: f64,
+    /// The coefficient of determination: the fraction of the variation in y that the line
+    /// explains, from 0 to 1.
+    pub r_squared: f64,
+}
+
+impl RegressionStats {
+    /// Fit a line to the points where both `x` and `y` are present.
+    ///
+    /// Returns `None` if there are fewer than 2 such points, or they all have the same `x`.
+    pub fn fit(xs: &[f64], ys: &[f64]) -> Option<Self> {
+        let points: Vec<(f64, f64)> = xs
+            .iter()
+            .copied()
+            .zip(ys.iter().copied())
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .collect();
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let (mut covariance, mut variance_x, mut variance_y) = (0., 0., 0.);
+        for (x, y) in points.iter() {
+            covariance += (x - mean_x) * (y - mean_y);
+            variance_x += (x - mean_x) * (x - mean_x);
+            variance_y += (y - mean_y) * (y - mean_y);
+        }
+        if points.len() < 2 || variance_x == 0. {
+            return None;
+        }
+        let slope = covariance / variance_x;
+        Some(RegressionStats {
+            slope,
+            intercept: mean_y - slope * mean_x,
+            // a flat line through flat data fits perfectly.
+            r_squared: if variance_y == 0. {
+                1.
+            } else {
+                covariance * covariance / (variance_x * variance_y)
+            },
+        })
+    }
+
+    /// The default text for a stats box, e.g. `y = 2x − 1` and `R² = 0.95` on separate lines.
+    fn describe(&self, formatter: &Formatter) -> String {
+        let (sign, intercept) = if self.intercept < 0. {
+            ("\u{2212}", -self.intercept)
+        } else {
+            ("+", self.intercept)
+        };
+        format!(
+            "y = {}x {} {}\nR\u{b2} = {}",
+            formatter.format(self.slope),
+            sign,
+            formatter.format(intercept),
+            formatter.format(self.r_squared)
+        )
+    }
+}
+
+/// The straight line `y = slope * x + intercept` in pixels, across the whole x axis.
+pub(crate) fn line_across(x_scale: &Scale, y_scale: &Scale, slope: f64, intercept: f64) -> Line {
+    let (x0, x1) = x_scale.data_range().into();
+    let pixel = |x: f64| {
+        Point::new(
+            x_scale.pixel_location(x),
+            y_scale.pixel_location(slope * x + intercept),
+        )
+    };
+    Line::new(pixel(x0), pixel(x1))
+}
+
+/// A trend line for one of the series of a `LineChart`.
+#[derive(Clone)]
+pub struct TrendLine {
+    pub(crate) series: usize,
+    pub(crate) trend: Trend,
+    /// If `None`, the color of the series is used.
+    pub(crate) color: Option<KeyOrValue<Color>>,
+    pub(crate) stroke_width: KeyOrValue<f64>,
+    /// Where to show the fit of a linear regression, if at all.
+    pub(crate) stats_corner: Option<Corner>,
+    stats_formatter: Formatter,
+    /// If `None`, the equation of the line and R² are shown.
+    stats_text: Option<Arc<dyn Fn(&RegressionStats) -> String>>,
+}
+
+impl TrendLine {
+    /// Show `trend` for `series` (`0` is `y_data`, `1` is the first of the `extra_series`, etc.).
+    pub fn new(series: usize, trend: Trend) -> Self {
+        TrendLine {
+            series,
+            trend,
+            color: None,
+            stroke_width: 2.0.into(),
+            stats_corner: None,
+            stats_formatter: Formatter::precision(3),
+            stats_text: None,
+        }
+    }
+
+    /// Set the color of the line. The default is the color of the series.
+    pub fn with_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    /// Set the width of the line. The default is 2.
+    pub fn with_stroke_width(mut self, width: impl Into<KeyOrValue<f64>>) -> Self {
+        self.stroke_width = width.into();
+        self
+    }
+
+    /// Show the slope, intercept and R² of the line in a box in a corner of the graph. This only
+    /// does anything for `Trend::LinearRegression`.
+    pub fn with_stats(mut self, corner: Corner) -> Self {
+        self.stats_corner = Some(corner);
+        self
+    }
+
+    /// Set how the numbers in the stats box are formatted. The default is 3 significant figures.
+    pub fn with_stats_formatter(mut self, formatter: Formatter) -> Self {
+        self.stats_formatter = formatter;
+        self
+    }
+
+    /// Write the text of the stats box yourself, e.g. to only show R².
+    pub fn with_stats_text(mut self, text: impl Fn(&RegressionStats) -> String + 'static) -> Self {
+        self.stats_text = Some(Arc::new(text));
+        self
+    }
+
+    /// The text of the stats box, if there should be one.
+    pub(crate) fn stats_text(&self, xs: &[f64], ys: &[f64]) -> Option<String> {
+        self.stats_corner?;
+        if self.trend != Trend::LinearRegression {
+            return None;
+        }
+        let stats = RegressionStats::fit(xs, ys)?;
+        Some(match self.stats_text.as_ref() {
+            Some(text) => text(&stats),
+            None => stats.describe(&self.stats_formatter),
+        })
+    }
+}
+
+fn moving_average(ys: &[f64], n: usize) -> Vec<f64> {
+    // keep a running total of the window, and how many values in it are missing.
+    let mut sum = 0.;
+    let mut missing = 0;
+    let mut out = Vec::with_capacity(ys.len());
+    for (idx, y) in ys.iter().copied().enumerate() {
+        if y.is_finite() {
+            sum += y;
+        } else {
+            missing += 1;
+        }
+        if let Some(old) = idx.checked_sub(n).map(|old| ys[old]) {
+            if old.is_finite() {
+                sum -= old;
+            } else {
+                missing -= 1;
+            }
+        }
+        out.push(if idx + 1 >= n && missing == 0 {
+            sum / n as f64
+        } else {
+            f64::NAN
+        });
+    }
+    out
+}
+
+fn exponential_moving_average(ys: &[f64], alpha: f64) -> Vec<f64> {
+    let mut average: Option<f64> = None;
+    ys.iter()
+        .copied()
+        .map(|y| {
+            if !y.is_finite() {
+                return f64::NAN;
+            }
+            let next = match average {
+                Some(average) => average + alpha * (y - average),
+                None => y,
+            };
+            average = Some(next);
+            next
+        })
+        .collect()
+}
+
+fn linear_regression(xs: &[f64], ys: &[f64]) -> Vec<f64> {
+    match RegressionStats::fit(xs, ys) {
+        Some(stats) => xs
+            .iter()
+            .map(|x| stats.intercept + stats.slope * x)
+            .collect(),
+        None => vec![f64::NAN; xs.len()],
+    }
+}
+
+#[test]
+fn test_moving_average() {
+    let averages = Trend::MovingAverage(2).calculate(&[], &[1., 2., 3., f64::NAN, 5., 6.]);
+    assert!(averages[0].is_nan());
+    assert_eq!(&averages[1..3], &[1.5, 2.5]);
+    // a missing value spoils every window it is in.
+    assert!(averages[3].is_nan() && averages[4].is_nan());
+    assert_eq!(averages[5], 5.5);
+}
+
+#[test]
+fn test_exponential_moving_average() {
+    let averages = Trend::ExponentialMovingAverage(0.5).calculate(&[], &[2., 4., f64::NAN, 6.]);
+    assert_eq!(&averages[..2], &[2., 3.]);
+    assert!(averages[2].is_nan());
+    assert_eq!(averages[3], 4.5);
+}
+
+#[test]
+fn test_linear_regression() {
+    let xs = [0., 1., 2., 3.];
+    let line = Trend::LinearRegression.calculate(&xs, &[1., 3., f64::NAN, 7.]);
+    assert_eq!(line, vec![1., 3., 5., 7.]);
+    // not enough points to fit a line.
+    assert!(Trend::LinearRegression.calculate(&xs[..1], &[1.])[0].is_nan());
+}
+
+#[test]
+fn test_regression_stats() {
+    let stats = RegressionStats::fit(&[0., 1., 2., 3.], &[-1., 1., 3., 5.]).unwrap();
+    assert_eq!(
+        (stats.slope, stats.intercept, stats.r_squared),
+        (2., -1., 1.)
+    );
+    assert_eq!(
+        stats.describe(&Formatter::fixed(2)),
+        "y = 2.00x \u{2212} 1.00\nR\u{b2} = 1.00"
+    );
+    let stats = RegressionStats::fit(&[0., 1., 2.], &[0., 2., 1.]).unwrap();
+    assert_eq!(stats.r_squared, 0.25);
+    assert!(RegressionStats::fit(&[1., 1.], &[0., 2.]).is_none());
+}
@@ -0,0 +1,107 @@
+//! Values that can be plotted.
+use druid::{im::Vector, Data};
+use std::sync::Arc;
+
+/// A value that can be drawn on a chart.
+///
+/// Any [`SeriesData`] of these can be plotted, so charts can show integers or your own types
+/// without copying the data into a `Vector<f64>` first. It is implemented for the built-in number
+/// types. For a newtype, implement it by converting the inner value.
+pub trait ToF64: Data {
+    /// The value, as it will be drawn.
+    fn to_f64(&self) -> f64;
+}
+
+macro_rules! impl_to_f64 {
+    ($($ty:ty),*) => {
+        $(
+            impl ToF64 for $ty {
+                fn to_f64(&self) -> f64 {
+                    *self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_to_f64!(f64, f32, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// A series of values that a chart can draw as it is, without copying it.
+///
+/// This is implemented for `Vector`, which is cheap to change a bit at a time, and for
+/// `Arc<Vec<_>>` and `Arc<[_]>`, so large buffers you already have can be drawn directly. `Arc`s
+/// are compared by pointer, so to change the data you need to make a new one.
+pub trait SeriesData: Data {
+    /// The number of values.
+    fn len(&self) -> usize;
+
+    /// The value at `idx`, or `None` if there are fewer values.
+    fn get(&self, idx: usize) -> Option<f64>;
+
+    /// All the values, in order.
+    fn values(&self) -> Box<dyn Iterator<Item = f64> + '_>;
+
+    /// Whether there are no values.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<V: ToF64> SeriesData for Vector<V> {
+    fn len(&self) -> usize {
+        Vector::len(self)
+    }
+
+    fn get(&self, idx: usize) -> Option<f64> {
+        Vector::get(self, idx).map(ToF64::to_f64)
+    }
+
+    fn values(&self) -> Box<dyn Iterator<Item = f64> + '_> {
+        Box::new(self.iter().map(ToF64::to_f64))
+    }
+}
+
+impl<V: ToF64> SeriesData for Arc<Vec<V>> {
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn get(&self, idx: usize) -> Option<f64> {
+        self.as_slice().get(idx).map(ToF64::to_f64)
+    }
+
+    fn values(&self) -> Box<dyn Iterator<Item = f64> + '_> {
+        Box::new(self.iter().map(ToF64::to_f64))
+    }
+}
+
+impl<V: ToF64> SeriesData for Arc<[V]> {
+    fn len(&self) -> usize {
+        <[V]>::len(self)
+    }
+
+    fn get(&self, idx: usize) -> Option<f64> {
+        <[V]>::get(self, idx).map(ToF64::to_f64)
+    }
+
+    fn values(&self) -> Box<dyn Iterator<Item = f64> + '_> {
+        Box::new(self.iter().map(ToF64::to_f64))
+    }
+}
+
+#[test]
+fn test_to_f64() {
+    assert_eq!(3i32.to_f64(), 3.);
+    assert_eq!(u64::MAX.to_f64(), 18446744073709551615.);
+    assert_eq!(0.5f32.to_f64(), 0.5);
+}
+
+#[test]
+fn test_series_data() {
+    let shared: Arc<Vec<i32>> = Arc::new(vec![1, 2, 3]);
+    assert_eq!(SeriesData::len(&shared), 3);
+    assert_eq!(SeriesData::get(&shared, 1), Some(2.));
+    assert_eq!(SeriesData::get(&shared, 3), None);
+    let slice: Arc<[f64]> = vec![0.5, 1.5].into();
+    assert_eq!(slice.values().collect::<Vec<_>>(), vec![0.5, 1.5]);
+}
@@ -0,0 +1,232 @@
+//! A logo or line of text drawn faintly behind a chart, e.g. for branding exported charts.
+use druid::{
+    kurbo::{Rect, Size},
+    piet::{ImageFormat, InterpolationMode, PietImage},
+    theme::LABEL_COLOR,
+    ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, ImageBuf, KeyOrValue, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, RenderContext, TextLayout, UnitPoint, UpdateCtx, Widget,
+};
+
+use crate::theme;
+
+/// What a [`Watermark`] draws.
+enum Content {
+    Image {
+        image: ImageBuf,
+        /// The faded image, made the first time it is drawn.
+        cached: Option<PietImage>,
+    },
+    Text {
+        layout: TextLayout<ArcStr>,
+        color: KeyOrValue<Color>,
+        /// The faded color the layout was last given, as RGBA.
+        faded: Option<u32>,
+    },
+}
+
+/// Wraps a chart (or any widget) to draw an image or some text behind it.
+///
+/// The watermark is drawn before the chart, so the data goes over it. Images are shrunk to fit
+/// if they are bigger than the chart, but never stretched.
+pub struct Watermark<W> {
+    inner: W,
+    content: Content,
+    alignment: UnitPoint,
+    opacity: f64,
+}
+
+impl<W> Watermark<W> {
+    /// Draw `image` (e.g. a logo) behind `inner`.
+    pub fn image(inner: W, image: ImageBuf) -> Self {
+        Watermark::new(
+            inner,
+            Content::Image {
+                image,
+                cached: None,
+            },
+        )
+    }
+
+    /// Draw `text` behind `inner`, in large type.
+    pub fn text(inner: W, text: impl Into<ArcStr>) -> Self {
+        let mut layout = TextLayout::from_text(text.into());
+        layout.set_text_size(48.);
+        Watermark::new(
+            inner,
+            Content::Text {
+                layout,
+                color: LABEL_COLOR.into(),
+                faded: None,
+            },
+        )
+    }
+
+    fn new(inner: W, content: Content) -> Self {
+        Watermark {
+            inner,
+            content,
+            alignment: UnitPoint::CENTER,
+            opacity: 0.15,
+        }
+    }
+
+    /// Where to put the watermark, e.g. `UnitPoint::BOTTOM_RIGHT`. The default is the center.
+    pub fn with_alignment(mut self, alignment: UnitPoint) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// How solid the watermark is, from 0 (invisible) to 1. The default is `0.15`.
+    pub fn with_opacity(mut self, opacity: f64) -> Self {
+        self.opacity = opacity.max(0.).min(1.);
+        match &mut self.content {
+            Content::Image { cached, .. } => *cached = None,
+            Content::Text { faded, .. } => *faded = None,
+        }
+        self
+    }
+
+    /// Set the size of a text watermark. The default is 48.
+    pub fn with_text_size(mut self, size: impl Into<KeyOrValue<f64>>) -> Self {
+        if let Content::Text { layout, .. } = &mut self.content {
+            layout.set_text_size(size);
+        }
+        self
+    }
+
+    /// Set the color of a text watermark, before it is faded. The default is the label color.
+    pub fn with_text_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        if let Content::Text {
+            color: text_color,
+            faded,
+            ..
+        } = &mut self.content
+        {
+            *text_color = color.into();
+            *faded = None;
+        }
+        self
+    }
+}
+
+impl<T: Data, W: Widget<T>> Widget<T> for Watermark<W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.inner.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.inner.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        if let Content::Text { layout, color, .. } = &mut self.content {
+            if layout.needs_rebuild_after_update(ctx) || ctx.env_key_changed(color) {
+                ctx.request_layout();
+            }
+        }
+        self.inner.update(ctx, old_data, data, env)
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        if let Content::Text {
+            layout,
+            color,
+            faded,
+        } = &mut self.content
+        {
+            // there's no opacity for text, so fade its color instead.
+            let color = color.resolve(env);
+            let color = color.clone().with_alpha(alpha(&color) * self.opacity);
+            if *faded != Some(color.as_rgba_u32()) {
+                *faded = Some(color.as_rgba_u32());
+                layout.set_text_color(color);
+            }
+            layout.rebuild_if_needed(ctx.text(), env);
+        }
+        self.inner.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        let bounds = ctx.size().to_rect().inset(-env.get(theme::MARGIN)).abs();
+        let (alignment, opacity) = (self.alignment, self.opacity);
+        match &mut self.content {
+            Content::Image { image, cached } => {
+                // shrink the image to fit, keeping its shape.
+                let image_size = image.size();
+                let shrink = (bounds.width() / image_size.width)
+                    .min(bounds.height() / image_size.height)
+                    .min(1.);
+                let rect = place(alignment, bounds, image_size * shrink);
+                let faded =
+                    cached.get_or_insert_with(|| fade(image, opacity).to_image(ctx.render_ctx));
+                ctx.draw_image(faded, rect, InterpolationMode::Bilinear);
+            }
+            Content::Text { layout, .. } => {
+                let rect = place(alignment, bounds, layout.size());
+                ctx.with_save(|ctx| {
+                    ctx.clip(bounds);
+                    layout.draw(ctx, rect.origin());
+                });
+            }
+        }
+        self.inner.paint(ctx, data, env)
+    }
+}
+
+/// Where to draw something of `size` in `bounds`, lined up by `alignment`.
+fn place(alignment: UnitPoint, bounds: Rect, size: Size) -> Rect {
+    let free = Rect::from_origin_size(bounds.origin(), bounds.size() - size);
+    Rect::from_origin_size(alignment.resolve(free), size)
+}
+
+/// The alpha of `color`, from 0 to 1.
+fn alpha(color: &Color) -> f64 {
+    (color.as_rgba_u32() & 0xff) as f64 / 255.
+}
+
+/// A copy of `image` with its alpha multiplied by `opacity`.
+fn fade(image: &ImageBuf, opacity: f64) -> ImageBuf {
+    let fade_alpha = |a: u8| (a as f64 * opacity).round() as u8;
+    let pixels = image.raw_pixels();
+    let rgba: Vec<u8> = match image.format() {
+        ImageFormat::Grayscale => pixels
+            .iter()
+            .flat_map(|&v| vec![v, v, v, fade_alpha(255)])
+            .collect(),
+        ImageFormat::Rgb => pixels
+            .chunks_exact(3)
+            .flat_map(|p| vec![p[0], p[1], p[2], fade_alpha(255)])
+            .collect(),
+        ImageFormat::RgbaSeparate => pixels
+            .chunks_exact(4)
+            .flat_map(|p| vec![p[0], p[1], p[2], fade_alpha(p[3])])
+            .collect(),
+        // the colors are already multiplied by the alpha, so they fade too.
+        ImageFormat::RgbaPremul => {
+            let premul: Vec<u8> = pixels.iter().map(|&v| fade_alpha(v)).collect();
+            return ImageBuf::from_raw(
+                premul,
+                ImageFormat::RgbaPremul,
+                image.width(),
+                image.height(),
+            );
+        }
+        _ => pixels.to_vec(),
+    };
+    ImageBuf::from_raw(
+        rgba,
+        ImageFormat::RgbaSeparate,
+        image.width(),
+        image.height(),
+    )
+}
+
+#[test]
+fn test_fade() {
+    let image = ImageBuf::from_raw(vec![10, 20, 30, 200], ImageFormat::RgbaSeparate, 1, 1);
+    assert_eq!(fade(&image, 0.5).raw_pixels(), &[10, 20, 30, 100]);
+    let image = ImageBuf::from_raw(vec![10, 20, 30], ImageFormat::Rgb, 1, 1);
+    let faded = fade(&image, 0.2);
+    assert_eq!(faded.format(), ImageFormat::RgbaSeparate);
+    assert_eq!(faded.raw_pixels(), &[10, 20, 30, 51]);
+}